@@ -0,0 +1,17 @@
+-- Add down migration script here
+CREATE TABLE resources (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    plan_id UUID NOT NULL,
+    max BIGINT NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    description TEXT,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (plan_id) REFERENCES plans(id)
+);
+
+INSERT INTO resources (id, plan_id, max, name, description, created_at, updated_at)
+SELECT id, plan_id, max, name, description, created_at, updated_at FROM plan_limits;
+
+DROP TABLE plan_limits;
+DROP TABLE resource_types;
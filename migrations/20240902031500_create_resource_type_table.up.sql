@@ -0,0 +1,42 @@
+-- Add up migration script here
+-- Resource type là danh mục tài nguyên độc lập với Plan (users, roles, ...)
+-- Giới hạn của từng Plan cho mỗi loại tài nguyên được lưu trong plan_limits
+CREATE TABLE resource_types (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    code VARCHAR(50) NOT NULL UNIQUE,
+    name VARCHAR(255) NOT NULL,
+    description TEXT,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE plan_limits (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    plan_id UUID NOT NULL,
+    resource_type_id UUID NOT NULL,
+    max BIGINT NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    description TEXT,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (plan_id) REFERENCES plans(id) ON DELETE CASCADE,
+    FOREIGN KEY (resource_type_id) REFERENCES resource_types(id) ON DELETE CASCADE,
+    UNIQUE (plan_id, resource_type_id)
+);
+
+INSERT INTO resource_types (code, name, description) VALUES
+('users', 'Người dùng', 'Số người dùng trong nhóm'),
+('roles', 'Phân quyền', 'Số phân quyền được tạo');
+
+-- Resource cũ được đặt tên theo dạng <loại>_<plan>_resource, ví dụ user_free_resource
+INSERT INTO resource_types (code, name)
+SELECT DISTINCT split_part(name, '_', 1) || 's', split_part(name, '_', 1) || 's'
+FROM resources
+ON CONFLICT (code) DO NOTHING;
+
+INSERT INTO plan_limits (id, plan_id, resource_type_id, max, name, description, created_at, updated_at)
+SELECT DISTINCT ON (r.plan_id, rt.id) r.id, r.plan_id, rt.id, r.max, r.name, r.description, r.created_at, r.updated_at
+FROM resources AS r
+INNER JOIN resource_types AS rt ON rt.code = split_part(r.name, '_', 1) || 's'
+ORDER BY r.plan_id, rt.id, r.updated_at DESC;
+
+DROP TABLE resources;
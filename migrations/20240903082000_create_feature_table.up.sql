@@ -0,0 +1,32 @@
+-- Add up migration script here
+-- Feature là các quyền bật/tắt theo Plan, ví dụ: xuất dữ liệu, truy cập API, tạo phân quyền tùy chỉnh
+CREATE TABLE features (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    code VARCHAR(50) NOT NULL UNIQUE,
+    name VARCHAR(255) NOT NULL,
+    description TEXT,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE plan_features (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    plan_id UUID NOT NULL,
+    feature_id UUID NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (plan_id) REFERENCES plans(id) ON DELETE CASCADE,
+    FOREIGN KEY (feature_id) REFERENCES features(id) ON DELETE CASCADE,
+    UNIQUE (plan_id, feature_id)
+);
+
+INSERT INTO features (code, name, description) VALUES
+('export', 'Xuất dữ liệu', 'Xuất dữ liệu ra file'),
+('api_access', 'Truy cập API', 'Truy cập hệ thống qua API'),
+('custom_roles', 'Phân quyền tùy chỉnh', 'Tạo phân quyền tùy chỉnh');
+
+INSERT INTO plan_features (plan_id, feature_id)
+SELECT p.id, f.id
+FROM plans AS p, features AS f
+WHERE (f.code = 'custom_roles')
+   OR (f.code = 'export' AND p.name IN ('Advance', 'Premium'))
+   OR (f.code = 'api_access' AND p.name = 'Premium');
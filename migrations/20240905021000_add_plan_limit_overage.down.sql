@@ -0,0 +1,4 @@
+-- Add down migration script here
+DROP TABLE usage_overages;
+DROP TABLE usage_notifications;
+ALTER TABLE plan_limits DROP COLUMN overage_unit_price, DROP COLUMN hard_limit;
@@ -0,0 +1,40 @@
+-- Add up migration script here
+-- max là giới hạn mềm (số lượng đã bao gồm trong Plan), vượt quá max sẽ tính phí overage
+-- hard_limit là giới hạn cứng, NULL nghĩa là không giới hạn
+ALTER TABLE plan_limits
+    ADD COLUMN hard_limit BIGINT,
+    ADD COLUMN overage_unit_price BIGINT NOT NULL DEFAULT 0;
+
+UPDATE plan_limits SET hard_limit = max;
+
+-- Mỗi ngưỡng (80%, 100%) chỉ được thông báo một lần trong một chu kỳ
+CREATE TABLE usage_notifications (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    subscription_id UUID NOT NULL,
+    resource_type_id UUID NOT NULL,
+    threshold INT NOT NULL,
+    period_start TIMESTAMP NOT NULL,
+    usage BIGINT NOT NULL,
+    limit_value BIGINT NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (subscription_id) REFERENCES subscriptions(id) ON DELETE CASCADE,
+    FOREIGN KEY (resource_type_id) REFERENCES resource_types(id) ON DELETE CASCADE,
+    UNIQUE (subscription_id, resource_type_id, threshold, period_start)
+);
+
+-- Số lượng vượt giới hạn mềm trong chu kỳ, được tính phí cuối chu kỳ cùng với giá Plan
+CREATE TABLE usage_overages (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    subscription_id UUID NOT NULL,
+    resource_type_id UUID NOT NULL,
+    period_start TIMESTAMP NOT NULL,
+    period_end TIMESTAMP,
+    quantity BIGINT NOT NULL,
+    unit_price BIGINT NOT NULL,
+    billed_at TIMESTAMP,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (subscription_id) REFERENCES subscriptions(id) ON DELETE CASCADE,
+    FOREIGN KEY (resource_type_id) REFERENCES resource_types(id) ON DELETE CASCADE,
+    UNIQUE (subscription_id, resource_type_id, period_start)
+);
@@ -0,0 +1,20 @@
+-- Add up migration script here
+-- Giới hạn riêng cho từng tài khoản theo thỏa thuận với bộ phận kinh doanh, ví dụ: Premium nhưng 200 người dùng
+-- Override trên tài khoản chủ nhóm (parent trong user_groups) áp dụng cho cả nhóm
+CREATE TABLE quota_overrides (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL,
+    resource_type_id UUID NOT NULL,
+    max BIGINT NOT NULL,
+    hard_limit BIGINT NOT NULL,
+    reason TEXT NOT NULL,
+    expires_at TIMESTAMP,
+    created_by UUID NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    revoked_at TIMESTAMP,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (resource_type_id) REFERENCES resource_types(id) ON DELETE CASCADE,
+    FOREIGN KEY (created_by) REFERENCES sys(id)
+);
+
+CREATE INDEX quota_overrides_user_id_idx ON quota_overrides (user_id, resource_type_id);
@@ -0,0 +1,8 @@
+-- Add down migration script here
+DROP TABLE usage_period_rollups;
+DROP TABLE usage_daily_rollups;
+DROP TABLE usage_events;
+
+DELETE FROM resource_types WHERE code IN ('api_calls', 'messages');
+
+ALTER TABLE resource_types DROP COLUMN is_metered;
@@ -0,0 +1,51 @@
+-- Add up migration script here
+-- Loại tài nguyên được đo theo sự kiện (API calls, tin nhắn...) thay vì đếm bản ghi
+ALTER TABLE resource_types ADD COLUMN is_metered BOOLEAN NOT NULL DEFAULT FALSE;
+
+INSERT INTO resource_types (code, name, description, is_metered)
+VALUES
+    ('api_calls', 'API calls', 'Number of API calls', TRUE),
+    ('messages', 'Messages', 'Number of messages sent', TRUE);
+
+-- event_id do client gửi lên, dùng để chống ghi trùng khi gửi lại
+CREATE TABLE usage_events (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    event_id VARCHAR(255) NOT NULL,
+    user_id UUID NOT NULL,
+    subscription_id UUID NOT NULL,
+    resource_type_id UUID NOT NULL,
+    quantity BIGINT NOT NULL CHECK (quantity > 0),
+    occurred_at TIMESTAMP NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (subscription_id) REFERENCES subscriptions(id) ON DELETE CASCADE,
+    FOREIGN KEY (resource_type_id) REFERENCES resource_types(id) ON DELETE CASCADE,
+    UNIQUE (user_id, event_id)
+);
+
+-- Tổng theo ngày
+CREATE TABLE usage_daily_rollups (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    subscription_id UUID NOT NULL,
+    resource_type_id UUID NOT NULL,
+    day DATE NOT NULL,
+    quantity BIGINT NOT NULL DEFAULT 0,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (subscription_id) REFERENCES subscriptions(id) ON DELETE CASCADE,
+    FOREIGN KEY (resource_type_id) REFERENCES resource_types(id) ON DELETE CASCADE,
+    UNIQUE (subscription_id, resource_type_id, day)
+);
+
+-- Tổng theo chu kỳ thanh toán, dùng để so sánh với giới hạn của Plan
+CREATE TABLE usage_period_rollups (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    subscription_id UUID NOT NULL,
+    resource_type_id UUID NOT NULL,
+    period_start TIMESTAMP NOT NULL,
+    period_end TIMESTAMP,
+    quantity BIGINT NOT NULL DEFAULT 0,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (subscription_id) REFERENCES subscriptions(id) ON DELETE CASCADE,
+    FOREIGN KEY (resource_type_id) REFERENCES resource_types(id) ON DELETE CASCADE,
+    UNIQUE (subscription_id, resource_type_id, period_start)
+);
@@ -0,0 +1,9 @@
+-- Add down migration script here
+DROP TABLE subscription_events;
+
+ALTER TABLE subscriptions DROP COLUMN is_active;
+ALTER TABLE subscriptions ADD COLUMN is_active BOOLEAN DEFAULT TRUE;
+
+UPDATE subscriptions SET is_active = status IN ('trialing', 'active', 'past_due');
+
+ALTER TABLE subscriptions DROP COLUMN status;
@@ -0,0 +1,30 @@
+-- Add up migration script here
+-- Trạng thái của Subscription, chỉ được thay đổi qua SubscriptionService
+ALTER TABLE subscriptions ADD COLUMN status VARCHAR(20) NOT NULL DEFAULT 'incomplete'
+    CHECK (status IN ('incomplete', 'trialing', 'active', 'past_due', 'paused', 'canceled', 'expired'));
+
+UPDATE subscriptions SET status = CASE
+    WHEN is_active AND (end_date IS NULL OR end_date > CURRENT_TIMESTAMP) THEN 'active'
+    WHEN is_active THEN 'expired'
+    ELSE 'incomplete'
+END;
+
+-- is_active không còn được ghi trực tiếp, chỉ được suy ra từ status
+ALTER TABLE subscriptions DROP COLUMN is_active;
+ALTER TABLE subscriptions ADD COLUMN is_active BOOLEAN
+    GENERATED ALWAYS AS (status IN ('trialing', 'active', 'past_due')) STORED;
+
+-- Lịch sử chuyển trạng thái
+CREATE TABLE subscription_events (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    subscription_id UUID NOT NULL,
+    from_status VARCHAR(20),
+    to_status VARCHAR(20) NOT NULL,
+    actor_type VARCHAR(20) NOT NULL,
+    actor_id UUID,
+    reason TEXT,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (subscription_id) REFERENCES subscriptions(id) ON DELETE CASCADE
+);
+
+CREATE INDEX subscription_events_subscription_id_idx ON subscription_events (subscription_id);
@@ -0,0 +1,6 @@
+-- Add down migration script here
+ALTER TABLE plans DROP CONSTRAINT plans_trial_days_check;
+
+UPDATE plans SET trial_days = -1 WHERE is_free_forever;
+
+ALTER TABLE plans DROP COLUMN is_free_forever;
@@ -0,0 +1,14 @@
+-- Add up migration script here
+-- Gói miễn phí vĩnh viễn thay cho trial_days = -1
+-- trial_days = 0 nghĩa là không có thời gian dùng thử
+ALTER TABLE plans ADD COLUMN is_free_forever BOOLEAN NOT NULL DEFAULT FALSE;
+
+UPDATE plans SET is_free_forever = TRUE, trial_days = 0 WHERE trial_days = -1;
+UPDATE plans SET trial_days = 0 WHERE trial_days IS NULL OR trial_days < 0;
+
+ALTER TABLE plans ADD CONSTRAINT plans_trial_days_check CHECK (trial_days >= 0);
+
+-- Subscription của gói miễn phí vĩnh viễn không có ngày kết thúc
+UPDATE subscriptions AS s SET end_date = NULL, status = 'active'
+FROM plans AS p
+WHERE s.plan_id = p.id AND p.is_free_forever AND s.status IN ('active', 'expired');
@@ -0,0 +1,3 @@
+-- Add down migration script here
+DROP TABLE renewal_invoices;
+DROP TABLE job_runs;
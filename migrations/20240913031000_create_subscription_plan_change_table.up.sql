@@ -0,0 +1,24 @@
+-- Add up migration script here
+-- Lịch sử đổi Plan của Subscription
+-- proration_amount > 0 là số tiền phải trả thêm, < 0 là số tiền được hoàn lại
+CREATE TABLE subscription_plan_changes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    subscription_id UUID NOT NULL,
+    from_plan_id UUID NOT NULL,
+    to_plan_id UUID NOT NULL,
+    mode VARCHAR(20) NOT NULL CHECK (mode IN ('immediate', 'end_of_period')),
+    status VARCHAR(20) NOT NULL CHECK (status IN ('scheduled', 'applied', 'canceled')),
+    proration_amount BIGINT NOT NULL DEFAULT 0,
+    effective_at TIMESTAMP NOT NULL,
+    requested_by UUID,
+    reason TEXT,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    applied_at TIMESTAMP,
+    FOREIGN KEY (subscription_id) REFERENCES subscriptions(id) ON DELETE CASCADE,
+    FOREIGN KEY (from_plan_id) REFERENCES plans(id) ON DELETE CASCADE,
+    FOREIGN KEY (to_plan_id) REFERENCES plans(id) ON DELETE CASCADE
+);
+
+-- Mỗi Subscription chỉ có tối đa một lần đổi Plan đang chờ
+CREATE UNIQUE INDEX subscription_plan_changes_scheduled_idx
+    ON subscription_plan_changes (subscription_id) WHERE status = 'scheduled';
@@ -0,0 +1,5 @@
+-- Add down migration script here
+ALTER TABLE subscriptions DROP COLUMN cancellation_feedback;
+ALTER TABLE subscriptions DROP COLUMN cancellation_reason;
+ALTER TABLE subscriptions DROP COLUMN canceled_at;
+ALTER TABLE subscriptions DROP COLUMN cancel_at_period_end;
@@ -0,0 +1,7 @@
+-- Add up migration script here
+-- Huỷ Subscription: huỷ ngay hoặc huỷ khi hết kỳ (cancel_at_period_end)
+ALTER TABLE subscriptions ADD COLUMN cancel_at_period_end BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE subscriptions ADD COLUMN canceled_at TIMESTAMP;
+-- Lý do và góp ý của người dùng khi huỷ
+ALTER TABLE subscriptions ADD COLUMN cancellation_reason VARCHAR(255);
+ALTER TABLE subscriptions ADD COLUMN cancellation_feedback TEXT;
@@ -0,0 +1,4 @@
+-- Add down migration script here
+DROP TABLE subscription_pauses;
+
+ALTER TABLE plans DROP COLUMN max_pauses_per_year;
@@ -0,0 +1,19 @@
+-- Add up migration script here
+-- Số lần được tạm dừng Subscription trong 12 tháng, 0 là không cho tạm dừng
+ALTER TABLE plans ADD COLUMN max_pauses_per_year INT NOT NULL DEFAULT 0 CHECK (max_pauses_per_year >= 0);
+
+-- Các lần tạm dừng Subscription, resume_at là ngày tự động tiếp tục
+CREATE TABLE subscription_pauses (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    subscription_id UUID NOT NULL,
+    paused_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    resume_at TIMESTAMP,
+    resumed_at TIMESTAMP,
+    paused_by UUID,
+    reason TEXT,
+    FOREIGN KEY (subscription_id) REFERENCES subscriptions(id) ON DELETE CASCADE
+);
+
+-- Mỗi Subscription chỉ có một lần tạm dừng chưa kết thúc
+CREATE UNIQUE INDEX subscription_pauses_open_idx
+    ON subscription_pauses (subscription_id) WHERE resumed_at IS NULL;
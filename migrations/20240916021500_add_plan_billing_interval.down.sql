@@ -0,0 +1,8 @@
+-- Add down migration script here
+ALTER TABLE subscription_plan_changes DROP COLUMN to_plan_price_id;
+ALTER TABLE subscriptions DROP COLUMN plan_price_id;
+
+DROP TABLE plan_prices;
+
+ALTER TABLE plans DROP COLUMN billing_interval_count;
+ALTER TABLE plans DROP COLUMN billing_interval;
@@ -0,0 +1,23 @@
+-- Add up migration script here
+-- Chu kỳ thanh toán của Plan: billing_interval_count lần billing_interval
+-- Ví dụ: hàng quý là ('month', 3), 45 ngày là ('day', 45)
+ALTER TABLE plans ADD COLUMN billing_interval VARCHAR(10) NOT NULL DEFAULT 'month'
+    CHECK (billing_interval IN ('day', 'month', 'year'));
+ALTER TABLE plans ADD COLUMN billing_interval_count INT NOT NULL DEFAULT 1 CHECK (billing_interval_count > 0);
+
+-- Các mức giá khác của Plan (ví dụ giá theo năm có giảm giá)
+CREATE TABLE plan_prices (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    plan_id UUID NOT NULL,
+    billing_interval VARCHAR(10) NOT NULL CHECK (billing_interval IN ('day', 'month', 'year')),
+    billing_interval_count INT NOT NULL DEFAULT 1 CHECK (billing_interval_count > 0),
+    price BIGINT NOT NULL CHECK (price >= 0),
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (plan_id) REFERENCES plans(id) ON DELETE CASCADE,
+    UNIQUE (plan_id, billing_interval, billing_interval_count)
+);
+
+-- Mức giá Subscription đã chọn, NULL là giá mặc định của Plan
+ALTER TABLE subscriptions ADD COLUMN plan_price_id UUID REFERENCES plan_prices(id) ON DELETE SET NULL;
+ALTER TABLE subscription_plan_changes ADD COLUMN to_plan_price_id UUID REFERENCES plan_prices(id) ON DELETE SET NULL;
@@ -0,0 +1,2 @@
+-- Add down migration script here
+DROP INDEX subscriptions_current_idx;
@@ -0,0 +1,20 @@
+-- Add up migration script here
+-- Mỗi User giữ lại một Subscription hiện tại (mới nhất), các Subscription khác bị huỷ
+WITH ranked AS (
+    SELECT id, status,
+        ROW_NUMBER() OVER (PARTITION BY user_id ORDER BY is_active DESC, start_date DESC NULLS LAST) AS rank
+    FROM subscriptions
+    WHERE status NOT IN ('canceled', 'expired')
+),
+superseded AS (
+    UPDATE subscriptions AS s SET status = 'canceled'
+    FROM ranked AS r
+    WHERE s.id = r.id AND r.rank > 1
+    RETURNING s.id, r.status
+)
+INSERT INTO subscription_events (subscription_id, from_status, to_status, actor_type, reason)
+SELECT id, status, 'canceled', 'system', 'Superseded by a newer subscription' FROM superseded;
+
+-- Tối đa một Subscription hiện tại cho mỗi User, các Subscription đã huỷ hoặc hết hạn là lịch sử
+CREATE UNIQUE INDEX subscriptions_current_idx
+    ON subscriptions (user_id) WHERE status NOT IN ('canceled', 'expired');
@@ -0,0 +1,48 @@
+-- Add up migration script here
+-- Chỗ ngồi (seat) của User con trên Subscription nhóm của User cha
+CREATE TABLE subscription_seats (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    subscription_id UUID NOT NULL,
+    user_id UUID NOT NULL,
+    assigned_by UUID,
+    assigned_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    released_at TIMESTAMP,
+    FOREIGN KEY (subscription_id) REFERENCES subscriptions(id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+-- Mỗi User chỉ giữ một seat chưa trả lại
+CREATE UNIQUE INDEX subscription_seats_user_idx
+    ON subscription_seats (user_id) WHERE released_at IS NULL;
+CREATE INDEX subscription_seats_subscription_id_idx ON subscription_seats (subscription_id);
+
+-- Huỷ các Subscription đã được sao chép từ User cha: cùng Plan và cùng kỳ hạn
+WITH copied AS (
+    SELECT DISTINCT s.id, s.status
+    FROM subscriptions AS s
+    INNER JOIN user_groups AS g ON g.user_id = s.user_id
+    INNER JOIN subscriptions AS p ON p.user_id = g.parent_id
+    WHERE s.status NOT IN ('canceled', 'expired')
+      AND p.plan_id = s.plan_id
+      AND p.start_date IS NOT DISTINCT FROM s.start_date
+      AND p.end_date IS NOT DISTINCT FROM s.end_date
+),
+canceled AS (
+    UPDATE subscriptions AS s SET status = 'canceled'
+    FROM copied AS c
+    WHERE s.id = c.id
+    RETURNING s.id, c.status
+)
+INSERT INTO subscription_events (subscription_id, from_status, to_status, actor_type, reason)
+SELECT id, status, 'canceled', 'system', 'Converted to a seat on the parent subscription' FROM canceled;
+
+-- Chuyển các User con sang seat trên Subscription hiện tại của User cha
+INSERT INTO subscription_seats (subscription_id, user_id, assigned_by, assigned_at)
+SELECT DISTINCT ON (g.user_id) s.id, g.user_id, g.parent_id, g.created_at
+FROM user_groups AS g
+INNER JOIN subscriptions AS s ON s.user_id = g.parent_id AND s.is_active = TRUE
+WHERE NOT EXISTS (
+    SELECT 1 FROM subscriptions AS c
+    WHERE c.user_id = g.user_id AND c.status NOT IN ('canceled', 'expired')
+)
+ORDER BY g.user_id, g.created_at;
@@ -0,0 +1,6 @@
+-- Add down migration script here
+ALTER TABLE renewal_invoices DROP COLUMN coupon_redemption_id;
+ALTER TABLE renewal_invoices DROP COLUMN discount_amount;
+DROP TABLE coupon_redemptions;
+DROP TABLE coupon_plans;
+DROP TABLE coupons;
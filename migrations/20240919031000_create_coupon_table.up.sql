@@ -0,0 +1,54 @@
+-- Add up migration script here
+-- Mã giảm giá: giảm theo phần trăm hoặc số tiền cố định
+-- duration: once (lần thanh toán đầu), repeating (trong duration_in_months tháng), forever
+CREATE TABLE coupons (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    code VARCHAR(50) NOT NULL UNIQUE,
+    name VARCHAR(255) NOT NULL,
+    percent_off INT CHECK (percent_off BETWEEN 1 AND 100),
+    amount_off BIGINT CHECK (amount_off > 0),
+    duration VARCHAR(20) NOT NULL CHECK (duration IN ('once', 'repeating', 'forever')),
+    duration_in_months INT CHECK (duration_in_months > 0),
+    max_redemptions INT CHECK (max_redemptions > 0),
+    max_redemptions_per_user INT CHECK (max_redemptions_per_user > 0),
+    valid_from TIMESTAMP,
+    valid_until TIMESTAMP,
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    CHECK ((percent_off IS NULL) <> (amount_off IS NULL)),
+    CHECK ((duration = 'repeating') = (duration_in_months IS NOT NULL))
+);
+
+-- Giới hạn mã giảm giá cho một số Plan, không có dòng nào là áp dụng cho mọi Plan
+CREATE TABLE coupon_plans (
+    coupon_id UUID NOT NULL,
+    plan_id UUID NOT NULL,
+    PRIMARY KEY (coupon_id, plan_id),
+    FOREIGN KEY (coupon_id) REFERENCES coupons(id) ON DELETE CASCADE,
+    FOREIGN KEY (plan_id) REFERENCES plans(id) ON DELETE CASCADE
+);
+
+-- Các lần sử dụng mã giảm giá, mỗi Subscription chỉ có một mã đang áp dụng
+CREATE TABLE coupon_redemptions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    coupon_id UUID NOT NULL,
+    subscription_id UUID NOT NULL,
+    user_id UUID NOT NULL,
+    redeemed_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    applies_until TIMESTAMP,
+    times_applied INT NOT NULL DEFAULT 0,
+    total_discount BIGINT NOT NULL DEFAULT 0,
+    ended_at TIMESTAMP,
+    FOREIGN KEY (coupon_id) REFERENCES coupons(id),
+    FOREIGN KEY (subscription_id) REFERENCES subscriptions(id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX coupon_redemptions_coupon_id_idx ON coupon_redemptions (coupon_id, user_id);
+CREATE UNIQUE INDEX coupon_redemptions_subscription_idx
+    ON coupon_redemptions (subscription_id) WHERE ended_at IS NULL;
+
+-- Số tiền được giảm trên hoá đơn gia hạn
+ALTER TABLE renewal_invoices ADD COLUMN discount_amount BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE renewal_invoices ADD COLUMN coupon_redemption_id UUID REFERENCES coupon_redemptions(id);
@@ -0,0 +1,5 @@
+-- Add down migration script here
+DROP TABLE plan_version_migrations;
+ALTER TABLE subscriptions DROP COLUMN plan_version_id;
+ALTER TABLE plans DROP COLUMN current_version_id;
+DROP TABLE plan_versions;
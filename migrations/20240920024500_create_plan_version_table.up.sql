@@ -0,0 +1,52 @@
+-- Add up migration script here
+-- Các phiên bản bất biến của giá và điều khoản Plan, mỗi lần sửa tạo một phiên bản mới
+CREATE TABLE plan_versions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    plan_id UUID NOT NULL,
+    version INT NOT NULL,
+    price BIGINT NOT NULL,
+    trial_days INT NOT NULL DEFAULT 0,
+    is_free_forever BOOLEAN NOT NULL DEFAULT FALSE,
+    max_pauses_per_year INT NOT NULL DEFAULT 0,
+    billing_interval VARCHAR(10) NOT NULL,
+    billing_interval_count INT NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (plan_id) REFERENCES plans(id) ON DELETE CASCADE,
+    UNIQUE (plan_id, version)
+);
+
+INSERT INTO plan_versions (plan_id, version, price, trial_days, is_free_forever, max_pauses_per_year,
+    billing_interval, billing_interval_count)
+SELECT id, 1, price, COALESCE(trial_days, 0), is_free_forever, max_pauses_per_year, billing_interval,
+    billing_interval_count
+FROM plans;
+
+-- Phiên bản dành cho Subscription mới
+ALTER TABLE plans ADD COLUMN current_version_id UUID REFERENCES plan_versions(id);
+UPDATE plans SET current_version_id = v.id FROM plan_versions AS v WHERE v.plan_id = plans.id;
+
+-- Subscription giữ phiên bản đã mua cho đến khi được chuyển sang phiên bản mới
+ALTER TABLE subscriptions ADD COLUMN plan_version_id UUID REFERENCES plan_versions(id);
+UPDATE subscriptions SET plan_version_id = p.current_version_id FROM plans AS p WHERE p.id = subscriptions.plan_id;
+
+-- Chuyển Subscription sang phiên bản mới, effective_at là hết thời gian báo trước
+CREATE TABLE plan_version_migrations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    subscription_id UUID NOT NULL,
+    from_version_id UUID,
+    to_version_id UUID NOT NULL,
+    status VARCHAR(20) NOT NULL CHECK (status IN ('scheduled', 'applied', 'canceled')),
+    effective_at TIMESTAMP NOT NULL,
+    requested_by UUID,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    applied_at TIMESTAMP,
+    FOREIGN KEY (subscription_id) REFERENCES subscriptions(id) ON DELETE CASCADE,
+    FOREIGN KEY (from_version_id) REFERENCES plan_versions(id),
+    FOREIGN KEY (to_version_id) REFERENCES plan_versions(id)
+);
+
+-- Mỗi Subscription chỉ có một lần chuyển phiên bản đang chờ
+CREATE UNIQUE INDEX plan_version_migrations_scheduled_idx
+    ON plan_version_migrations (subscription_id) WHERE status = 'scheduled';
+CREATE INDEX plan_version_migrations_effective_at_idx
+    ON plan_version_migrations (effective_at) WHERE status = 'scheduled';
@@ -0,0 +1,11 @@
+-- Add down migration script here
+ALTER TABLE coupons DROP COLUMN currency;
+ALTER TABLE coupon_redemptions DROP COLUMN currency;
+ALTER TABLE subscription_plan_changes DROP COLUMN currency;
+ALTER TABLE renewal_invoices DROP COLUMN currency;
+ALTER TABLE payments DROP COLUMN currency;
+ALTER TABLE plan_prices DROP CONSTRAINT plan_prices_plan_id_billing_interval_currency_key;
+ALTER TABLE plan_prices DROP COLUMN currency;
+ALTER TABLE plan_prices ADD CONSTRAINT plan_prices_plan_id_billing_interval_billing_interval_count_key
+    UNIQUE (plan_id, billing_interval, billing_interval_count);
+ALTER TABLE plans DROP COLUMN currency;
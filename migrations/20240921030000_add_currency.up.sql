@@ -0,0 +1,29 @@
+-- Add up migration script here
+-- Đơn vị tiền tệ (ISO 4217) của mọi số tiền, số tiền tính theo đơn vị nhỏ nhất
+-- của đơn vị tiền tệ đó (VND không có đơn vị lẻ, USD là cent). Dữ liệu cũ là VND.
+ALTER TABLE plans ADD COLUMN currency VARCHAR(3) NOT NULL DEFAULT 'VND';
+ALTER TABLE plans ALTER COLUMN currency DROP DEFAULT;
+
+-- Bảng giá theo từng đơn vị tiền tệ: mỗi chu kỳ có thể có một giá cho mỗi đơn vị tiền tệ
+ALTER TABLE plan_prices ADD COLUMN currency VARCHAR(3) NOT NULL DEFAULT 'VND';
+ALTER TABLE plan_prices ALTER COLUMN currency DROP DEFAULT;
+ALTER TABLE plan_prices DROP CONSTRAINT plan_prices_plan_id_billing_interval_billing_interval_count_key;
+ALTER TABLE plan_prices ADD CONSTRAINT plan_prices_plan_id_billing_interval_currency_key
+    UNIQUE (plan_id, billing_interval, billing_interval_count, currency);
+
+ALTER TABLE payments ADD COLUMN currency VARCHAR(3) NOT NULL DEFAULT 'VND';
+ALTER TABLE payments ALTER COLUMN currency DROP DEFAULT;
+
+ALTER TABLE renewal_invoices ADD COLUMN currency VARCHAR(3) NOT NULL DEFAULT 'VND';
+ALTER TABLE renewal_invoices ALTER COLUMN currency DROP DEFAULT;
+
+ALTER TABLE subscription_plan_changes ADD COLUMN currency VARCHAR(3) NOT NULL DEFAULT 'VND';
+ALTER TABLE subscription_plan_changes ALTER COLUMN currency DROP DEFAULT;
+
+ALTER TABLE coupon_redemptions ADD COLUMN currency VARCHAR(3) NOT NULL DEFAULT 'VND';
+ALTER TABLE coupon_redemptions ALTER COLUMN currency DROP DEFAULT;
+
+-- Mã giảm số tiền cố định chỉ áp dụng cho giá cùng đơn vị tiền tệ
+ALTER TABLE coupons ADD COLUMN currency VARCHAR(3);
+UPDATE coupons SET currency = 'VND' WHERE amount_off IS NOT NULL;
+ALTER TABLE coupons ADD CONSTRAINT coupons_currency_check CHECK ((amount_off IS NULL) = (currency IS NULL));
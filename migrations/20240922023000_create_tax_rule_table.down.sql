@@ -0,0 +1,20 @@
+-- Add down migration script here
+ALTER TABLE payments DROP COLUMN tax_rule_id;
+ALTER TABLE payments DROP COLUMN tax_region;
+ALTER TABLE payments DROP COLUMN tax_name;
+ALTER TABLE payments DROP COLUMN tax_inclusive;
+ALTER TABLE payments DROP COLUMN tax_rate_bps;
+ALTER TABLE payments DROP COLUMN tax_amount;
+ALTER TABLE payments DROP COLUMN subtotal;
+ALTER TABLE renewal_invoices DROP COLUMN tax_rule_id;
+ALTER TABLE renewal_invoices DROP COLUMN tax_region;
+ALTER TABLE renewal_invoices DROP COLUMN tax_name;
+ALTER TABLE renewal_invoices DROP COLUMN tax_inclusive;
+ALTER TABLE renewal_invoices DROP COLUMN tax_rate_bps;
+ALTER TABLE renewal_invoices DROP COLUMN tax_amount;
+ALTER TABLE renewal_invoices DROP COLUMN subtotal;
+ALTER TABLE users DROP COLUMN tax_id;
+ALTER TABLE users DROP COLUMN billing_region;
+ALTER TABLE users DROP COLUMN billing_address;
+ALTER TABLE users DROP COLUMN billing_name;
+DROP TABLE tax_rules;
@@ -0,0 +1,46 @@
+-- Add up migration script here
+-- Thuế suất theo vùng: mã quốc gia ISO 3166 (VN) hoặc kèm mã vùng (US-CA)
+-- rate_bps tính theo phần vạn (1000 = 10%)
+-- is_inclusive: giá đã gồm thuế (VAT) hay thuế được cộng thêm vào giá (sales tax)
+CREATE TABLE tax_rules (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    region VARCHAR(10) NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    rate_bps INT NOT NULL CHECK (rate_bps BETWEEN 0 AND 10000),
+    is_inclusive BOOLEAN NOT NULL DEFAULT FALSE,
+    effective_from TIMESTAMP NOT NULL,
+    effective_until TIMESTAMP,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    CHECK (effective_until IS NULL OR effective_until > effective_from)
+);
+
+CREATE INDEX tax_rules_region_idx ON tax_rules (region, effective_from);
+
+-- Thông tin xuất hóa đơn của khách hàng, billing_region quyết định thuế suất
+ALTER TABLE users ADD COLUMN billing_name VARCHAR(255);
+ALTER TABLE users ADD COLUMN billing_address TEXT;
+ALTER TABLE users ADD COLUMN billing_region VARCHAR(10);
+ALTER TABLE users ADD COLUMN tax_id VARCHAR(50);
+
+-- Thuế đã tính được lưu lại để hóa đơn cũ không đổi khi thuế suất thay đổi
+-- amount là tổng phải trả, subtotal là số tiền chưa thuế
+ALTER TABLE renewal_invoices ADD COLUMN subtotal BIGINT;
+UPDATE renewal_invoices SET subtotal = amount;
+ALTER TABLE renewal_invoices ALTER COLUMN subtotal SET NOT NULL;
+ALTER TABLE renewal_invoices ADD COLUMN tax_amount BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE renewal_invoices ADD COLUMN tax_rate_bps INT NOT NULL DEFAULT 0;
+ALTER TABLE renewal_invoices ADD COLUMN tax_inclusive BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE renewal_invoices ADD COLUMN tax_name VARCHAR(255);
+ALTER TABLE renewal_invoices ADD COLUMN tax_region VARCHAR(10);
+ALTER TABLE renewal_invoices ADD COLUMN tax_rule_id UUID REFERENCES tax_rules(id) ON DELETE SET NULL;
+
+ALTER TABLE payments ADD COLUMN subtotal BIGINT;
+UPDATE payments SET subtotal = amount;
+ALTER TABLE payments ALTER COLUMN subtotal SET NOT NULL;
+ALTER TABLE payments ADD COLUMN tax_amount BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE payments ADD COLUMN tax_rate_bps INT NOT NULL DEFAULT 0;
+ALTER TABLE payments ADD COLUMN tax_inclusive BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE payments ADD COLUMN tax_name VARCHAR(255);
+ALTER TABLE payments ADD COLUMN tax_region VARCHAR(10);
+ALTER TABLE payments ADD COLUMN tax_rule_id UUID REFERENCES tax_rules(id) ON DELETE SET NULL;
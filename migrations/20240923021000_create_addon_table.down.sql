@@ -0,0 +1,4 @@
+-- Add down migration script here
+ALTER TABLE renewal_invoices DROP COLUMN addons_amount;
+DROP TABLE subscription_addons;
+DROP TABLE addons;
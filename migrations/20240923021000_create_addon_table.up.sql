@@ -0,0 +1,30 @@
+-- Add up migration script here
+-- Gói mua thêm: tăng giới hạn của một loại tài nguyên thêm quantity đơn vị cho mỗi gói
+-- price là giá mỗi gói cho một tháng, được tính theo chu kỳ thanh toán của gói đăng ký
+CREATE TABLE addons (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    code VARCHAR(50) NOT NULL UNIQUE,
+    name VARCHAR(255) NOT NULL,
+    description TEXT,
+    resource_type_id UUID NOT NULL REFERENCES resource_types(id),
+    quantity BIGINT NOT NULL CHECK (quantity > 0),
+    price BIGINT NOT NULL CHECK (price >= 0),
+    currency VARCHAR(3) NOT NULL,
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Số gói mua thêm của mỗi gói đăng ký, được tính tiền cùng với gói đăng ký
+CREATE TABLE subscription_addons (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    subscription_id UUID NOT NULL REFERENCES subscriptions(id) ON DELETE CASCADE,
+    addon_id UUID NOT NULL REFERENCES addons(id),
+    quantity INT NOT NULL CHECK (quantity > 0),
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (subscription_id, addon_id)
+);
+
+-- Tiền gói mua thêm trong hóa đơn gia hạn, đã gồm trong subtotal
+ALTER TABLE renewal_invoices ADD COLUMN addons_amount BIGINT NOT NULL DEFAULT 0;
@@ -0,0 +1,7 @@
+-- Add down migration script here
+DROP INDEX renewal_invoices_pending_idx;
+ALTER TABLE renewal_invoices DROP COLUMN marked_paid_reason;
+ALTER TABLE renewal_invoices DROP COLUMN marked_paid_by;
+ALTER TABLE renewal_invoices DROP COLUMN read_only_at;
+ALTER TABLE renewal_invoices DROP COLUMN last_retry_at;
+ALTER TABLE renewal_invoices DROP COLUMN retries_sent;
@@ -0,0 +1,12 @@
+-- Add up migration script here
+-- Theo dõi quá trình nhắc nợ của hóa đơn gia hạn chưa thanh toán
+-- retries_sent: số lần đã nhắc thanh toán theo lịch nhắc
+-- read_only_at: thời điểm hạn mức chuyển sang chỉ đọc, NULL khi chưa tới giai đoạn này
+ALTER TABLE renewal_invoices ADD COLUMN retries_sent INT NOT NULL DEFAULT 0;
+ALTER TABLE renewal_invoices ADD COLUMN last_retry_at TIMESTAMP;
+ALTER TABLE renewal_invoices ADD COLUMN read_only_at TIMESTAMP;
+-- Ai đã đánh dấu hóa đơn là đã thanh toán thủ công
+ALTER TABLE renewal_invoices ADD COLUMN marked_paid_by UUID;
+ALTER TABLE renewal_invoices ADD COLUMN marked_paid_reason TEXT;
+
+CREATE INDEX renewal_invoices_pending_idx ON renewal_invoices (created_at) WHERE status = 'pending';
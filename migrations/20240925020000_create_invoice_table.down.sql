@@ -0,0 +1,47 @@
+-- Add down migration script here
+ALTER TABLE subscription_plan_changes DROP COLUMN invoice_id;
+ALTER TABLE payments DROP COLUMN invoice_id;
+
+ALTER TABLE invoices ADD COLUMN discount_amount BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE invoices ADD COLUMN addons_amount BIGINT NOT NULL DEFAULT 0;
+UPDATE invoices AS i SET
+    discount_amount = COALESCE((SELECT -SUM(amount) FROM invoice_lines WHERE invoice_id = i.id AND kind = 'discount'), 0),
+    addons_amount = COALESCE((SELECT SUM(amount) FROM invoice_lines WHERE invoice_id = i.id AND kind = 'addon'), 0);
+DROP TABLE invoice_lines;
+
+-- Hóa đơn gia hạn chỉ có một chu kỳ, các hóa đơn khác bị xóa
+DELETE FROM invoices WHERE kind <> 'period' OR status = 'draft';
+DROP INDEX invoices_open_idx;
+DROP INDEX invoices_user_id_idx;
+DROP INDEX invoices_period_idx;
+ALTER TABLE invoices DROP CONSTRAINT invoices_number_check;
+ALTER TABLE invoices DROP COLUMN number;
+DROP TABLE invoice_number_sequences;
+
+ALTER TABLE invoices DROP COLUMN updated_at;
+ALTER TABLE invoices DROP COLUMN void_reason;
+ALTER TABLE invoices DROP COLUMN voided_at;
+ALTER TABLE invoices DROP COLUMN paid_at;
+ALTER TABLE invoices DROP COLUMN due_at;
+ALTER TABLE invoices DROP COLUMN issued_at;
+
+ALTER TABLE invoices DROP CONSTRAINT invoices_status_check;
+UPDATE invoices SET status = 'void' WHERE status = 'uncollectible';
+UPDATE invoices SET status = 'pending' WHERE status = 'open';
+ALTER TABLE invoices ALTER COLUMN status SET DEFAULT 'pending';
+
+ALTER TABLE invoices DROP COLUMN user_id;
+ALTER TABLE invoices ALTER COLUMN period_end SET NOT NULL;
+ALTER TABLE invoices ALTER COLUMN period_start SET NOT NULL;
+ALTER TABLE invoices DROP COLUMN kind;
+
+-- Các hóa đơn chu kỳ bị hủy trùng chu kỳ với hóa đơn mới hơn được bỏ đi
+DELETE FROM invoices AS i WHERE EXISTS (
+    SELECT 1 FROM invoices AS o
+    WHERE o.subscription_id = i.subscription_id AND o.period_start = i.period_start
+      AND (o.created_at, o.id) > (i.created_at, i.id)
+);
+ALTER TABLE invoices ADD CONSTRAINT renewal_invoices_subscription_id_period_start_key
+    UNIQUE (subscription_id, period_start);
+CREATE INDEX renewal_invoices_pending_idx ON invoices (created_at) WHERE status = 'pending';
+ALTER TABLE invoices RENAME TO renewal_invoices;
@@ -0,0 +1,98 @@
+-- Add up migration script here
+-- Hóa đơn: mở rộng hóa đơn gia hạn thành hóa đơn cho mọi khoản phải trả
+-- kind: period (chu kỳ thanh toán kèm phí vượt mức của chu kỳ trước), plan_change (chênh lệch khi đổi Plan),
+-- addon (gói mua thêm giữa chu kỳ)
+-- status: draft (đang lập, chưa có số) -> open -> paid / void / uncollectible (nhắc nợ không thành công)
+ALTER TABLE renewal_invoices RENAME TO invoices;
+ALTER TABLE invoices DROP CONSTRAINT renewal_invoices_subscription_id_period_start_key;
+DROP INDEX renewal_invoices_pending_idx;
+
+ALTER TABLE invoices ADD COLUMN kind VARCHAR(20) NOT NULL DEFAULT 'period'
+    CHECK (kind IN ('period', 'plan_change', 'addon'));
+ALTER TABLE invoices ALTER COLUMN period_start DROP NOT NULL;
+ALTER TABLE invoices ALTER COLUMN period_end DROP NOT NULL;
+
+ALTER TABLE invoices ADD COLUMN user_id UUID REFERENCES users(id) ON DELETE CASCADE;
+UPDATE invoices AS i SET user_id = s.user_id FROM subscriptions AS s WHERE s.id = i.subscription_id;
+ALTER TABLE invoices ALTER COLUMN user_id SET NOT NULL;
+
+UPDATE invoices SET status = 'open' WHERE status = 'pending';
+ALTER TABLE invoices ALTER COLUMN status SET DEFAULT 'draft';
+ALTER TABLE invoices ADD CONSTRAINT invoices_status_check
+    CHECK (status IN ('draft', 'open', 'paid', 'void', 'uncollectible'));
+
+-- amount là tổng phải trả (subtotal cộng thuế), due_at là hạn thanh toán
+ALTER TABLE invoices ADD COLUMN issued_at TIMESTAMP;
+ALTER TABLE invoices ADD COLUMN due_at TIMESTAMP;
+ALTER TABLE invoices ADD COLUMN paid_at TIMESTAMP;
+ALTER TABLE invoices ADD COLUMN voided_at TIMESTAMP;
+ALTER TABLE invoices ADD COLUMN void_reason TEXT;
+ALTER TABLE invoices ADD COLUMN updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP;
+UPDATE invoices SET issued_at = COALESCE(created_at, period_start), due_at = period_start;
+
+-- Số hóa đơn liên tục theo từng năm (INV-2024-000001), chỉ được cấp khi phát hành
+CREATE TABLE invoice_number_sequences (
+    year INT PRIMARY KEY,
+    last_number INT NOT NULL
+);
+
+ALTER TABLE invoices ADD COLUMN number VARCHAR(30) UNIQUE;
+UPDATE invoices AS i SET number = n.number
+FROM (
+    SELECT id, 'INV-' || EXTRACT(YEAR FROM issued_at)::INT || '-'
+        || LPAD((ROW_NUMBER() OVER (PARTITION BY EXTRACT(YEAR FROM issued_at) ORDER BY issued_at, id))::TEXT, 6, '0')
+        AS number
+    FROM invoices
+) AS n
+WHERE n.id = i.id;
+INSERT INTO invoice_number_sequences (year, last_number)
+SELECT EXTRACT(YEAR FROM issued_at)::INT, COUNT(*) FROM invoices GROUP BY 1;
+
+ALTER TABLE invoices ADD CONSTRAINT invoices_number_check CHECK ((status = 'draft') = (number IS NULL));
+
+-- Mỗi chu kỳ của gói đăng ký chỉ có một hóa đơn còn hiệu lực
+CREATE UNIQUE INDEX invoices_period_idx ON invoices (subscription_id, period_start)
+    WHERE kind = 'period' AND status <> 'void';
+CREATE INDEX invoices_user_id_idx ON invoices (user_id, issued_at DESC);
+CREATE INDEX invoices_open_idx ON invoices (issued_at) WHERE status = 'open';
+
+-- Các dòng của hóa đơn, amount = quantity * unit_amount (dòng giảm giá có số tiền âm)
+CREATE TABLE invoice_lines (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    invoice_id UUID NOT NULL REFERENCES invoices(id) ON DELETE CASCADE,
+    kind VARCHAR(20) NOT NULL CHECK (kind IN ('plan', 'addon', 'overage', 'proration', 'discount')),
+    description TEXT NOT NULL,
+    quantity BIGINT NOT NULL,
+    unit_amount BIGINT NOT NULL,
+    amount BIGINT NOT NULL,
+    period_start TIMESTAMP,
+    period_end TIMESTAMP,
+    position INT NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (invoice_id, position)
+);
+
+INSERT INTO invoice_lines (invoice_id, kind, description, quantity, unit_amount, amount, period_start, period_end,
+    position)
+SELECT i.id, 'plan', p.name, 1, i.subtotal + i.discount_amount - i.addons_amount,
+    i.subtotal + i.discount_amount - i.addons_amount, i.period_start, i.period_end, 1
+FROM invoices AS i
+INNER JOIN subscriptions AS s ON s.id = i.subscription_id
+INNER JOIN plans AS p ON p.id = s.plan_id;
+
+INSERT INTO invoice_lines (invoice_id, kind, description, quantity, unit_amount, amount, position)
+SELECT id, 'discount', 'Discount', 1, -discount_amount, -discount_amount, 2
+FROM invoices WHERE discount_amount > 0;
+
+INSERT INTO invoice_lines (invoice_id, kind, description, quantity, unit_amount, amount, period_start, period_end,
+    position)
+SELECT id, 'addon', 'Add-ons', 1, addons_amount, addons_amount, period_start, period_end, 3
+FROM invoices WHERE addons_amount > 0;
+
+ALTER TABLE invoices DROP COLUMN discount_amount;
+ALTER TABLE invoices DROP COLUMN addons_amount;
+
+-- Thanh toán được ghi nhận cho một hóa đơn
+ALTER TABLE payments ADD COLUMN invoice_id UUID REFERENCES invoices(id);
+-- Hóa đơn phần chênh lệch của lần đổi Plan
+ALTER TABLE subscription_plan_changes ADD COLUMN invoice_id UUID REFERENCES invoices(id);
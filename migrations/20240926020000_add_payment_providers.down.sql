@@ -0,0 +1,14 @@
+-- Add down migration script here
+DROP TABLE webhook_events;
+DROP INDEX payments_pending_invoice_idx;
+ALTER TABLE payments DROP CONSTRAINT payments_provider_payment_id_key;
+ALTER TABLE payments DROP COLUMN updated_at;
+ALTER TABLE payments DROP COLUMN created_at;
+ALTER TABLE payments DROP COLUMN refunded_amount;
+ALTER TABLE payments DROP COLUMN failure_reason;
+ALTER TABLE payments DROP COLUMN instructions;
+ALTER TABLE payments DROP COLUMN provider_payment_id;
+ALTER TABLE payments DROP COLUMN provider;
+DELETE FROM payments WHERE status <> 'succeeded' AND status <> 'refunded';
+ALTER TABLE payments ALTER COLUMN payment_date SET DEFAULT CURRENT_TIMESTAMP;
+ALTER TABLE payments DROP COLUMN status;
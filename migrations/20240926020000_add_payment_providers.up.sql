@@ -0,0 +1,32 @@
+-- Add up migration script here
+-- Thanh toán được mở với nhà cung cấp ở trạng thái chờ, webhook của nhà cung cấp cho biết kết quả
+-- payment_date: thời điểm thanh toán thành công, NULL khi chưa thành công
+ALTER TABLE payments ADD COLUMN status VARCHAR(20) NOT NULL DEFAULT 'succeeded'
+    CHECK (status IN ('pending', 'succeeded', 'failed', 'refunded'));
+ALTER TABLE payments ALTER COLUMN status SET DEFAULT 'pending';
+ALTER TABLE payments ALTER COLUMN payment_date DROP DEFAULT;
+ALTER TABLE payments ADD COLUMN provider VARCHAR(30) NOT NULL DEFAULT 'manual';
+ALTER TABLE payments ALTER COLUMN provider DROP DEFAULT;
+-- Mã của thanh toán bên nhà cung cấp, ví dụ nội dung chuyển khoản
+ALTER TABLE payments ADD COLUMN provider_payment_id VARCHAR(255);
+-- Hướng dẫn thanh toán cho khách hàng, ví dụ thông tin chuyển khoản
+ALTER TABLE payments ADD COLUMN instructions TEXT;
+ALTER TABLE payments ADD COLUMN failure_reason TEXT;
+ALTER TABLE payments ADD COLUMN refunded_amount BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE payments ADD COLUMN created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP;
+ALTER TABLE payments ADD COLUMN updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP;
+ALTER TABLE payments ADD CONSTRAINT payments_provider_payment_id_key UNIQUE (provider, provider_payment_id);
+-- Mỗi hóa đơn chỉ có một thanh toán đang chờ
+CREATE UNIQUE INDEX payments_pending_invoice_idx ON payments (invoice_id) WHERE status = 'pending';
+
+-- Sự kiện webhook đã nhận, mỗi sự kiện chỉ được xử lý một lần
+CREATE TABLE webhook_events (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    provider VARCHAR(30) NOT NULL,
+    event_id VARCHAR(255) NOT NULL,
+    event_type VARCHAR(50) NOT NULL,
+    payment_id UUID REFERENCES payments(id) ON DELETE SET NULL,
+    payload TEXT NOT NULL,
+    received_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (provider, event_id)
+);
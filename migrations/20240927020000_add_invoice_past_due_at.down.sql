@@ -0,0 +1,3 @@
+-- Add down migration script here
+DROP INDEX invoices_due_idx;
+ALTER TABLE invoices DROP COLUMN past_due_at;
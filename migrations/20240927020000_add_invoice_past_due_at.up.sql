@@ -0,0 +1,11 @@
+-- Add up migration script here
+-- past_due_at: thời điểm Subscription chuyển sang quá hạn vì hóa đơn gia hạn này
+-- (quá hạn thanh toán hoặc thanh toán thất bại), mốc tính các bước nhắc nợ
+ALTER TABLE invoices ADD COLUMN past_due_at TIMESTAMP;
+
+-- Các hóa đơn đang được nhắc nợ giữ mốc cũ là lúc phát hành
+UPDATE invoices AS i SET past_due_at = i.issued_at
+FROM subscriptions AS s
+WHERE s.id = i.subscription_id AND s.status = 'past_due' AND i.status = 'open' AND i.kind = 'period';
+
+CREATE INDEX invoices_due_idx ON invoices (due_at) WHERE status = 'open' AND past_due_at IS NULL;
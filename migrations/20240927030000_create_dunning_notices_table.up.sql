@@ -0,0 +1,17 @@
+-- Add up migration script here
+-- Email nhắc nợ được ghi cùng giao dịch với bước nhắc nợ, gửi sau khi commit
+-- sent_at: NULL khi chưa gửi được, lần chạy sau sẽ gửi lại
+CREATE TABLE dunning_notices (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    invoice_id UUID NOT NULL,
+    recipient VARCHAR(255) NOT NULL,
+    subject TEXT NOT NULL,
+    body TEXT NOT NULL,
+    attempts INT NOT NULL DEFAULT 0,
+    last_error TEXT,
+    sent_at TIMESTAMP,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (invoice_id) REFERENCES invoices(id) ON DELETE CASCADE
+);
+
+CREATE INDEX dunning_notices_unsent_idx ON dunning_notices (created_at) WHERE sent_at IS NULL;
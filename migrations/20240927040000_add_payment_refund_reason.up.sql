@@ -0,0 +1,4 @@
+-- Add up migration script here
+-- refund_reason: lý do phải hoàn tiền khoản thanh toán đã nhận nhưng không dùng được,
+-- ví dụ hóa đơn đã huỷ hoặc Subscription đã hết hạn, NULL khi không cần hoàn tiền
+ALTER TABLE payments ADD COLUMN refund_reason TEXT;
@@ -0,0 +1,4 @@
+-- Add down migration script here
+DROP INDEX plan_prices_active_key;
+ALTER TABLE plan_prices ADD CONSTRAINT plan_prices_plan_id_billing_interval_currency_key
+    UNIQUE (plan_id, billing_interval, billing_interval_count, currency);
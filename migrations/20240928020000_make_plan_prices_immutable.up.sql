@@ -0,0 +1,6 @@
+-- Add up migration script here
+-- Giá của plan_prices không bao giờ bị sửa: đổi giá là tạo dòng mới và ngừng dòng cũ,
+-- Subscription đã mua vẫn giữ dòng giá cũ. Mỗi chu kỳ và đơn vị tiền tệ chỉ có một giá đang bán.
+ALTER TABLE plan_prices DROP CONSTRAINT plan_prices_plan_id_billing_interval_currency_key;
+CREATE UNIQUE INDEX plan_prices_active_key ON plan_prices (plan_id, billing_interval, billing_interval_count, currency)
+    WHERE is_active;
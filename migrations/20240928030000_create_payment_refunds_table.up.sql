@@ -0,0 +1,17 @@
+-- Add up migration script here
+-- Các lần hoàn tiền của thanh toán. Hoàn tiền được ghi trước khi gọi nhà cung cấp (pending),
+-- webhook payment.refunded của nhà cung cấp xác nhận lần hoàn tiền có cùng provider_refund_id (succeeded)
+-- thay vì cộng thêm một lần hoàn tiền mới. failed: nhà cung cấp từ chối, không tính vào refunded_amount
+CREATE TABLE payment_refunds (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    payment_id UUID NOT NULL,
+    amount BIGINT NOT NULL CHECK (amount > 0),
+    currency VARCHAR(3) NOT NULL,
+    -- Mã của lần hoàn tiền bên nhà cung cấp, NULL khi chưa nhận được
+    provider_refund_id VARCHAR(255),
+    status VARCHAR(20) NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'succeeded', 'failed')),
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    confirmed_at TIMESTAMP,
+    FOREIGN KEY (payment_id) REFERENCES payments(id) ON DELETE CASCADE,
+    UNIQUE (payment_id, provider_refund_id)
+);
@@ -0,0 +1,7 @@
+-- Add down migration script here
+ALTER TABLE invoices DROP CONSTRAINT invoices_buyer_check;
+ALTER TABLE invoices DROP COLUMN buyer_tax_id;
+ALTER TABLE invoices DROP COLUMN buyer_region;
+ALTER TABLE invoices DROP COLUMN buyer_address;
+ALTER TABLE invoices DROP COLUMN buyer_email;
+ALTER TABLE invoices DROP COLUMN buyer_name;
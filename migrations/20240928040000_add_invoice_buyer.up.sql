@@ -0,0 +1,18 @@
+-- Add up migration script here
+-- Thông tin người mua được lưu lại khi phát hành hóa đơn, để hóa đơn đã phát hành (PDF, HTML, hóa đơn điện tử)
+-- không đổi khi khách hàng sửa thông tin xuất hóa đơn. NULL khi hóa đơn còn là bản nháp
+ALTER TABLE invoices ADD COLUMN buyer_name VARCHAR(255);
+ALTER TABLE invoices ADD COLUMN buyer_email VARCHAR(255);
+ALTER TABLE invoices ADD COLUMN buyer_address TEXT;
+ALTER TABLE invoices ADD COLUMN buyer_region VARCHAR(10);
+ALTER TABLE invoices ADD COLUMN buyer_tax_id VARCHAR(50);
+
+-- Hóa đơn đã phát hành lấy thông tin hiện tại của khách hàng
+UPDATE invoices AS i
+SET buyer_name = COALESCE(u.billing_name, u.name), buyer_email = u.email, buyer_address = u.billing_address,
+    buyer_region = u.billing_region, buyer_tax_id = u.tax_id
+FROM users AS u
+WHERE u.id = i.user_id AND i.issued_at IS NOT NULL;
+
+ALTER TABLE invoices ADD CONSTRAINT invoices_buyer_check
+    CHECK (issued_at IS NULL OR (buyer_name IS NOT NULL AND buyer_email IS NOT NULL));
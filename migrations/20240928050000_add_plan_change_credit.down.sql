@@ -0,0 +1,2 @@
+-- Add down migration script here
+ALTER TABLE subscription_plan_changes DROP COLUMN credited_amount;
@@ -0,0 +1,5 @@
+-- Add up migration script here
+-- Số tiền được hoàn lại khi đổi sang gói rẻ hơn (proration_amount < 0) được trừ vào hóa đơn các kỳ sau,
+-- credited_amount là phần đã trừ
+ALTER TABLE subscription_plan_changes ADD COLUMN credited_amount BIGINT NOT NULL DEFAULT 0
+    CHECK (credited_amount >= 0);
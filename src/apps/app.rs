@@ -13,10 +13,17 @@ use tower_http::{
 };
 use tracing::info_span;
 
-use crate::{apps::routes::AppRouter, infra::configs::Config};
+use crate::{
+    apps::routes::AppRouter,
+    domain::models::dunning_model::DunningPolicy,
+    infra::{configs::Config, documents::Seller, payments::PaymentProviders},
+};
 
 pub struct AppState {
     pub pool: sqlx::PgPool,
+    pub dunning: DunningPolicy,
+    pub seller: Seller,
+    pub payment_providers: PaymentProviders,
 }
 
 pub async fn run_app(app_state: Arc<AppState>) {
@@ -0,0 +1,140 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+
+use crate::{
+    app::AppState,
+    domain::dtos::addon_dtos::{
+        CreateAddonRequest, SetSubscriptionAddonRequest, UpdateAddonRequest,
+    },
+    infra::services::{
+        addon_service::{AddonService, AddonServiceImpl},
+        claim_service::Claims,
+    },
+};
+
+pub async fn create_addon(
+    State(state): State<Arc<AppState>>,
+    Json(addon): Json<CreateAddonRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let service = AddonService::new(state.pool.clone());
+
+    match service.create_addon(addon).await {
+        Ok(addon) => Ok((
+            StatusCode::CREATED,
+            Json(serde_json::json!({ "message": "Add-on created", "data": addon })),
+        )),
+        Err((status, e)) => Err((status, Json(serde_json::json!({ "error": e })))),
+    }
+}
+
+/// All add-ons, including the ones no longer sold.
+pub async fn get_addons_for_sys(
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let service = AddonService::new(state.pool.clone());
+
+    match service.get_addons(false).await {
+        Ok(addons) => Ok((StatusCode::OK, Json(addons))),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e })),
+        )),
+    }
+}
+
+pub async fn get_addons(
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let service = AddonService::new(state.pool.clone());
+
+    match service.get_addons(true).await {
+        Ok(addons) => Ok((StatusCode::OK, Json(addons))),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e })),
+        )),
+    }
+}
+
+pub async fn update_addon(
+    Path(id): Path<uuid::Uuid>,
+    State(state): State<Arc<AppState>>,
+    Json(addon): Json<UpdateAddonRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let service = AddonService::new(state.pool.clone());
+
+    match service.update_addon(id, addon).await {
+        Ok(addon) => Ok((
+            StatusCode::OK,
+            Json(serde_json::json!({ "message": "Add-on updated", "data": addon })),
+        )),
+        Err((status, e)) => Err((status, Json(serde_json::json!({ "error": e })))),
+    }
+}
+
+pub async fn deactivate_addon(
+    Path(id): Path<uuid::Uuid>,
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let service = AddonService::new(state.pool.clone());
+
+    match service.deactivate_addon(id).await {
+        Ok(_) => Ok(StatusCode::NO_CONTENT),
+        Err((status, e)) => Err((status, Json(serde_json::json!({ "error": e })))),
+    }
+}
+
+pub async fn get_subscription_addons(
+    claims: Claims,
+    Path(id): Path<uuid::Uuid>,
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let service = AddonService::new(state.pool.clone());
+
+    match service.get_subscription_addons(id, claims.id).await {
+        Ok(addons) => Ok((StatusCode::OK, Json(addons))),
+        Err((status, e)) => Err((status, Json(serde_json::json!({ "error": e })))),
+    }
+}
+
+pub async fn set_subscription_addon(
+    claims: Claims,
+    Path((id, addon_id)): Path<(uuid::Uuid, uuid::Uuid)>,
+    State(state): State<Arc<AppState>>,
+    Json(addon): Json<SetSubscriptionAddonRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let service = AddonService::new(state.pool.clone());
+
+    match service
+        .set_subscription_addon(id, claims.id, addon_id, addon)
+        .await
+    {
+        Ok(addons) => Ok((
+            StatusCode::OK,
+            Json(serde_json::json!({ "message": "Add-on updated", "data": addons })),
+        )),
+        Err((status, e)) => Err((status, Json(serde_json::json!({ "error": e })))),
+    }
+}
+
+pub async fn remove_subscription_addon(
+    claims: Claims,
+    Path((id, addon_id)): Path<(uuid::Uuid, uuid::Uuid)>,
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let service = AddonService::new(state.pool.clone());
+
+    match service
+        .remove_subscription_addon(id, claims.id, addon_id)
+        .await
+    {
+        Ok(_) => Ok(StatusCode::NO_CONTENT),
+        Err((status, e)) => Err((status, Json(serde_json::json!({ "error": e })))),
+    }
+}
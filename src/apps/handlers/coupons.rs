@@ -0,0 +1,98 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+
+use crate::{
+    app::AppState,
+    domain::dtos::coupon_dtos::{CreateCouponRequest, UpdateCouponRequest},
+    infra::services::coupon_service::{CouponService, CouponServiceImpl},
+};
+
+pub async fn create_coupon(
+    State(state): State<Arc<AppState>>,
+    Json(coupon): Json<CreateCouponRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let service = CouponService::new(state.pool.clone());
+
+    match service.create_coupon(coupon).await {
+        Ok(coupon) => Ok((
+            StatusCode::CREATED,
+            Json(serde_json::json!({ "message": "Coupon created", "data": coupon })),
+        )),
+        Err((status, e)) => Err((status, Json(serde_json::json!({ "error": e })))),
+    }
+}
+
+pub async fn get_coupons(
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let service = CouponService::new(state.pool.clone());
+
+    match service.get_coupons().await {
+        Ok(coupons) => Ok((StatusCode::OK, Json(coupons))),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e })),
+        )),
+    }
+}
+
+pub async fn get_coupon(
+    Path(id): Path<uuid::Uuid>,
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let service = CouponService::new(state.pool.clone());
+
+    match service.get_coupon(id).await {
+        Ok(coupon) => Ok((StatusCode::OK, Json(coupon))),
+        Err((status, e)) => Err((status, Json(serde_json::json!({ "error": e })))),
+    }
+}
+
+pub async fn update_coupon(
+    Path(id): Path<uuid::Uuid>,
+    State(state): State<Arc<AppState>>,
+    Json(coupon): Json<UpdateCouponRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let service = CouponService::new(state.pool.clone());
+
+    match service.update_coupon(id, coupon).await {
+        Ok(coupon) => Ok((
+            StatusCode::OK,
+            Json(serde_json::json!({ "message": "Coupon updated", "data": coupon })),
+        )),
+        Err((status, e)) => Err((status, Json(serde_json::json!({ "error": e })))),
+    }
+}
+
+pub async fn delete_coupon(
+    Path(id): Path<uuid::Uuid>,
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let service = CouponService::new(state.pool.clone());
+
+    match service.delete_coupon(id).await {
+        Ok(_) => Ok(StatusCode::NO_CONTENT),
+        Err((status, e)) => Err((status, Json(serde_json::json!({ "error": e })))),
+    }
+}
+
+pub async fn get_coupon_redemptions(
+    Path(id): Path<uuid::Uuid>,
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let service = CouponService::new(state.pool.clone());
+
+    match service.get_redemptions(id).await {
+        Ok(redemptions) => Ok((StatusCode::OK, Json(redemptions))),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e })),
+        )),
+    }
+}
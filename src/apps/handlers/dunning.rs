@@ -0,0 +1,49 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+
+use crate::{
+    app::AppState,
+    domain::dtos::dunning_dtos::{DunningQuery, MarkPaidRequest},
+    infra::services::{
+        claim_service::Claims,
+        dunning_service::{DunningService, DunningServiceImpl},
+    },
+};
+
+pub async fn get_dunning_accounts(
+    Query(query): Query<DunningQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let service = DunningService::new(state.pool.clone());
+
+    match service.get_accounts(&state.dunning, query.stage).await {
+        Ok(accounts) => Ok((StatusCode::OK, Json(accounts))),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e })),
+        )),
+    }
+}
+
+pub async fn mark_paid(
+    claims: Claims,
+    Path(id): Path<uuid::Uuid>,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<MarkPaidRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let service = DunningService::new(state.pool.clone());
+
+    match service.mark_paid(id, claims.id, payload).await {
+        Ok(sub) => Ok((
+            StatusCode::OK,
+            Json(serde_json::json!({ "message": "Renewal invoice marked as paid", "data": sub })),
+        )),
+        Err((status, e)) => Err((status, Json(serde_json::json!({ "error": e })))),
+    }
+}
@@ -0,0 +1,96 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+
+use crate::{
+    app::AppState,
+    domain::dtos::feature_dtos::{CreateFeatureRequest, SetPlanFeaturesRequest},
+    infra::services::feature_service::{FeatureService, FeatureServiceImpl},
+};
+
+pub async fn create_feature(
+    State(state): State<Arc<AppState>>,
+    Json(feature): Json<CreateFeatureRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let service = FeatureService::new(state.pool.clone());
+
+    match service.create_feature(feature).await {
+        Ok(feature) => Ok((StatusCode::CREATED, Json(feature))),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e })),
+        )),
+    }
+}
+
+pub async fn get_features(
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let service = FeatureService::new(state.pool.clone());
+
+    match service.get_features().await {
+        Ok(features) => Ok((StatusCode::OK, Json(features))),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e })),
+        )),
+    }
+}
+
+pub async fn update_feature(
+    Path(id): Path<uuid::Uuid>,
+    State(state): State<Arc<AppState>>,
+    Json(feature): Json<CreateFeatureRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let service = FeatureService::new(state.pool.clone());
+
+    match service.update_feature(id, feature).await {
+        Ok(feature) => Ok((StatusCode::OK, Json(feature))),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e })),
+        )),
+    }
+}
+
+pub async fn delete_feature(
+    Path(id): Path<uuid::Uuid>,
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let service = FeatureService::new(state.pool.clone());
+
+    match service.delete_feature(id).await {
+        Ok(_) => Ok((
+            StatusCode::OK,
+            Json(serde_json::json!({ "message": "Feature deleted" })),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e })),
+        )),
+    }
+}
+
+pub async fn set_plan_features(
+    Path(plan_id): Path<uuid::Uuid>,
+    State(state): State<Arc<AppState>>,
+    Json(features): Json<SetPlanFeaturesRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let service = FeatureService::new(state.pool.clone());
+
+    match service.set_plan_features(plan_id, features).await {
+        Ok(features) => Ok((
+            StatusCode::OK,
+            Json(serde_json::json!({ "message": "Plan features updated", "data": features })),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e })),
+        )),
+    }
+}
@@ -0,0 +1,179 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+
+use crate::{
+    app::AppState,
+    domain::dtos::{
+        invoice_dtos::{InvoiceSearchQuery, VoidInvoiceRequest},
+        pagination_dtos::PaginationQuery,
+    },
+    infra::{
+        documents::{render_html, render_pdf},
+        services::{
+            claim_service::Claims,
+            invoice_service::{InvoiceService, InvoiceServiceImpl},
+        },
+    },
+};
+
+pub async fn get_invoices(
+    claims: Claims,
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let service = InvoiceService::new(state.pool.clone());
+
+    match service.get_invoices(claims.id).await {
+        Ok(invoices) => Ok((StatusCode::OK, Json(invoices))),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e })),
+        )),
+    }
+}
+
+/// `/:id` answers with the invoice as JSON, `/:id.html` and `/:id.pdf` with
+/// its document.
+pub async fn get_invoice(
+    claims: Claims,
+    Path(file): Path<String>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Response, (StatusCode, Json<serde_json::Value>)> {
+    let error = |status: StatusCode, e: String| (status, Json(serde_json::json!({ "error": e })));
+
+    let (id, extension) = match file.split_once('.') {
+        Some((id, extension)) => (id, Some(extension)),
+        None => (file.as_str(), None),
+    };
+    let id: uuid::Uuid = id
+        .parse()
+        .map_err(|_| error(StatusCode::BAD_REQUEST, "Invalid invoice id".to_string()))?;
+
+    let service = InvoiceService::new(state.pool.clone());
+
+    let (content_type, body) = match extension {
+        None => {
+            return match service.get_invoice(id, claims.id).await {
+                Ok(invoice) => Ok((StatusCode::OK, Json(invoice)).into_response()),
+                Err((status, e)) => Err(error(status, e)),
+            }
+        }
+        Some("html") => ("text/html; charset=utf-8", "html"),
+        Some("pdf") => ("application/pdf", "pdf"),
+        Some(_) => {
+            return Err(error(
+                StatusCode::NOT_FOUND,
+                "Invoice not found".to_string(),
+            ))
+        }
+    };
+
+    let document = service
+        .get_document(id, claims.id, state.seller.clone())
+        .await
+        .map_err(|(status, e)| error(status, e))?;
+
+    let rendered = match body {
+        "html" => render_html(&document).map(String::into_bytes),
+        _ => render_pdf(&document),
+    }
+    .map_err(|e| error(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    Ok((
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("inline; filename=\"{}\"", document.file_name(body)),
+            ),
+        ],
+        rendered,
+    )
+        .into_response())
+}
+
+pub async fn search_invoices(
+    Query(query): Query<InvoiceSearchQuery>,
+    Query(pagination): Query<PaginationQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let service = InvoiceService::new(state.pool.clone());
+
+    match service.search_invoices(query, pagination).await {
+        Ok(invoices) => Ok((StatusCode::OK, Json(invoices))),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e })),
+        )),
+    }
+}
+
+pub async fn export_einvoices(
+    Query(query): Query<InvoiceSearchQuery>,
+    Query(pagination): Query<PaginationQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let service = InvoiceService::new(state.pool.clone());
+
+    match service
+        .export_einvoices(query, pagination, state.seller.clone())
+        .await
+    {
+        Ok(exports) => Ok((StatusCode::OK, Json(exports))),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e })),
+        )),
+    }
+}
+
+pub async fn export_einvoice(
+    Path(id): Path<uuid::Uuid>,
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let service = InvoiceService::new(state.pool.clone());
+
+    match service.export_einvoice(id, state.seller.clone()).await {
+        Ok(export) => Ok((
+            StatusCode::OK,
+            [
+                (
+                    header::CONTENT_TYPE,
+                    "application/xml; charset=utf-8".to_string(),
+                ),
+                (
+                    header::CONTENT_DISPOSITION,
+                    format!(
+                        "attachment; filename=\"{}.xml\"",
+                        export.number.unwrap_or_default()
+                    ),
+                ),
+            ],
+            export.xml.unwrap_or_default(),
+        )),
+        Err((status, e)) => Err((status, Json(serde_json::json!({ "error": e })))),
+    }
+}
+
+pub async fn void_invoice(
+    claims: Claims,
+    Path(id): Path<uuid::Uuid>,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<VoidInvoiceRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let service = InvoiceService::new(state.pool.clone());
+
+    match service.void_invoice(id, claims.id, payload).await {
+        Ok(invoice) => Ok((
+            StatusCode::OK,
+            Json(serde_json::json!({ "message": "Invoice voided", "data": invoice })),
+        )),
+        Err((status, e)) => Err((status, Json(serde_json::json!({ "error": e })))),
+    }
+}
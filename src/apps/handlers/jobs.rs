@@ -0,0 +1,42 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+
+use crate::{
+    app::AppState,
+    infra::services::job_service::{JobService, JobServiceImpl},
+};
+
+pub async fn get_jobs(
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let service = JobService::new(state.pool.clone());
+
+    match service.get_latest_runs().await {
+        Ok(runs) => Ok((StatusCode::OK, Json(runs))),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e })),
+        )),
+    }
+}
+
+pub async fn get_job_runs(
+    Path(job): Path<String>,
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let service = JobService::new(state.pool.clone());
+
+    match service.get_runs(&job).await {
+        Ok(runs) => Ok((StatusCode::OK, Json(runs))),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e })),
+        )),
+    }
+}
@@ -1,4 +1,10 @@
+pub mod addons;
+pub mod coupons;
+pub mod dunning;
+pub mod features;
 pub mod health;
+pub mod invoices;
+pub mod jobs;
 pub mod payment;
 pub mod permissions;
 pub mod plans;
@@ -6,4 +12,6 @@ pub mod resources;
 pub mod roles;
 pub mod subscriptions;
 pub mod sys;
+pub mod taxes;
+pub mod usage;
 pub mod users;
@@ -1,24 +1,36 @@
 use std::sync::Arc;
 
-use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
 
 use crate::{
-    app::AppState, domain::dtos::payment_dtos::CreatePaymentRequest,
-    infra::services::payment_service::PaymentService,
+    app::AppState,
+    domain::dtos::payment_dtos::{CreatePaymentRequest, RefundPaymentRequest},
+    infra::services::{claim_service::Claims, payment_service::PaymentService},
 };
 
+/// Header carrying the signature of payment webhooks, see
+/// `infra::payments::sign`.
+const SIGNATURE_HEADER: &str = "webhook-signature";
+
 pub async fn make_payment(
+    claims: Claims,
     State(state): State<Arc<AppState>>,
     Json(payment): Json<CreatePaymentRequest>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
     let payment_service = PaymentService::new(state.pool.clone());
 
-    match payment_service.make_payment(payment).await {
+    match payment_service
+        .make_payment(payment, claims.id, &state.payment_providers)
+        .await
+    {
         Ok(payment) => Ok((StatusCode::CREATED, Json(payment))),
-        Err(e) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(serde_json::json!({ "error": e })),
-        )),
+        Err((status, e)) => Err((status, Json(serde_json::json!({ "error": e })))),
     }
 }
 
@@ -35,3 +47,62 @@ pub async fn get_payments_for_sys(
         )),
     }
 }
+
+pub async fn capture_payment(
+    claims: Claims,
+    Path(id): Path<uuid::Uuid>,
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let payment_service = PaymentService::new(state.pool.clone());
+
+    match payment_service
+        .capture_payment(id, &state.payment_providers, claims.id)
+        .await
+    {
+        Ok(payment) => Ok((StatusCode::OK, Json(payment))),
+        Err((status, e)) => Err((status, Json(serde_json::json!({ "error": e })))),
+    }
+}
+
+pub async fn refund_payment(
+    Path(id): Path<uuid::Uuid>,
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<RefundPaymentRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let payment_service = PaymentService::new(state.pool.clone());
+
+    match payment_service
+        .refund_payment(id, request, &state.payment_providers)
+        .await
+    {
+        Ok(payment) => Ok((StatusCode::OK, Json(payment))),
+        Err((status, e)) => Err((status, Json(serde_json::json!({ "error": e })))),
+    }
+}
+
+pub async fn payment_webhook(
+    Path(provider): Path<String>,
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let payment_service = PaymentService::new(state.pool.clone());
+    let signature = headers
+        .get(SIGNATURE_HEADER)
+        .and_then(|value| value.to_str().ok());
+
+    match payment_service
+        .handle_webhook(&provider, signature, &body, &state.payment_providers)
+        .await
+    {
+        Ok(true) => Ok((
+            StatusCode::OK,
+            Json(serde_json::json!({ "message": "Event processed" })),
+        )),
+        Ok(false) => Ok((
+            StatusCode::OK,
+            Json(serde_json::json!({ "message": "Event already processed" })),
+        )),
+        Err((status, e)) => Err((status, Json(serde_json::json!({ "error": e })))),
+    }
+}
@@ -9,8 +9,14 @@ use axum::{
 
 use crate::{
     app::AppState,
-    domain::dtos::plan_dtos::CreatePlanRequest,
-    infra::services::plan_service::{PlanService, PlanServiceImpl},
+    domain::dtos::plan_dtos::{
+        CreatePlanPriceRequest, CreatePlanRequest, MigratePlanVersionRequest,
+    },
+    infra::services::{
+        claim_service::Claims,
+        plan_service::{PlanService, PlanServiceImpl},
+        plan_version_service::{PlanVersionService, PlanVersionServiceImpl},
+    },
 };
 
 pub async fn get_plan(
@@ -78,3 +84,87 @@ pub async fn update_plan(
         )),
     }
 }
+
+pub async fn get_plan_versions(
+    Path(id): Path<uuid::Uuid>,
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let service = PlanVersionService::new(state.pool.clone());
+
+    match service.get_plan_versions(id).await {
+        Ok(versions) => Ok((StatusCode::OK, Json(versions))),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e })),
+        )),
+    }
+}
+
+pub async fn migrate_plan_version(
+    claims: Claims,
+    Path((id, version_id)): Path<(uuid::Uuid, uuid::Uuid)>,
+    State(state): State<Arc<AppState>>,
+    Json(migration): Json<MigratePlanVersionRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let service = PlanVersionService::new(state.pool.clone());
+
+    match service
+        .migrate_subscribers(id, version_id, migration, claims.id)
+        .await
+    {
+        Ok(migration) => Ok((
+            StatusCode::OK,
+            Json(serde_json::json!({ "message": "Subscribers migrated", "data": migration })),
+        )),
+        Err((status, e)) => Err((status, Json(serde_json::json!({ "error": e })))),
+    }
+}
+
+pub async fn get_plan_prices(
+    Path(id): Path<uuid::Uuid>,
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let service = PlanService::new(state.pool.clone());
+
+    match service.get_plan_prices(id).await {
+        Ok(prices) => Ok((StatusCode::OK, Json(prices))),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e })),
+        )),
+    }
+}
+
+pub async fn create_plan_price(
+    Path(id): Path<uuid::Uuid>,
+    State(state): State<Arc<AppState>>,
+    Json(price): Json<CreatePlanPriceRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let service = PlanService::new(state.pool.clone());
+
+    match service.create_plan_price(id, price).await {
+        Ok(price) => Ok((
+            StatusCode::CREATED,
+            Json(serde_json::json!({ "message": "Plan price created", "data": price })),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e })),
+        )),
+    }
+}
+
+pub async fn deactivate_plan_price(
+    Path(id): Path<uuid::Uuid>,
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let service = PlanService::new(state.pool.clone());
+
+    match service.deactivate_plan_price(id).await {
+        Ok(_) => Ok(StatusCode::NO_CONTENT),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e })),
+        )),
+    }
+}
@@ -9,7 +9,9 @@ use axum::{
 
 use crate::{
     app::AppState,
-    domain::dtos::resource_dtos::CreateResourceRequest,
+    domain::dtos::resource_dtos::{
+        CreateResourceRequest, CreateResourceTypeRequest, SetPlanLimitRequest,
+    },
     infra::services::resource_service::{ResourceService, ResourceServiceImpl},
 };
 
@@ -87,3 +89,82 @@ pub async fn update_resource(
         )),
     }
 }
+
+pub async fn create_resource_type(
+    State(state): State<Arc<AppState>>,
+    Json(resource_type): Json<CreateResourceTypeRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let service = ResourceService::new(state.pool.clone());
+
+    match service.create_resource_type(resource_type).await {
+        Ok(resource_type) => Ok((StatusCode::CREATED, Json(resource_type))),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e })),
+        )),
+    }
+}
+
+pub async fn get_resource_types(
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let service = ResourceService::new(state.pool.clone());
+
+    match service.get_resource_types().await {
+        Ok(resource_types) => Ok((StatusCode::OK, Json(resource_types))),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e })),
+        )),
+    }
+}
+
+pub async fn get_plan_limits(
+    Path(plan_id): Path<uuid::Uuid>,
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let service = ResourceService::new(state.pool.clone());
+
+    match service.get_plan_limits(plan_id).await {
+        Ok(limits) => Ok((StatusCode::OK, Json(limits))),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e })),
+        )),
+    }
+}
+
+pub async fn get_plan_limit(
+    Path((plan_id, resource_type)): Path<(uuid::Uuid, String)>,
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let service = ResourceService::new(state.pool.clone());
+
+    match service.get_plan_limit(plan_id, &resource_type).await {
+        Ok(Some(limit)) => Ok((StatusCode::OK, Json(limit))),
+        Ok(None) => Err((
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "Plan limit not found" })),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e })),
+        )),
+    }
+}
+
+pub async fn set_plan_limit(
+    Path((plan_id, resource_type)): Path<(uuid::Uuid, String)>,
+    State(state): State<Arc<AppState>>,
+    Json(limit): Json<SetPlanLimitRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let service = ResourceService::new(state.pool.clone());
+
+    match service.set_plan_limit(plan_id, &resource_type, limit).await {
+        Ok(limit) => Ok((StatusCode::OK, Json(limit))),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e })),
+        )),
+    }
+}
@@ -9,22 +9,33 @@ use axum::{
 
 use crate::{
     app::AppState,
+    apps::middlewares::require_feature::{CustomRoles, RequireFeature},
     domain::dtos::role_dtos::CreateRoleRequest,
-    infra::services::role_service::{RoleService, RoleServiceImpl},
+    infra::services::{
+        claim_service::Claims,
+        role_service::{RoleService, RoleServiceImpl},
+    },
 };
 
 pub async fn create_role(
+    claims: Claims,
+    _: RequireFeature<CustomRoles>,
     State(state): State<Arc<AppState>>,
     Json(role): Json<CreateRoleRequest>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    // The feature and the quota checked are the caller's.
+    if role.created_by != claims.id {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(serde_json::json!({ "error": "Roles can only be created for yourself" })),
+        ));
+    }
+
     let service = RoleService::new(state.pool.clone());
 
     match service.create_role(role).await {
         Ok(role) => Ok((StatusCode::CREATED, Json(role))),
-        Err(e) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(serde_json::json!({ "error": e })),
-        )),
+        Err((status, e)) => Err((status, Json(serde_json::json!({ "error": e })))),
     }
 }
 
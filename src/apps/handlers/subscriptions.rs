@@ -1,7 +1,7 @@
 use std::sync::Arc;
 
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     response::IntoResponse,
     Json,
@@ -9,60 +9,129 @@ use axum::{
 
 use crate::{
     app::AppState,
-    domain::dtos::subscription_dtos::CreateSubscriptionRequest,
+    domain::{
+        dtos::{
+            pagination_dtos::PaginationQuery,
+            subscription_dtos::{
+                CancelSubscriptionRequest, ChangePlanRequest, CreateSubscriptionRequest,
+                PauseSubscriptionRequest, SubscriptionReasonRequest,
+                UpdateSubscriptionStatusRequest,
+            },
+        },
+        models::subscription_model::Actor,
+    },
     infra::services::{
         claim_service::Claims,
+        plan_change_service::{PlanChangeService, PlanChangeServiceImpl},
         subscription_service::{SubscriptionService, SubscriptionServiceImpl},
     },
 };
 
 pub async fn create_subscription(
+    claims: Claims,
     State(state): State<Arc<AppState>>,
     Json(subscription): Json<CreateSubscriptionRequest>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
     let sub_service = SubscriptionService::new(state.pool.clone());
+    let actor = if claims.is_sys.unwrap_or_default() {
+        Actor::Sys(claims.id)
+    } else {
+        Actor::User(claims.id)
+    };
 
-    match sub_service.create_subscription(subscription).await {
+    match sub_service.create_subscription(subscription, actor).await {
         Ok(sub) => Ok((
             StatusCode::CREATED,
             Json(serde_json::json!({ "message": "Subscription created", "data": sub })),
         )),
-        Err(e) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(serde_json::json!({ "error": e })),
-        )),
+        Err((status, e)) => Err((status, Json(serde_json::json!({ "error": e })))),
     }
 }
 
 pub async fn activate_subscription(
+    claims: Claims,
     Path(id): Path<uuid::Uuid>,
     State(state): State<Arc<AppState>>,
+    payload: Option<Json<SubscriptionReasonRequest>>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
     let sub_service = SubscriptionService::new(state.pool.clone());
+    let reason = payload.and_then(|Json(payload)| payload.reason);
 
-    match sub_service.activate_subscription(id).await {
+    match sub_service
+        .activate_subscription(id, Actor::Sys(claims.id), reason)
+        .await
+    {
         Ok(sub) => Ok((
             StatusCode::CREATED,
             Json(serde_json::json!({ "message": "Subscription activated", "data": sub })),
         )),
-        Err(e) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(serde_json::json!({ "error": e })),
-        )),
+        Err((status, e)) => Err((status, Json(serde_json::json!({ "error": e })))),
     }
 }
 
 pub async fn deactivate_subscription(
+    claims: Claims,
     Path(id): Path<uuid::Uuid>,
     State(state): State<Arc<AppState>>,
+    payload: Option<Json<SubscriptionReasonRequest>>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
     let sub_service = SubscriptionService::new(state.pool.clone());
+    let reason = payload.and_then(|Json(payload)| payload.reason);
 
-    match sub_service.deactivate_subscription(id).await {
+    match sub_service
+        .deactivate_subscription(id, Actor::Sys(claims.id), reason)
+        .await
+    {
         Ok(sub) => Ok((
             StatusCode::CREATED,
             Json(serde_json::json!({ "message": "Subscription deactivated", "data": sub })),
         )),
+        Err((status, e)) => Err((status, Json(serde_json::json!({ "error": e })))),
+    }
+}
+
+pub async fn update_subscription_status(
+    claims: Claims,
+    Path(id): Path<uuid::Uuid>,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<UpdateSubscriptionStatusRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let sub_service = SubscriptionService::new(state.pool.clone());
+
+    match sub_service
+        .update_status(id, payload.status, Actor::Sys(claims.id), payload.reason)
+        .await
+    {
+        Ok(sub) => Ok((StatusCode::OK, Json(serde_json::json!(sub)))),
+        Err((status, e)) => Err((status, Json(serde_json::json!({ "error": e })))),
+    }
+}
+
+pub async fn expire_trials(
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let sub_service = SubscriptionService::new(state.pool.clone());
+
+    match sub_service.expire_trials().await {
+        Ok(expired) => Ok((
+            StatusCode::OK,
+            Json(serde_json::json!({ "expired": expired })),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e })),
+        )),
+    }
+}
+
+pub async fn get_subscription_events(
+    Path(id): Path<uuid::Uuid>,
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let sub_service = SubscriptionService::new(state.pool.clone());
+
+    match sub_service.get_subscription_events(id).await {
+        Ok(events) => Ok((StatusCode::OK, Json(events))),
         Err(e) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(serde_json::json!({ "error": e })),
@@ -99,16 +168,36 @@ pub async fn get_subscription(
     }
 }
 
+pub async fn get_current_subscription(
+    claims: Claims,
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let sub_service = SubscriptionService::new(state.pool.clone());
+
+    match sub_service.get_current_subscription(claims.id).await {
+        Ok(Some(sub)) => Ok((StatusCode::OK, Json(serde_json::json!(sub)))),
+        Ok(None) => Err((
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "No current subscription" })),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e })),
+        )),
+    }
+}
+
 pub async fn get_subscription_by_user(
     claims: Claims,
     State(state): State<Arc<AppState>>,
+    Query(pagination): Query<PaginationQuery>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
     let username = claims.username;
 
     let sub_service = SubscriptionService::new(state.pool.clone());
 
     match sub_service
-        .get_subscriptions_for_by_username(username)
+        .get_subscriptions_for_by_username(username, pagination)
         .await
     {
         Ok(sub) => Ok((StatusCode::OK, Json(serde_json::json!(sub)))),
@@ -118,3 +207,129 @@ pub async fn get_subscription_by_user(
         )),
     }
 }
+
+pub async fn change_plan(
+    claims: Claims,
+    Path(id): Path<uuid::Uuid>,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<ChangePlanRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let plan_change_service = PlanChangeService::new(state.pool.clone());
+
+    match plan_change_service
+        .change_plan(id, claims.id, payload)
+        .await
+    {
+        Ok(plan_change) => Ok((StatusCode::OK, Json(serde_json::json!(plan_change)))),
+        Err((status, e)) => Err((status, Json(serde_json::json!({ "error": e })))),
+    }
+}
+
+pub async fn cancel_subscription(
+    claims: Claims,
+    Path(id): Path<uuid::Uuid>,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<CancelSubscriptionRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let sub_service = SubscriptionService::new(state.pool.clone());
+
+    match sub_service
+        .cancel_subscription(id, claims.id, payload)
+        .await
+    {
+        Ok(sub) => Ok((
+            StatusCode::OK,
+            Json(serde_json::json!({ "message": "Subscription canceled", "data": sub })),
+        )),
+        Err((status, e)) => Err((status, Json(serde_json::json!({ "error": e })))),
+    }
+}
+
+pub async fn reactivate_subscription(
+    claims: Claims,
+    Path(id): Path<uuid::Uuid>,
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let sub_service = SubscriptionService::new(state.pool.clone());
+
+    match sub_service.reactivate_subscription(id, claims.id).await {
+        Ok(sub) => Ok((
+            StatusCode::OK,
+            Json(serde_json::json!({ "message": "Subscription reactivated", "data": sub })),
+        )),
+        Err((status, e)) => Err((status, Json(serde_json::json!({ "error": e })))),
+    }
+}
+
+pub async fn get_seats(
+    claims: Claims,
+    Path(id): Path<uuid::Uuid>,
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let sub_service = SubscriptionService::new(state.pool.clone());
+
+    match sub_service.get_seats(id, claims.id).await {
+        Ok(seats) => Ok((StatusCode::OK, Json(seats))),
+        Err((status, e)) => Err((status, Json(serde_json::json!({ "error": e })))),
+    }
+}
+
+pub async fn release_seat(
+    claims: Claims,
+    Path((id, user_id)): Path<(uuid::Uuid, uuid::Uuid)>,
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let sub_service = SubscriptionService::new(state.pool.clone());
+
+    match sub_service.release_seat(id, user_id, claims.id).await {
+        Ok(_) => Ok(StatusCode::NO_CONTENT),
+        Err((status, e)) => Err((status, Json(serde_json::json!({ "error": e })))),
+    }
+}
+
+pub async fn get_pending_cancellations(
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let sub_service = SubscriptionService::new(state.pool.clone());
+
+    match sub_service.get_pending_cancellations().await {
+        Ok(subs) => Ok((StatusCode::OK, Json(subs))),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e })),
+        )),
+    }
+}
+
+pub async fn pause_subscription(
+    claims: Claims,
+    Path(id): Path<uuid::Uuid>,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<PauseSubscriptionRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let sub_service = SubscriptionService::new(state.pool.clone());
+
+    match sub_service.pause_subscription(id, claims.id, payload).await {
+        Ok(sub) => Ok((
+            StatusCode::OK,
+            Json(serde_json::json!({ "message": "Subscription paused", "data": sub })),
+        )),
+        Err((status, e)) => Err((status, Json(serde_json::json!({ "error": e })))),
+    }
+}
+
+pub async fn resume_subscription(
+    claims: Claims,
+    Path(id): Path<uuid::Uuid>,
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let sub_service = SubscriptionService::new(state.pool.clone());
+
+    match sub_service.resume_subscription(id, claims.id).await {
+        Ok(sub) => Ok((
+            StatusCode::OK,
+            Json(serde_json::json!({ "message": "Subscription resumed", "data": sub })),
+        )),
+        Err((status, e)) => Err((status, Json(serde_json::json!({ "error": e })))),
+    }
+}
@@ -0,0 +1,71 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+
+use crate::{
+    app::AppState,
+    domain::dtos::tax_dtos::CreateTaxRuleRequest,
+    infra::services::tax_service::{TaxService, TaxServiceImpl},
+};
+
+pub async fn create_tax_rule(
+    State(state): State<Arc<AppState>>,
+    Json(rule): Json<CreateTaxRuleRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let service = TaxService::new(state.pool.clone());
+
+    match service.create_tax_rule(rule).await {
+        Ok(rule) => Ok((
+            StatusCode::CREATED,
+            Json(serde_json::json!({ "message": "Tax rule created", "data": rule })),
+        )),
+        Err((status, e)) => Err((status, Json(serde_json::json!({ "error": e })))),
+    }
+}
+
+pub async fn get_tax_rules(
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let service = TaxService::new(state.pool.clone());
+
+    match service.get_tax_rules().await {
+        Ok(rules) => Ok((StatusCode::OK, Json(rules))),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e })),
+        )),
+    }
+}
+
+pub async fn update_tax_rule(
+    Path(id): Path<uuid::Uuid>,
+    State(state): State<Arc<AppState>>,
+    Json(rule): Json<CreateTaxRuleRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let service = TaxService::new(state.pool.clone());
+
+    match service.update_tax_rule(id, rule).await {
+        Ok(rule) => Ok((
+            StatusCode::OK,
+            Json(serde_json::json!({ "message": "Tax rule updated", "data": rule })),
+        )),
+        Err((status, e)) => Err((status, Json(serde_json::json!({ "error": e })))),
+    }
+}
+
+pub async fn delete_tax_rule(
+    Path(id): Path<uuid::Uuid>,
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let service = TaxService::new(state.pool.clone());
+
+    match service.delete_tax_rule(id).await {
+        Ok(_) => Ok(StatusCode::NO_CONTENT),
+        Err((status, e)) => Err((status, Json(serde_json::json!({ "error": e })))),
+    }
+}
@@ -0,0 +1,140 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+
+use crate::{
+    app::AppState,
+    domain::dtos::{
+        quota_dtos::{CreateQuotaOverrideRequest, QuotaOverrideQuery},
+        usage_dtos::{BulkUsageEventRequest, UsageEventRequest, UsageRollupQuery},
+    },
+    infra::services::{
+        claim_service::Claims,
+        quota_service::{QuotaService, QuotaServiceImpl},
+        usage_service::{UsageService, UsageServiceImpl},
+    },
+};
+
+pub async fn get_quotas(
+    claims: Claims,
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let service = QuotaService::new(state.pool.clone());
+
+    match service.get_quotas(claims.id).await {
+        Ok(quotas) => Ok((StatusCode::OK, Json(quotas))),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e })),
+        )),
+    }
+}
+
+pub async fn create_quota_override(
+    claims: Claims,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<CreateQuotaOverrideRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let service = QuotaService::new(state.pool.clone());
+
+    match service.create_override(claims.id, payload).await {
+        Ok(quota_override) => Ok((StatusCode::CREATED, Json(quota_override))),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e })),
+        )),
+    }
+}
+
+pub async fn get_quota_overrides(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<QuotaOverrideQuery>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let service = QuotaService::new(state.pool.clone());
+
+    match service.get_overrides(query.user_id).await {
+        Ok(overrides) => Ok((StatusCode::OK, Json(overrides))),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e })),
+        )),
+    }
+}
+
+pub async fn revoke_quota_override(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<uuid::Uuid>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let service = QuotaService::new(state.pool.clone());
+
+    match service.revoke_override(id).await {
+        Ok(_) => Ok(StatusCode::NO_CONTENT),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e })),
+        )),
+    }
+}
+
+pub async fn record_usage_event(
+    claims: Claims,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<UsageEventRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let service = UsageService::new(state.pool.clone());
+
+    match service.record_events(claims.id, vec![payload]).await {
+        Ok(response) => Ok((StatusCode::OK, Json(response))),
+        Err((status, e)) => Err((status, Json(serde_json::json!({ "error": e })))),
+    }
+}
+
+pub async fn record_usage_events(
+    claims: Claims,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<BulkUsageEventRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let service = UsageService::new(state.pool.clone());
+
+    match service.record_events(claims.id, payload.events).await {
+        Ok(response) => Ok((StatusCode::OK, Json(response))),
+        Err((status, e)) => Err((status, Json(serde_json::json!({ "error": e })))),
+    }
+}
+
+pub async fn get_daily_usage(
+    claims: Claims,
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<UsageRollupQuery>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let service = UsageService::new(state.pool.clone());
+
+    match service.get_daily_usage(claims.id, query).await {
+        Ok(usage) => Ok((StatusCode::OK, Json(usage))),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e })),
+        )),
+    }
+}
+
+pub async fn get_period_usage(
+    claims: Claims,
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<UsageRollupQuery>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let service = UsageService::new(state.pool.clone());
+
+    match service.get_period_usage(claims.id, query).await {
+        Ok(usage) => Ok((StatusCode::OK, Json(usage))),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e })),
+        )),
+    }
+}
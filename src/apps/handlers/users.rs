@@ -10,7 +10,8 @@ use axum::{
 use crate::{
     app::AppState,
     domain::dtos::user_dtos::{
-        ChangePasswordRequest, CreateUserRequest, LoginRequest, UpdateUserRequest,
+        ChangePasswordRequest, CreateUserRequest, LoginRequest, UpdateBillingDetailsRequest,
+        UpdateUserRequest,
     },
     infra::services::{
         claim_service::Claims,
@@ -82,6 +83,40 @@ pub async fn get_current_user(
     }
 }
 
+pub async fn get_billing_details(
+    claims: Claims,
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let user_service = UserService::new(state.pool.clone());
+
+    match user_service.get_billing_details(claims.id).await {
+        Ok(details) => Ok((StatusCode::OK, Json(details))),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e })),
+        )),
+    }
+}
+
+pub async fn update_billing_details(
+    claims: Claims,
+    State(state): State<Arc<AppState>>,
+    Json(details): Json<UpdateBillingDetailsRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let user_service = UserService::new(state.pool.clone());
+
+    match user_service
+        .update_billing_details(claims.id, details)
+        .await
+    {
+        Ok(details) => Ok((
+            StatusCode::OK,
+            Json(serde_json::json!({ "message": "Billing details updated", "data": details })),
+        )),
+        Err((status, e)) => Err((status, Json(serde_json::json!({ "error": e })))),
+    }
+}
+
 pub async fn register(
     State(state): State<Arc<AppState>>,
     Json(user): Json<CreateUserRequest>,
@@ -181,11 +216,19 @@ pub async fn change_password(
 }
 
 pub async fn create_child_user(
-    _: Claims,
+    claims: Claims,
     Path(username): Path<String>,
     State(state): State<Arc<AppState>>,
     Json(user): Json<String>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    // Seats are charged to the parent, only they or sys can add them.
+    if claims.username != username && !claims.is_sys.unwrap_or_default() {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(serde_json::json!({ "error": "Child users can only be added to yourself" })),
+        ));
+    }
+
     let user_service = UserService::new(state.pool.clone());
 
     match user_service.create_child_user(username, user).await {
@@ -193,10 +236,7 @@ pub async fn create_child_user(
             StatusCode::CREATED,
             Json(serde_json::json!({ "message": "User created" })),
         )),
-        Err(e) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(serde_json::json!({ "error": e })),
-        )),
+        Err((status, e)) => Err((status, Json(serde_json::json!({ "error": e })))),
     }
 }
 
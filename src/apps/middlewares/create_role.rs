@@ -1,51 +1,51 @@
 use axum::{extract::Request, http::StatusCode, middleware::Next, response::IntoResponse, Json};
 
+use crate::domain::models::subscription_model::SubscriptionStatus;
+
 use super::get_jwt_decoded;
 
 pub async fn allow_create_role(
     req: Request,
     next: Next,
 ) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
-    let claims = get_jwt_decoded(&req);
-
-    match claims {
-        Ok(claims) => {
-            if claims.is_sys.unwrap() {
-                let subscription = claims.subscription;
-
-                if subscription.is_none() {
-                    return Err((
-                        StatusCode::FORBIDDEN,
-                        Json(serde_json::json!({ "error": "Forbidden" })),
-                    ));
-                }
-
-                let now = chrono::Utc::now();
-                let subscription_end_date = subscription.clone().unwrap().end_date.unwrap();
-                let subscription_trial_end_date =
-                    subscription.clone().unwrap().trial_end_date.unwrap();
-
-                if now.timestamp() > subscription_end_date.and_utc().timestamp()
-                    || now.timestamp() > subscription_trial_end_date.and_utc().timestamp()
-                {
-                    return Err((
-                        StatusCode::FORBIDDEN,
-                        Json(serde_json::json!({ "error": "Forbidden" })),
-                    ));
-                }
-
-                let response = next.run(req).await;
-                Ok(response)
-            } else {
-                Err((
-                    StatusCode::FORBIDDEN,
-                    Json(serde_json::json!({ "error": "Forbidden" })),
-                ))
-            }
-        }
-        Err(e) => Err((
+    let forbidden = || {
+        (
+            StatusCode::FORBIDDEN,
+            Json(serde_json::json!({ "error": "Forbidden" })),
+        )
+    };
+
+    let claims = get_jwt_decoded(&req).map_err(|e| {
+        (
             StatusCode::FORBIDDEN,
             Json(serde_json::json!({ "error": "Forbidden", "message": e.1 })),
-        )),
+        )
+    })?;
+
+    if claims.is_sys.unwrap_or_default() {
+        return Ok(next.run(req).await);
     }
+
+    let subscription = claims.subscription.ok_or_else(forbidden)?;
+
+    if !subscription.is_active {
+        return Err(forbidden());
+    }
+
+    // Free forever subscriptions have no end date, trials end at trial_end_date.
+    // An active subscription past its end date is waiting for its renewal,
+    // unless it is set to cancel at the period end.
+    let now = chrono::Utc::now().naive_utc();
+    let trial_ended = subscription.status == SubscriptionStatus::Trialing
+        && subscription
+            .trial_end_date
+            .is_some_and(|trial_end_date| now > trial_end_date);
+    let period_ended = subscription.end_date.is_some_and(|end_date| now > end_date)
+        && (subscription.cancel_at_period_end || subscription.status != SubscriptionStatus::Active);
+
+    if trial_ended || period_ended {
+        return Err(forbidden());
+    }
+
+    Ok(next.run(req).await)
 }
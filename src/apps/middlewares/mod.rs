@@ -1,5 +1,6 @@
 pub mod auth;
 pub mod create_role;
+pub mod require_feature;
 pub mod sys;
 
 use axum::{extract::Request, http::StatusCode};
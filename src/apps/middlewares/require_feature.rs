@@ -0,0 +1,65 @@
+use std::{marker::PhantomData, sync::Arc};
+
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{request::Parts, StatusCode},
+    Json,
+};
+
+use crate::{
+    apps::app::AppState,
+    domain::models::feature_model,
+    infra::services::{
+        claim_service::Claims,
+        feature_service::{FeatureService, FeatureServiceImpl},
+    },
+};
+
+pub trait Feature {
+    const CODE: &'static str;
+}
+
+pub struct CustomRoles;
+
+impl Feature for CustomRoles {
+    const CODE: &'static str = feature_model::CUSTOM_ROLES;
+}
+
+/// Rejects the request unless the caller's active subscription plan has feature `F`.
+///
+/// Use it as a handler argument, e.g. `_: RequireFeature<CustomRoles>`.
+pub struct RequireFeature<F: Feature>(PhantomData<F>);
+
+#[async_trait]
+impl<F> FromRequestParts<Arc<AppState>> for RequireFeature<F>
+where
+    F: Feature,
+{
+    type Rejection = (StatusCode, Json<serde_json::Value>);
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        let claims = Claims::from_request_parts(parts, state).await?;
+
+        let feature_service = FeatureService::new(state.pool.clone());
+
+        match feature_service.has_feature(claims.id, F::CODE).await {
+            Ok(true) => Ok(Self(PhantomData)),
+            Ok(false) => Err((
+                StatusCode::FORBIDDEN,
+                Json(serde_json::json!({
+                    "error": "Forbidden",
+                    "message": format!("Your plan does not include the {} feature", F::CODE),
+                    "status": 403
+                })),
+            )),
+            Err(e) => Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": e })),
+            )),
+        }
+    }
+}
@@ -0,0 +1,9 @@
+use std::sync::Arc;
+
+use axum::{routing::get, Router};
+
+use crate::{apps::app::AppState, apps::handlers::addons::get_addons};
+
+pub fn addon_routes() -> Router<Arc<AppState>> {
+    Router::new().route("/", get(get_addons))
+}
@@ -0,0 +1,16 @@
+use std::sync::Arc;
+
+use axum::{middleware, routing::get, Router};
+
+use crate::{
+    apps::app::AppState,
+    apps::handlers::invoices::{get_invoice, get_invoices},
+    apps::middlewares::auth::auth_middleware,
+};
+
+pub fn invoice_routes() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", get(get_invoices))
+        .route("/:id", get(get_invoice))
+        .layer(middleware::from_fn(auth_middleware))
+}
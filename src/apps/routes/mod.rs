@@ -1,4 +1,6 @@
+pub mod addons;
 pub mod auth;
+pub mod invoices;
 pub mod payments;
 pub mod permissions;
 pub mod plans;
@@ -6,7 +8,9 @@ pub mod resources;
 pub mod roles;
 pub mod subscriptions;
 pub mod sys;
+pub mod usage;
 pub mod users;
+pub mod webhooks;
 
 use std::sync::Arc;
 
@@ -19,9 +23,10 @@ use crate::{
     apps::app::AppState,
     apps::handlers::{health::health, sys::sys_login},
     apps::routes::{
-        auth::auth_routes, payments::payment_routes, permissions::permission_routes,
-        plans::plan_routes, resources::resource_routes, roles::role_routes,
-        subscriptions::subscription_routes, sys::sys_routes, users::user_routes,
+        addons::addon_routes, auth::auth_routes, invoices::invoice_routes,
+        payments::payment_routes, permissions::permission_routes, plans::plan_routes,
+        resources::resource_routes, roles::role_routes, subscriptions::subscription_routes,
+        sys::sys_routes, usage::usage_routes, users::user_routes, webhooks::webhook_routes,
     },
 };
 
@@ -46,9 +51,13 @@ impl AppRouter {
             .nest("/permissions", permission_routes())
             .nest("/users", user_routes())
             .nest("/plans", plan_routes())
+            .nest("/addons", addon_routes())
             .nest("/resources", resource_routes())
             .nest("/subscriptions", subscription_routes())
-            .nest("/payments", payment_routes());
+            .nest("/payments", payment_routes())
+            .nest("/invoices", invoice_routes())
+            .nest("/usage", usage_routes())
+            .nest("/webhooks", webhook_routes());
 
         Router::new()
             .route("/health", get(health))
@@ -4,11 +4,12 @@ use axum::{routing::get, Router};
 
 use crate::{
     apps::app::AppState,
-    apps::handlers::plans::{get_plan, get_plans},
+    apps::handlers::plans::{get_plan, get_plan_prices, get_plans},
 };
 
 pub fn plan_routes() -> Router<Arc<AppState>> {
     Router::new()
         .route("/", get(get_plans))
         .route("/:id", get(get_plan))
+        .route("/:id/prices", get(get_plan_prices))
 }
@@ -4,12 +4,18 @@ use axum::{routing::get, Router};
 
 use crate::{
     apps::app::AppState,
-    apps::handlers::resources::{get_resource, get_resources, get_resources_by_plan},
+    apps::handlers::resources::{
+        get_plan_limit, get_plan_limits, get_resource, get_resource_types, get_resources,
+        get_resources_by_plan,
+    },
 };
 
 pub fn resource_routes() -> Router<Arc<AppState>> {
     Router::new()
         .route("/", get(get_resources))
         .route("/:id", get(get_resource))
+        .route("/types", get(get_resource_types))
         .route("/plan/:plan_id", get(get_resources_by_plan))
+        .route("/plan/:plan_id/limits", get(get_plan_limits))
+        .route("/plan/:plan_id/limits/:resource_type", get(get_plan_limit))
 }
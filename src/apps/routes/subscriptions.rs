@@ -2,14 +2,19 @@ use std::sync::Arc;
 
 use axum::{
     middleware,
-    routing::{get, post},
+    routing::{delete, get, post, put},
     Router,
 };
 
 use crate::{
     apps::app::AppState,
+    apps::handlers::addons::{
+        get_subscription_addons, remove_subscription_addon, set_subscription_addon,
+    },
     apps::handlers::subscriptions::{
-        create_subscription, get_subscription, get_subscription_by_user,
+        cancel_subscription, change_plan, create_subscription, get_current_subscription, get_seats,
+        get_subscription, get_subscription_by_user, pause_subscription, reactivate_subscription,
+        release_seat, resume_subscription,
     },
     apps::middlewares::auth::auth_middleware,
 };
@@ -17,7 +22,20 @@ use crate::{
 pub fn subscription_routes() -> Router<Arc<AppState>> {
     Router::new()
         .route("/", post(create_subscription))
+        .route("/current", get(get_current_subscription))
         .route("/:id", get(get_subscription))
+        .route("/:id/change-plan", post(change_plan))
+        .route("/:id/cancel", post(cancel_subscription))
+        .route("/:id/reactivate", post(reactivate_subscription))
+        .route("/:id/pause", post(pause_subscription))
+        .route("/:id/resume", post(resume_subscription))
+        .route("/:id/seats", get(get_seats))
+        .route("/:id/addons", get(get_subscription_addons))
+        .route(
+            "/:id/addons/:addon_id",
+            put(set_subscription_addon).delete(remove_subscription_addon),
+        )
+        .route("/:id/seats/:user_id", delete(release_seat))
         .route("/user/:username", get(get_subscription_by_user))
         .layer(middleware::from_fn(auth_middleware))
 }
@@ -2,18 +2,38 @@ use std::sync::Arc;
 
 use axum::{
     middleware,
-    routing::{get, patch, post, put},
+    routing::{delete, get, patch, post, put},
     Router,
 };
 
 use crate::{
     apps::app::AppState,
     apps::handlers::{
-        payment::get_payments_for_sys,
-        plans::{create_plan, update_plan},
-        resources::{create_resource, update_resource},
-        subscriptions::{activate_subscription, deactivate_subscription, get_subscriptions},
+        addons::{create_addon, deactivate_addon, get_addons_for_sys, update_addon},
+        coupons::{
+            create_coupon, delete_coupon, get_coupon, get_coupon_redemptions, get_coupons,
+            update_coupon,
+        },
+        dunning::{get_dunning_accounts, mark_paid},
+        features::{
+            create_feature, delete_feature, get_features, set_plan_features, update_feature,
+        },
+        invoices::{export_einvoice, export_einvoices, search_invoices, void_invoice},
+        jobs::{get_job_runs, get_jobs},
+        payment::{capture_payment, get_payments_for_sys, refund_payment},
+        plans::{
+            create_plan, create_plan_price, deactivate_plan_price, get_plan_versions,
+            migrate_plan_version, update_plan,
+        },
+        resources::{create_resource, create_resource_type, set_plan_limit, update_resource},
+        subscriptions::{
+            activate_subscription, deactivate_subscription, expire_trials,
+            get_pending_cancellations, get_subscription_events, get_subscriptions,
+            update_subscription_status,
+        },
         sys::get_sys,
+        taxes::{create_tax_rule, delete_tax_rule, get_tax_rules, update_tax_rule},
+        usage::{create_quota_override, get_quota_overrides, revoke_quota_override},
         users::{create_user, get_users},
     },
     apps::middlewares::sys::sys_middleware,
@@ -23,15 +43,66 @@ pub fn sys_routes() -> Router<Arc<AppState>> {
     Router::new()
         .route("/users", post(create_user).get(get_users))
         .route("/me", get(get_sys))
-        .route("/plans", post(create_plan).put(update_plan))
+        .route("/plans", post(create_plan))
+        .route("/plans/:id", put(update_plan))
         .route("/payments", get(get_payments_for_sys))
+        .route("/payments/:id/capture", post(capture_payment))
+        .route("/payments/:id/refund", post(refund_payment))
+        .route("/invoices", get(search_invoices))
+        .route("/invoices/:id/void", post(void_invoice))
+        .route("/invoices/einvoices", get(export_einvoices))
+        .route("/invoices/:id/einvoice", get(export_einvoice))
         .route("/subscriptions", get(get_subscriptions))
+        .route("/subscriptions/expire-trials", post(expire_trials))
+        .route(
+            "/subscriptions/pending-cancellations",
+            get(get_pending_cancellations),
+        )
         .route("/subscriptions/:id", patch(activate_subscription))
         .route(
             "/subscriptions/:id/deactivate",
             patch(deactivate_subscription),
         )
+        .route(
+            "/subscriptions/:id/status",
+            patch(update_subscription_status),
+        )
+        .route("/subscriptions/:id/events", get(get_subscription_events))
+        .route("/subscriptions/:id/mark-paid", post(mark_paid))
+        .route("/dunning", get(get_dunning_accounts))
         .route("/resources", post(create_resource))
         .route("/resources/:id", put(update_resource))
+        .route("/resource-types", post(create_resource_type))
+        .route("/plans/:id/limits/:resource_type", put(set_plan_limit))
+        .route("/plans/:id/features", put(set_plan_features))
+        .route("/plans/:id/prices", post(create_plan_price))
+        .route("/plans/:id/versions", get(get_plan_versions))
+        .route(
+            "/plans/:id/versions/:version_id/migrate",
+            post(migrate_plan_version),
+        )
+        .route("/plan-prices/:id", delete(deactivate_plan_price))
+        .route("/features", post(create_feature).get(get_features))
+        .route("/features/:id", put(update_feature).delete(delete_feature))
+        .route("/coupons", post(create_coupon).get(get_coupons))
+        .route(
+            "/coupons/:id",
+            get(get_coupon).put(update_coupon).delete(delete_coupon),
+        )
+        .route("/coupons/:id/redemptions", get(get_coupon_redemptions))
+        .route("/addons", post(create_addon).get(get_addons_for_sys))
+        .route("/addons/:id", put(update_addon).delete(deactivate_addon))
+        .route("/tax-rules", post(create_tax_rule).get(get_tax_rules))
+        .route(
+            "/tax-rules/:id",
+            put(update_tax_rule).delete(delete_tax_rule),
+        )
+        .route(
+            "/quota-overrides",
+            post(create_quota_override).get(get_quota_overrides),
+        )
+        .route("/quota-overrides/:id", delete(revoke_quota_override))
+        .route("/jobs", get(get_jobs))
+        .route("/jobs/:job/runs", get(get_job_runs))
         .layer(middleware::from_fn(sys_middleware))
 }
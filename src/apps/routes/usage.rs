@@ -0,0 +1,25 @@
+use std::sync::Arc;
+
+use axum::{
+    middleware,
+    routing::{get, post},
+    Router,
+};
+
+use crate::{
+    apps::app::AppState,
+    apps::handlers::usage::{
+        get_daily_usage, get_period_usage, get_quotas, record_usage_event, record_usage_events,
+    },
+    apps::middlewares::auth::auth_middleware,
+};
+
+pub fn usage_routes() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/quotas", get(get_quotas))
+        .route("/events", post(record_usage_event))
+        .route("/events/bulk", post(record_usage_events))
+        .route("/daily", get(get_daily_usage))
+        .route("/periods", get(get_period_usage))
+        .layer(middleware::from_fn(auth_middleware))
+}
@@ -9,8 +9,8 @@ use axum::{
 use crate::{
     apps::app::AppState,
     apps::handlers::users::{
-        change_password, create_child_user, get_current_user, get_user, get_user_groups,
-        get_user_groups_by_child_id, update_user,
+        change_password, create_child_user, get_billing_details, get_current_user, get_user,
+        get_user_groups, get_user_groups_by_child_id, update_billing_details, update_user,
     },
     apps::middlewares::auth::auth_middleware,
 };
@@ -18,6 +18,10 @@ use crate::{
 pub fn user_routes() -> Router<Arc<AppState>> {
     Router::new()
         .route("/profile/me", get(get_current_user))
+        .route(
+            "/billing/me",
+            get(get_billing_details).put(update_billing_details),
+        )
         .route("/change-password", post(change_password))
         .route("/:username/group", get(get_user_groups))
         .route(
@@ -0,0 +1,11 @@
+use std::sync::Arc;
+
+use axum::{routing::post, Router};
+
+use crate::{apps::app::AppState, apps::handlers::payment::payment_webhook};
+
+/// Called by payment providers, which are authenticated by the signature of
+/// the events instead of a token.
+pub fn webhook_routes() -> Router<Arc<AppState>> {
+    Router::new().route("/payments/:provider", post(payment_webhook))
+}
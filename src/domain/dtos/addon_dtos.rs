@@ -0,0 +1,61 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::domain::models::money_model::Money;
+
+#[derive(Deserialize)]
+pub struct CreateAddonRequest {
+    pub code: String,
+    pub name: String,
+    pub description: Option<String>,
+    /// Code of the resource type whose limit each pack raises.
+    pub resource_type: String,
+    /// Units added to the limit per pack, e.g. 5 for "+5 users".
+    pub quantity: i64,
+    /// Monthly price of one pack, charged for each billing period of the
+    /// subscription it is added to.
+    pub price: Money,
+}
+
+/// The resource type and quantity of a pack can't change once customers may
+/// have bought it. A new price applies from their next renewal.
+#[derive(Deserialize)]
+pub struct UpdateAddonRequest {
+    pub name: String,
+    pub description: Option<String>,
+    pub price: Money,
+    pub is_active: bool,
+}
+
+#[derive(Serialize)]
+pub struct AddonResponse {
+    pub id: uuid::Uuid,
+    pub code: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub resource_type: String,
+    pub quantity: i64,
+    pub price: Money,
+    pub is_active: bool,
+    pub created_at: Option<NaiveDateTime>,
+}
+
+#[derive(Deserialize)]
+pub struct SetSubscriptionAddonRequest {
+    /// Number of packs on the subscription.
+    pub quantity: i32,
+}
+
+#[derive(Serialize)]
+pub struct SubscriptionAddonResponse {
+    pub addon_id: uuid::Uuid,
+    pub code: String,
+    pub name: String,
+    pub resource_type: String,
+    pub quantity: i32,
+    /// Units added to the limit by all packs.
+    pub units: i64,
+    /// Charged with every renewal of the subscription.
+    pub period_price: Money,
+    pub updated_at: Option<NaiveDateTime>,
+}
@@ -0,0 +1,73 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::domain::models::{coupon_model::CouponDuration, money_model::Money};
+
+#[derive(Deserialize)]
+pub struct CreateCouponRequest {
+    /// Promotion code entered by customers, stored in upper case.
+    pub code: String,
+    pub name: String,
+    /// Exactly one of `percent_off` and `amount_off` is set. An amount off
+    /// only applies to prices in its currency.
+    pub percent_off: Option<i32>,
+    pub amount_off: Option<Money>,
+    pub duration: CouponDuration,
+    /// Required for `repeating` coupons only.
+    pub duration_in_months: Option<i32>,
+    /// `None` means unlimited.
+    pub max_redemptions: Option<i32>,
+    pub max_redemptions_per_user: Option<i32>,
+    pub valid_from: Option<NaiveDateTime>,
+    pub valid_until: Option<NaiveDateTime>,
+    /// Plans the coupon is restricted to, all plans when empty.
+    #[serde(default)]
+    pub plan_ids: Vec<uuid::Uuid>,
+}
+
+/// The discount itself can't change once customers may have redeemed it.
+#[derive(Deserialize)]
+pub struct UpdateCouponRequest {
+    pub name: String,
+    pub max_redemptions: Option<i32>,
+    pub max_redemptions_per_user: Option<i32>,
+    pub valid_from: Option<NaiveDateTime>,
+    pub valid_until: Option<NaiveDateTime>,
+    pub is_active: bool,
+    #[serde(default)]
+    pub plan_ids: Vec<uuid::Uuid>,
+}
+
+#[derive(Serialize)]
+pub struct CouponResponse {
+    pub id: uuid::Uuid,
+    pub code: String,
+    pub name: String,
+    pub percent_off: Option<i32>,
+    pub amount_off: Option<Money>,
+    pub duration: String,
+    pub duration_in_months: Option<i32>,
+    pub max_redemptions: Option<i32>,
+    pub max_redemptions_per_user: Option<i32>,
+    pub valid_from: Option<NaiveDateTime>,
+    pub valid_until: Option<NaiveDateTime>,
+    pub is_active: bool,
+    pub plan_ids: Vec<uuid::Uuid>,
+    pub times_redeemed: i64,
+    pub created_at: Option<NaiveDateTime>,
+}
+
+#[derive(Serialize)]
+pub struct CouponRedemptionResponse {
+    pub id: uuid::Uuid,
+    pub coupon_id: uuid::Uuid,
+    pub subscription_id: uuid::Uuid,
+    pub user_id: uuid::Uuid,
+    pub username: String,
+    pub redeemed_at: NaiveDateTime,
+    /// Charges for periods starting after this date are no longer discounted.
+    pub applies_until: Option<NaiveDateTime>,
+    pub times_applied: i32,
+    pub total_discount: Money,
+    pub ended_at: Option<NaiveDateTime>,
+}
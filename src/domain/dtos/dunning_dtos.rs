@@ -0,0 +1,38 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::domain::models::{dunning_model::DunningStage, money_model::Money};
+
+#[derive(Deserialize)]
+pub struct DunningQuery {
+    pub stage: Option<DunningStage>,
+}
+
+/// An account with an unpaid renewal invoice.
+#[derive(Serialize)]
+pub struct DunningAccountResponse {
+    pub subscription_id: uuid::Uuid,
+    pub user_id: uuid::Uuid,
+    pub username: String,
+    pub email: String,
+    pub invoice_id: uuid::Uuid,
+    pub invoice_number: String,
+    pub amount: Money,
+    pub issued_at: NaiveDateTime,
+    /// When the invoice became overdue or its payment failed, the dunning
+    /// steps are counted from here.
+    pub past_due_at: NaiveDateTime,
+    pub stage: DunningStage,
+    pub retries_sent: i32,
+    pub last_retry_at: Option<NaiveDateTime>,
+    /// When quotas became, or will become, read-only.
+    pub read_only_at: NaiveDateTime,
+    pub cancels_at: NaiveDateTime,
+}
+
+/// Settles the renewal invoice without a payment, e.g. after a bank
+/// transfer was confirmed outside the system.
+#[derive(Deserialize)]
+pub struct MarkPaidRequest {
+    pub reason: String,
+}
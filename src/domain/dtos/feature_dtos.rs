@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+pub struct CreateFeatureRequest {
+    pub code: String,
+    pub name: String,
+    pub description: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct SetPlanFeaturesRequest {
+    pub features: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct FeatureResponse {
+    pub id: uuid::Uuid,
+    pub code: String,
+    pub name: String,
+    pub description: String,
+    pub created_at: Option<chrono::NaiveDateTime>,
+}
@@ -0,0 +1,75 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::domain::models::{
+    invoice_model::{InvoiceKind, InvoiceLineKind, InvoiceStatus},
+    money_model::Money,
+};
+
+/// Filters of the sys invoice search, all optional.
+#[derive(Deserialize)]
+pub struct InvoiceSearchQuery {
+    pub status: Option<InvoiceStatus>,
+    pub kind: Option<InvoiceKind>,
+    pub user_id: Option<uuid::Uuid>,
+    pub subscription_id: Option<uuid::Uuid>,
+    /// Matches the start of the number, e.g. `INV-2024`.
+    pub number: Option<String>,
+    /// Issued at or after.
+    pub from: Option<NaiveDateTime>,
+    /// Issued before.
+    pub to: Option<NaiveDateTime>,
+}
+
+#[derive(Deserialize)]
+pub struct VoidInvoiceRequest {
+    pub reason: String,
+}
+
+/// An invoice exported as an e-invoice, or why it can't be.
+#[derive(Serialize)]
+pub struct EInvoiceExportResponse {
+    pub invoice_id: uuid::Uuid,
+    pub number: Option<String>,
+    pub xml: Option<String>,
+    pub errors: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct InvoiceLineResponse {
+    pub kind: InvoiceLineKind,
+    pub description: String,
+    pub quantity: i64,
+    pub unit_amount: Money,
+    pub amount: Money,
+    pub period_start: Option<NaiveDateTime>,
+    pub period_end: Option<NaiveDateTime>,
+}
+
+#[derive(Serialize)]
+pub struct InvoiceResponse {
+    pub id: uuid::Uuid,
+    /// Assigned when the invoice is issued.
+    pub number: Option<String>,
+    pub kind: InvoiceKind,
+    pub status: InvoiceStatus,
+    pub subscription_id: uuid::Uuid,
+    pub user_id: uuid::Uuid,
+    pub period_start: Option<NaiveDateTime>,
+    pub period_end: Option<NaiveDateTime>,
+    /// Sum of the lines.
+    pub subtotal: Money,
+    pub tax_amount: Money,
+    pub tax_rate_bps: i32,
+    pub tax_name: Option<String>,
+    /// Whether the lines already include the tax, as VAT does.
+    pub tax_inclusive: bool,
+    /// The amount to pay, `subtotal` plus exclusive tax.
+    pub total: Money,
+    pub issued_at: Option<NaiveDateTime>,
+    pub due_at: Option<NaiveDateTime>,
+    pub paid_at: Option<NaiveDateTime>,
+    pub voided_at: Option<NaiveDateTime>,
+    pub void_reason: Option<String>,
+    pub lines: Vec<InvoiceLineResponse>,
+}
@@ -0,0 +1,12 @@
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub struct JobRunResponse {
+    pub id: uuid::Uuid,
+    pub job: String,
+    pub status: String,
+    pub processed: i64,
+    pub error: Option<String>,
+    pub started_at: chrono::NaiveDateTime,
+    pub finished_at: chrono::NaiveDateTime,
+}
@@ -1,7 +1,17 @@
+pub mod addon_dtos;
+pub mod coupon_dtos;
+pub mod dunning_dtos;
+pub mod feature_dtos;
+pub mod invoice_dtos;
+pub mod job_dtos;
+pub mod pagination_dtos;
 pub mod payment_dtos;
 pub mod permission_dtos;
 pub mod plan_dtos;
+pub mod quota_dtos;
 pub mod resource_dtos;
 pub mod role_dtos;
 pub mod subscription_dtos;
+pub mod tax_dtos;
+pub mod usage_dtos;
 pub mod user_dtos;
@@ -1,29 +1,68 @@
 use serde::{Deserialize, Serialize};
 
+use crate::domain::models::{money_model::Money, payment_model::PaymentStatus};
+
 use super::{plan_dtos::PlanResponse, subscription_dtos::SubscriptionResponse};
 
 #[derive(Debug, Deserialize)]
 pub struct CreatePaymentRequest {
-    pub subscription_id: uuid::Uuid,
-    pub amount: i64,
+    /// The subscription paid for, by default the one of `invoice_id`.
+    pub subscription_id: Option<uuid::Uuid>,
+    /// The invoice paid, by default the one the subscription is waiting for.
+    pub invoice_id: Option<uuid::Uuid>,
+    /// The amount the customer was shown, rejected when it isn't the amount
+    /// due anymore. The amount due is paid when not given.
+    pub amount: Option<Money>,
     pub payment_method: String,
+    /// The provider taking the payment, by default manual bank transfer.
+    pub provider: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RefundPaymentRequest {
+    /// By default everything not refunded yet.
+    pub amount: Option<Money>,
 }
 
 #[derive(Serialize)]
 pub struct PaymentResponse {
     pub id: uuid::Uuid,
     pub subscription_id: Option<uuid::Uuid>,
-    pub amount: i64,
+    pub invoice_id: Option<uuid::Uuid>,
+    /// The total paid, `subtotal` plus `tax_amount`.
+    pub amount: Money,
+    pub subtotal: Money,
+    pub tax_amount: Money,
+    pub tax_rate_bps: i32,
+    pub status: PaymentStatus,
+    /// When the payment succeeded.
     pub payment_date: Option<chrono::NaiveDateTime>,
     pub payment_method: String,
+    pub provider: String,
+    pub provider_payment_id: Option<String>,
+    /// What the customer has to do to pay, when the provider needs it.
+    pub instructions: Option<String>,
+    pub failure_reason: Option<String>,
+    pub refunded_amount: Money,
 }
 
 #[derive(Serialize)]
 pub struct PaymentForSysResponse {
     pub id: uuid::Uuid,
-    pub amount: i64,
+    pub invoice_id: Option<uuid::Uuid>,
+    pub amount: Money,
+    pub subtotal: Money,
+    pub tax_amount: Money,
+    pub tax_rate_bps: i32,
+    pub status: PaymentStatus,
     pub payment_date: Option<chrono::NaiveDateTime>,
     pub payment_method: String,
+    pub provider: String,
+    pub provider_payment_id: Option<String>,
+    pub failure_reason: Option<String>,
+    pub refunded_amount: Money,
+    /// Why the money has to be paid back, e.g. it came in for a voided invoice.
+    pub refund_reason: Option<String>,
     pub subscription: SubscriptionResponse,
     pub plan: PlanResponse,
     pub username: String,
@@ -1,14 +1,26 @@
 use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 
+use crate::domain::models::{money_model::Money, plan_model::BillingInterval};
+
 #[derive(Deserialize)]
 pub struct CreatePlanRequest {
     pub name: String,
     pub description: String,
-    pub price: i64,
+    /// The currency of a plan can't change once created, other currencies
+    /// are added as plan prices.
+    pub price: Money,
     pub is_active: bool,
     pub tags: Vec<String>,
+    /// Length of the trial in days, 0 or `None` means no trial.
     pub trial_days: Option<i32>,
+    /// Free plans that never expire and need no payment.
+    pub is_free_forever: Option<bool>,
+    /// Pauses allowed within 12 months, 0 or `None` disables pausing.
+    pub max_pauses_per_year: Option<i32>,
+    /// Defaults to a period of 1 month.
+    pub billing_interval: Option<BillingInterval>,
+    pub billing_interval_count: Option<i32>,
 }
 
 #[derive(Serialize)]
@@ -16,19 +28,25 @@ pub struct PlanResponse {
     pub id: uuid::Uuid,
     pub name: String,
     pub description: String,
-    pub price: i64,
+    pub price: Money,
     pub is_active: Option<bool>,
     pub tags: Option<Vec<String>>,
     pub trial_days: Option<i32>,
+    pub is_free_forever: Option<bool>,
+    pub max_pauses_per_year: Option<i32>,
+    pub billing_interval: Option<String>,
+    pub billing_interval_count: Option<i32>,
+    pub features: Option<Vec<String>>,
     pub created_at: Option<NaiveDateTime>,
 }
 
 impl PlanResponse {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         id: uuid::Uuid,
         name: String,
         description: String,
-        price: i64,
+        price: Money,
         is_active: Option<bool>,
         tags: Option<Vec<String>>,
         trial_days: Option<i32>,
@@ -42,7 +60,66 @@ impl PlanResponse {
             is_active,
             tags,
             trial_days,
+            is_free_forever: None,
+            max_pauses_per_year: None,
+            billing_interval: None,
+            billing_interval_count: None,
+            features: None,
             created_at,
         }
     }
 }
+
+/// An alternative price of a plan, e.g. a discounted yearly price or the
+/// price in another currency. A plan has one price per period and currency,
+/// a new one replaces it for new subscriptions only.
+#[derive(Deserialize)]
+pub struct CreatePlanPriceRequest {
+    pub billing_interval: BillingInterval,
+    pub billing_interval_count: Option<i32>,
+    pub price: Money,
+}
+
+#[derive(Serialize)]
+pub struct PlanPriceResponse {
+    pub id: uuid::Uuid,
+    pub plan_id: uuid::Uuid,
+    pub billing_interval: String,
+    pub billing_interval_count: i32,
+    pub price: Money,
+    pub is_active: bool,
+    pub created_at: Option<NaiveDateTime>,
+}
+
+#[derive(Serialize)]
+pub struct PlanVersionResponse {
+    pub id: uuid::Uuid,
+    pub plan_id: uuid::Uuid,
+    pub version: i32,
+    pub price: Money,
+    pub trial_days: i32,
+    pub is_free_forever: bool,
+    pub max_pauses_per_year: i32,
+    pub billing_interval: String,
+    pub billing_interval_count: i32,
+    pub is_current: bool,
+    /// Current subscriptions billed on this version.
+    pub subscribers: i64,
+    pub created_at: Option<NaiveDateTime>,
+}
+
+/// Moves the subscribers of older versions to a version, from `from_version_id`
+/// only when set. With `notice_days` the move is scheduled that many days out.
+#[derive(Deserialize)]
+pub struct MigratePlanVersionRequest {
+    pub from_version_id: Option<uuid::Uuid>,
+    pub notice_days: Option<i32>,
+}
+
+#[derive(Serialize)]
+pub struct PlanVersionMigrationResponse {
+    pub to_version_id: uuid::Uuid,
+    pub subscriptions: u64,
+    pub status: String,
+    pub effective_at: NaiveDateTime,
+}
@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+
+use crate::domain::models::quota_override_model::LimitSource;
+
+#[derive(Serialize)]
+pub struct QuotaResponse {
+    pub resource_type: String,
+    pub usage: i64,
+    pub max: i64,
+    pub hard_limit: Option<i64>,
+    pub source: LimitSource,
+    pub addon_quantity: i64,
+    pub overage_quantity: i64,
+    pub overage_unit_price: i64,
+}
+
+#[derive(Deserialize)]
+pub struct CreateQuotaOverrideRequest {
+    pub user_id: uuid::Uuid,
+    pub resource_type: String,
+    pub max: i64,
+    pub hard_limit: Option<i64>,
+    pub reason: String,
+    pub expires_at: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(Deserialize)]
+pub struct QuotaOverrideQuery {
+    pub user_id: Option<uuid::Uuid>,
+}
+
+#[derive(Serialize)]
+pub struct QuotaOverrideResponse {
+    pub id: uuid::Uuid,
+    pub user_id: uuid::Uuid,
+    pub resource_type: String,
+    pub max: i64,
+    pub hard_limit: i64,
+    pub reason: String,
+    pub expires_at: Option<chrono::NaiveDateTime>,
+    pub created_by: uuid::Uuid,
+    pub created_at: Option<chrono::NaiveDateTime>,
+    pub revoked_at: Option<chrono::NaiveDateTime>,
+}
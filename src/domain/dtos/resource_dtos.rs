@@ -1,19 +1,78 @@
 use serde::{Deserialize, Serialize};
 
+use crate::domain::models::quota_override_model::LimitSource;
+
 #[derive(Deserialize)]
 pub struct CreateResourceRequest {
     pub plan_id: uuid::Uuid,
     pub max: i64,
     pub name: String,
     pub description: String,
+    pub resource_type: Option<String>,
 }
 
 #[derive(Serialize)]
 pub struct ResourceResponse {
     pub id: uuid::Uuid,
     pub plan_id: uuid::Uuid,
+    pub resource_type: String,
     pub max: i64,
     pub name: String,
     pub description: String,
     pub created_at: Option<chrono::NaiveDateTime>,
 }
+
+#[derive(Deserialize)]
+pub struct CreateResourceTypeRequest {
+    pub code: String,
+    pub name: String,
+    pub description: Option<String>,
+    /// Metered types are counted from usage events instead of existing records.
+    pub is_metered: Option<bool>,
+}
+
+#[derive(Serialize)]
+pub struct ResourceTypeResponse {
+    pub id: uuid::Uuid,
+    pub code: String,
+    pub name: String,
+    pub description: String,
+    pub is_metered: bool,
+    pub created_at: Option<chrono::NaiveDateTime>,
+}
+
+/// `max` is the soft limit included in the plan. Usage above it is billed as
+/// overage at `overage_unit_price` and blocked once it reaches `hard_limit`
+/// (no hard cap when `hard_limit` is `None`).
+#[derive(Deserialize)]
+pub struct SetPlanLimitRequest {
+    pub max: i64,
+    pub hard_limit: Option<i64>,
+    pub overage_unit_price: Option<i64>,
+    pub description: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct PlanLimitResponse {
+    pub plan_id: uuid::Uuid,
+    pub resource_type: String,
+    pub max: i64,
+    pub hard_limit: Option<i64>,
+    pub overage_unit_price: i64,
+}
+
+/// A limit as it applies to one account: the plan limit, or a sales override
+/// replacing it, as reported by `source`, raised by the `addon_quantity`
+/// units of the add-on packs on the subscription.
+#[derive(Serialize)]
+pub struct EffectiveLimitResponse {
+    pub id: uuid::Uuid,
+    pub name: String,
+    pub resource_type_id: uuid::Uuid,
+    pub resource_type: String,
+    pub max: i64,
+    pub hard_limit: Option<i64>,
+    pub overage_unit_price: i64,
+    pub source: LimitSource,
+    pub addon_quantity: i64,
+}
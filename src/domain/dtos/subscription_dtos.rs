@@ -1,11 +1,19 @@
 use serde::{Deserialize, Serialize};
 
+use crate::domain::models::{money_model::Money, subscription_model::SubscriptionStatus};
+
 use super::{plan_dtos::PlanResponse, user_dtos::UserResponse};
 
 #[derive(Deserialize)]
 pub struct CreateSubscriptionRequest {
-    pub user_id: uuid::Uuid,
+    /// The user to subscribe, the caller when `None`. Only sys may
+    /// subscribe another user.
+    pub user_id: Option<uuid::Uuid>,
     pub plan_id: uuid::Uuid,
+    /// One of the plan's prices, the plan's own price when `None`. The
+    /// subscription is billed in the currency of this price for good.
+    pub plan_price_id: Option<uuid::Uuid>,
+    pub promo_code: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -13,9 +21,16 @@ pub struct SubscriptionResponse {
     pub id: uuid::Uuid,
     pub user_id: Option<uuid::Uuid>,
     pub plan_id: Option<uuid::Uuid>,
+    pub plan_price_id: Option<uuid::Uuid>,
+    /// The version of the plan the subscription is billed on.
+    pub plan_version_id: Option<uuid::Uuid>,
     pub start_date: Option<chrono::NaiveDateTime>,
     pub end_date: Option<chrono::NaiveDateTime>,
+    pub trial_start_date: Option<chrono::NaiveDateTime>,
+    pub trial_end_date: Option<chrono::NaiveDateTime>,
+    pub status: SubscriptionStatus,
     pub is_active: bool,
+    pub cancel_at_period_end: bool,
 }
 
 #[derive(Serialize)]
@@ -25,6 +40,117 @@ pub struct SubscriptionForSysResponse {
     pub plan_id: Option<uuid::Uuid>,
     pub start_date: Option<chrono::NaiveDateTime>,
     pub end_date: Option<chrono::NaiveDateTime>,
+    pub status: SubscriptionStatus,
     pub user: UserResponse,
     pub plan: PlanResponse,
 }
+
+#[derive(Deserialize)]
+pub struct UpdateSubscriptionStatusRequest {
+    pub status: SubscriptionStatus,
+    pub reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct SubscriptionReasonRequest {
+    pub reason: Option<String>,
+}
+
+/// Cancels at the end of the current period unless `immediately` is set.
+#[derive(Deserialize)]
+pub struct CancelSubscriptionRequest {
+    #[serde(default)]
+    pub immediately: bool,
+    pub reason: Option<String>,
+    pub feedback: Option<String>,
+}
+
+/// Pauses until resumed, or until `resume_at` when it is set.
+#[derive(Deserialize)]
+pub struct PauseSubscriptionRequest {
+    pub resume_at: Option<chrono::NaiveDateTime>,
+    pub reason: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct PendingCancellationResponse {
+    pub subscription_id: uuid::Uuid,
+    pub user_id: uuid::Uuid,
+    pub username: String,
+    pub email: String,
+    pub plan_id: uuid::Uuid,
+    pub plan_name: String,
+    pub status: SubscriptionStatus,
+    pub end_date: chrono::NaiveDateTime,
+    pub canceled_at: Option<chrono::NaiveDateTime>,
+    pub cancellation_reason: Option<String>,
+    pub cancellation_feedback: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct SubscriptionEventResponse {
+    pub id: uuid::Uuid,
+    pub subscription_id: uuid::Uuid,
+    pub from_status: Option<String>,
+    pub to_status: String,
+    pub actor_type: String,
+    pub actor_id: Option<uuid::Uuid>,
+    pub reason: Option<String>,
+    pub created_at: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangePlanMode {
+    Immediate,
+    EndOfPeriod,
+}
+
+impl ChangePlanMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Immediate => "immediate",
+            Self::EndOfPeriod => "end_of_period",
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ChangePlanRequest {
+    pub plan_id: uuid::Uuid,
+    /// Must be in the currency the subscription is billed in.
+    pub plan_price_id: Option<uuid::Uuid>,
+    pub mode: ChangePlanMode,
+    /// Replaces the coupon of the subscription, checked against the target plan.
+    pub promo_code: Option<String>,
+}
+
+/// `proration_amount` is positive when the user owes the difference and
+/// negative when it is credited, taken off the next period invoices.
+#[derive(Serialize)]
+pub struct PlanChangeResponse {
+    pub id: uuid::Uuid,
+    pub subscription_id: uuid::Uuid,
+    pub from_plan_id: uuid::Uuid,
+    pub to_plan_id: uuid::Uuid,
+    pub to_plan_price_id: Option<uuid::Uuid>,
+    pub mode: String,
+    pub status: String,
+    pub proration_amount: Money,
+    /// The invoice of a prorated charge.
+    pub invoice_id: Option<uuid::Uuid>,
+    pub effective_at: chrono::NaiveDateTime,
+    pub created_at: Option<chrono::NaiveDateTime>,
+}
+
+/// A child user's seat on a group subscription owned by their parent.
+#[derive(Serialize)]
+pub struct SeatResponse {
+    pub id: uuid::Uuid,
+    pub subscription_id: uuid::Uuid,
+    pub user_id: uuid::Uuid,
+    pub username: String,
+    pub email: String,
+    pub assigned_by: Option<uuid::Uuid>,
+    pub assigned_at: chrono::NaiveDateTime,
+}
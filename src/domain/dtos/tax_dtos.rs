@@ -0,0 +1,28 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+pub struct CreateTaxRuleRequest {
+    /// ISO 3166 country code, optionally with a subdivision, e.g. `US-CA`.
+    pub region: String,
+    pub name: String,
+    /// Rate in basis points, 1000 is 10%.
+    pub rate_bps: i32,
+    /// Whether prices already include the tax, `false` adds it on top.
+    pub is_inclusive: Option<bool>,
+    /// Defaults to now for new rules.
+    pub effective_from: Option<NaiveDateTime>,
+    pub effective_until: Option<NaiveDateTime>,
+}
+
+#[derive(Serialize)]
+pub struct TaxRuleResponse {
+    pub id: uuid::Uuid,
+    pub region: String,
+    pub name: String,
+    pub rate_bps: i32,
+    pub is_inclusive: bool,
+    pub effective_from: NaiveDateTime,
+    pub effective_until: Option<NaiveDateTime>,
+    pub created_at: Option<NaiveDateTime>,
+}
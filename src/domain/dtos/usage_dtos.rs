@@ -0,0 +1,52 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+pub struct UsageEventRequest {
+    /// Client generated id, an event sent twice is only recorded once.
+    pub event_id: String,
+    pub resource_type: String,
+    pub quantity: i64,
+    pub occurred_at: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(Deserialize)]
+pub struct BulkUsageEventRequest {
+    pub events: Vec<UsageEventRequest>,
+}
+
+#[derive(Serialize)]
+pub struct RejectedUsageEvent {
+    pub event_id: String,
+    pub error: String,
+}
+
+#[derive(Serialize, Default)]
+pub struct UsageIngestionResponse {
+    pub accepted: usize,
+    pub duplicates: usize,
+    pub rejected: Vec<RejectedUsageEvent>,
+}
+
+#[derive(Deserialize)]
+pub struct UsageRollupQuery {
+    pub resource_type: Option<String>,
+    pub from: Option<chrono::NaiveDate>,
+    pub to: Option<chrono::NaiveDate>,
+}
+
+#[derive(Serialize)]
+pub struct DailyUsageResponse {
+    pub subscription_id: uuid::Uuid,
+    pub resource_type: String,
+    pub day: chrono::NaiveDate,
+    pub quantity: i64,
+}
+
+#[derive(Serialize)]
+pub struct PeriodUsageResponse {
+    pub subscription_id: uuid::Uuid,
+    pub resource_type: String,
+    pub period_start: chrono::NaiveDateTime,
+    pub period_end: Option<chrono::NaiveDateTime>,
+    pub quantity: i64,
+}
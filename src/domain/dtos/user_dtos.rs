@@ -15,6 +15,24 @@ pub struct UpdateUserRequest {
     pub name: Option<String>,
 }
 
+/// Printed on invoices, `billing_region` decides the tax charged.
+#[derive(Deserialize)]
+pub struct UpdateBillingDetailsRequest {
+    pub billing_name: Option<String>,
+    pub billing_address: Option<String>,
+    /// ISO 3166 country code, optionally with a subdivision, e.g. `US-CA`.
+    pub billing_region: Option<String>,
+    pub tax_id: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct BillingDetailsResponse {
+    pub billing_name: Option<String>,
+    pub billing_address: Option<String>,
+    pub billing_region: Option<String>,
+    pub tax_id: Option<String>,
+}
+
 #[derive(Deserialize)]
 pub struct LoginRequest {
     pub username: String,
@@ -0,0 +1,38 @@
+use super::{
+    money_model::Money,
+    plan_model::{BillingInterval, BillingPeriod},
+};
+
+/// Price of `packs` add-on packs for one billing `period`, from the monthly
+/// price of a pack. Daily periods are charged 1/30 of a month per day.
+pub fn period_price(monthly_price: Money, packs: i32, period: BillingPeriod) -> Money {
+    let packs = packs as i64;
+    let count = period.count as i64;
+
+    match period.interval {
+        BillingInterval::Day => monthly_price.mul_ratio(packs * count, 30),
+        BillingInterval::Month => monthly_price.mul_ratio(packs * count, 1),
+        BillingInterval::Year => monthly_price.mul_ratio(packs * count * 12, 1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::models::money_model::Currency;
+
+    #[test]
+    fn test_period_price() {
+        let vnd = |amount| Money::new(amount, Currency::Vnd);
+        let period = |interval, count| BillingPeriod::new(interval, count).unwrap();
+
+        assert_eq!(period_price(vnd(20000), 1, period("month", 1)), vnd(20000));
+        assert_eq!(period_price(vnd(20000), 2, period("month", 3)), vnd(120000));
+        assert_eq!(period_price(vnd(20000), 1, period("year", 1)), vnd(240000));
+        assert_eq!(period_price(vnd(20000), 1, period("day", 45)), vnd(30000));
+        assert_eq!(
+            period_price(Money::new(999, Currency::Usd), 1, period("day", 1)),
+            Money::new(33, Currency::Usd)
+        );
+    }
+}
@@ -0,0 +1,111 @@
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use super::money_model::Money;
+
+/// How long a redeemed coupon keeps discounting the subscription.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CouponDuration {
+    /// Only the first charge after the redemption.
+    Once,
+    /// Charges for periods starting within `duration_in_months` of the redemption.
+    Repeating,
+    Forever,
+}
+
+impl CouponDuration {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Once => "once",
+            Self::Repeating => "repeating",
+            Self::Forever => "forever",
+        }
+    }
+}
+
+impl fmt::Display for CouponDuration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for CouponDuration {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "once" => Ok(Self::Once),
+            "repeating" => Ok(Self::Repeating),
+            "forever" => Ok(Self::Forever),
+            _ => Err(format!("Unknown coupon duration: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Discount {
+    Percent(i32),
+    Amount(Money),
+}
+
+impl Discount {
+    pub fn new(percent_off: Option<i32>, amount_off: Option<Money>) -> Result<Self, String> {
+        match (percent_off, amount_off) {
+            (Some(percent), None) if (1..=100).contains(&percent) => Ok(Self::Percent(percent)),
+            (Some(_), None) => Err("percent_off must be between 1 and 100".to_string()),
+            (None, Some(amount)) if amount.amount_minor > 0 => Ok(Self::Amount(amount)),
+            (None, Some(_)) => Err("amount_off must be more than 0".to_string()),
+            _ => Err("Exactly one of percent_off and amount_off must be set".to_string()),
+        }
+    }
+
+    /// The amount taken off `price`, never more than the price itself. An
+    /// amount off in another currency doesn't apply.
+    pub fn amount_off(&self, price: Money) -> Result<Money, String> {
+        let amount = match self {
+            Self::Percent(percent) => price.mul_ratio(*percent as i64, 100),
+            Self::Amount(amount) => amount.checked_min(price)?,
+        };
+
+        Ok(Money::new(amount.amount_minor.max(0), price.currency))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::models::money_model::Currency;
+
+    #[test]
+    fn test_discount() {
+        let vnd = |amount| Money::new(amount, Currency::Vnd);
+        let amount_off = |percent_off, amount_off, price| {
+            Discount::new(percent_off, amount_off)
+                .unwrap()
+                .amount_off(price)
+        };
+
+        assert_eq!(amount_off(Some(20), None, vnd(49000)), Ok(vnd(9800)));
+        assert_eq!(amount_off(Some(33), None, vnd(100)), Ok(vnd(33)));
+        assert_eq!(amount_off(Some(100), None, vnd(19000)), Ok(vnd(19000)));
+        assert_eq!(amount_off(None, Some(vnd(5000)), vnd(19000)), Ok(vnd(5000)));
+        assert_eq!(
+            amount_off(None, Some(vnd(50000)), vnd(19000)),
+            Ok(vnd(19000))
+        );
+        assert_eq!(amount_off(None, Some(vnd(5000)), vnd(0)), Ok(vnd(0)));
+        assert_eq!(
+            amount_off(Some(20), None, Money::new(1999, Currency::Usd)),
+            Ok(Money::new(400, Currency::Usd))
+        );
+        assert!(amount_off(None, Some(vnd(5000)), Money::new(1999, Currency::Usd)).is_err());
+
+        assert!(Discount::new(Some(0), None).is_err());
+        assert!(Discount::new(Some(101), None).is_err());
+        assert!(Discount::new(None, Some(vnd(0))).is_err());
+        assert!(Discount::new(Some(10), Some(vnd(1000))).is_err());
+        assert!(Discount::new(None, None).is_err());
+    }
+}
@@ -0,0 +1,142 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+/// How long an unpaid renewal invoice is chased before the subscription is
+/// canceled, in days after the subscription went past due.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DunningPolicy {
+    /// Customers pay renewal invoices themselves, so every retry is a
+    /// reminder to pay, e.g. `[0, 3, 7]`.
+    pub retry_days: Vec<i64>,
+    /// Quotas become read-only from this day on.
+    pub read_only_after_days: i64,
+    /// The subscription is canceled at the end of the grace period.
+    pub grace_days: i64,
+}
+
+/// Where an account with an unpaid renewal invoice is in the dunning process,
+/// in the order of the steps.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum DunningStage {
+    /// Reminded to pay, the plan still works as usual.
+    Grace,
+    /// Quotas are read-only until the invoice is paid.
+    ReadOnly,
+    /// The grace period is over, the subscription is canceled.
+    Canceled,
+}
+
+impl DunningPolicy {
+    pub fn new(
+        retry_days: Vec<i64>,
+        read_only_after_days: i64,
+        grace_days: i64,
+    ) -> Result<Self, String> {
+        if grace_days <= 0 {
+            return Err("The dunning grace period must be 1 day or more".to_string());
+        }
+
+        if !(0..=grace_days).contains(&read_only_after_days) {
+            return Err("Quotas must become read-only within the grace period".to_string());
+        }
+
+        if retry_days.windows(2).any(|days| days[0] >= days[1])
+            || retry_days.iter().any(|day| !(0..grace_days).contains(day))
+        {
+            return Err(
+                "Dunning retry days must be increasing and within the grace period".to_string(),
+            );
+        }
+
+        Ok(Self {
+            retry_days,
+            read_only_after_days,
+            grace_days,
+        })
+    }
+
+    /// Reads a comma separated list of retry days, e.g. `0,3,7`.
+    pub fn parse_retry_days(retry_days: &str) -> Result<Vec<i64>, String> {
+        retry_days
+            .split(',')
+            .map(str::trim)
+            .filter(|day| !day.is_empty())
+            .map(|day| {
+                day.parse()
+                    .map_err(|_| format!("Invalid dunning retry day: {}", day))
+            })
+            .collect()
+    }
+
+    pub fn stage(&self, past_due_at: NaiveDateTime, now: NaiveDateTime) -> DunningStage {
+        let elapsed = now - past_due_at;
+
+        if elapsed >= chrono::Duration::days(self.grace_days) {
+            DunningStage::Canceled
+        } else if elapsed >= chrono::Duration::days(self.read_only_after_days) {
+            DunningStage::ReadOnly
+        } else {
+            DunningStage::Grace
+        }
+    }
+
+    /// Number of retries that are due at `now`.
+    pub fn retries_due(&self, past_due_at: NaiveDateTime, now: NaiveDateTime) -> i32 {
+        self.retry_days
+            .iter()
+            .take_while(|day| past_due_at + chrono::Duration::days(**day) <= now)
+            .count() as i32
+    }
+
+    pub fn read_only_at(&self, past_due_at: NaiveDateTime) -> NaiveDateTime {
+        past_due_at + chrono::Duration::days(self.read_only_after_days)
+    }
+
+    pub fn cancels_at(&self, past_due_at: NaiveDateTime) -> NaiveDateTime {
+        past_due_at + chrono::Duration::days(self.grace_days)
+    }
+}
+
+impl Default for DunningPolicy {
+    fn default() -> Self {
+        Self {
+            retry_days: vec![0, 3, 7],
+            read_only_after_days: 10,
+            grace_days: 14,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dunning_policy() {
+        let issued_at = chrono::NaiveDate::from_ymd_opt(2024, 9, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        let day = |n| issued_at + chrono::Duration::days(n);
+        let policy = DunningPolicy::default();
+
+        assert_eq!(policy.retries_due(issued_at, day(0)), 1);
+        assert_eq!(policy.retries_due(issued_at, day(2)), 1);
+        assert_eq!(policy.retries_due(issued_at, day(3)), 2);
+        assert_eq!(policy.retries_due(issued_at, day(13)), 3);
+
+        assert_eq!(policy.stage(issued_at, day(9)), DunningStage::Grace);
+        assert_eq!(policy.stage(issued_at, day(10)), DunningStage::ReadOnly);
+        assert_eq!(policy.stage(issued_at, day(14)), DunningStage::Canceled);
+
+        assert_eq!(DunningPolicy::parse_retry_days("0, 3,7"), Ok(vec![0, 3, 7]));
+        assert!(DunningPolicy::parse_retry_days("0,x").is_err());
+
+        assert!(DunningPolicy::new(vec![0, 3], 10, 14).is_ok());
+        assert!(DunningPolicy::new(vec![3, 0], 10, 14).is_err());
+        assert!(DunningPolicy::new(vec![0, 14], 10, 14).is_err());
+        assert!(DunningPolicy::new(vec![0], 15, 14).is_err());
+        assert!(DunningPolicy::new(vec![], 0, 0).is_err());
+    }
+}
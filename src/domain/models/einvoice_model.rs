@@ -0,0 +1,495 @@
+use chrono::NaiveDate;
+
+use super::money_model::Currency;
+
+/// Version of the XML format of Decision 1450/QĐ-TCT, which implements
+/// Decree 123/2020/NĐ-CP and Circular 78/2021/TT-BTC.
+pub const EINVOICE_VERSION: &str = "2.0.0";
+
+const DIGITS: [&str; 10] = [
+    "không", "một", "hai", "ba", "bốn", "năm", "sáu", "bảy", "tám", "chín",
+];
+
+/// A VAT rate as printed on e-invoices.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VatRate {
+    /// In basis points, e.g. 1000 is 10%.
+    Percent(i32),
+    /// Not subject to VAT (KCT).
+    NotTaxable,
+}
+
+impl VatRate {
+    /// `10%`, `KCT`, or `KHAC:x%` for rates outside 0, 5, 8 and 10%.
+    pub fn code(&self) -> String {
+        match self {
+            Self::NotTaxable => "KCT".to_string(),
+            Self::Percent(bps @ (0 | 500 | 800 | 1000)) => format!("{}%", bps / 100),
+            Self::Percent(bps) => format!("KHAC:{}.{:02}%", bps / 100, bps % 100),
+        }
+    }
+}
+
+/// What a line is (TChat).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineNature {
+    /// Goods or services sold.
+    Goods = 1,
+    /// A trade discount, deducted from the goods.
+    Discount = 3,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EInvoiceParty {
+    pub name: String,
+    /// Mã số thuế, required for the seller and for buyers that are businesses.
+    pub tax_code: Option<String>,
+    pub address: Option<String>,
+    pub email: Option<String>,
+}
+
+/// A line of an e-invoice. Amounts are in đồng, before VAT and positive for
+/// discounts too.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EInvoiceLine {
+    pub nature: LineNature,
+    pub name: String,
+    pub quantity: i64,
+    pub unit_price: i64,
+    pub amount: i64,
+}
+
+/// An invoice in the tax authority's e-invoice format, before it is signed
+/// by the provider submitting it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EInvoice {
+    /// Ký hiệu hóa đơn, e.g. `C24TAA`.
+    pub series: String,
+    pub number: i64,
+    pub issued_on: NaiveDate,
+    pub currency: Currency,
+    pub seller: EInvoiceParty,
+    pub buyer: EInvoiceParty,
+    pub lines: Vec<EInvoiceLine>,
+    pub vat_rate: VatRate,
+    /// Goods less discounts, before VAT.
+    pub subtotal: i64,
+    pub vat: i64,
+    pub total: i64,
+}
+
+/// The series of invoices with a code from the tax authority (`C`), issued in
+/// `year` by a business (`T`), ending with the two letters it registered.
+pub fn series(year: i32, letters: &str) -> String {
+    format!("C{:02}T{}", year.rem_euclid(100), letters)
+}
+
+fn is_series(series: &str) -> bool {
+    let chars: Vec<char> = series.chars().collect();
+
+    chars.len() == 6
+        && matches!(chars[0], 'C' | 'K')
+        && chars[1..3].iter().all(|c| c.is_ascii_digit())
+        && "TDLMNBGH".contains(chars[3])
+        && chars[4..].iter().all(|c| c.is_ascii_uppercase())
+}
+
+/// Tax codes of businesses have 10 digits, 13 with a branch suffix
+/// (`0312345678-001`). Individuals use their 12 digit citizen id.
+pub fn is_tax_code(code: &str) -> bool {
+    let all_digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+
+    match code.split_once('-') {
+        Some((code, branch)) => {
+            code.len() == 10 && all_digits(code) && branch.len() == 3 && all_digits(branch)
+        }
+        None => matches!(code.len(), 10 | 12) && all_digits(code),
+    }
+}
+
+/// Reads a group of three digits. Groups after the first are read in full,
+/// e.g. `5` as `không trăm lẻ năm`.
+fn read_group(n: u64, full: bool, words: &mut Vec<&'static str>) {
+    let (hundreds, tens, units) = (
+        (n / 100) as usize,
+        (n / 10 % 10) as usize,
+        (n % 10) as usize,
+    );
+
+    if full || hundreds > 0 {
+        words.extend([DIGITS[hundreds], "trăm"]);
+    }
+
+    match tens {
+        0 if units > 0 && (full || hundreds > 0) => words.push("lẻ"),
+        0 => {}
+        1 => words.push("mười"),
+        _ => words.extend([DIGITS[tens], "mươi"]),
+    }
+
+    match units {
+        0 => {}
+        1 if tens > 1 => words.push("mốt"),
+        4 if tens > 1 => words.push("tư"),
+        5 if tens > 0 => words.push("lăm"),
+        _ => words.push(DIGITS[units]),
+    }
+}
+
+fn read_number(n: u64, full: bool, words: &mut Vec<&'static str>) {
+    const BILLION: u64 = 1_000_000_000;
+
+    if n >= BILLION {
+        read_number(n / BILLION, full, words);
+        words.push("tỷ");
+
+        if !n.is_multiple_of(BILLION) {
+            read_number(n % BILLION, true, words);
+        }
+
+        return;
+    }
+
+    let mut started = full;
+
+    for (group, unit) in [
+        (n / 1_000_000, "triệu"),
+        (n / 1_000 % 1_000, "nghìn"),
+        (n % 1_000, ""),
+    ] {
+        if group == 0 {
+            continue;
+        }
+
+        read_group(group, started, words);
+        words.extend(Some(unit).filter(|unit| !unit.is_empty()));
+        started = true;
+    }
+}
+
+/// An amount of đồng in words, e.g. `Năm mươi ba nghìn chín trăm đồng`.
+pub fn amount_in_words(amount: u64) -> String {
+    let mut words = vec![];
+
+    match amount {
+        0 => words.push(DIGITS[0]),
+        _ => read_number(amount, false, &mut words),
+    }
+    words.push("đồng");
+
+    let text = words.join(" ");
+    let mut chars = text.chars();
+
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => text,
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Writes indented XML elements.
+struct XmlWriter {
+    xml: String,
+    depth: usize,
+}
+
+impl XmlWriter {
+    /// Opens the element `tag`, which may have attributes.
+    fn open(&mut self, tag: &str) {
+        self.xml
+            .push_str(&format!("{}<{}>\n", "  ".repeat(self.depth), tag));
+        self.depth += 1;
+    }
+
+    fn close(&mut self, name: &str) {
+        self.depth -= 1;
+        self.xml
+            .push_str(&format!("{}</{}>\n", "  ".repeat(self.depth), name));
+    }
+
+    fn element(&mut self, name: &str, value: impl ToString) {
+        self.xml.push_str(&format!(
+            "{}<{}>{}</{}>\n",
+            "  ".repeat(self.depth),
+            name,
+            escape(&value.to_string()),
+            name
+        ));
+    }
+
+    fn empty(&mut self, name: &str) {
+        self.xml
+            .push_str(&format!("{}<{}/>\n", "  ".repeat(self.depth), name));
+    }
+
+    fn optional(&mut self, name: &str, value: Option<&String>) {
+        if let Some(value) = value {
+            self.element(name, value);
+        }
+    }
+
+    fn party(&mut self, name: &str, party: &EInvoiceParty) {
+        self.open(name);
+        self.element("Ten", &party.name);
+        self.optional("MST", party.tax_code.as_ref());
+        self.optional("DChi", party.address.as_ref());
+        self.optional("DCTDTu", party.email.as_ref());
+        self.close(name);
+    }
+}
+
+impl EInvoice {
+    fn total_of(&self, nature: LineNature) -> i64 {
+        self.lines
+            .iter()
+            .filter(|line| line.nature == nature)
+            .map(|line| line.amount)
+            .sum()
+    }
+
+    /// Checks the invoice against the rules of the schema: required fields,
+    /// lengths, code formats and totals that add up.
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut errors = vec![];
+        let mut check = |valid: bool, error: String| {
+            if !valid {
+                errors.push(error);
+            }
+        };
+
+        check(
+            is_series(&self.series),
+            format!("Invalid invoice series: {}", self.series),
+        );
+        check(
+            (1..=99_999_999).contains(&self.number),
+            format!("Invoice number must have at most 8 digits: {}", self.number),
+        );
+        check(
+            self.currency == Currency::Vnd,
+            format!("Only VND invoices can be exported, not {}", self.currency),
+        );
+
+        for (role, party) in [("Seller", &self.seller), ("Buyer", &self.buyer)] {
+            check(
+                !party.name.trim().is_empty() && party.name.chars().count() <= 400,
+                format!("{} name must have 1 to 400 characters", role),
+            );
+            check(
+                party
+                    .address
+                    .as_ref()
+                    .is_none_or(|a| a.chars().count() <= 400),
+                format!("{} address must have at most 400 characters", role),
+            );
+            check(
+                party.email.as_ref().is_none_or(|e| e.chars().count() <= 50),
+                format!("{} email must have at most 50 characters", role),
+            );
+
+            match &party.tax_code {
+                Some(code) => {
+                    check(
+                        is_tax_code(code),
+                        format!("Invalid {} tax code: {}", role.to_lowercase(), code),
+                    );
+                    check(
+                        party.address.as_ref().is_some_and(|a| !a.trim().is_empty()),
+                        format!("{} address is required with a tax code", role),
+                    );
+                }
+                None => check(role == "Buyer", "Seller tax code is required".to_string()),
+            }
+        }
+
+        check(
+            !self.lines.is_empty(),
+            "An invoice needs at least one line".to_string(),
+        );
+
+        for (index, line) in self.lines.iter().enumerate() {
+            check(
+                !line.name.trim().is_empty() && line.name.chars().count() <= 500,
+                format!("Line {} name must have 1 to 500 characters", index + 1),
+            );
+            check(
+                line.quantity > 0 && line.unit_price >= 0 && line.amount >= 0,
+                format!("Line {} amounts can't be negative", index + 1),
+            );
+        }
+
+        let net = self.total_of(LineNature::Goods) - self.total_of(LineNature::Discount);
+        check(
+            net == self.subtotal,
+            format!(
+                "Lines add up to {}, not the subtotal {}",
+                net, self.subtotal
+            ),
+        );
+        check(
+            self.subtotal >= 0 && self.vat >= 0 && self.subtotal + self.vat == self.total,
+            format!(
+                "Subtotal {} and VAT {} don't add up to the total {}",
+                self.subtotal, self.vat, self.total
+            ),
+        );
+        check(
+            self.vat_rate != VatRate::NotTaxable || self.vat == 0,
+            "Invoices not subject to VAT can't charge VAT".to_string(),
+        );
+
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(errors),
+        }
+    }
+
+    /// The invoice as XML, with the signature left to the provider.
+    pub fn to_xml(&self) -> String {
+        let rate = self.vat_rate.code();
+        let mut xml = XmlWriter {
+            xml: "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n".to_string(),
+            depth: 0,
+        };
+
+        xml.open("HDon");
+        xml.open("DLHDon Id=\"data\"");
+
+        xml.open("TTChung");
+        xml.element("PBan", EINVOICE_VERSION);
+        xml.element("THDon", "Hóa đơn giá trị gia tăng");
+        xml.element("KHMSHDon", 1);
+        xml.element("KHHDon", &self.series);
+        xml.element("SHDon", self.number);
+        xml.element("NLap", self.issued_on.format("%Y-%m-%d"));
+        xml.element("DVTTe", self.currency);
+        xml.element("HTTToan", "TM/CK");
+        xml.close("TTChung");
+
+        xml.open("NDHDon");
+        xml.party("NBan", &self.seller);
+        xml.party("NMua", &self.buyer);
+
+        xml.open("DSHHDVu");
+        for (index, line) in self.lines.iter().enumerate() {
+            xml.open("HHDVu");
+            xml.element("TChat", line.nature as i32);
+            xml.element("STT", index + 1);
+            xml.element("THHDVu", &line.name);
+            xml.element("SLuong", line.quantity);
+            xml.element("DGia", line.unit_price);
+            xml.element("ThTien", line.amount);
+            xml.element("TSuat", &rate);
+            xml.close("HHDVu");
+        }
+        xml.close("DSHHDVu");
+
+        xml.open("TToan");
+        xml.open("THTTLTSuat");
+        xml.open("LTSuat");
+        xml.element("TSuat", &rate);
+        xml.element("ThTien", self.subtotal);
+        xml.element("TThue", self.vat);
+        xml.close("LTSuat");
+        xml.close("THTTLTSuat");
+        xml.element("TgTCThue", self.subtotal);
+        xml.element("TgTThue", self.vat);
+        xml.element("TTCKTMai", self.total_of(LineNature::Discount));
+        xml.element("TgTTTBSo", self.total);
+        xml.element("TgTTTBChu", amount_in_words(self.total.max(0) as u64));
+        xml.close("TToan");
+        xml.close("NDHDon");
+
+        xml.close("DLHDon");
+        xml.open("DSCKS");
+        xml.empty("NBan");
+        xml.close("DSCKS");
+        xml.close("HDon");
+
+        xml.xml
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_amount_in_words() {
+        assert_eq!(amount_in_words(0), "Không đồng");
+        assert_eq!(amount_in_words(15), "Mười lăm đồng");
+        assert_eq!(amount_in_words(21), "Hai mươi mốt đồng");
+        assert_eq!(amount_in_words(105), "Một trăm lẻ năm đồng");
+        assert_eq!(amount_in_words(53_900), "Năm mươi ba nghìn chín trăm đồng");
+        assert_eq!(
+            amount_in_words(1_005_000),
+            "Một triệu không trăm lẻ năm nghìn đồng"
+        );
+        assert_eq!(
+            amount_in_words(2_024_000_000),
+            "Hai tỷ không trăm hai mươi tư triệu đồng"
+        );
+    }
+
+    #[test]
+    fn test_einvoice() {
+        let party = |name: &str, tax_code: Option<&str>| EInvoiceParty {
+            name: name.to_string(),
+            tax_code: tax_code.map(str::to_string),
+            address: Some("Hà Nội".to_string()),
+            email: None,
+        };
+        let line = |nature, amount| EInvoiceLine {
+            nature,
+            name: "Premium".to_string(),
+            quantity: 1,
+            unit_price: amount,
+            amount,
+        };
+
+        let mut invoice = EInvoice {
+            series: series(2024, "AA"),
+            number: 42,
+            issued_on: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            currency: Currency::Vnd,
+            seller: party("Công ty A & B", Some("0312345678")),
+            buyer: party("Nguyễn Văn Ánh", None),
+            lines: vec![
+                line(LineNature::Goods, 49000),
+                line(LineNature::Discount, 4900),
+            ],
+            vat_rate: VatRate::Percent(1000),
+            subtotal: 44100,
+            vat: 4410,
+            total: 48510,
+        };
+
+        assert_eq!(invoice.series, "C24TAA");
+        assert_eq!(invoice.validate(), Ok(()));
+
+        let xml = invoice.to_xml();
+        assert!(xml.contains("<Ten>Công ty A &amp; B</Ten>"));
+        assert!(xml.contains("<TChat>3</TChat>"));
+        assert!(xml.contains("<TSuat>10%</TSuat>"));
+        assert!(xml.contains("<TTCKTMai>4900</TTCKTMai>"));
+        assert!(xml.contains("<TgTTTBChu>Bốn mươi tám nghìn năm trăm mười đồng</TgTTTBChu>"));
+
+        invoice.seller.tax_code = None;
+        invoice.buyer.tax_code = Some("123".to_string());
+        invoice.total = 48000;
+        assert_eq!(invoice.validate().unwrap_err().len(), 3);
+
+        assert!(is_tax_code("0312345678-001"));
+        assert!(is_tax_code("001099012345"));
+        assert!(!is_tax_code("03123456789"));
+        assert_eq!(VatRate::Percent(800).code(), "8%");
+        assert_eq!(VatRate::Percent(850).code(), "KHAC:8.50%");
+    }
+}
@@ -0,0 +1 @@
+pub const CUSTOM_ROLES: &str = "custom_roles";
@@ -0,0 +1,306 @@
+use std::{fmt, str::FromStr};
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+use super::money_model::Money;
+
+/// Days customers have to pay renewal invoices and invoices issued in the
+/// middle of a period, e.g. for a plan change. The first invoice of a
+/// subscription is due when its period starts.
+pub const PAYMENT_TERMS_DAYS: i64 = 7;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum InvoiceStatus {
+    /// Being assembled, has no number yet.
+    Draft,
+    Open,
+    Paid,
+    Void,
+    /// Given up on by dunning.
+    Uncollectible,
+}
+
+impl InvoiceStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Draft => "draft",
+            Self::Open => "open",
+            Self::Paid => "paid",
+            Self::Void => "void",
+            Self::Uncollectible => "uncollectible",
+        }
+    }
+
+    /// How the status is printed on invoice documents.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Draft => "Draft",
+            Self::Open => "Open",
+            Self::Paid => "Paid",
+            Self::Void => "Void",
+            Self::Uncollectible => "Uncollectible",
+        }
+    }
+
+    pub fn can_transition_to(&self, to: InvoiceStatus) -> bool {
+        use InvoiceStatus::*;
+
+        matches!(
+            (self, to),
+            (Draft, Open | Void)
+                | (Open, Paid | Void | Uncollectible)
+                | (Uncollectible, Paid | Void)
+        )
+    }
+}
+
+impl fmt::Display for InvoiceStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for InvoiceStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "draft" => Ok(Self::Draft),
+            "open" => Ok(Self::Open),
+            "paid" => Ok(Self::Paid),
+            "void" => Ok(Self::Void),
+            "uncollectible" => Ok(Self::Uncollectible),
+            _ => Err(format!("Unknown invoice status: {}", s)),
+        }
+    }
+}
+
+/// What an invoice bills for.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum InvoiceKind {
+    /// A billing period of the subscription, with the overage of the
+    /// period before it.
+    Period,
+    /// The prorated difference of a plan change.
+    PlanChange,
+    /// Add-on packs bought in the middle of a period.
+    Addon,
+}
+
+impl InvoiceKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Period => "period",
+            Self::PlanChange => "plan_change",
+            Self::Addon => "addon",
+        }
+    }
+}
+
+impl FromStr for InvoiceKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "period" => Ok(Self::Period),
+            "plan_change" => Ok(Self::PlanChange),
+            "addon" => Ok(Self::Addon),
+            _ => Err(format!("Unknown invoice kind: {}", s)),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum InvoiceLineKind {
+    Plan,
+    Addon,
+    Overage,
+    Proration,
+    Discount,
+}
+
+impl InvoiceLineKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Plan => "plan",
+            Self::Addon => "addon",
+            Self::Overage => "overage",
+            Self::Proration => "proration",
+            Self::Discount => "discount",
+        }
+    }
+}
+
+impl FromStr for InvoiceLineKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "plan" => Ok(Self::Plan),
+            "addon" => Ok(Self::Addon),
+            "overage" => Ok(Self::Overage),
+            "proration" => Ok(Self::Proration),
+            "discount" => Ok(Self::Discount),
+            _ => Err(format!("Unknown invoice line kind: {}", s)),
+        }
+    }
+}
+
+/// A line of an invoice. Amounts are before exclusive tax, which is added
+/// on the invoice total.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvoiceLine {
+    pub kind: InvoiceLineKind,
+    pub description: String,
+    pub quantity: i64,
+    pub unit_amount: Money,
+    pub amount: Money,
+    pub period_start: Option<NaiveDateTime>,
+    pub period_end: Option<NaiveDateTime>,
+}
+
+impl InvoiceLine {
+    pub fn new(
+        kind: InvoiceLineKind,
+        description: String,
+        quantity: i64,
+        unit_amount: Money,
+    ) -> Self {
+        Self {
+            kind,
+            description,
+            quantity,
+            unit_amount,
+            amount: Money::new(unit_amount.amount_minor * quantity, unit_amount.currency),
+            period_start: None,
+            period_end: None,
+        }
+    }
+
+    /// A line for a charge already worked out, e.g. a prorated amount.
+    pub fn single(kind: InvoiceLineKind, description: String, amount: Money) -> Self {
+        Self::new(kind, description, 1, amount)
+    }
+
+    pub fn for_period(mut self, start: NaiveDateTime, end: NaiveDateTime) -> Self {
+        self.period_start = Some(start);
+        self.period_end = Some(end);
+        self
+    }
+}
+
+/// Sum of `lines` in `currency`.
+pub fn lines_total(
+    lines: &[InvoiceLine],
+    currency: super::money_model::Currency,
+) -> Result<Money, String> {
+    lines.iter().try_fold(Money::zero(currency), |total, line| {
+        total.checked_add(line.amount)
+    })
+}
+
+/// Invoice numbers run without gaps within a year, e.g. `INV-2024-000042`.
+pub fn format_number(year: i32, sequence: i32) -> String {
+    format!("INV-{}-{:06}", year, sequence)
+}
+
+/// The year and sequence of an invoice number.
+pub fn parse_number(number: &str) -> Option<(i32, i32)> {
+    let (year, sequence) = number.strip_prefix("INV-")?.split_once('-')?;
+
+    Some((year.parse().ok()?, sequence.parse().ok()?))
+}
+
+/// Share of `amount` for the part of the period left at `now`, rounded to
+/// the nearest unit.
+pub fn prorate(
+    amount: Money,
+    period_start: NaiveDateTime,
+    period_end: NaiveDateTime,
+    now: NaiveDateTime,
+) -> Money {
+    let total = (period_end - period_start).num_seconds();
+
+    if total <= 0 {
+        return Money::zero(amount.currency);
+    }
+
+    let remaining = (period_end - now).num_seconds().clamp(0, total);
+
+    amount.mul_ratio(remaining, total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::models::money_model::Currency;
+
+    #[test]
+    fn test_invoice_status_transitions() {
+        use InvoiceStatus::*;
+
+        assert!(Draft.can_transition_to(Open));
+        assert!(Open.can_transition_to(Paid));
+        assert!(Open.can_transition_to(Uncollectible));
+        assert!(Uncollectible.can_transition_to(Paid));
+        assert!(Open.can_transition_to(Void));
+
+        assert!(!Draft.can_transition_to(Paid));
+        assert!(!Paid.can_transition_to(Void));
+        assert!(!Void.can_transition_to(Open));
+        assert!(!Open.can_transition_to(Draft));
+
+        for status in [Draft, Open, Paid, Void, Uncollectible] {
+            assert_eq!(status.as_str().parse::<InvoiceStatus>(), Ok(status));
+        }
+    }
+
+    #[test]
+    fn test_invoice_lines() {
+        let vnd = |amount| Money::new(amount, Currency::Vnd);
+        let lines = [
+            InvoiceLine::single(InvoiceLineKind::Plan, "Premium".to_string(), vnd(49000)),
+            InvoiceLine::new(
+                InvoiceLineKind::Overage,
+                "api_calls".to_string(),
+                120,
+                vnd(10),
+            ),
+            InvoiceLine::single(InvoiceLineKind::Discount, "WELCOME".to_string(), vnd(-4900)),
+        ];
+
+        assert_eq!(lines[1].amount, vnd(1200));
+        assert_eq!(lines_total(&lines, Currency::Vnd), Ok(vnd(45300)));
+        assert!(lines_total(&lines, Currency::Usd).is_err());
+
+        assert_eq!(format_number(2024, 42), "INV-2024-000042");
+        assert_eq!(parse_number("INV-2024-000042"), Some((2024, 42)));
+        assert_eq!(parse_number("2024-000042"), None);
+    }
+
+    #[test]
+    fn test_prorate() {
+        let day = |d: u32| {
+            chrono::NaiveDate::from_ymd_opt(2024, 1, d)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap()
+        };
+
+        let vnd = |amount| Money::new(amount, Currency::Vnd);
+
+        // Upgrade halfway through a 10 day period.
+        assert_eq!(prorate(vnd(10000), day(1), day(11), day(6)), vnd(5000));
+        // Downgrade with 3 of 10 days left is credited.
+        assert_eq!(prorate(vnd(-10000), day(1), day(11), day(8)), vnd(-3000));
+        // Nothing is left once the period ended.
+        assert_eq!(prorate(vnd(10000), day(1), day(11), day(20)), vnd(0));
+        // The whole difference is due at the start of the period.
+        assert_eq!(prorate(vnd(10000), day(1), day(11), day(1)), vnd(10000));
+        assert_eq!(prorate(vnd(10000), day(1), day(1), day(1)), vnd(0));
+    }
+}
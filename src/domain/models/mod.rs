@@ -1,9 +1,19 @@
+pub mod addon_model;
+pub mod coupon_model;
+pub mod dunning_model;
+pub mod einvoice_model;
+pub mod feature_model;
+pub mod invoice_model;
+pub mod money_model;
 pub mod payment_model;
 pub mod permission_model;
 pub mod plan_model;
+pub mod quota_override_model;
 pub mod resource_model;
+pub mod resource_type_model;
 pub mod role_model;
 pub mod subscription_model;
+pub mod tax_model;
 pub mod token_model;
 pub mod user_group_model;
 pub mod user_model;
@@ -0,0 +1,194 @@
+use std::{fmt, ops::Neg, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+/// ISO 4217 currencies prices can be set in.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Currency {
+    Vnd,
+    Usd,
+    Eur,
+}
+
+impl Currency {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Vnd => "VND",
+            Self::Usd => "USD",
+            Self::Eur => "EUR",
+        }
+    }
+
+    /// Digits after the decimal point, e.g. 2 as amounts in USD are cents.
+    pub fn exponent(&self) -> u32 {
+        match self {
+            Self::Vnd => 0,
+            Self::Usd | Self::Eur => 2,
+        }
+    }
+}
+
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Currency {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "VND" => Ok(Self::Vnd),
+            "USD" => Ok(Self::Usd),
+            "EUR" => Ok(Self::Eur),
+            _ => Err(format!("Unknown currency: {}", s)),
+        }
+    }
+}
+
+/// An amount in the smallest unit of its currency, e.g. 1999 USD is $19.99.
+/// Amounts in different currencies never add up or compare.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Money {
+    pub amount_minor: i64,
+    pub currency: Currency,
+}
+
+impl Money {
+    pub fn new(amount_minor: i64, currency: Currency) -> Self {
+        Self {
+            amount_minor,
+            currency,
+        }
+    }
+
+    /// Reads an amount stored next to its currency code.
+    pub fn parse(amount_minor: i64, currency: &str) -> Result<Self, String> {
+        Ok(Self::new(amount_minor, currency.parse()?))
+    }
+
+    pub fn zero(currency: Currency) -> Self {
+        Self::new(0, currency)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.amount_minor == 0
+    }
+
+    fn same_currency(&self, other: &Money) -> Result<(), String> {
+        if self.currency != other.currency {
+            return Err(format!(
+                "Cannot mix {} and {} amounts",
+                self.currency, other.currency
+            ));
+        }
+
+        Ok(())
+    }
+
+    pub fn checked_add(self, other: Money) -> Result<Money, String> {
+        self.same_currency(&other)?;
+
+        Ok(Self::new(
+            self.amount_minor + other.amount_minor,
+            self.currency,
+        ))
+    }
+
+    pub fn checked_sub(self, other: Money) -> Result<Money, String> {
+        self.same_currency(&other)?;
+
+        Ok(Self::new(
+            self.amount_minor - other.amount_minor,
+            self.currency,
+        ))
+    }
+
+    /// The smaller of two amounts in the same currency.
+    pub fn checked_min(self, other: Money) -> Result<Money, String> {
+        self.same_currency(&other)?;
+
+        Ok(Self::new(
+            self.amount_minor.min(other.amount_minor),
+            self.currency,
+        ))
+    }
+
+    /// Rounds `amount_minor * numerator / denominator` to the nearest minor unit.
+    pub fn mul_ratio(self, numerator: i64, denominator: i64) -> Money {
+        let amount = (self.amount_minor as i128 * numerator as i128) as f64 / denominator as f64;
+
+        Self::new(amount.round() as i64, self.currency)
+    }
+}
+
+impl Neg for Money {
+    type Output = Money;
+
+    fn neg(self) -> Money {
+        Self::new(-self.amount_minor, self.currency)
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let exponent = self.currency.exponent();
+
+        if exponent == 0 {
+            return write!(f, "{} {}", self.amount_minor, self.currency);
+        }
+
+        let scale = 10_i64.pow(exponent);
+        let sign = if self.amount_minor < 0 { "-" } else { "" };
+        let amount = self.amount_minor.unsigned_abs();
+
+        write!(
+            f,
+            "{}{}.{:0width$} {}",
+            sign,
+            amount / scale as u64,
+            amount % scale as u64,
+            self.currency,
+            width = exponent as usize
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_money() {
+        let vnd = Money::new(49000, Currency::Vnd);
+        let usd = Money::new(1999, Currency::Usd);
+
+        assert_eq!(vnd.to_string(), "49000 VND");
+        assert_eq!(usd.to_string(), "19.99 USD");
+        assert_eq!(Money::new(-5, Currency::Eur).to_string(), "-0.05 EUR");
+
+        assert_eq!(
+            vnd.checked_sub(Money::new(9800, Currency::Vnd)),
+            Ok(Money::new(39200, Currency::Vnd))
+        );
+        assert_eq!(
+            usd.checked_add(Money::new(1, Currency::Usd)),
+            Ok(Money::new(2000, Currency::Usd))
+        );
+        assert!(vnd.checked_add(usd).is_err());
+        assert!(vnd.checked_sub(usd).is_err());
+        assert!(vnd.checked_min(usd).is_err());
+
+        assert_eq!(usd.mul_ratio(1, 3), Money::new(666, Currency::Usd));
+        assert_eq!(-usd, Money::new(-1999, Currency::Usd));
+        assert_eq!(Money::parse(100, "USD"), Ok(Money::new(100, Currency::Usd)));
+        assert!(Money::parse(100, "VN").is_err());
+
+        assert_eq!(
+            serde_json::to_value(vnd).unwrap(),
+            serde_json::json!({ "amount_minor": 49000, "currency": "VND" })
+        );
+    }
+}
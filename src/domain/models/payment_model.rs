@@ -1,10 +1,90 @@
+use std::{fmt, str::FromStr};
+
 use serde::{Deserialize, Serialize};
 
+use super::money_model::Money;
+
+#[allow(dead_code)]
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Payment {
     pub id: uuid::Uuid,
     pub subscription_id: uuid::Uuid,
-    pub amount: f64,
+    pub amount: Money,
+    pub status: PaymentStatus,
     pub payment_date: chrono::DateTime<chrono::Utc>,
     pub payment_method: String,
 }
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PaymentStatus {
+    /// Opened with the provider, waiting for the money.
+    Pending,
+    Succeeded,
+    Failed,
+    /// Paid back in full.
+    Refunded,
+}
+
+impl PaymentStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Succeeded => "succeeded",
+            Self::Failed => "failed",
+            Self::Refunded => "refunded",
+        }
+    }
+
+    pub fn can_transition_to(&self, to: PaymentStatus) -> bool {
+        use PaymentStatus::*;
+
+        matches!(
+            (self, to),
+            (Pending, Succeeded | Failed) | (Succeeded, Refunded)
+        )
+    }
+}
+
+impl fmt::Display for PaymentStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for PaymentStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(Self::Pending),
+            "succeeded" => Ok(Self::Succeeded),
+            "failed" => Ok(Self::Failed),
+            "refunded" => Ok(Self::Refunded),
+            _ => Err(format!("Unknown payment status: {}", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payment_status_transitions() {
+        use PaymentStatus::*;
+
+        assert!(Pending.can_transition_to(Succeeded));
+        assert!(Pending.can_transition_to(Failed));
+        assert!(Succeeded.can_transition_to(Refunded));
+
+        assert!(!Failed.can_transition_to(Succeeded));
+        assert!(!Pending.can_transition_to(Refunded));
+        assert!(!Refunded.can_transition_to(Succeeded));
+        assert!(!Succeeded.can_transition_to(Pending));
+
+        for status in [Pending, Succeeded, Failed, Refunded] {
+            assert_eq!(status.as_str().parse::<PaymentStatus>(), Ok(status));
+        }
+    }
+}
@@ -1,14 +1,123 @@
+use std::{fmt, str::FromStr};
+
+use chrono::{Months, NaiveDateTime};
 use serde::{Deserialize, Serialize};
 
+use super::money_model::Money;
+
+#[allow(dead_code)]
 #[derive(Serialize, Deserialize, Debug)]
 pub struct PlanModel {
     pub id: uuid::Uuid,
     pub name: String,
     pub description: String,
-    pub price: f64,
+    pub price: Money,
     pub is_active: bool,
     pub tags: Vec<String>,
     pub trial_days: i32,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BillingInterval {
+    Day,
+    Month,
+    Year,
+}
+
+impl BillingInterval {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Day => "day",
+            Self::Month => "month",
+            Self::Year => "year",
+        }
+    }
+}
+
+impl fmt::Display for BillingInterval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for BillingInterval {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "day" => Ok(Self::Day),
+            "month" => Ok(Self::Month),
+            "year" => Ok(Self::Year),
+            _ => Err(format!("Unknown billing interval: {}", s)),
+        }
+    }
+}
+
+/// A billing period of `count` intervals, e.g. a quarter is 3 months.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BillingPeriod {
+    pub interval: BillingInterval,
+    pub count: u32,
+}
+
+impl BillingPeriod {
+    pub fn new(interval: &str, count: i32) -> Result<Self, String> {
+        Ok(Self {
+            interval: interval.parse()?,
+            count: u32::try_from(count)
+                .ok()
+                .filter(|count| *count > 0)
+                .ok_or_else(|| format!("Invalid billing interval count: {}", count))?,
+        })
+    }
+
+    /// Start of the `n`th period after `anchor`. Months are added on the
+    /// calendar and clamped to the end of shorter months, so a period
+    /// anchored on Jan 31 ends on Feb 29, then Mar 31.
+    pub fn nth(&self, anchor: NaiveDateTime, n: u32) -> NaiveDateTime {
+        let count = self.count * n;
+
+        match self.interval {
+            BillingInterval::Day => anchor + chrono::Duration::days(count as i64),
+            BillingInterval::Month => anchor + Months::new(count),
+            BillingInterval::Year => anchor + Months::new(count * 12),
+        }
+    }
+
+    pub fn end_of(&self, start: NaiveDateTime) -> NaiveDateTime {
+        self.nth(start, 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDateTime {
+        chrono::NaiveDate::from_ymd_opt(y, m, d)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_billing_period() {
+        let monthly = BillingPeriod::new("month", 1).unwrap();
+        let quarterly = BillingPeriod::new("month", 3).unwrap();
+        let yearly = BillingPeriod::new("year", 1).unwrap();
+        let custom = BillingPeriod::new("day", 45).unwrap();
+
+        assert_eq!(monthly.end_of(date(2024, 1, 15)), date(2024, 2, 15));
+        assert_eq!(monthly.end_of(date(2024, 1, 31)), date(2024, 2, 29));
+        assert_eq!(monthly.nth(date(2024, 1, 31), 2), date(2024, 3, 31));
+        assert_eq!(quarterly.end_of(date(2024, 11, 30)), date(2025, 2, 28));
+        assert_eq!(yearly.end_of(date(2024, 2, 29)), date(2025, 2, 28));
+        assert_eq!(custom.end_of(date(2024, 1, 1)), date(2024, 2, 15));
+
+        assert!(BillingPeriod::new("week", 1).is_err());
+        assert!(BillingPeriod::new("month", 0).is_err());
+    }
+}
@@ -0,0 +1,9 @@
+use serde::{Deserialize, Serialize};
+
+/// Where the effective value of a limit came from.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LimitSource {
+    Plan,
+    Override,
+}
@@ -1,9 +1,11 @@
 use serde::{Deserialize, Serialize};
 
+#[allow(dead_code)]
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ResourceModel {
     pub id: uuid::Uuid,
     pub plan_id: uuid::Uuid,
+    pub resource_type_id: uuid::Uuid,
     pub max: i64,
     pub name: String,
     pub description: String,
@@ -0,0 +1,2 @@
+pub const USERS: &str = "users";
+pub const ROLES: &str = "roles";
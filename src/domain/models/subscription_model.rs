@@ -1,5 +1,8 @@
+use std::{fmt, str::FromStr};
+
 use serde::{Deserialize, Serialize};
 
+#[allow(dead_code)]
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Subscription {
     pub id: uuid::Uuid,
@@ -7,7 +10,137 @@ pub struct Subscription {
     pub plan_id: uuid::Uuid,
     pub start_date: chrono::DateTime<chrono::Utc>,
     pub end_date: chrono::DateTime<chrono::Utc>,
+    pub status: SubscriptionStatus,
     pub is_active: bool,
     pub trial_start_date: chrono::DateTime<chrono::Utc>,
     pub trial_end_date: chrono::DateTime<chrono::Utc>,
 }
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SubscriptionStatus {
+    Incomplete,
+    Trialing,
+    Active,
+    PastDue,
+    Paused,
+    Canceled,
+    Expired,
+}
+
+impl SubscriptionStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Incomplete => "incomplete",
+            Self::Trialing => "trialing",
+            Self::Active => "active",
+            Self::PastDue => "past_due",
+            Self::Paused => "paused",
+            Self::Canceled => "canceled",
+            Self::Expired => "expired",
+        }
+    }
+
+    /// Statuses that give access to the plan, mirrored by the `is_active` column.
+    pub fn is_active(&self) -> bool {
+        matches!(self, Self::Trialing | Self::Active | Self::PastDue)
+    }
+
+    pub fn can_transition_to(&self, to: SubscriptionStatus) -> bool {
+        use SubscriptionStatus::*;
+
+        matches!(
+            (self, to),
+            (Incomplete, Trialing | Active | Canceled | Expired)
+                | (Trialing, Active | PastDue | Canceled | Expired)
+                | (Active, PastDue | Paused | Canceled | Expired)
+                | (PastDue, Active | Canceled | Expired)
+                | (Paused, Active | Canceled | Expired)
+        )
+    }
+}
+
+impl fmt::Display for SubscriptionStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for SubscriptionStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "incomplete" => Ok(Self::Incomplete),
+            "trialing" => Ok(Self::Trialing),
+            "active" => Ok(Self::Active),
+            "past_due" => Ok(Self::PastDue),
+            "paused" => Ok(Self::Paused),
+            "canceled" => Ok(Self::Canceled),
+            "expired" => Ok(Self::Expired),
+            _ => Err(format!("Unknown subscription status: {}", s)),
+        }
+    }
+}
+
+/// Who triggered a subscription status change.
+#[derive(Debug, Clone, Copy)]
+pub enum Actor {
+    Sys(uuid::Uuid),
+    User(uuid::Uuid),
+    System,
+}
+
+impl Actor {
+    pub fn actor_type(&self) -> &'static str {
+        match self {
+            Self::Sys(_) => "sys",
+            Self::User(_) => "user",
+            Self::System => "system",
+        }
+    }
+
+    pub fn actor_id(&self) -> Option<uuid::Uuid> {
+        match self {
+            Self::Sys(id) | Self::User(id) => Some(*id),
+            Self::System => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_transitions() {
+        use SubscriptionStatus::*;
+
+        assert!(Incomplete.can_transition_to(Active));
+        assert!(Trialing.can_transition_to(Active));
+        assert!(Active.can_transition_to(Paused));
+        assert!(Paused.can_transition_to(Active));
+        assert!(PastDue.can_transition_to(Active));
+
+        assert!(!Active.can_transition_to(Active));
+        assert!(!Active.can_transition_to(Trialing));
+        assert!(!Paused.can_transition_to(PastDue));
+        assert!(!Canceled.can_transition_to(Active));
+        assert!(!Expired.can_transition_to(Active));
+    }
+
+    #[test]
+    fn test_status_round_trip() {
+        for status in [
+            SubscriptionStatus::Incomplete,
+            SubscriptionStatus::Trialing,
+            SubscriptionStatus::Active,
+            SubscriptionStatus::PastDue,
+            SubscriptionStatus::Paused,
+            SubscriptionStatus::Canceled,
+            SubscriptionStatus::Expired,
+        ] {
+            assert_eq!(status.as_str().parse::<SubscriptionStatus>(), Ok(status));
+        }
+    }
+}
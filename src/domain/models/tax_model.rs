@@ -0,0 +1,128 @@
+use serde::Serialize;
+
+use super::money_model::Money;
+
+/// A tax rate in basis points, e.g. 1000 is 10%. Inclusive rates are part of
+/// the price (VAT), exclusive rates are added on top of it (sales tax).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TaxRate {
+    pub rate_bps: i32,
+    pub is_inclusive: bool,
+}
+
+/// How much of a charge is tax.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TaxBreakdown {
+    pub subtotal: Money,
+    pub tax: Money,
+    pub total: Money,
+}
+
+impl TaxRate {
+    pub fn new(rate_bps: i32, is_inclusive: bool) -> Result<Self, String> {
+        if !(0..=10000).contains(&rate_bps) {
+            return Err("rate_bps must be between 0 and 10000".to_string());
+        }
+
+        Ok(Self {
+            rate_bps,
+            is_inclusive,
+        })
+    }
+
+    /// No tax, for customers without a billing region or a matching rule.
+    pub fn none() -> Self {
+        Self {
+            rate_bps: 0,
+            is_inclusive: false,
+        }
+    }
+
+    /// Tax on a charge of `price`, added on top of it for exclusive rates.
+    pub fn charge(&self, price: Money) -> TaxBreakdown {
+        if self.is_inclusive {
+            return self.split(price);
+        }
+
+        let tax = price.mul_ratio(self.rate_bps as i64, 10000);
+
+        TaxBreakdown {
+            subtotal: price,
+            tax,
+            total: Money::new(price.amount_minor + tax.amount_minor, price.currency),
+        }
+    }
+
+    /// Tax already included in `total`, e.g. in an amount paid.
+    pub fn split(&self, total: Money) -> TaxBreakdown {
+        let tax = total.mul_ratio(self.rate_bps as i64, 10000 + self.rate_bps as i64);
+
+        TaxBreakdown {
+            subtotal: Money::new(total.amount_minor - tax.amount_minor, total.currency),
+            tax,
+            total,
+        }
+    }
+}
+
+/// Normalizes a tax region: an ISO 3166 country code, optionally with a
+/// subdivision, e.g. `VN` or `US-CA`.
+pub fn parse_region(region: &str) -> Result<String, String> {
+    let region = region.trim().to_uppercase();
+    let (country, subdivision) = match region.split_once('-') {
+        Some((country, subdivision)) => (country, Some(subdivision)),
+        None => (region.as_str(), None),
+    };
+
+    let valid = country.len() == 2
+        && country.chars().all(|c| c.is_ascii_uppercase())
+        && subdivision.is_none_or(|subdivision| {
+            (1..=3).contains(&subdivision.len())
+                && subdivision.chars().all(|c| c.is_ascii_alphanumeric())
+        });
+
+    if !valid {
+        return Err(format!("Invalid region: {}", region));
+    }
+
+    Ok(region)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::models::money_model::Currency;
+
+    #[test]
+    fn test_tax_rate() {
+        let vnd = |amount| Money::new(amount, Currency::Vnd);
+        let breakdown = |subtotal, tax, total| TaxBreakdown {
+            subtotal: vnd(subtotal),
+            tax: vnd(tax),
+            total: vnd(total),
+        };
+
+        let exclusive = TaxRate::new(1000, false).unwrap();
+        let inclusive = TaxRate::new(1000, true).unwrap();
+
+        assert_eq!(exclusive.charge(vnd(49000)), breakdown(49000, 4900, 53900));
+        assert_eq!(inclusive.charge(vnd(49000)), breakdown(44545, 4455, 49000));
+        assert_eq!(exclusive.split(vnd(53900)), breakdown(49000, 4900, 53900));
+        assert_eq!(
+            TaxRate::none().charge(vnd(49000)),
+            breakdown(49000, 0, 49000)
+        );
+
+        assert!(TaxRate::new(-1, false).is_err());
+        assert!(TaxRate::new(10001, false).is_err());
+    }
+
+    #[test]
+    fn test_parse_region() {
+        assert_eq!(parse_region(" vn "), Ok("VN".to_string()));
+        assert_eq!(parse_region("us-ca"), Ok("US-CA".to_string()));
+        assert!(parse_region("VNM").is_err());
+        assert!(parse_region("US-").is_err());
+        assert!(parse_region("").is_err());
+    }
+}
@@ -1,3 +1,11 @@
+use crate::{
+    domain::models::dunning_model::DunningPolicy,
+    infra::{
+        documents::Seller,
+        payments::{ManualProvider, MockProvider, PaymentProviders, Provider},
+    },
+};
+
 #[derive(Debug, Clone)]
 pub struct Config {
     pub host: String,
@@ -5,7 +13,15 @@ pub struct Config {
     pub database_url: String,
     pub jwt_secret: String,
     pub refresh_secret: String,
+    #[allow(dead_code)]
     pub jwt_expire_in: usize,
+    pub scheduler_interval_secs: u64,
+    pub dunning: DunningPolicy,
+    /// Emails are written to this directory instead of being logged when set.
+    pub mail_sink_dir: Option<String>,
+    /// Printed on invoice documents.
+    pub seller: Seller,
+    pub payment_providers: PaymentProviders,
 }
 
 impl Config {
@@ -21,6 +37,48 @@ impl Config {
         let jwt_secret: String = std::env::var("SECRET_KEY").expect("JWT_SECRET must be set");
         let refresh_secret: String =
             std::env::var("REFRESH_SECRET_KEY").expect("REFRESH_SECRET must be set");
+        let scheduler_interval_secs = std::env::var("SCHEDULER_INTERVAL_SECS")
+            .unwrap_or("60".to_owned())
+            .parse()
+            .unwrap();
+
+        let default_dunning = DunningPolicy::default();
+        let dunning = DunningPolicy::new(
+            std::env::var("DUNNING_RETRY_DAYS")
+                .map(|days| DunningPolicy::parse_retry_days(&days).unwrap())
+                .unwrap_or(default_dunning.retry_days),
+            std::env::var("DUNNING_READ_ONLY_AFTER_DAYS")
+                .map(|days| days.parse().unwrap())
+                .unwrap_or(default_dunning.read_only_after_days),
+            std::env::var("DUNNING_GRACE_DAYS")
+                .map(|days| days.parse().unwrap())
+                .unwrap_or(default_dunning.grace_days),
+        )
+        .unwrap();
+        let mail_sink_dir = std::env::var("MAIL_SINK_DIR").ok();
+
+        let default_seller = Seller::default();
+        let seller = Seller {
+            name: std::env::var("SELLER_NAME").unwrap_or(default_seller.name),
+            address: std::env::var("SELLER_ADDRESS").ok(),
+            tax_id: std::env::var("SELLER_TAX_ID").ok(),
+            email: std::env::var("SELLER_EMAIL").ok(),
+            brand_color: std::env::var("SELLER_BRAND_COLOR").unwrap_or(default_seller.brand_color),
+            einvoice_series: std::env::var("EINVOICE_SERIES")
+                .unwrap_or(default_seller.einvoice_series),
+        };
+        seller.brand_rgb().unwrap();
+
+        let mut providers = vec![Provider::Manual(ManualProvider {
+            bank_account: std::env::var("BANK_TRANSFER_ACCOUNT").ok(),
+            webhook_secret: std::env::var("MANUAL_PAYMENTS_WEBHOOK_SECRET").ok(),
+        })];
+        providers.extend(
+            std::env::var("MOCK_PAYMENTS_WEBHOOK_SECRET")
+                .ok()
+                .map(|secret| Provider::Mock(MockProvider::new(secret))),
+        );
+        let payment_providers = PaymentProviders { providers };
 
         Self {
             host,
@@ -29,6 +87,11 @@ impl Config {
             jwt_secret,
             refresh_secret,
             jwt_expire_in: now.timestamp() as usize + 60 * 60,
+            scheduler_interval_secs,
+            dunning,
+            mail_sink_dir,
+            seller,
+            payment_providers,
         }
     }
 }
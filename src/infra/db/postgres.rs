@@ -18,12 +18,14 @@ pub async fn connect() -> PgPool {
 
 #[cfg(test)]
 mod tests {
+    use dotenv::dotenv;
+
     use super::*;
 
     #[tokio::test]
     async fn test_connect() {
         dotenv().ok();
         let pool: sqlx::Pool<sqlx::Postgres> = connect().await;
-        assert_eq!(pool.is_closed(), false);
+        assert!(!pool.is_closed());
     }
 }
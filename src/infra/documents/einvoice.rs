@@ -0,0 +1,113 @@
+use crate::domain::models::{
+    einvoice_model::{self, EInvoice, EInvoiceLine, EInvoiceParty, LineNature, VatRate},
+    invoice_model::{self, InvoiceLineKind, InvoiceStatus},
+    tax_model::TaxRate,
+};
+
+use super::InvoiceDocument;
+
+/// The e-invoice of an issued invoice. Lines are shown before VAT, so VAT
+/// included in them is taken out, the rounding left over going to the
+/// first line.
+fn einvoice(document: &InvoiceDocument) -> Result<EInvoice, Vec<String>> {
+    let invoice = &document.invoice;
+
+    let (number, issued_at) = match (invoice.status, &invoice.number, invoice.issued_at) {
+        (InvoiceStatus::Draft, _, _) => {
+            return Err(vec!["Draft invoices can't be exported".to_string()])
+        }
+        (InvoiceStatus::Void, _, _) => {
+            return Err(vec!["Voided invoices are not exported".to_string()])
+        }
+        (_, Some(number), Some(issued_at)) => (number, issued_at),
+        _ => return Err(vec!["The invoice has not been issued".to_string()]),
+    };
+    let (year, sequence) = invoice_model::parse_number(number)
+        .ok_or_else(|| vec![format!("Invalid invoice number: {}", number)])?;
+
+    let rate = TaxRate {
+        rate_bps: invoice.tax_rate_bps,
+        is_inclusive: invoice.tax_inclusive,
+    };
+    let before_vat = |amount| match rate.is_inclusive {
+        true => rate.split(amount).subtotal.amount_minor,
+        false => amount.amount_minor,
+    };
+
+    let mut lines: Vec<EInvoiceLine> = invoice
+        .lines
+        .iter()
+        .map(|line| {
+            let nature =
+                match line.kind == InvoiceLineKind::Discount || line.amount.amount_minor < 0 {
+                    true => LineNature::Discount,
+                    false => LineNature::Goods,
+                };
+
+            EInvoiceLine {
+                nature,
+                name: line.description.clone(),
+                quantity: line.quantity,
+                unit_price: before_vat(line.unit_amount).abs(),
+                amount: before_vat(line.amount).abs(),
+            }
+        })
+        .collect();
+
+    let net: i64 = lines
+        .iter()
+        .map(|line| match line.nature {
+            LineNature::Goods => line.amount,
+            LineNature::Discount => -line.amount,
+        })
+        .sum();
+
+    if let Some(first) = lines
+        .iter_mut()
+        .find(|line| line.nature == LineNature::Goods)
+    {
+        first.amount += invoice.subtotal.amount_minor - net;
+
+        if first.quantity == 1 {
+            first.unit_price = first.amount;
+        }
+    }
+
+    let (seller, buyer) = (&document.seller, &document.buyer);
+
+    Ok(EInvoice {
+        series: einvoice_model::series(year, &seller.einvoice_series),
+        number: sequence as i64,
+        issued_on: issued_at.date(),
+        currency: invoice.total.currency,
+        seller: EInvoiceParty {
+            name: seller.name.clone(),
+            tax_code: seller.tax_id.clone(),
+            address: seller.address.clone(),
+            email: seller.email.clone(),
+        },
+        buyer: EInvoiceParty {
+            name: buyer.name.clone(),
+            tax_code: buyer.tax_id.clone(),
+            address: buyer.address.clone(),
+            email: Some(buyer.email.clone()),
+        },
+        lines,
+        vat_rate: match (&invoice.tax_name, invoice.tax_rate_bps) {
+            (None, 0) => VatRate::NotTaxable,
+            (_, bps) => VatRate::Percent(bps),
+        },
+        subtotal: invoice.subtotal.amount_minor,
+        vat: invoice.tax_amount.amount_minor,
+        total: invoice.total.amount_minor,
+    })
+}
+
+/// The invoice as e-invoice XML, or the rules of the schema it breaks.
+pub fn render_einvoice(document: &InvoiceDocument) -> Result<String, Vec<String>> {
+    let einvoice = einvoice(document)?;
+
+    einvoice.validate()?;
+
+    Ok(einvoice.to_xml())
+}
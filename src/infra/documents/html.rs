@@ -0,0 +1,23 @@
+use askama::Template;
+
+use super::{DocumentView, InvoiceDocument};
+
+#[derive(Template)]
+#[template(path = "invoice.html")]
+struct InvoiceTemplate {
+    view: DocumentView,
+}
+
+pub fn render_html(document: &InvoiceDocument) -> Result<String, String> {
+    // The color is written into the stylesheet, so only valid ones are let through.
+    document.seller.brand_rgb()?;
+
+    InvoiceTemplate {
+        view: document.view()?,
+    }
+    .render()
+    .map_err(|e| {
+        tracing::error!("Failed to render invoice: {:?}", e);
+        "Failed to render invoice".to_string()
+    })
+}
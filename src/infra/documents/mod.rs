@@ -0,0 +1,354 @@
+mod einvoice;
+mod html;
+mod pdf;
+
+use chrono::NaiveDateTime;
+
+use crate::domain::{
+    dtos::invoice_dtos::InvoiceResponse,
+    models::{invoice_model::InvoiceStatus, money_model::Money},
+};
+
+pub use einvoice::render_einvoice;
+pub use html::render_html;
+pub use pdf::render_pdf;
+
+/// The company issuing the invoices, printed in the document header.
+#[derive(Debug, Clone)]
+pub struct Seller {
+    pub name: String,
+    pub address: Option<String>,
+    pub tax_id: Option<String>,
+    pub email: Option<String>,
+    /// Accent color of the documents as `#rrggbb`.
+    pub brand_color: String,
+    /// The two letters ending the e-invoice series registered with the tax
+    /// authority, e.g. `AA` in `C24TAA`.
+    pub einvoice_series: String,
+}
+
+impl Default for Seller {
+    fn default() -> Self {
+        Self {
+            name: "CRM System".to_string(),
+            address: None,
+            tax_id: None,
+            email: None,
+            brand_color: "#1f6feb".to_string(),
+            einvoice_series: "AA".to_string(),
+        }
+    }
+}
+
+impl Seller {
+    /// The brand color as RGB components between 0 and 1.
+    pub fn brand_rgb(&self) -> Result<(f32, f32, f32), String> {
+        let invalid = || format!("Invalid brand color: {}", self.brand_color);
+
+        let hex = self
+            .brand_color
+            .strip_prefix('#')
+            .filter(|hex| hex.len() == 6)
+            .ok_or_else(invalid)?;
+        let component = |i: usize| {
+            u8::from_str_radix(&hex[i..i + 2], 16)
+                .map(|c| c as f32 / 255.0)
+                .map_err(|_| invalid())
+        };
+
+        Ok((component(0)?, component(2)?, component(4)?))
+    }
+}
+
+/// The billing details of the customer, falling back to the account name.
+#[derive(Debug, Clone)]
+pub struct Buyer {
+    pub name: String,
+    pub email: String,
+    pub address: Option<String>,
+    pub region: Option<String>,
+    pub tax_id: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DocumentPayment {
+    pub paid_at: Option<NaiveDateTime>,
+    pub method: Option<String>,
+    pub amount: Money,
+}
+
+/// Everything printed on an invoice. Documents only depend on this data, so
+/// the same invoice always renders to the same bytes.
+pub struct InvoiceDocument {
+    pub seller: Seller,
+    pub buyer: Buyer,
+    pub invoice: InvoiceResponse,
+    pub payments: Vec<DocumentPayment>,
+}
+
+/// The texts of a document, shared by the HTML and PDF layouts.
+struct DocumentView {
+    title: &'static str,
+    brand_color: String,
+    seller_name: String,
+    seller_details: Vec<String>,
+    buyer_name: String,
+    buyer_details: Vec<String>,
+    facts: Vec<(&'static str, String)>,
+    lines: Vec<LineView>,
+    totals: Vec<(String, String)>,
+    amount_due: (String, String),
+    payments: Vec<(String, String, String)>,
+    status_note: String,
+}
+
+struct LineView {
+    description: String,
+    period: Option<String>,
+    quantity: String,
+    unit_amount: String,
+    amount: String,
+}
+
+fn date(value: NaiveDateTime) -> String {
+    value.format("%Y-%m-%d").to_string()
+}
+
+fn period(start: Option<NaiveDateTime>, end: Option<NaiveDateTime>) -> Option<String> {
+    Some(format!("{} – {}", date(start?), date(end?)))
+}
+
+/// A tax rate in basis points as a percentage, e.g. `8.25%`.
+fn rate(bps: i32) -> String {
+    let rate = format!("{}.{:02}", bps / 100, bps % 100);
+
+    format!("{}%", rate.trim_end_matches('0').trim_end_matches('.'))
+}
+
+impl InvoiceDocument {
+    /// Paid invoices are printed as receipts.
+    pub fn title(&self) -> &'static str {
+        match self.invoice.status {
+            InvoiceStatus::Paid => "Receipt",
+            _ => "Invoice",
+        }
+    }
+
+    /// Name of the downloaded file, e.g. `INV-2024-000042.pdf`.
+    pub fn file_name(&self, extension: &str) -> String {
+        match &self.invoice.number {
+            Some(number) => format!("{}.{}", number, extension),
+            None => format!("{}.{}", self.invoice.id, extension),
+        }
+    }
+
+    fn view(&self) -> Result<DocumentView, String> {
+        let invoice = &self.invoice;
+        let currency = invoice.total.currency;
+
+        let mut seller_details = vec![];
+        seller_details.extend(self.seller.address.clone());
+        seller_details.extend(
+            self.seller
+                .tax_id
+                .iter()
+                .map(|id| format!("Tax ID: {}", id)),
+        );
+        seller_details.extend(self.seller.email.clone());
+
+        let mut buyer_details = vec![];
+        buyer_details.extend(self.buyer.address.clone());
+        buyer_details.extend(self.buyer.region.clone());
+        buyer_details.extend(self.buyer.tax_id.iter().map(|id| format!("Tax ID: {}", id)));
+        buyer_details.push(self.buyer.email.clone());
+
+        let mut facts = vec![(
+            "Number",
+            invoice
+                .number
+                .clone()
+                .unwrap_or_else(|| "Draft".to_string()),
+        )];
+        facts.extend(invoice.issued_at.map(|at| ("Issued", date(at))));
+        facts.extend(invoice.due_at.map(|at| ("Due", date(at))));
+        facts.extend(period(invoice.period_start, invoice.period_end).map(|p| ("Period", p)));
+        facts.push(("Status", invoice.status.label().to_string()));
+
+        let tax_label = match &invoice.tax_name {
+            Some(name) => format!("{} ({})", name, rate(invoice.tax_rate_bps)),
+            None => format!("Tax ({})", rate(invoice.tax_rate_bps)),
+        };
+
+        let paid = self
+            .payments
+            .iter()
+            .try_fold(Money::zero(currency), |total, payment| {
+                total.checked_add(payment.amount)
+            })?;
+        let amount_due = match invoice.status {
+            InvoiceStatus::Open | InvoiceStatus::Uncollectible => {
+                invoice.total.checked_sub(paid)?
+            }
+            _ => Money::zero(currency),
+        };
+
+        let status_note = match invoice.status {
+            InvoiceStatus::Paid => match invoice.paid_at {
+                Some(at) => format!("Paid in full on {}. Thank you!", date(at)),
+                None => "Paid in full. Thank you!".to_string(),
+            },
+            InvoiceStatus::Void => format!(
+                "This invoice was voided{}{}.",
+                invoice
+                    .voided_at
+                    .map(|at| format!(" on {}", date(at)))
+                    .unwrap_or_default(),
+                invoice
+                    .void_reason
+                    .as_ref()
+                    .map(|reason| format!(": {}", reason))
+                    .unwrap_or_default()
+            ),
+            _ => match invoice.due_at {
+                Some(at) => format!("Please pay {} by {}.", amount_due, date(at)),
+                None => format!("Please pay {}.", amount_due),
+            },
+        };
+
+        Ok(DocumentView {
+            title: self.title(),
+            brand_color: self.seller.brand_color.clone(),
+            seller_name: self.seller.name.clone(),
+            seller_details,
+            buyer_name: self.buyer.name.clone(),
+            buyer_details,
+            facts,
+            lines: invoice
+                .lines
+                .iter()
+                .map(|line| LineView {
+                    description: line.description.clone(),
+                    period: period(line.period_start, line.period_end),
+                    quantity: line.quantity.to_string(),
+                    unit_amount: line.unit_amount.to_string(),
+                    amount: line.amount.to_string(),
+                })
+                .collect(),
+            totals: vec![
+                ("Subtotal".to_string(), invoice.subtotal.to_string()),
+                (tax_label, invoice.tax_amount.to_string()),
+                ("Total".to_string(), invoice.total.to_string()),
+                ("Amount paid".to_string(), paid.to_string()),
+            ],
+            amount_due: ("Amount due".to_string(), amount_due.to_string()),
+            payments: self
+                .payments
+                .iter()
+                .map(|payment| {
+                    (
+                        payment.paid_at.map(date).unwrap_or_default(),
+                        payment.method.clone().unwrap_or_default(),
+                        payment.amount.to_string(),
+                    )
+                })
+                .collect(),
+            status_note,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{
+        dtos::invoice_dtos::InvoiceLineResponse,
+        models::{
+            invoice_model::{InvoiceKind, InvoiceLineKind},
+            money_model::Currency,
+        },
+    };
+
+    fn document() -> InvoiceDocument {
+        let vnd = |amount| Money::new(amount, Currency::Vnd);
+        let day = |d: u32| {
+            chrono::NaiveDate::from_ymd_opt(2024, 1, d)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap()
+        };
+
+        InvoiceDocument {
+            seller: Seller {
+                address: Some("12 Lê Lợi, Quận 1, TP. Hồ Chí Minh".to_string()),
+                tax_id: Some("0312345678".to_string()),
+                ..Default::default()
+            },
+            buyer: Buyer {
+                name: "Nguyễn Văn Ánh".to_string(),
+                email: "anh@example.com".to_string(),
+                address: Some("Đường Trần Hưng Đạo, Hà Nội".to_string()),
+                region: Some("VN".to_string()),
+                tax_id: None,
+            },
+            invoice: InvoiceResponse {
+                id: uuid::Uuid::nil(),
+                number: Some("INV-2024-000042".to_string()),
+                kind: InvoiceKind::Period,
+                status: InvoiceStatus::Paid,
+                subscription_id: uuid::Uuid::nil(),
+                user_id: uuid::Uuid::nil(),
+                period_start: Some(day(1)),
+                period_end: Some(day(31)),
+                subtotal: vnd(49000),
+                tax_amount: vnd(4900),
+                tax_rate_bps: 1000,
+                tax_name: Some("Thuế GTGT".to_string()),
+                tax_inclusive: false,
+                total: vnd(53900),
+                issued_at: Some(day(1)),
+                due_at: Some(day(1)),
+                paid_at: Some(day(2)),
+                voided_at: None,
+                void_reason: None,
+                lines: vec![InvoiceLineResponse {
+                    kind: InvoiceLineKind::Plan,
+                    description: "Gói Premium".to_string(),
+                    quantity: 1,
+                    unit_amount: vnd(49000),
+                    amount: vnd(49000),
+                    period_start: Some(day(1)),
+                    period_end: Some(day(31)),
+                }],
+            },
+            payments: vec![DocumentPayment {
+                paid_at: Some(day(2)),
+                method: Some("BANK_TRANSFER".to_string()),
+                amount: vnd(53900),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_render_invoice_documents() {
+        let document = document();
+
+        let html = render_html(&document).unwrap();
+        assert_eq!(html, render_html(&document).unwrap());
+        assert!(html.contains("Nguyễn Văn Ánh"));
+        assert!(html.contains("Thuế GTGT (10%)"));
+        assert!(html.contains("Paid in full on 2024-01-02"));
+
+        let pdf = render_pdf(&document).unwrap();
+        assert_eq!(pdf, render_pdf(&document).unwrap());
+        assert!(pdf.starts_with(b"%PDF-"));
+        assert_eq!(document.file_name("pdf"), "INV-2024-000042.pdf");
+
+        assert_eq!(rate(1000), "10%");
+        assert_eq!(rate(825), "8.25%");
+        assert_eq!(rate(0), "0%");
+        assert_eq!(
+            Seller::default().brand_rgb().map(|(r, _, _)| r < 0.2),
+            Ok(true)
+        );
+    }
+}
@@ -0,0 +1,521 @@
+use std::collections::BTreeMap;
+
+use pdf_writer::{
+    types::{CidFontType, FontFlags, SystemInfo, UnicodeCmap},
+    Content, Finish, Name, Pdf, Rect, Ref, Str, TextStr,
+};
+use ttf_parser::{Face, GlyphId};
+use unicode_normalization::UnicodeNormalization;
+
+use super::{DocumentView, InvoiceDocument};
+
+// DejaVu Sans covers the precomposed Vietnamese letters, so text can be
+// written glyph by glyph without shaping.
+static REGULAR: &[u8] = include_bytes!("../../../assets/fonts/DejaVuSans.ttf");
+static BOLD: &[u8] = include_bytes!("../../../assets/fonts/DejaVuSans-Bold.ttf");
+
+/// A4 in points.
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 50.0;
+const RIGHT: f32 = PAGE_WIDTH - MARGIN;
+
+const TEXT: (f32, f32, f32) = (0.13, 0.13, 0.13);
+const MUTED: (f32, f32, f32) = (0.4, 0.4, 0.4);
+const RULE: (f32, f32, f32) = (0.85, 0.85, 0.85);
+
+const QUANTITY_RIGHT: f32 = 360.0;
+const UNIT_AMOUNT_RIGHT: f32 = 455.0;
+const DESCRIPTION_WIDTH: f32 = 250.0;
+
+#[derive(Clone, Copy)]
+enum Style {
+    Regular,
+    Bold,
+}
+
+struct Font {
+    face: Face<'static>,
+    data: &'static [u8],
+    base_name: &'static [u8],
+    resource: &'static [u8],
+    /// The glyphs written so far with the character each one shows.
+    used: BTreeMap<u16, char>,
+}
+
+impl Font {
+    fn new(
+        data: &'static [u8],
+        base_name: &'static [u8],
+        resource: &'static [u8],
+    ) -> Result<Self, String> {
+        let face = Face::parse(data, 0).map_err(|e| {
+            tracing::error!("Failed to load font: {:?}", e);
+            "Failed to load font".to_string()
+        })?;
+
+        Ok(Self {
+            face,
+            data,
+            base_name,
+            resource,
+            used: BTreeMap::new(),
+        })
+    }
+
+    fn glyphs(&self, text: &str) -> impl Iterator<Item = (GlyphId, char)> + '_ {
+        text.nfc()
+            .map(|c| (self.face.glyph_index(c).unwrap_or(GlyphId(0)), c))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    /// Advance of `glyph` in thousandths of the font size.
+    fn advance(&self, glyph: GlyphId) -> f32 {
+        let advance = self.face.glyph_hor_advance(glyph).unwrap_or(0);
+
+        self.scale(advance as f32)
+    }
+
+    fn scale(&self, units: f32) -> f32 {
+        units * 1000.0 / self.face.units_per_em() as f32
+    }
+
+    fn width(&self, text: &str, size: f32) -> f32 {
+        self.glyphs(text)
+            .map(|(glyph, _)| self.advance(glyph))
+            .sum::<f32>()
+            * size
+            / 1000.0
+    }
+
+    /// `text` as the big endian glyph ids expected by Identity-H.
+    fn encode(&mut self, text: &str) -> Vec<u8> {
+        let glyphs: Vec<_> = self.glyphs(text).collect();
+
+        glyphs
+            .into_iter()
+            .flat_map(|(glyph, c)| {
+                self.used.entry(glyph.0).or_insert(c);
+                glyph.0.to_be_bytes()
+            })
+            .collect()
+    }
+
+    /// Splits `text` into lines no wider than `max_width`. Words longer than
+    /// a line are kept whole.
+    fn wrap(&self, text: &str, size: f32, max_width: f32) -> Vec<String> {
+        let mut lines = vec![];
+        let mut line = String::new();
+
+        for word in text.split_whitespace() {
+            let candidate = match line.is_empty() {
+                true => word.to_string(),
+                false => format!("{} {}", line, word),
+            };
+
+            if !line.is_empty() && self.width(&candidate, size) > max_width {
+                lines.push(std::mem::replace(&mut line, word.to_string()));
+            } else {
+                line = candidate;
+            }
+        }
+
+        if !line.is_empty() || lines.is_empty() {
+            lines.push(line);
+        }
+
+        lines
+    }
+}
+
+/// Lays out the pages top to bottom, starting a new page when one is full.
+struct Layout {
+    regular: Font,
+    bold: Font,
+    brand: (f32, f32, f32),
+    pages: Vec<Content>,
+    y: f32,
+}
+
+impl Layout {
+    fn font(&self, style: Style) -> &Font {
+        match style {
+            Style::Regular => &self.regular,
+            Style::Bold => &self.bold,
+        }
+    }
+
+    fn page(&mut self) -> &mut Content {
+        self.pages.last_mut().expect("layout has a page")
+    }
+
+    fn new_page(&mut self) {
+        self.pages.push(Content::new());
+        self.y = PAGE_HEIGHT - MARGIN;
+    }
+
+    /// Starts a new page unless `height` still fits on this one.
+    fn reserve(&mut self, height: f32) -> bool {
+        if self.y - height < MARGIN + 20.0 {
+            self.new_page();
+            return true;
+        }
+
+        false
+    }
+
+    fn text(
+        &mut self,
+        (style, size, color): (Style, f32, (f32, f32, f32)),
+        x: f32,
+        y: f32,
+        text: &str,
+    ) {
+        let font = match style {
+            Style::Regular => &mut self.regular,
+            Style::Bold => &mut self.bold,
+        };
+        let resource = font.resource;
+        let encoded = font.encode(text);
+
+        let page = self.page();
+        page.set_fill_rgb(color.0, color.1, color.2);
+        page.begin_text();
+        page.set_font(Name(resource), size);
+        page.next_line(x, y);
+        page.show(Str(&encoded));
+        page.end_text();
+    }
+
+    fn text_right(
+        &mut self,
+        (style, size, color): (Style, f32, (f32, f32, f32)),
+        right: f32,
+        y: f32,
+        text: &str,
+    ) {
+        let x = right - self.font(style).width(text, size);
+
+        self.text((style, size, color), x, y, text);
+    }
+
+    /// A horizontal line from `left` to the right margin.
+    fn rule(&mut self, left: f32, y: f32, width: f32, color: (f32, f32, f32)) {
+        let page = self.page();
+        page.set_stroke_rgb(color.0, color.1, color.2);
+        page.set_line_width(width);
+        page.move_to(left, y);
+        page.line_to(RIGHT, y);
+        page.stroke();
+    }
+
+    fn table_header(&mut self) {
+        let bold = (Style::Bold, 9.0, TEXT);
+
+        self.y -= 12.0;
+        self.text(bold, MARGIN, self.y, "Description");
+        self.text_right(bold, QUANTITY_RIGHT, self.y, "Quantity");
+        self.text_right(bold, UNIT_AMOUNT_RIGHT, self.y, "Unit price");
+        self.text_right(bold, RIGHT, self.y, "Amount");
+        self.y -= 6.0;
+        self.rule(MARGIN, self.y, 1.5, self.brand);
+    }
+
+    fn header(&mut self, view: &DocumentView) {
+        let top = self.y;
+
+        self.text_right(
+            (Style::Bold, 22.0, self.brand),
+            RIGHT,
+            top - 20.0,
+            view.title,
+        );
+
+        // Long names are wrapped to stay clear of the title.
+        for line in self.bold.wrap(&view.seller_name, 18.0, 320.0) {
+            self.y -= 22.0;
+            self.text((Style::Bold, 18.0, self.brand), MARGIN, self.y, &line);
+        }
+        self.y -= 4.0;
+
+        for detail in &view.seller_details {
+            for line in self.regular.wrap(detail, 9.0, 300.0) {
+                self.y -= 12.0;
+                self.text((Style::Regular, 9.0, MUTED), MARGIN, self.y, &line);
+            }
+        }
+
+        self.y -= 30.0;
+        let parties_top = self.y;
+
+        self.text((Style::Bold, 8.0, MUTED), MARGIN, self.y, "BILL TO");
+        self.y -= 15.0;
+        self.text((Style::Bold, 11.0, TEXT), MARGIN, self.y, &view.buyer_name);
+
+        for detail in &view.buyer_details {
+            for line in self.regular.wrap(detail, 9.0, 260.0) {
+                self.y -= 12.0;
+                self.text((Style::Regular, 9.0, TEXT), MARGIN, self.y, &line);
+            }
+        }
+
+        let mut facts_y = parties_top;
+
+        for (label, value) in &view.facts {
+            self.text((Style::Regular, 9.0, MUTED), 340.0, facts_y, label);
+            self.text_right((Style::Regular, 9.0, TEXT), RIGHT, facts_y, value);
+            facts_y -= 14.0;
+        }
+
+        self.y = self.y.min(facts_y) - 24.0;
+    }
+
+    fn lines(&mut self, view: &DocumentView) {
+        self.table_header();
+
+        for line in &view.lines {
+            let description = self
+                .regular
+                .wrap(&line.description, 10.0, DESCRIPTION_WIDTH);
+            let height =
+                description.len() as f32 * 13.0 + line.period.as_ref().map_or(0.0, |_| 11.0) + 10.0;
+
+            if self.reserve(height) {
+                self.table_header();
+            }
+
+            let row_top = self.y - 15.0;
+            let regular = (Style::Regular, 10.0, TEXT);
+
+            self.text_right(regular, QUANTITY_RIGHT, row_top, &line.quantity);
+            self.text_right(regular, UNIT_AMOUNT_RIGHT, row_top, &line.unit_amount);
+            self.text_right(regular, RIGHT, row_top, &line.amount);
+
+            self.y -= 2.0;
+
+            for text in &description {
+                self.y -= 13.0;
+                self.text(regular, MARGIN, self.y, text);
+            }
+
+            if let Some(period) = &line.period {
+                self.y -= 11.0;
+                self.text((Style::Regular, 8.0, MUTED), MARGIN, self.y, period);
+            }
+
+            self.y -= 8.0;
+            self.rule(MARGIN, self.y, 0.5, RULE);
+        }
+    }
+
+    fn totals(&mut self, view: &DocumentView) {
+        self.reserve(view.totals.len() as f32 * 15.0 + 30.0);
+        self.y -= 6.0;
+
+        for (label, value) in &view.totals {
+            self.y -= 15.0;
+            self.text((Style::Regular, 10.0, TEXT), 340.0, self.y, label);
+            self.text_right((Style::Regular, 10.0, TEXT), RIGHT, self.y, value);
+        }
+
+        self.y -= 8.0;
+
+        self.rule(340.0, self.y, 1.5, self.brand);
+
+        self.y -= 15.0;
+        let (label, value) = &view.amount_due;
+        self.text((Style::Bold, 11.0, TEXT), 340.0, self.y, label);
+        self.text_right((Style::Bold, 11.0, TEXT), RIGHT, self.y, value);
+    }
+
+    fn payments(&mut self, view: &DocumentView) {
+        if view.payments.is_empty() {
+            return;
+        }
+
+        self.reserve(50.0);
+        self.y -= 30.0;
+        self.text((Style::Bold, 8.0, MUTED), MARGIN, self.y, "PAYMENTS");
+
+        for (paid_at, method, amount) in &view.payments {
+            self.reserve(14.0);
+            self.y -= 14.0;
+            self.text((Style::Regular, 9.0, TEXT), MARGIN, self.y, paid_at);
+            self.text((Style::Regular, 9.0, TEXT), 130.0, self.y, method);
+            self.text_right((Style::Regular, 9.0, TEXT), RIGHT, self.y, amount);
+        }
+    }
+
+    fn status_note(&mut self, view: &DocumentView) {
+        let lines = self.regular.wrap(&view.status_note, 10.0, RIGHT - MARGIN);
+
+        self.reserve(lines.len() as f32 * 13.0 + 24.0);
+        self.y -= 24.0;
+
+        for line in lines {
+            self.y -= 13.0;
+            self.text((Style::Regular, 10.0, self.brand), MARGIN, self.y, &line);
+        }
+    }
+
+    fn page_numbers(&mut self) {
+        let count = self.pages.len();
+
+        for index in 0..count {
+            let text = format!("Page {} of {}", index + 1, count);
+            let x = RIGHT - self.regular.width(&text, 8.0);
+            let encoded = self.regular.encode(&text);
+            let page = &mut self.pages[index];
+
+            page.set_fill_rgb(MUTED.0, MUTED.1, MUTED.2);
+            page.begin_text();
+            page.set_font(Name(self.regular.resource), 8.0);
+            page.next_line(x, MARGIN - 20.0);
+            page.show(Str(&encoded));
+            page.end_text();
+        }
+    }
+}
+
+/// Embeds the glyphs of `font` used by the document as a subset.
+fn write_font(pdf: &mut Pdf, next: &mut Ref, font: &Font) -> Result<Ref, String> {
+    let mut alloc = || {
+        let id = *next;
+        *next = next.next();
+        id
+    };
+    let (type0, cid, descriptor, file, cmap) = (alloc(), alloc(), alloc(), alloc(), alloc());
+
+    let glyphs: Vec<u16> = font.used.keys().copied().collect();
+    let subset =
+        subsetter::subset(font.data, 0, subsetter::Profile::pdf(&glyphs)).map_err(|e| {
+            tracing::error!("Failed to subset font: {:?}", e);
+            "Failed to subset font".to_string()
+        })?;
+
+    let system_info = SystemInfo {
+        registry: Str(b"Adobe"),
+        ordering: Str(b"Identity"),
+        supplement: 0,
+    };
+
+    pdf.type0_font(type0)
+        .base_font(Name(font.base_name))
+        .encoding_predefined(Name(b"Identity-H"))
+        .descendant_font(cid)
+        .to_unicode(cmap);
+
+    let mut cid_font = pdf.cid_font(cid);
+    cid_font
+        .subtype(CidFontType::Type2)
+        .base_font(Name(font.base_name))
+        .system_info(system_info)
+        .font_descriptor(descriptor)
+        .default_width(0.0)
+        .cid_to_gid_map_predefined(Name(b"Identity"));
+
+    let mut widths = cid_font.widths();
+    for &glyph in &glyphs {
+        widths.consecutive(glyph, [font.advance(GlyphId(glyph))]);
+    }
+    widths.finish();
+    cid_font.finish();
+
+    let bbox = font.face.global_bounding_box();
+    pdf.font_descriptor(descriptor)
+        .name(Name(font.base_name))
+        .flags(FontFlags::NON_SYMBOLIC)
+        .bbox(Rect::new(
+            font.scale(bbox.x_min as f32),
+            font.scale(bbox.y_min as f32),
+            font.scale(bbox.x_max as f32),
+            font.scale(bbox.y_max as f32),
+        ))
+        .italic_angle(0.0)
+        .ascent(font.scale(font.face.ascender() as f32))
+        .descent(font.scale(font.face.descender() as f32))
+        .cap_height(font.scale(font.face.capital_height().unwrap_or(0) as f32))
+        .stem_v(80.0)
+        .font_file2(file);
+
+    pdf.stream(file, &subset);
+
+    let mut unicode = UnicodeCmap::new(Name(b"Custom"), system_info);
+    for (&glyph, &c) in &font.used {
+        unicode.pair(glyph, c);
+    }
+    pdf.cmap(cmap, &unicode.finish());
+
+    Ok(type0)
+}
+
+/// Renders an A4 PDF of the invoice. Nothing time dependent is written, so
+/// the same document always gives the same bytes.
+pub fn render_pdf(document: &InvoiceDocument) -> Result<Vec<u8>, String> {
+    let view = document.view()?;
+
+    let mut layout = Layout {
+        regular: Font::new(REGULAR, b"AAAAAA+DejaVuSans", b"F1")?,
+        bold: Font::new(BOLD, b"AAAAAB+DejaVuSans-Bold", b"F2")?,
+        brand: document.seller.brand_rgb()?,
+        pages: vec![],
+        y: 0.0,
+    };
+
+    layout.new_page();
+    layout.header(&view);
+    layout.lines(&view);
+    layout.totals(&view);
+    layout.payments(&view);
+    layout.status_note(&view);
+    layout.page_numbers();
+
+    let mut pdf = Pdf::new();
+    let catalog = Ref::new(1);
+    let page_tree = Ref::new(2);
+    let info = Ref::new(3);
+    let mut next = Ref::new(4);
+
+    let regular = write_font(&mut pdf, &mut next, &layout.regular)?;
+    let bold = write_font(&mut pdf, &mut next, &layout.bold)?;
+
+    let mut page_ids = vec![];
+
+    for content in layout.pages {
+        let (page_id, content_id) = (next, next.next());
+        next = content_id.next();
+        page_ids.push(page_id);
+
+        let mut page = pdf.page(page_id);
+        page.media_box(Rect::new(0.0, 0.0, PAGE_WIDTH, PAGE_HEIGHT))
+            .parent(page_tree)
+            .contents(content_id);
+        page.resources()
+            .fonts()
+            .pair(Name(b"F1"), regular)
+            .pair(Name(b"F2"), bold);
+        page.finish();
+
+        pdf.stream(content_id, &content.finish());
+    }
+
+    pdf.catalog(catalog).pages(page_tree);
+    pdf.pages(page_tree)
+        .count(page_ids.len() as i32)
+        .kids(page_ids);
+
+    let title = format!(
+        "{} {}",
+        view.title,
+        document.file_name("pdf").trim_end_matches(".pdf")
+    );
+    pdf.document_info(info)
+        .title(TextStr(&title))
+        .author(TextStr(&view.seller_name));
+
+    let file_id = document.invoice.id.as_bytes().to_vec();
+    pdf.set_file_id((file_id.clone(), file_id));
+
+    Ok(pdf.finish())
+}
@@ -0,0 +1,52 @@
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+/// Internal events published by services. Listeners subscribe through
+/// [`subscribe`]; publishing never fails when nobody is listening.
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DomainEvent {
+    UsageThresholdCrossed {
+        subscription_id: uuid::Uuid,
+        user_id: uuid::Uuid,
+        resource_type: String,
+        threshold: i32,
+        usage: i64,
+        limit: i64,
+    },
+    /// Subscribers are told ahead of a move to a new version of their plan.
+    PlanVersionMigrationScheduled {
+        subscription_id: uuid::Uuid,
+        user_id: uuid::Uuid,
+        from_version_id: Option<uuid::Uuid>,
+        to_version_id: uuid::Uuid,
+        effective_at: chrono::NaiveDateTime,
+    },
+}
+
+static EVENTS: Lazy<broadcast::Sender<DomainEvent>> = Lazy::new(|| broadcast::channel(256).0);
+
+pub fn publish(event: DomainEvent) {
+    let _ = EVENTS.send(event);
+}
+
+pub fn subscribe() -> broadcast::Receiver<DomainEvent> {
+    EVENTS.subscribe()
+}
+
+pub fn spawn_listeners() {
+    let mut receiver = subscribe();
+
+    tokio::spawn(async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) => tracing::info!("events --> {}", serde_json::json!(event)),
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!("events --> listener lagged, skipped {} events", skipped)
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+}
@@ -0,0 +1,61 @@
+use std::path::PathBuf;
+
+/// A plain text email.
+#[derive(Debug, Clone)]
+pub struct Mail {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+/// Delivers emails. Senders are picked from the config: emails are logged
+/// by default, or written to a directory when `MAIL_SINK_DIR` is set.
+pub trait MailSender {
+    async fn send(&self, mail: &Mail) -> Result<(), String>;
+}
+
+/// Logs emails instead of sending them.
+pub struct LogMailSender;
+
+impl MailSender for LogMailSender {
+    async fn send(&self, mail: &Mail) -> Result<(), String> {
+        tracing::info!("mail --> to {}: {}", mail.to, mail.subject);
+        Ok(())
+    }
+}
+
+/// Writes every email to its own `.eml` file in `dir`, so tests can read
+/// what would have been sent. Files are written in place, whatever runtime
+/// the caller is on, as this sink is not meant for production traffic.
+pub struct FileMailSender {
+    pub dir: PathBuf,
+}
+
+impl FileMailSender {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+}
+
+impl MailSender for FileMailSender {
+    async fn send(&self, mail: &Mail) -> Result<(), String> {
+        let internal_error = |e: std::io::Error| {
+            tracing::error!("Failed to write mail: {:?}", e);
+            "Failed to write mail".to_string()
+        };
+
+        std::fs::create_dir_all(&self.dir).map_err(internal_error)?;
+
+        let path = self.dir.join(format!(
+            "{}-{}.eml",
+            chrono::Utc::now().format("%Y%m%d%H%M%S%6f"),
+            uuid::Uuid::new_v4()
+        ));
+        let content = format!(
+            "To: {}\r\nSubject: {}\r\nContent-Type: text/plain; charset=utf-8\r\n\r\n{}\r\n",
+            mail.to, mail.subject, mail.body
+        );
+
+        std::fs::write(path, content).map_err(internal_error)
+    }
+}
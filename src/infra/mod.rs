@@ -1,5 +1,10 @@
 pub mod configs;
 pub mod db;
+pub mod documents;
+pub mod events;
 pub mod keys;
+pub mod mail;
+pub mod payments;
+pub mod scheduler;
 pub mod services;
 pub mod tracing;
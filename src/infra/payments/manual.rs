@@ -0,0 +1,91 @@
+use crate::domain::models::money_model::Money;
+
+use super::{
+    parse_event, verify_signature, PaymentIntent, PaymentProvider, WebhookError, WebhookEvent,
+};
+
+/// Bank transfers paid by hand. Customers transfer the amount with the
+/// payment reference, which is confirmed by sys or by a webhook of the
+/// bank reconciliation when `webhook_secret` is set.
+#[derive(Debug, Clone)]
+pub struct ManualProvider {
+    /// Where customers transfer the money, shown in the instructions.
+    pub bank_account: Option<String>,
+    pub webhook_secret: Option<String>,
+}
+
+impl ManualProvider {
+    /// The transfer reference of a payment, short enough for the message
+    /// of a bank transfer.
+    fn reference(payment_id: uuid::Uuid) -> String {
+        format!(
+            "CRM{}",
+            payment_id.simple().to_string()[..12].to_uppercase()
+        )
+    }
+}
+
+impl PaymentProvider for ManualProvider {
+    fn name(&self) -> &'static str {
+        "manual"
+    }
+
+    async fn create_intent(
+        &self,
+        payment_id: uuid::Uuid,
+        amount: Money,
+    ) -> Result<PaymentIntent, String> {
+        let reference = Self::reference(payment_id);
+        let account = self
+            .bank_account
+            .as_ref()
+            .map(|account| format!(" to {}", account))
+            .unwrap_or_default();
+
+        Ok(PaymentIntent {
+            instructions: Some(format!(
+                "Transfer {}{} with the reference {}",
+                amount, account, reference
+            )),
+            provider_payment_id: reference,
+        })
+    }
+
+    /// The money is already on the account when sys confirms the transfer.
+    async fn capture(&self, _provider_payment_id: &str, _amount: Money) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// The refund is transferred by hand with the reference of the refund.
+    async fn refund(
+        &self,
+        provider_payment_id: &str,
+        refund_id: uuid::Uuid,
+        amount: Money,
+    ) -> Result<String, String> {
+        let reference = Self::reference(refund_id);
+
+        tracing::info!(
+            "Refund {} of bank transfer {} by hand with the reference {}",
+            amount,
+            provider_payment_id,
+            reference
+        );
+        Ok(reference)
+    }
+
+    fn parse_webhook(
+        &self,
+        signature: Option<&str>,
+        body: &[u8],
+        now: i64,
+    ) -> Result<WebhookEvent, WebhookError> {
+        let secret = self
+            .webhook_secret
+            .as_ref()
+            .ok_or(WebhookError::InvalidSignature)?;
+
+        verify_signature(secret, signature, body, now)?;
+        parse_event(body)
+    }
+}
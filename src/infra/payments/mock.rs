@@ -0,0 +1,58 @@
+use crate::domain::models::money_model::Money;
+
+use super::{
+    parse_event, verify_signature, PaymentIntent, PaymentProvider, WebhookError, WebhookEvent,
+};
+
+/// Accepts every payment without moving money, so tests can drive payments
+/// with signed webhooks.
+#[derive(Debug, Clone)]
+pub struct MockProvider {
+    pub webhook_secret: String,
+}
+
+impl MockProvider {
+    pub fn new(webhook_secret: String) -> Self {
+        Self { webhook_secret }
+    }
+}
+
+impl PaymentProvider for MockProvider {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    async fn create_intent(
+        &self,
+        payment_id: uuid::Uuid,
+        _amount: Money,
+    ) -> Result<PaymentIntent, String> {
+        Ok(PaymentIntent {
+            provider_payment_id: format!("mock_{}", payment_id.simple()),
+            instructions: None,
+        })
+    }
+
+    async fn capture(&self, _provider_payment_id: &str, _amount: Money) -> Result<(), String> {
+        Ok(())
+    }
+
+    async fn refund(
+        &self,
+        _provider_payment_id: &str,
+        refund_id: uuid::Uuid,
+        _amount: Money,
+    ) -> Result<String, String> {
+        Ok(format!("mock_re_{}", refund_id.simple()))
+    }
+
+    fn parse_webhook(
+        &self,
+        signature: Option<&str>,
+        body: &[u8],
+        now: i64,
+    ) -> Result<WebhookEvent, WebhookError> {
+        verify_signature(&self.webhook_secret, signature, body, now)?;
+        parse_event(body)
+    }
+}
@@ -0,0 +1,326 @@
+mod manual;
+mod mock;
+
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::Sha256;
+
+use crate::domain::models::money_model::Money;
+
+pub use manual::ManualProvider;
+pub use mock::MockProvider;
+
+/// Webhooks older than this are rejected, so a captured request can't be
+/// replayed later.
+pub const SIGNATURE_TOLERANCE_SECS: i64 = 5 * 60;
+
+/// A payment opened with a provider.
+#[derive(Debug, Clone)]
+pub struct PaymentIntent {
+    /// How the provider refers to the payment in its webhooks.
+    pub provider_payment_id: String,
+    /// What the customer has to do to pay, e.g. the bank transfer details.
+    pub instructions: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WebhookEventKind {
+    Succeeded {
+        amount: Money,
+    },
+    Failed {
+        reason: String,
+    },
+    /// `refund_id` is the provider's id of the refund, the one returned
+    /// by [`PaymentProvider::refund`] for refunds made here.
+    Refunded {
+        amount: Money,
+        refund_id: Option<String>,
+    },
+}
+
+/// A verified webhook event of a provider.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebhookEvent {
+    /// Unique per provider, events delivered again are skipped.
+    pub id: String,
+    pub event_type: String,
+    pub provider_payment_id: String,
+    pub kind: WebhookEventKind,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum WebhookError {
+    InvalidSignature,
+    InvalidPayload(String),
+}
+
+/// Takes payments on behalf of the system. Providers are picked by the
+/// customer among the ones enabled in the config, and tell the outcome of
+/// payments through signed webhooks.
+pub trait PaymentProvider {
+    fn name(&self) -> &'static str;
+
+    async fn create_intent(
+        &self,
+        payment_id: uuid::Uuid,
+        amount: Money,
+    ) -> Result<PaymentIntent, String>;
+
+    async fn capture(&self, provider_payment_id: &str, amount: Money) -> Result<(), String>;
+
+    /// Pays back `amount` of a payment, `refund_id` identifies the refund
+    /// here. Returns the provider's id of the refund.
+    async fn refund(
+        &self,
+        provider_payment_id: &str,
+        refund_id: uuid::Uuid,
+        amount: Money,
+    ) -> Result<String, String>;
+
+    /// Checks the `signature` of a webhook sent at or before `now`, in
+    /// seconds since the epoch, and reads its event.
+    fn parse_webhook(
+        &self,
+        signature: Option<&str>,
+        body: &[u8],
+        now: i64,
+    ) -> Result<WebhookEvent, WebhookError>;
+}
+
+/// The signature of a webhook `body` sent at `timestamp`: the hex HMAC-SHA256
+/// of `{timestamp}.{body}`, sent as `t={timestamp},v1={signature}`.
+#[cfg(test)]
+pub fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    format!(
+        "t={},v1={}",
+        timestamp,
+        hex::encode(hmac(secret, timestamp, body).finalize().into_bytes())
+    )
+}
+
+fn hmac(secret: &str, timestamp: i64, body: &[u8]) -> Hmac<Sha256> {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any size");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    mac
+}
+
+/// Checks a signature made by [`sign`] within the tolerance of `now`.
+pub fn verify_signature(
+    secret: &str,
+    signature: Option<&str>,
+    body: &[u8],
+    now: i64,
+) -> Result<(), WebhookError> {
+    let (mut timestamp, mut signatures) = (None, vec![]);
+
+    for part in signature.ok_or(WebhookError::InvalidSignature)?.split(',') {
+        match part.trim().split_once('=') {
+            Some(("t", value)) => timestamp = value.parse::<i64>().ok(),
+            Some(("v1", value)) => signatures.extend(hex::decode(value).ok()),
+            _ => {}
+        }
+    }
+
+    let timestamp = timestamp.ok_or(WebhookError::InvalidSignature)?;
+
+    if (now - timestamp).abs() > SIGNATURE_TOLERANCE_SECS {
+        return Err(WebhookError::InvalidSignature);
+    }
+
+    // Compared in constant time by the MAC.
+    signatures
+        .iter()
+        .any(|signature| {
+            hmac(secret, timestamp, body)
+                .verify_slice(signature)
+                .is_ok()
+        })
+        .then_some(())
+        .ok_or(WebhookError::InvalidSignature)
+}
+
+/// The JSON events of the built-in providers.
+#[derive(Deserialize)]
+struct EventPayload {
+    id: String,
+    #[serde(rename = "type")]
+    event_type: String,
+    payment_id: String,
+    amount: Option<Money>,
+    reason: Option<String>,
+    refund_id: Option<String>,
+}
+
+fn parse_event(body: &[u8]) -> Result<WebhookEvent, WebhookError> {
+    let payload: EventPayload = serde_json::from_slice(body)
+        .map_err(|e| WebhookError::InvalidPayload(format!("Invalid event: {}", e)))?;
+    let amount = || {
+        payload.amount.ok_or_else(|| {
+            WebhookError::InvalidPayload(format!("{} events have an amount", payload.event_type))
+        })
+    };
+
+    let kind = match payload.event_type.as_str() {
+        "payment.succeeded" => WebhookEventKind::Succeeded { amount: amount()? },
+        "payment.failed" => WebhookEventKind::Failed {
+            reason: payload
+                .reason
+                .clone()
+                .unwrap_or_else(|| "Declined by the provider".to_string()),
+        },
+        "payment.refunded" => WebhookEventKind::Refunded {
+            amount: amount()?,
+            refund_id: payload.refund_id.clone(),
+        },
+        other => {
+            return Err(WebhookError::InvalidPayload(format!(
+                "Unknown event type: {}",
+                other
+            )))
+        }
+    };
+
+    Ok(WebhookEvent {
+        id: payload.id,
+        event_type: payload.event_type,
+        provider_payment_id: payload.payment_id,
+        kind,
+    })
+}
+
+#[derive(Debug, Clone)]
+pub enum Provider {
+    Manual(ManualProvider),
+    Mock(MockProvider),
+}
+
+impl PaymentProvider for Provider {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Manual(provider) => provider.name(),
+            Self::Mock(provider) => provider.name(),
+        }
+    }
+
+    async fn create_intent(
+        &self,
+        payment_id: uuid::Uuid,
+        amount: Money,
+    ) -> Result<PaymentIntent, String> {
+        match self {
+            Self::Manual(provider) => provider.create_intent(payment_id, amount).await,
+            Self::Mock(provider) => provider.create_intent(payment_id, amount).await,
+        }
+    }
+
+    async fn capture(&self, provider_payment_id: &str, amount: Money) -> Result<(), String> {
+        match self {
+            Self::Manual(provider) => provider.capture(provider_payment_id, amount).await,
+            Self::Mock(provider) => provider.capture(provider_payment_id, amount).await,
+        }
+    }
+
+    async fn refund(
+        &self,
+        provider_payment_id: &str,
+        refund_id: uuid::Uuid,
+        amount: Money,
+    ) -> Result<String, String> {
+        match self {
+            Self::Manual(provider) => {
+                provider
+                    .refund(provider_payment_id, refund_id, amount)
+                    .await
+            }
+            Self::Mock(provider) => {
+                provider
+                    .refund(provider_payment_id, refund_id, amount)
+                    .await
+            }
+        }
+    }
+
+    fn parse_webhook(
+        &self,
+        signature: Option<&str>,
+        body: &[u8],
+        now: i64,
+    ) -> Result<WebhookEvent, WebhookError> {
+        match self {
+            Self::Manual(provider) => provider.parse_webhook(signature, body, now),
+            Self::Mock(provider) => provider.parse_webhook(signature, body, now),
+        }
+    }
+}
+
+/// The providers enabled in the config. Manual bank transfers are always
+/// available, the mock provider only when its webhook secret is set.
+#[derive(Debug, Clone)]
+pub struct PaymentProviders {
+    pub providers: Vec<Provider>,
+}
+
+impl PaymentProviders {
+    pub fn get(&self, name: &str) -> Option<&Provider> {
+        self.providers
+            .iter()
+            .find(|provider| provider.name() == name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::models::money_model::Currency;
+
+    #[test]
+    fn test_webhook_signature() {
+        let body = br#"{"id":"evt_1","type":"payment.succeeded","payment_id":"mock_1","amount":{"amount_minor":19000,"currency":"VND"}}"#;
+        let signature = sign("secret", 1_700_000_000, body);
+
+        assert_eq!(
+            verify_signature("secret", Some(&signature), body, 1_700_000_100),
+            Ok(())
+        );
+        for (secret, signature, body, now) in [
+            ("other", Some(signature.as_str()), &body[..], 1_700_000_100),
+            ("secret", Some(signature.as_str()), b"{}", 1_700_000_100),
+            ("secret", Some(signature.as_str()), &body[..], 1_700_000_400),
+            (
+                "secret",
+                Some("t=1700000000,v1=zz"),
+                &body[..],
+                1_700_000_000,
+            ),
+            ("secret", None, &body[..], 1_700_000_000),
+        ] {
+            assert_eq!(
+                verify_signature(secret, signature, body, now),
+                Err(WebhookError::InvalidSignature)
+            );
+        }
+
+        let provider = MockProvider::new("secret".to_string());
+        assert_eq!(
+            provider.parse_webhook(Some(&signature), body, 1_700_000_000),
+            Ok(WebhookEvent {
+                id: "evt_1".to_string(),
+                event_type: "payment.succeeded".to_string(),
+                provider_payment_id: "mock_1".to_string(),
+                kind: WebhookEventKind::Succeeded {
+                    amount: Money::new(19000, Currency::Vnd)
+                },
+            })
+        );
+
+        let body = br#"{"id":"evt_2","type":"payment.refunded","payment_id":"mock_1"}"#;
+        assert!(matches!(
+            provider.parse_webhook(Some(&sign("secret", 1, body)), body, 1),
+            Err(WebhookError::InvalidPayload(_))
+        ));
+    }
+}
@@ -0,0 +1,163 @@
+use std::time::Duration;
+
+use crate::infra::{
+    configs::Config,
+    mail::{FileMailSender, LogMailSender},
+    services::{
+        dunning_service::{DunningService, DunningServiceImpl},
+        job_service::{JobService, JobServiceImpl},
+        plan_change_service::{PlanChangeService, PlanChangeServiceImpl},
+        plan_version_service::{PlanVersionService, PlanVersionServiceImpl},
+        subscription_service::{SubscriptionService, SubscriptionServiceImpl},
+    },
+};
+
+/// Periodic jobs, run in this order on every tick.
+#[derive(Clone, Copy, Debug)]
+pub enum Job {
+    Resumes,
+    Cancellations,
+    EndTrials,
+    ExpireLapsed,
+    PlanChanges,
+    PlanVersions,
+    Renewals,
+    PastDue,
+    Dunning,
+}
+
+/// Ended pauses are resumed first so the other jobs see the subscription
+/// active again. Cancellations run before the trial and renewal jobs so a
+/// subscription set to cancel is neither expired nor renewed, and scheduled
+/// plan changes and version migrations run before renewals so the renewal
+/// bills the new plan and version. Dunning runs last so the first reminder
+/// goes out on the run that moved the subscription to past due.
+pub const JOBS: [Job; 9] = [
+    Job::Resumes,
+    Job::Cancellations,
+    Job::EndTrials,
+    Job::ExpireLapsed,
+    Job::PlanChanges,
+    Job::PlanVersions,
+    Job::Renewals,
+    Job::PastDue,
+    Job::Dunning,
+];
+
+impl Job {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Resumes => "resumes",
+            Self::Cancellations => "cancellations",
+            Self::EndTrials => "end_trials",
+            Self::ExpireLapsed => "expire_lapsed",
+            Self::PlanChanges => "plan_changes",
+            Self::PlanVersions => "plan_versions",
+            Self::Renewals => "renewals",
+            Self::PastDue => "past_due",
+            Self::Dunning => "dunning",
+        }
+    }
+
+    /// Key of the Postgres advisory lock that keeps a job to one replica at a time.
+    fn lock_key(&self) -> i64 {
+        match self {
+            Self::EndTrials => 0x5343_4845_0001,
+            Self::ExpireLapsed => 0x5343_4845_0002,
+            Self::Renewals => 0x5343_4845_0003,
+            Self::PlanChanges => 0x5343_4845_0004,
+            Self::Cancellations => 0x5343_4845_0005,
+            Self::Resumes => 0x5343_4845_0006,
+            Self::PlanVersions => 0x5343_4845_0007,
+            Self::Dunning => 0x5343_4845_0008,
+            Self::PastDue => 0x5343_4845_0009,
+        }
+    }
+
+    async fn run(&self, pool: &sqlx::PgPool, config: &Config) -> Result<u64, String> {
+        let subscription_service = SubscriptionService::new(pool.clone());
+
+        match self {
+            Self::Resumes => subscription_service.resume_due().await,
+            Self::Cancellations => subscription_service.cancel_due().await,
+            Self::EndTrials => subscription_service.expire_trials().await,
+            Self::ExpireLapsed => subscription_service.expire_lapsed().await,
+            Self::PlanChanges => PlanChangeService::new(pool.clone()).apply_scheduled().await,
+            Self::PlanVersions => {
+                PlanVersionService::new(pool.clone())
+                    .apply_scheduled()
+                    .await
+            }
+            Self::Renewals => subscription_service.renew_due().await,
+            Self::PastDue => subscription_service.past_due().await,
+            Self::Dunning => {
+                let dunning_service = DunningService::new(pool.clone());
+
+                match &config.mail_sink_dir {
+                    Some(dir) => {
+                        dunning_service
+                            .run(&config.dunning, &FileMailSender::new(dir))
+                            .await
+                    }
+                    None => dunning_service.run(&config.dunning, &LogMailSender).await,
+                }
+            }
+        }
+    }
+}
+
+/// Runs `job` unless another replica holds its lock, and records the result.
+async fn run_job(pool: &sqlx::PgPool, job: Job, config: &Config) -> Result<(), sqlx::Error> {
+    // The transaction scoped lock is released when `lock` is committed or dropped.
+    let mut lock = pool.begin().await?;
+
+    let locked = sqlx::query_scalar!(
+        r#"
+        SELECT pg_try_advisory_xact_lock($1) AS "locked!"
+        "#,
+        job.lock_key()
+    )
+    .fetch_one(&mut *lock)
+    .await?;
+
+    if !locked {
+        tracing::debug!(
+            "scheduler --> {} is running on another instance",
+            job.name()
+        );
+        return Ok(());
+    }
+
+    let started_at = chrono::Utc::now().naive_utc();
+    let result = job.run(pool, config).await;
+
+    match &result {
+        Ok(processed) => tracing::info!("scheduler --> {} processed {}", job.name(), processed),
+        Err(e) => tracing::error!("scheduler --> {} failed: {}", job.name(), e),
+    }
+
+    if let Err(e) = JobService::new(pool.clone())
+        .record_run(job.name(), started_at, &result)
+        .await
+    {
+        tracing::error!("scheduler --> {}", e);
+    }
+
+    lock.commit().await
+}
+
+pub fn spawn(pool: sqlx::PgPool, config: Config) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(Duration::from_secs(config.scheduler_interval_secs));
+
+        loop {
+            ticker.tick().await;
+
+            for job in JOBS {
+                if let Err(e) = run_job(&pool, job, &config).await {
+                    tracing::error!("scheduler --> failed to run {}: {:?}", job.name(), e);
+                }
+            }
+        }
+    });
+}
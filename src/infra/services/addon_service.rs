@@ -0,0 +1,682 @@
+use axum::http::StatusCode;
+
+use crate::domain::{
+    dtos::addon_dtos::{
+        AddonResponse, CreateAddonRequest, SetSubscriptionAddonRequest, SubscriptionAddonResponse,
+        UpdateAddonRequest,
+    },
+    models::{
+        addon_model,
+        invoice_model::{self, InvoiceKind, InvoiceLine, InvoiceLineKind},
+        money_model::{Currency, Money},
+        plan_model::BillingPeriod,
+        subscription_model::SubscriptionStatus,
+    },
+};
+
+use super::{
+    invoice_service::{InvoiceService, NewInvoice},
+    quota_service::{QuotaService, QuotaServiceImpl},
+    resource_service::{ResourceService, ResourceServiceImpl},
+    subscription_service::{BillingTerms, SubscriptionService},
+};
+
+pub struct AddonService {
+    pub pool: sqlx::PgPool,
+}
+
+pub trait AddonServiceImpl {
+    fn new(pool: sqlx::PgPool) -> Self;
+
+    async fn create_addon(
+        &self,
+        addon: CreateAddonRequest,
+    ) -> Result<AddonResponse, (StatusCode, String)>;
+
+    async fn get_addons(&self, active_only: bool) -> Result<Vec<AddonResponse>, String>;
+
+    async fn update_addon(
+        &self,
+        id: uuid::Uuid,
+        addon: UpdateAddonRequest,
+    ) -> Result<AddonResponse, (StatusCode, String)>;
+
+    async fn deactivate_addon(&self, id: uuid::Uuid) -> Result<(), (StatusCode, String)>;
+
+    async fn get_subscription_addons(
+        &self,
+        subscription_id: uuid::Uuid,
+        user_id: uuid::Uuid,
+    ) -> Result<Vec<SubscriptionAddonResponse>, (StatusCode, String)>;
+
+    async fn set_subscription_addon(
+        &self,
+        subscription_id: uuid::Uuid,
+        user_id: uuid::Uuid,
+        addon_id: uuid::Uuid,
+        addon: SetSubscriptionAddonRequest,
+    ) -> Result<Vec<SubscriptionAddonResponse>, (StatusCode, String)>;
+
+    async fn remove_subscription_addon(
+        &self,
+        subscription_id: uuid::Uuid,
+        user_id: uuid::Uuid,
+        addon_id: uuid::Uuid,
+    ) -> Result<(), (StatusCode, String)>;
+}
+
+fn internal_error(e: sqlx::Error) -> (StatusCode, String) {
+    tracing::error!("Failed to save add-on: {:?}", e);
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "Failed to save add-on".to_string(),
+    )
+}
+
+fn validate_price(price: &Money) -> Result<(), (StatusCode, String)> {
+    if price.amount_minor < 0 {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "price must be 0 or more".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// A subscription locked for changing its add-ons.
+struct AddonSubscription {
+    plan_id: uuid::Uuid,
+    status: SubscriptionStatus,
+    start_date: Option<chrono::NaiveDateTime>,
+    end_date: Option<chrono::NaiveDateTime>,
+    terms: BillingTerms,
+}
+
+impl AddonService {
+    /// Invoice lines of the add-on packs on `subscription_id` for one
+    /// billing `period`, in the `currency` the subscription is billed in.
+    pub async fn period_lines(
+        conn: &mut sqlx::PgConnection,
+        subscription_id: uuid::Uuid,
+        period: BillingPeriod,
+        currency: Currency,
+    ) -> Result<Vec<InvoiceLine>, (StatusCode, String)> {
+        let addons = sqlx::query!(
+            r#"
+            SELECT a.name, a.price, a.currency, sa.quantity
+            FROM subscription_addons AS sa
+            INNER JOIN addons AS a ON a.id = sa.addon_id
+            WHERE sa.subscription_id = $1
+            ORDER BY a.code
+            "#,
+            subscription_id
+        )
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(internal_error)?;
+
+        addons
+            .into_iter()
+            .map(|addon| {
+                let price = Money::parse(addon.price, &addon.currency)
+                    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+                if price.currency != currency {
+                    return Err((
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        format!("The add-on {} is priced in {}", addon.name, price.currency),
+                    ));
+                }
+
+                Ok(InvoiceLine {
+                    amount: addon_model::period_price(price, addon.quantity, period),
+                    ..InvoiceLine::new(
+                        InvoiceLineKind::Addon,
+                        addon.name,
+                        addon.quantity as i64,
+                        addon_model::period_price(price, 1, period),
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// Locks a subscription owned by `user_id` whose add-ons can change,
+    /// others are reported as not found.
+    async fn lock_subscription(
+        conn: &mut sqlx::PgConnection,
+        subscription_id: uuid::Uuid,
+        user_id: uuid::Uuid,
+    ) -> Result<AddonSubscription, (StatusCode, String)> {
+        let subscription = sqlx::query!(
+            r#"
+            SELECT s.plan_id AS "plan_id!", s.plan_version_id, s.plan_price_id, s.status, s.start_date, s.end_date,
+                v.is_free_forever
+            FROM subscriptions AS s
+            INNER JOIN plans AS p ON p.id = s.plan_id
+            INNER JOIN plan_versions AS v ON v.id = COALESCE(s.plan_version_id, p.current_version_id)
+            WHERE s.id = $1 AND s.user_id = $2
+            FOR UPDATE OF s
+            "#,
+            subscription_id,
+            user_id
+        )
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Subscription not found".to_string()))?;
+
+        let status: SubscriptionStatus = subscription
+            .status
+            .parse()
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+        if !matches!(
+            status,
+            SubscriptionStatus::Active | SubscriptionStatus::Trialing
+        ) {
+            return Err((
+                StatusCode::CONFLICT,
+                format!(
+                    "Cannot change the add-ons of a subscription that is {}",
+                    status
+                ),
+            ));
+        }
+
+        // Free forever subscriptions are never renewed, so the packs would
+        // never be billed.
+        if subscription.is_free_forever {
+            return Err((
+                StatusCode::CONFLICT,
+                "Add-ons can't be added to a free plan".to_string(),
+            ));
+        }
+
+        let terms = SubscriptionService::billing_terms(
+            conn,
+            subscription.plan_id,
+            subscription.plan_version_id,
+            subscription.plan_price_id,
+            false,
+        )
+        .await?;
+
+        Ok(AddonSubscription {
+            plan_id: subscription.plan_id,
+            status,
+            start_date: subscription.start_date,
+            end_date: subscription.end_date,
+            terms,
+        })
+    }
+
+    /// Refuses to take `units` off the limit of `resource_type_id` when the
+    /// current usage would be above the hard limit without them.
+    async fn check_usage(
+        &self,
+        user_id: uuid::Uuid,
+        plan_id: uuid::Uuid,
+        resource_type_id: uuid::Uuid,
+        units: i64,
+    ) -> Result<(), (StatusCode, String)> {
+        let limit = ResourceService::new(self.pool.clone())
+            .get_effective_limits(user_id, Some(plan_id))
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?
+            .into_iter()
+            .find(|limit| limit.resource_type_id == resource_type_id);
+
+        let Some((resource_type, hard_limit)) =
+            limit.and_then(|limit| Some((limit.resource_type, limit.hard_limit? - units)))
+        else {
+            return Ok(());
+        };
+
+        let usage = QuotaService::new(self.pool.clone())
+            .get_usage(user_id, &resource_type)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+        if usage > hard_limit {
+            return Err((
+                StatusCode::CONFLICT,
+                format!(
+                    "The {} usage is above the limit without these add-ons ({}/{})",
+                    resource_type, usage, hard_limit
+                ),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl AddonServiceImpl for AddonService {
+    fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+
+    async fn create_addon(
+        &self,
+        addon: CreateAddonRequest,
+    ) -> Result<AddonResponse, (StatusCode, String)> {
+        validate_price(&addon.price)?;
+
+        if addon.quantity <= 0 {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "quantity must be 1 or more".to_string(),
+            ));
+        }
+
+        let created = sqlx::query!(
+            r#"
+            INSERT INTO addons (code, name, description, resource_type_id, quantity, price, currency)
+            SELECT $1, $2, $3, rt.id, $5, $6, $7
+            FROM resource_types AS rt
+            WHERE rt.code = $4
+            RETURNING id, code, name, description, quantity, price, currency, is_active, created_at
+            "#,
+            addon.code.trim(),
+            addon.name,
+            addon.description,
+            addon.resource_type,
+            addon.quantity,
+            addon.price.amount_minor,
+            addon.price.currency.as_str()
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(e) if e.is_unique_violation() => (
+                StatusCode::CONFLICT,
+                "An add-on with this code already exists".to_string(),
+            ),
+            e => internal_error(e),
+        })?
+        .ok_or_else(|| {
+            (
+                StatusCode::BAD_REQUEST,
+                format!("Unknown resource type: {}", addon.resource_type),
+            )
+        })?;
+
+        Ok(AddonResponse {
+            id: created.id,
+            code: created.code,
+            name: created.name,
+            description: created.description,
+            resource_type: addon.resource_type,
+            quantity: created.quantity,
+            price: Money::parse(created.price, &created.currency)
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?,
+            is_active: created.is_active,
+            created_at: created.created_at,
+        })
+    }
+
+    async fn get_addons(&self, active_only: bool) -> Result<Vec<AddonResponse>, String> {
+        let addons = sqlx::query!(
+            r#"
+            SELECT a.id, a.code, a.name, a.description, rt.code AS resource_type, a.quantity, a.price, a.currency,
+                a.is_active, a.created_at
+            FROM addons AS a
+            INNER JOIN resource_types AS rt ON rt.id = a.resource_type_id
+            WHERE a.is_active OR NOT $1
+            ORDER BY rt.code, a.quantity
+            "#,
+            active_only
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get add-ons: {:?}", e);
+            "Failed to get add-ons".to_string()
+        })?;
+
+        addons
+            .into_iter()
+            .map(|addon| {
+                Ok(AddonResponse {
+                    id: addon.id,
+                    code: addon.code,
+                    name: addon.name,
+                    description: addon.description,
+                    resource_type: addon.resource_type,
+                    quantity: addon.quantity,
+                    price: Money::parse(addon.price, &addon.currency)?,
+                    is_active: addon.is_active,
+                    created_at: addon.created_at,
+                })
+            })
+            .collect()
+    }
+
+    async fn update_addon(
+        &self,
+        id: uuid::Uuid,
+        addon: UpdateAddonRequest,
+    ) -> Result<AddonResponse, (StatusCode, String)> {
+        validate_price(&addon.price)?;
+
+        let mut tx = self.pool.begin().await.map_err(internal_error)?;
+
+        let currency = sqlx::query_scalar!(
+            r#"
+            SELECT currency FROM addons WHERE id = $1 FOR UPDATE
+            "#,
+            id
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Add-on not found".to_string()))?;
+
+        // Subscriptions are billed in one currency, the packs they hold too.
+        if currency != addon.price.currency.as_str() {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("The add-on is priced in {}", currency),
+            ));
+        }
+
+        let updated = sqlx::query!(
+            r#"
+            UPDATE addons AS a
+            SET name = $1, description = $2, price = $3, is_active = $4, updated_at = CURRENT_TIMESTAMP
+            FROM resource_types AS rt
+            WHERE a.id = $5 AND rt.id = a.resource_type_id
+            RETURNING a.id, a.code, a.name, a.description, rt.code AS resource_type, a.quantity, a.price, a.currency,
+                a.is_active, a.created_at
+            "#,
+            addon.name,
+            addon.description,
+            addon.price.amount_minor,
+            addon.is_active,
+            id
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(internal_error)?;
+
+        tx.commit().await.map_err(internal_error)?;
+
+        Ok(AddonResponse {
+            id: updated.id,
+            code: updated.code,
+            name: updated.name,
+            description: updated.description,
+            resource_type: updated.resource_type,
+            quantity: updated.quantity,
+            price: Money::parse(updated.price, &updated.currency)
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?,
+            is_active: updated.is_active,
+            created_at: updated.created_at,
+        })
+    }
+
+    /// Stops selling the add-on. Subscriptions holding it keep their packs.
+    async fn deactivate_addon(&self, id: uuid::Uuid) -> Result<(), (StatusCode, String)> {
+        sqlx::query!(
+            r#"
+            UPDATE addons SET is_active = FALSE, updated_at = CURRENT_TIMESTAMP WHERE id = $1
+            RETURNING id
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Add-on not found".to_string()))?;
+
+        Ok(())
+    }
+
+    async fn get_subscription_addons(
+        &self,
+        subscription_id: uuid::Uuid,
+        user_id: uuid::Uuid,
+    ) -> Result<Vec<SubscriptionAddonResponse>, (StatusCode, String)> {
+        let mut conn = self.pool.acquire().await.map_err(internal_error)?;
+
+        let subscription = sqlx::query!(
+            r#"
+            SELECT plan_id AS "plan_id!", plan_version_id, plan_price_id
+            FROM subscriptions
+            WHERE id = $1 AND user_id = $2
+            "#,
+            subscription_id,
+            user_id
+        )
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Subscription not found".to_string()))?;
+
+        let terms = SubscriptionService::billing_terms(
+            &mut conn,
+            subscription.plan_id,
+            subscription.plan_version_id,
+            subscription.plan_price_id,
+            false,
+        )
+        .await?;
+
+        let addons = sqlx::query!(
+            r#"
+            SELECT a.id, a.code, a.name, rt.code AS resource_type, a.quantity AS units, a.price, a.currency,
+                sa.quantity, sa.updated_at
+            FROM subscription_addons AS sa
+            INNER JOIN addons AS a ON a.id = sa.addon_id
+            INNER JOIN resource_types AS rt ON rt.id = a.resource_type_id
+            WHERE sa.subscription_id = $1
+            ORDER BY rt.code, a.quantity
+            "#,
+            subscription_id
+        )
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(internal_error)?;
+
+        addons
+            .into_iter()
+            .map(|addon| {
+                let price = Money::parse(addon.price, &addon.currency)
+                    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+                Ok(SubscriptionAddonResponse {
+                    addon_id: addon.id,
+                    code: addon.code,
+                    name: addon.name,
+                    resource_type: addon.resource_type,
+                    quantity: addon.quantity,
+                    units: addon.units * addon.quantity as i64,
+                    period_price: addon_model::period_price(price, addon.quantity, terms.period),
+                    updated_at: addon.updated_at,
+                })
+            })
+            .collect()
+    }
+
+    /// Sets the number of packs of an add-on on the subscription. The limit
+    /// changes right away. Packs added to an active subscription are
+    /// invoiced for the rest of the period, then billed with each renewal.
+    async fn set_subscription_addon(
+        &self,
+        subscription_id: uuid::Uuid,
+        user_id: uuid::Uuid,
+        addon_id: uuid::Uuid,
+        addon: SetSubscriptionAddonRequest,
+    ) -> Result<Vec<SubscriptionAddonResponse>, (StatusCode, String)> {
+        if addon.quantity <= 0 {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "quantity must be 1 or more, remove the add-on instead".to_string(),
+            ));
+        }
+
+        let mut tx = self.pool.begin().await.map_err(internal_error)?;
+
+        let subscription = Self::lock_subscription(&mut tx, subscription_id, user_id).await?;
+
+        let held = sqlx::query!(
+            r#"
+            SELECT a.name, a.resource_type_id, a.quantity AS units, a.price, a.currency, a.is_active,
+                sa.quantity AS "held?"
+            FROM addons AS a
+            LEFT JOIN subscription_addons AS sa ON sa.addon_id = a.id AND sa.subscription_id = $2
+            WHERE a.id = $1
+            "#,
+            addon_id,
+            subscription_id
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(internal_error)?
+        // Packs already held can still be reduced once the add-on is retired.
+        .filter(|a| a.is_active || a.held.is_some())
+        .ok_or((StatusCode::NOT_FOUND, "Add-on not found".to_string()))?;
+
+        let currency = subscription.terms.price.currency;
+
+        if held.currency != currency.as_str() {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!(
+                    "The subscription is billed in {}, the add-on is priced in {}",
+                    currency, held.currency
+                ),
+            ));
+        }
+
+        let held_quantity = held.held.unwrap_or_default();
+
+        if !held.is_active && addon.quantity > held_quantity {
+            return Err((
+                StatusCode::CONFLICT,
+                "The add-on is no longer sold".to_string(),
+            ));
+        }
+
+        if addon.quantity < held_quantity {
+            self.check_usage(
+                user_id,
+                subscription.plan_id,
+                held.resource_type_id,
+                held.units * (held_quantity - addon.quantity) as i64,
+            )
+            .await?;
+        }
+
+        sqlx::query!(
+            r#"
+            INSERT INTO subscription_addons (subscription_id, addon_id, quantity)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (subscription_id, addon_id)
+            DO UPDATE SET quantity = EXCLUDED.quantity, updated_at = CURRENT_TIMESTAMP
+            "#,
+            subscription_id,
+            addon_id,
+            addon.quantity
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(internal_error)?;
+
+        // Nothing was paid during a trial, the first payment covers the packs.
+        if let (SubscriptionStatus::Active, Some(start_date), Some(end_date)) = (
+            subscription.status,
+            subscription.start_date,
+            subscription.end_date,
+        ) {
+            let added = addon.quantity - held_quantity;
+            // A period paid in advance, after a trial, is charged in full.
+            let now = chrono::Utc::now().naive_utc().max(start_date);
+            let price = Money::parse(held.price, &held.currency)
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+            let amount = invoice_model::prorate(
+                addon_model::period_price(price, added, subscription.terms.period),
+                start_date,
+                end_date,
+                now,
+            );
+
+            if added > 0 && amount.amount_minor > 0 {
+                InvoiceService::issue(
+                    &mut tx,
+                    NewInvoice {
+                        subscription_id,
+                        user_id,
+                        kind: InvoiceKind::Addon,
+                        period: Some((now, end_date)),
+                        currency,
+                        due_at: now + chrono::Duration::days(invoice_model::PAYMENT_TERMS_DAYS),
+                        lines: vec![InvoiceLine::single(
+                            InvoiceLineKind::Addon,
+                            format!("{} x{} for the rest of the period", held.name, added),
+                            amount,
+                        )
+                        .for_period(now, end_date)],
+                        coupon_redemption_id: None,
+                    },
+                )
+                .await?;
+            }
+        }
+
+        tx.commit().await.map_err(internal_error)?;
+
+        self.get_subscription_addons(subscription_id, user_id).await
+    }
+
+    async fn remove_subscription_addon(
+        &self,
+        subscription_id: uuid::Uuid,
+        user_id: uuid::Uuid,
+        addon_id: uuid::Uuid,
+    ) -> Result<(), (StatusCode, String)> {
+        let mut tx = self.pool.begin().await.map_err(internal_error)?;
+
+        let subscription = Self::lock_subscription(&mut tx, subscription_id, user_id).await?;
+
+        let held = sqlx::query!(
+            r#"
+            SELECT a.resource_type_id, a.quantity AS units, sa.quantity
+            FROM subscription_addons AS sa
+            INNER JOIN addons AS a ON a.id = sa.addon_id
+            WHERE sa.subscription_id = $1 AND sa.addon_id = $2
+            "#,
+            subscription_id,
+            addon_id
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Add-on not found".to_string()))?;
+
+        self.check_usage(
+            user_id,
+            subscription.plan_id,
+            held.resource_type_id,
+            held.units * held.quantity as i64,
+        )
+        .await?;
+
+        sqlx::query!(
+            r#"
+            DELETE FROM subscription_addons WHERE subscription_id = $1 AND addon_id = $2
+            "#,
+            subscription_id,
+            addon_id
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(internal_error)?;
+
+        tx.commit().await.map_err(internal_error)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,613 @@
+use axum::http::StatusCode;
+use chrono::{Months, NaiveDateTime};
+
+use crate::domain::{
+    dtos::coupon_dtos::{
+        CouponRedemptionResponse, CouponResponse, CreateCouponRequest, UpdateCouponRequest,
+    },
+    models::{
+        coupon_model::{CouponDuration, Discount},
+        money_model::{Currency, Money},
+    },
+};
+
+pub struct CouponService {
+    pub pool: sqlx::PgPool,
+}
+
+pub trait CouponServiceImpl {
+    fn new(pool: sqlx::PgPool) -> Self;
+
+    async fn create_coupon(
+        &self,
+        coupon: CreateCouponRequest,
+    ) -> Result<CouponResponse, (StatusCode, String)>;
+
+    async fn get_coupons(&self) -> Result<Vec<CouponResponse>, String>;
+
+    async fn get_coupon(&self, id: uuid::Uuid) -> Result<CouponResponse, (StatusCode, String)>;
+
+    async fn update_coupon(
+        &self,
+        id: uuid::Uuid,
+        coupon: UpdateCouponRequest,
+    ) -> Result<CouponResponse, (StatusCode, String)>;
+
+    async fn delete_coupon(&self, id: uuid::Uuid) -> Result<(), (StatusCode, String)>;
+
+    async fn get_redemptions(
+        &self,
+        coupon_id: uuid::Uuid,
+    ) -> Result<Vec<CouponRedemptionResponse>, String>;
+}
+
+/// A discount taken off one charge by the coupon redeemed on the subscription.
+pub struct AppliedDiscount {
+    pub redemption_id: uuid::Uuid,
+    pub code: String,
+    pub amount: Money,
+}
+
+fn normalize_code(code: &str) -> String {
+    code.trim().to_uppercase()
+}
+
+/// The amount off of a coupon, stored next to its currency.
+fn amount_off(amount_off: Option<i64>, currency: Option<&str>) -> Result<Option<Money>, String> {
+    amount_off
+        .zip(currency)
+        .map(|(amount_off, currency)| Money::parse(amount_off, currency))
+        .transpose()
+}
+
+fn validate_limits(
+    max_redemptions: Option<i32>,
+    max_redemptions_per_user: Option<i32>,
+    valid_from: Option<NaiveDateTime>,
+    valid_until: Option<NaiveDateTime>,
+) -> Result<(), String> {
+    if max_redemptions.is_some_and(|max| max <= 0) {
+        return Err("max_redemptions must be 1 or more".to_string());
+    }
+
+    if max_redemptions_per_user.is_some_and(|max| max <= 0) {
+        return Err("max_redemptions_per_user must be 1 or more".to_string());
+    }
+
+    if let (Some(valid_from), Some(valid_until)) = (valid_from, valid_until) {
+        if valid_until <= valid_from {
+            return Err("valid_until must be after valid_from".to_string());
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_coupon(coupon: &CreateCouponRequest) -> Result<(), String> {
+    if normalize_code(&coupon.code).is_empty() {
+        return Err("code must not be empty".to_string());
+    }
+
+    Discount::new(coupon.percent_off, coupon.amount_off)?;
+
+    match (coupon.duration, coupon.duration_in_months) {
+        (CouponDuration::Repeating, Some(months)) if months > 0 => {}
+        (CouponDuration::Repeating, _) => {
+            return Err("duration_in_months must be 1 or more for repeating coupons".to_string())
+        }
+        (_, Some(_)) => {
+            return Err("duration_in_months is only allowed for repeating coupons".to_string())
+        }
+        (_, None) => {}
+    }
+
+    validate_limits(
+        coupon.max_redemptions,
+        coupon.max_redemptions_per_user,
+        coupon.valid_from,
+        coupon.valid_until,
+    )
+}
+
+fn internal_error(e: sqlx::Error) -> (StatusCode, String) {
+    tracing::error!("Failed to save coupon: {:?}", e);
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "Failed to save coupon".to_string(),
+    )
+}
+
+impl CouponService {
+    /// Redeems the coupon `code` on a subscription to `plan_id` billed in
+    /// `currency`, replacing the coupon the subscription had so far.
+    pub async fn redeem(
+        conn: &mut sqlx::PgConnection,
+        code: &str,
+        user_id: uuid::Uuid,
+        subscription_id: uuid::Uuid,
+        plan_id: uuid::Uuid,
+        currency: Currency,
+    ) -> Result<uuid::Uuid, (StatusCode, String)> {
+        // Locked so concurrent redemptions can't exceed the limits.
+        let coupon = sqlx::query!(
+            r#"
+            SELECT id, currency, duration, duration_in_months, max_redemptions, max_redemptions_per_user,
+                valid_from, valid_until,
+                NOT EXISTS (SELECT 1 FROM coupon_plans WHERE coupon_id = coupons.id)
+                    OR EXISTS (SELECT 1 FROM coupon_plans WHERE coupon_id = coupons.id AND plan_id = $2)
+                    AS "applies_to_plan!"
+            FROM coupons
+            WHERE code = $1 AND is_active
+            FOR UPDATE
+            "#,
+            normalize_code(code),
+            plan_id
+        )
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Coupon not found".to_string()))?;
+
+        let now = chrono::Utc::now().naive_utc();
+
+        if coupon.valid_from.is_some_and(|valid_from| now < valid_from)
+            || coupon
+                .valid_until
+                .is_some_and(|valid_until| now >= valid_until)
+        {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "The coupon is not valid at this time".to_string(),
+            ));
+        }
+
+        if !coupon.applies_to_plan {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "The coupon does not apply to this plan".to_string(),
+            ));
+        }
+
+        if coupon
+            .currency
+            .as_deref()
+            .is_some_and(|coupon_currency| coupon_currency != currency.as_str())
+        {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "The coupon does not apply to this currency".to_string(),
+            ));
+        }
+
+        let redemptions = sqlx::query!(
+            r#"
+            SELECT COUNT(*) AS "total!", COUNT(*) FILTER (WHERE user_id = $2) AS "by_user!"
+            FROM coupon_redemptions
+            WHERE coupon_id = $1
+            "#,
+            coupon.id,
+            user_id
+        )
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(internal_error)?;
+
+        if coupon
+            .max_redemptions
+            .is_some_and(|max| redemptions.total >= max as i64)
+        {
+            return Err((
+                StatusCode::CONFLICT,
+                "The coupon has reached its redemption limit".to_string(),
+            ));
+        }
+
+        if coupon
+            .max_redemptions_per_user
+            .is_some_and(|max| redemptions.by_user >= max as i64)
+        {
+            return Err((
+                StatusCode::CONFLICT,
+                "The coupon has already been redeemed by this user".to_string(),
+            ));
+        }
+
+        sqlx::query!(
+            r#"
+            UPDATE coupon_redemptions SET ended_at = $2
+            WHERE subscription_id = $1 AND ended_at IS NULL
+            "#,
+            subscription_id,
+            now
+        )
+        .execute(&mut *conn)
+        .await
+        .map_err(internal_error)?;
+
+        let duration: CouponDuration = coupon
+            .duration
+            .parse()
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+        let applies_until = match (duration, coupon.duration_in_months) {
+            (CouponDuration::Repeating, Some(months)) => Some(now + Months::new(months as u32)),
+            _ => None,
+        };
+
+        sqlx::query_scalar!(
+            r#"
+            INSERT INTO coupon_redemptions (coupon_id, subscription_id, user_id, redeemed_at, applies_until, currency)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING id
+            "#,
+            coupon.id,
+            subscription_id,
+            user_id,
+            now,
+            applies_until,
+            currency.as_str()
+        )
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(internal_error)
+    }
+
+    /// The discount the subscription's coupon takes off a charge of `price`
+    /// for the period starting at `period_start`, if it still applies.
+    pub async fn applicable_discount(
+        conn: &mut sqlx::PgConnection,
+        subscription_id: uuid::Uuid,
+        period_start: NaiveDateTime,
+        price: Money,
+    ) -> Result<Option<AppliedDiscount>, sqlx::Error> {
+        let redemption = sqlx::query!(
+            r#"
+            SELECT r.id, c.code, c.percent_off, c.amount_off, c.currency
+            FROM coupon_redemptions AS r
+            INNER JOIN coupons AS c ON c.id = r.coupon_id
+            WHERE r.subscription_id = $1 AND r.ended_at IS NULL
+              AND (r.applies_until IS NULL OR $2 < r.applies_until)
+              AND (c.duration <> 'once' OR r.times_applied = 0)
+            FOR UPDATE OF r
+            "#,
+            subscription_id,
+            period_start
+        )
+        .fetch_optional(&mut *conn)
+        .await?;
+
+        Ok(redemption.and_then(|redemption| {
+            let amount_off =
+                amount_off(redemption.amount_off, redemption.currency.as_deref()).ok()?;
+            let discount = Discount::new(redemption.percent_off, amount_off).ok()?;
+
+            Some(AppliedDiscount {
+                redemption_id: redemption.id,
+                code: redemption.code,
+                amount: discount.amount_off(price).ok()?,
+            })
+        }))
+    }
+
+    /// Takes the discount off a charge, counting it on the redemption.
+    pub async fn apply_discount(
+        conn: &mut sqlx::PgConnection,
+        subscription_id: uuid::Uuid,
+        period_start: NaiveDateTime,
+        price: Money,
+    ) -> Result<Option<AppliedDiscount>, sqlx::Error> {
+        let discount =
+            Self::applicable_discount(&mut *conn, subscription_id, period_start, price).await?;
+
+        if let Some(discount) = &discount {
+            sqlx::query!(
+                r#"
+                UPDATE coupon_redemptions
+                SET times_applied = times_applied + 1, total_discount = total_discount + $2
+                WHERE id = $1
+                "#,
+                discount.redemption_id,
+                discount.amount.amount_minor
+            )
+            .execute(&mut *conn)
+            .await?;
+        }
+
+        Ok(discount)
+    }
+
+    async fn set_plans(
+        conn: &mut sqlx::PgConnection,
+        coupon_id: uuid::Uuid,
+        plan_ids: &[uuid::Uuid],
+    ) -> Result<(), (StatusCode, String)> {
+        sqlx::query!(
+            r#"
+            DELETE FROM coupon_plans WHERE coupon_id = $1
+            "#,
+            coupon_id
+        )
+        .execute(&mut *conn)
+        .await
+        .map_err(internal_error)?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO coupon_plans (coupon_id, plan_id)
+            SELECT $1, plan_id FROM UNNEST($2::UUID[]) AS plan_id
+            ON CONFLICT DO NOTHING
+            "#,
+            coupon_id,
+            plan_ids
+        )
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(e) if e.is_foreign_key_violation() => {
+                (StatusCode::NOT_FOUND, "Plan not found".to_string())
+            }
+            e => internal_error(e),
+        })?;
+
+        Ok(())
+    }
+}
+
+impl CouponServiceImpl for CouponService {
+    fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+
+    async fn create_coupon(
+        &self,
+        coupon: CreateCouponRequest,
+    ) -> Result<CouponResponse, (StatusCode, String)> {
+        validate_coupon(&coupon).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+        let mut tx = self.pool.begin().await.map_err(internal_error)?;
+
+        let id = sqlx::query_scalar!(
+            r#"
+            INSERT INTO coupons (code, name, percent_off, amount_off, currency, duration, duration_in_months,
+                max_redemptions, max_redemptions_per_user, valid_from, valid_until)
+            VALUES ($1, $2, $3, $4, $11, $5, $6, $7, $8, $9, $10)
+            RETURNING id
+            "#,
+            normalize_code(&coupon.code),
+            coupon.name,
+            coupon.percent_off,
+            coupon.amount_off.map(|amount_off| amount_off.amount_minor),
+            coupon.duration.as_str(),
+            coupon.duration_in_months,
+            coupon.max_redemptions,
+            coupon.max_redemptions_per_user,
+            coupon.valid_from,
+            coupon.valid_until,
+            coupon.amount_off.map(|amount_off| amount_off.currency.as_str())
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(e) if e.is_unique_violation() => (
+                StatusCode::CONFLICT,
+                "A coupon with this code already exists".to_string(),
+            ),
+            e => internal_error(e),
+        })?;
+
+        Self::set_plans(&mut tx, id, &coupon.plan_ids).await?;
+
+        tx.commit().await.map_err(internal_error)?;
+
+        self.get_coupon(id).await
+    }
+
+    async fn get_coupons(&self) -> Result<Vec<CouponResponse>, String> {
+        let coupons = sqlx::query!(
+            r#"
+            SELECT id, code, name, percent_off, amount_off, currency, duration, duration_in_months, max_redemptions,
+                max_redemptions_per_user, valid_from, valid_until, is_active, created_at,
+                ARRAY(SELECT plan_id FROM coupon_plans WHERE coupon_id = coupons.id) AS "plan_ids!",
+                (SELECT COUNT(*) FROM coupon_redemptions WHERE coupon_id = coupons.id) AS "times_redeemed!"
+            FROM coupons
+            ORDER BY created_at DESC
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get coupons: {:?}", e);
+            "Failed to get coupons".to_string()
+        })?;
+
+        coupons
+            .into_iter()
+            .map(|coupon| {
+                Ok(CouponResponse {
+                    id: coupon.id,
+                    code: coupon.code,
+                    name: coupon.name,
+                    percent_off: coupon.percent_off,
+                    amount_off: amount_off(coupon.amount_off, coupon.currency.as_deref())?,
+                    duration: coupon.duration,
+                    duration_in_months: coupon.duration_in_months,
+                    max_redemptions: coupon.max_redemptions,
+                    max_redemptions_per_user: coupon.max_redemptions_per_user,
+                    valid_from: coupon.valid_from,
+                    valid_until: coupon.valid_until,
+                    is_active: coupon.is_active,
+                    plan_ids: coupon.plan_ids,
+                    times_redeemed: coupon.times_redeemed,
+                    created_at: coupon.created_at,
+                })
+            })
+            .collect()
+    }
+
+    async fn get_coupon(&self, id: uuid::Uuid) -> Result<CouponResponse, (StatusCode, String)> {
+        let coupon = sqlx::query!(
+            r#"
+            SELECT id, code, name, percent_off, amount_off, currency, duration, duration_in_months, max_redemptions,
+                max_redemptions_per_user, valid_from, valid_until, is_active, created_at,
+                ARRAY(SELECT plan_id FROM coupon_plans WHERE coupon_id = coupons.id) AS "plan_ids!",
+                (SELECT COUNT(*) FROM coupon_redemptions WHERE coupon_id = coupons.id) AS "times_redeemed!"
+            FROM coupons
+            WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get coupon: {:?}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to get coupon".to_string(),
+            )
+        })?
+        .ok_or((StatusCode::NOT_FOUND, "Coupon not found".to_string()))?;
+
+        Ok(CouponResponse {
+            id: coupon.id,
+            code: coupon.code,
+            name: coupon.name,
+            percent_off: coupon.percent_off,
+            amount_off: amount_off(coupon.amount_off, coupon.currency.as_deref())
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?,
+            duration: coupon.duration,
+            duration_in_months: coupon.duration_in_months,
+            max_redemptions: coupon.max_redemptions,
+            max_redemptions_per_user: coupon.max_redemptions_per_user,
+            valid_from: coupon.valid_from,
+            valid_until: coupon.valid_until,
+            is_active: coupon.is_active,
+            plan_ids: coupon.plan_ids,
+            times_redeemed: coupon.times_redeemed,
+            created_at: coupon.created_at,
+        })
+    }
+
+    async fn update_coupon(
+        &self,
+        id: uuid::Uuid,
+        coupon: UpdateCouponRequest,
+    ) -> Result<CouponResponse, (StatusCode, String)> {
+        validate_limits(
+            coupon.max_redemptions,
+            coupon.max_redemptions_per_user,
+            coupon.valid_from,
+            coupon.valid_until,
+        )
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+        let mut tx = self.pool.begin().await.map_err(internal_error)?;
+
+        sqlx::query!(
+            r#"
+            UPDATE coupons
+            SET name = $1, max_redemptions = $2, max_redemptions_per_user = $3, valid_from = $4,
+                valid_until = $5, is_active = $6, updated_at = CURRENT_TIMESTAMP
+            WHERE id = $7
+            RETURNING id
+            "#,
+            coupon.name,
+            coupon.max_redemptions,
+            coupon.max_redemptions_per_user,
+            coupon.valid_from,
+            coupon.valid_until,
+            coupon.is_active,
+            id
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Coupon not found".to_string()))?;
+
+        Self::set_plans(&mut tx, id, &coupon.plan_ids).await?;
+
+        tx.commit().await.map_err(internal_error)?;
+
+        self.get_coupon(id).await
+    }
+
+    async fn delete_coupon(&self, id: uuid::Uuid) -> Result<(), (StatusCode, String)> {
+        let coupon = sqlx::query!(
+            r#"
+            SELECT EXISTS (SELECT 1 FROM coupon_redemptions WHERE coupon_id = coupons.id) AS "redeemed!"
+            FROM coupons
+            WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Coupon not found".to_string()))?;
+
+        // Redemptions keep their coupon for the history of discounts.
+        if coupon.redeemed {
+            return Err((
+                StatusCode::CONFLICT,
+                "The coupon has been redeemed, deactivate it instead".to_string(),
+            ));
+        }
+
+        sqlx::query!(
+            r#"
+            DELETE FROM coupons WHERE id = $1
+            "#,
+            id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(e) if e.is_foreign_key_violation() => (
+                StatusCode::CONFLICT,
+                "The coupon has been redeemed, deactivate it instead".to_string(),
+            ),
+            e => internal_error(e),
+        })?;
+
+        Ok(())
+    }
+
+    async fn get_redemptions(
+        &self,
+        coupon_id: uuid::Uuid,
+    ) -> Result<Vec<CouponRedemptionResponse>, String> {
+        let redemptions = sqlx::query!(
+            r#"
+            SELECT r.id, r.coupon_id, r.subscription_id, r.user_id, u.username, r.redeemed_at,
+                r.applies_until, r.times_applied, r.total_discount, r.currency, r.ended_at
+            FROM coupon_redemptions AS r
+            INNER JOIN users AS u ON u.id = r.user_id
+            WHERE r.coupon_id = $1
+            ORDER BY r.redeemed_at DESC
+            "#,
+            coupon_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get coupon redemptions: {:?}", e);
+            "Failed to get coupon redemptions".to_string()
+        })?;
+
+        redemptions
+            .into_iter()
+            .map(|redemption| {
+                Ok(CouponRedemptionResponse {
+                    id: redemption.id,
+                    coupon_id: redemption.coupon_id,
+                    subscription_id: redemption.subscription_id,
+                    user_id: redemption.user_id,
+                    username: redemption.username,
+                    redeemed_at: redemption.redeemed_at,
+                    applies_until: redemption.applies_until,
+                    times_applied: redemption.times_applied,
+                    total_discount: Money::parse(redemption.total_discount, &redemption.currency)?,
+                    ended_at: redemption.ended_at,
+                })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,563 @@
+use axum::http::StatusCode;
+use chrono::NaiveDateTime;
+
+use crate::{
+    domain::{
+        dtos::{
+            dunning_dtos::{DunningAccountResponse, MarkPaidRequest},
+            subscription_dtos::SubscriptionResponse,
+        },
+        models::{
+            dunning_model::{DunningPolicy, DunningStage},
+            invoice_model::InvoiceStatus,
+            money_model::Money,
+            subscription_model::{Actor, SubscriptionStatus},
+        },
+    },
+    infra::mail::{Mail, MailSender},
+};
+
+use super::{
+    invoice_service::InvoiceService,
+    subscription_service::{SubscriptionService, SubscriptionServiceImpl},
+};
+
+pub struct DunningService {
+    pub pool: sqlx::PgPool,
+}
+
+pub trait DunningServiceImpl {
+    fn new(pool: sqlx::PgPool) -> Self;
+
+    async fn run(&self, policy: &DunningPolicy, mailer: &impl MailSender) -> Result<u64, String>;
+
+    async fn get_accounts(
+        &self,
+        policy: &DunningPolicy,
+        stage: Option<DunningStage>,
+    ) -> Result<Vec<DunningAccountResponse>, String>;
+
+    async fn mark_paid(
+        &self,
+        subscription_id: uuid::Uuid,
+        sys_id: uuid::Uuid,
+        request: MarkPaidRequest,
+    ) -> Result<SubscriptionResponse, (StatusCode, String)>;
+}
+
+fn internal_error(e: sqlx::Error) -> (StatusCode, String) {
+    tracing::error!("Failed to process unpaid invoice: {:?}", e);
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "Failed to process unpaid invoice".to_string(),
+    )
+}
+
+/// The email sent for a dunning step about the invoice `(number, amount)`.
+fn notice(
+    to: String,
+    name: &str,
+    plan: &str,
+    (number, amount): (&str, Money),
+    stage: DunningStage,
+    read_only_started: bool,
+    cancels_at: NaiveDateTime,
+) -> Mail {
+    let (subject, status) = match stage {
+        DunningStage::Canceled => (
+            format!("Your {} subscription has been canceled", plan),
+            "It has been canceled as the invoice was not paid in time.".to_string(),
+        ),
+        DunningStage::ReadOnly if read_only_started => (
+            format!("Your {} subscription is now read-only", plan),
+            format!(
+                "Your quotas are read-only until it is paid. The subscription will be canceled on {}.",
+                cancels_at.format("%Y-%m-%d")
+            ),
+        ),
+        _ => (
+            format!("Reminder: your {} renewal invoice is unpaid", plan),
+            format!(
+                "Please pay it before {} to keep your subscription.",
+                cancels_at.format("%Y-%m-%d")
+            ),
+        ),
+    };
+
+    Mail {
+        to,
+        subject,
+        body: format!(
+            "Hello {},\n\nThe renewal invoice {} of {} for your {} subscription is unpaid. {}\n",
+            name, number, amount, plan, status
+        ),
+    }
+}
+
+impl DunningService {
+    /// Moves the subscription of the unpaid invoice `id` to the dunning step
+    /// due at this time and records the notice of the step. Returns whether
+    /// a step was due.
+    async fn advance(&self, policy: &DunningPolicy, id: uuid::Uuid) -> Result<bool, String> {
+        let mut tx = self.pool.begin().await.map_err(|e| internal_error(e).1)?;
+
+        // Rows locked by another replica or a payment are left for the next run.
+        let invoice = sqlx::query!(
+            r#"
+            SELECT i.subscription_id, i.number AS "number!", i.amount, i.currency, i.past_due_at AS "past_due_at!",
+                i.retries_sent, i.read_only_at, u.email, u.name, p.name AS plan_name
+            FROM invoices AS i
+            INNER JOIN subscriptions AS s ON s.id = i.subscription_id
+            INNER JOIN users AS u ON u.id = s.user_id
+            INNER JOIN plans AS p ON p.id = s.plan_id
+            WHERE i.id = $1 AND i.status = 'open' AND i.kind = 'period' AND i.past_due_at IS NOT NULL
+              AND s.status = $2
+            FOR UPDATE OF i, s SKIP LOCKED
+            "#,
+            id,
+            SubscriptionStatus::PastDue.as_str()
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| internal_error(e).1)?;
+
+        let Some(invoice) = invoice else {
+            return Ok(false);
+        };
+
+        let now = chrono::Utc::now().naive_utc();
+        let stage = policy.stage(invoice.past_due_at, now);
+        let retries_due = policy.retries_due(invoice.past_due_at, now);
+        let read_only_started = stage == DunningStage::ReadOnly && invoice.read_only_at.is_none();
+
+        match stage {
+            DunningStage::Canceled => {
+                // Before the subscription, which voids the invoices left open.
+                InvoiceService::transition(&mut tx, id, InvoiceStatus::Uncollectible, None)
+                    .await
+                    .map_err(|(_, e)| e)?;
+
+                SubscriptionService::transition(
+                    &mut tx,
+                    invoice.subscription_id,
+                    SubscriptionStatus::Canceled,
+                    Actor::System,
+                    Some(format!(
+                        "Renewal invoice {} unpaid after the grace period",
+                        invoice.number
+                    )),
+                )
+                .await
+                .map_err(|(_, e)| e)?;
+            }
+            _ if read_only_started || retries_due > invoice.retries_sent => {
+                sqlx::query!(
+                    r#"
+                    UPDATE invoices
+                    SET retries_sent = $1, last_retry_at = CASE WHEN $1 > retries_sent THEN $2 ELSE last_retry_at END,
+                        read_only_at = COALESCE(read_only_at, $3)
+                    WHERE id = $4
+                    "#,
+                    retries_due,
+                    now,
+                    read_only_started.then_some(now),
+                    id
+                )
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| internal_error(e).1)?;
+            }
+            _ => return Ok(false),
+        }
+
+        let amount = Money::parse(invoice.amount, &invoice.currency)?;
+        let mail = notice(
+            invoice.email,
+            &invoice.name,
+            &invoice.plan_name,
+            (&invoice.number, amount),
+            stage,
+            read_only_started,
+            policy.cancels_at(invoice.past_due_at),
+        );
+
+        sqlx::query!(
+            r#"
+            INSERT INTO dunning_notices (invoice_id, recipient, subject, body)
+            VALUES ($1, $2, $3, $4)
+            "#,
+            id,
+            mail.to,
+            mail.subject,
+            mail.body
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| internal_error(e).1)?;
+
+        tx.commit().await.map_err(|e| internal_error(e).1)?;
+
+        Ok(true)
+    }
+
+    /// Sends the notices not sent yet, oldest first. Runs are kept to one
+    /// replica at a time by the scheduler, so a notice is sent once.
+    async fn send_notices(&self, mailer: &impl MailSender) -> Result<(), String> {
+        let notices = sqlx::query!(
+            r#"
+            SELECT id, recipient, subject, body FROM dunning_notices
+            WHERE sent_at IS NULL
+            ORDER BY created_at
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| internal_error(e).1)?;
+
+        for notice in notices {
+            let mail = Mail {
+                to: notice.recipient,
+                subject: notice.subject,
+                body: notice.body,
+            };
+
+            let sent = mailer.send(&mail).await;
+
+            if let Err(e) = &sent {
+                tracing::error!("Failed to send dunning notice {}: {}", notice.id, e);
+            }
+
+            sqlx::query!(
+                r#"
+                UPDATE dunning_notices
+                SET attempts = attempts + 1, last_error = $2, sent_at = CASE WHEN $2::TEXT IS NULL THEN CURRENT_TIMESTAMP END
+                WHERE id = $1
+                "#,
+                notice.id,
+                sent.err()
+            )
+            .execute(&self.pool)
+            .await
+            .map_err(|e| internal_error(e).1)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl DunningServiceImpl for DunningService {
+    fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Moves every past due subscription along the dunning steps due at this
+    /// time, then emails the customer about each step. Notices are recorded
+    /// with their step and sent once it is committed, the ones that can't be
+    /// sent are retried on the next run.
+    async fn run(&self, policy: &DunningPolicy, mailer: &impl MailSender) -> Result<u64, String> {
+        let unpaid = sqlx::query_scalar!(
+            r#"
+            SELECT i.id
+            FROM invoices AS i
+            INNER JOIN subscriptions AS s ON s.id = i.subscription_id
+            WHERE i.status = 'open' AND i.kind = 'period' AND i.past_due_at IS NOT NULL AND s.status = $1
+            "#,
+            SubscriptionStatus::PastDue.as_str()
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| internal_error(e).1)?;
+
+        let mut count = 0;
+
+        for id in unpaid {
+            match self.advance(policy, id).await {
+                Ok(advanced) => count += advanced as u64,
+                Err(e) => {
+                    tracing::error!("Failed to run dunning for invoice {}: {}", id, e);
+                }
+            }
+        }
+
+        self.send_notices(mailer).await?;
+
+        Ok(count)
+    }
+
+    async fn get_accounts(
+        &self,
+        policy: &DunningPolicy,
+        stage: Option<DunningStage>,
+    ) -> Result<Vec<DunningAccountResponse>, String> {
+        let accounts = sqlx::query!(
+            r#"
+            SELECT i.id, i.number AS "number!", i.subscription_id, s.user_id AS "user_id!", u.username, u.email,
+                i.amount, i.currency, i.issued_at AS "issued_at!", i.past_due_at AS "past_due_at!", i.retries_sent,
+                i.last_retry_at, i.read_only_at
+            FROM invoices AS i
+            INNER JOIN subscriptions AS s ON s.id = i.subscription_id
+            INNER JOIN users AS u ON u.id = s.user_id
+            WHERE i.status = 'open' AND i.kind = 'period' AND i.past_due_at IS NOT NULL AND s.status = $1
+            ORDER BY i.past_due_at
+            "#,
+            SubscriptionStatus::PastDue.as_str()
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get dunning accounts: {:?}", e);
+            "Failed to get dunning accounts".to_string()
+        })?;
+
+        let now = chrono::Utc::now().naive_utc();
+        let mut responses = vec![];
+
+        for account in accounts {
+            // The stage stored by the last run, or the one it is about to reach.
+            let account_stage = match account.read_only_at {
+                Some(_) => policy
+                    .stage(account.past_due_at, now)
+                    .max(DunningStage::ReadOnly),
+                None => policy.stage(account.past_due_at, now),
+            };
+
+            if stage.is_some_and(|stage| stage != account_stage) {
+                continue;
+            }
+
+            responses.push(DunningAccountResponse {
+                subscription_id: account.subscription_id,
+                user_id: account.user_id,
+                username: account.username,
+                email: account.email,
+                invoice_id: account.id,
+                invoice_number: account.number,
+                amount: Money::parse(account.amount, &account.currency)?,
+                issued_at: account.issued_at,
+                past_due_at: account.past_due_at,
+                stage: account_stage,
+                retries_sent: account.retries_sent,
+                last_retry_at: account.last_retry_at,
+                read_only_at: account
+                    .read_only_at
+                    .unwrap_or_else(|| policy.read_only_at(account.past_due_at)),
+                cancels_at: policy.cancels_at(account.past_due_at),
+            });
+        }
+
+        Ok(responses)
+    }
+
+    /// Settles the open renewal invoice of a subscription without a payment
+    /// and makes the subscription active again when it is past due.
+    async fn mark_paid(
+        &self,
+        subscription_id: uuid::Uuid,
+        sys_id: uuid::Uuid,
+        request: MarkPaidRequest,
+    ) -> Result<SubscriptionResponse, (StatusCode, String)> {
+        if request.reason.trim().is_empty() {
+            return Err((StatusCode::BAD_REQUEST, "reason is required".to_string()));
+        }
+
+        let mut tx = self.pool.begin().await.map_err(internal_error)?;
+
+        // The subscription is locked first, as payments do.
+        let current = sqlx::query!(
+            r#"
+            SELECT status, start_date FROM subscriptions WHERE id = $1 FOR UPDATE
+            "#,
+            subscription_id
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Subscription not found".to_string()))?;
+
+        let invoice_id = sqlx::query_scalar!(
+            r#"
+            UPDATE invoices SET marked_paid_by = $3, marked_paid_reason = $4
+            WHERE subscription_id = $1 AND kind = 'period' AND period_start = $2 AND status = 'open'
+            RETURNING id
+            "#,
+            subscription_id,
+            current.start_date,
+            sys_id,
+            request.reason
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(internal_error)?
+        .ok_or((
+            StatusCode::CONFLICT,
+            "The subscription has no unpaid renewal invoice".to_string(),
+        ))?;
+
+        InvoiceService::transition(&mut tx, invoice_id, InvoiceStatus::Paid, None).await?;
+
+        if current.status != SubscriptionStatus::PastDue.as_str() {
+            tx.commit().await.map_err(internal_error)?;
+
+            return SubscriptionService::new(self.pool.clone())
+                .get_subscription(subscription_id)
+                .await
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e));
+        }
+
+        let subscription = SubscriptionService::transition(
+            &mut tx,
+            subscription_id,
+            SubscriptionStatus::Active,
+            Actor::Sys(sys_id),
+            Some(format!("Marked as paid: {}", request.reason)),
+        )
+        .await?;
+
+        tx.commit().await.map_err(internal_error)?;
+
+        Ok(subscription)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infra::mail::FileMailSender;
+
+    const SUBSCRIPTION_ID: uuid::Uuid = uuid::uuid!("b0000000-0000-0000-0000-000000000001");
+
+    /// The emails written by a `FileMailSender` to `dir`, oldest first.
+    fn sent_mails(dir: &std::path::Path) -> Vec<String> {
+        let mut paths: Vec<_> = std::fs::read_dir(dir)
+            .map(|entries| entries.map(|entry| entry.unwrap().path()).collect())
+            .unwrap_or_default();
+        paths.sort();
+
+        paths
+            .into_iter()
+            .map(|path| std::fs::read_to_string(path).unwrap())
+            .collect()
+    }
+
+    async fn subscription_status(pool: &sqlx::PgPool) -> String {
+        sqlx::query_scalar!(
+            r#"
+            SELECT status FROM subscriptions WHERE id = $1
+            "#,
+            SUBSCRIPTION_ID
+        )
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    /// Moves the dunning clock of the renewal invoice back to `days` ago.
+    async fn past_due_for(pool: &sqlx::PgPool, days: i32) {
+        sqlx::query!(
+            r#"
+            UPDATE invoices SET past_due_at = CURRENT_TIMESTAMP - make_interval(days => $2)
+            WHERE subscription_id = $1 AND status = 'open'
+            "#,
+            SUBSCRIPTION_ID,
+            days
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    #[sqlx::test(fixtures("subscriptions"))]
+    async fn test_dunning_starts_when_the_renewal_invoice_is_overdue(pool: sqlx::PgPool) {
+        let subscriptions = SubscriptionService::new(pool.clone());
+        let dunning = DunningService::new(pool.clone());
+        let policy = DunningPolicy::default();
+
+        sqlx::query!(
+            r#"
+            UPDATE subscriptions
+            SET start_date = CURRENT_TIMESTAMP - INTERVAL '31 days', end_date = CURRENT_TIMESTAMP - INTERVAL '1 day'
+            WHERE id = $1
+            "#,
+            SUBSCRIPTION_ID
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        // Renewed, the subscription stays active until the invoice is due.
+        assert_eq!(subscriptions.renew_due().await, Ok(1));
+        assert_eq!(subscriptions.past_due().await, Ok(0));
+        assert_eq!(subscription_status(&pool).await, "active");
+
+        sqlx::query!(
+            r#"
+            UPDATE invoices SET due_at = CURRENT_TIMESTAMP - INTERVAL '1 minute'
+            WHERE subscription_id = $1 AND status = 'open'
+            "#,
+            SUBSCRIPTION_ID
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        assert_eq!(subscriptions.past_due().await, Ok(1));
+        assert_eq!(subscription_status(&pool).await, "past_due");
+
+        // The first reminder is kept when it can't be sent, and sent on the
+        // next run without moving the step again.
+        let tmp = std::env::temp_dir().join(format!("crm-dunning-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&tmp).unwrap();
+        std::fs::write(tmp.join("not-a-dir"), "").unwrap();
+
+        let down = FileMailSender::new(tmp.join("not-a-dir").join("mail"));
+        assert_eq!(dunning.run(&policy, &down).await, Ok(1));
+
+        let dir = tmp.join("mail");
+        let mailer = FileMailSender::new(&dir);
+        assert_eq!(dunning.run(&policy, &mailer).await, Ok(0));
+
+        let mails = sent_mails(&dir);
+        assert_eq!(mails.len(), 1);
+        assert!(mails[0].starts_with("To: parent@example.com\r\n"));
+
+        let unsent = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) AS "count!" FROM dunning_notices WHERE sent_at IS NULL
+            "#
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(unsent, 0);
+
+        past_due_for(&pool, 11).await;
+        assert_eq!(dunning.run(&policy, &mailer).await, Ok(1));
+
+        let accounts = dunning
+            .get_accounts(&policy, Some(DunningStage::ReadOnly))
+            .await
+            .unwrap();
+        assert_eq!(accounts.len(), 1);
+        assert_eq!(accounts[0].retries_sent, 3);
+        let mails = sent_mails(&dir);
+        assert_eq!(mails.len(), 2);
+        assert!(mails[1].contains("is now read-only"));
+
+        past_due_for(&pool, 15).await;
+        assert_eq!(dunning.run(&policy, &mailer).await, Ok(1));
+        assert_eq!(subscription_status(&pool).await, "canceled");
+
+        let invoice_status = sqlx::query_scalar!(
+            r#"
+            SELECT status FROM invoices WHERE subscription_id = $1 AND kind = 'period'
+            "#,
+            SUBSCRIPTION_ID
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(invoice_status, InvoiceStatus::Uncollectible.as_str());
+
+        std::fs::remove_dir_all(tmp).unwrap();
+    }
+}
@@ -0,0 +1,230 @@
+use crate::domain::dtos::feature_dtos::{
+    CreateFeatureRequest, FeatureResponse, SetPlanFeaturesRequest,
+};
+
+use super::subscription_service::SubscriptionService;
+
+pub struct FeatureService {
+    pub pool: sqlx::PgPool,
+}
+
+pub trait FeatureServiceImpl {
+    fn new(pool: sqlx::PgPool) -> Self;
+
+    async fn create_feature(
+        &self,
+        feature: CreateFeatureRequest,
+    ) -> Result<FeatureResponse, String>;
+
+    async fn get_features(&self) -> Result<Vec<FeatureResponse>, String>;
+
+    async fn update_feature(
+        &self,
+        id: uuid::Uuid,
+        feature: CreateFeatureRequest,
+    ) -> Result<FeatureResponse, String>;
+
+    async fn delete_feature(&self, id: uuid::Uuid) -> Result<(), String>;
+
+    async fn set_plan_features(
+        &self,
+        plan_id: uuid::Uuid,
+        features: SetPlanFeaturesRequest,
+    ) -> Result<Vec<String>, String>;
+
+    async fn has_feature(&self, user_id: uuid::Uuid, code: &str) -> Result<bool, String>;
+}
+
+impl FeatureServiceImpl for FeatureService {
+    fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+
+    async fn create_feature(
+        &self,
+        feature: CreateFeatureRequest,
+    ) -> Result<FeatureResponse, String> {
+        let feature = sqlx::query!(
+            r#"
+            INSERT INTO features (code, name, description)
+            VALUES ($1, $2, $3)
+            RETURNING id, code, name, description, created_at
+            "#,
+            feature.code,
+            feature.name,
+            feature.description
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to create feature: {:?}", e);
+            "Failed to create feature".to_string()
+        })?;
+
+        Ok(FeatureResponse {
+            id: feature.id,
+            code: feature.code,
+            name: feature.name,
+            description: feature.description.unwrap_or_default(),
+            created_at: feature.created_at,
+        })
+    }
+
+    async fn get_features(&self) -> Result<Vec<FeatureResponse>, String> {
+        let features = sqlx::query!(
+            r#"
+            SELECT id, code, name, description, created_at
+            FROM features
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get features: {:?}", e);
+            "Failed to get features".to_string()
+        })?;
+
+        Ok(features
+            .into_iter()
+            .map(|feature| FeatureResponse {
+                id: feature.id,
+                code: feature.code,
+                name: feature.name,
+                description: feature.description.unwrap_or_default(),
+                created_at: feature.created_at,
+            })
+            .collect())
+    }
+
+    async fn update_feature(
+        &self,
+        id: uuid::Uuid,
+        feature: CreateFeatureRequest,
+    ) -> Result<FeatureResponse, String> {
+        let feature = sqlx::query!(
+            r#"
+            UPDATE features
+            SET code = $1, name = $2, description = $3, updated_at = CURRENT_TIMESTAMP
+            WHERE id = $4
+            RETURNING id, code, name, description, created_at
+            "#,
+            feature.code,
+            feature.name,
+            feature.description,
+            id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to update feature: {:?}", e);
+            "Failed to update feature".to_string()
+        })?;
+
+        Ok(FeatureResponse {
+            id: feature.id,
+            code: feature.code,
+            name: feature.name,
+            description: feature.description.unwrap_or_default(),
+            created_at: feature.created_at,
+        })
+    }
+
+    async fn delete_feature(&self, id: uuid::Uuid) -> Result<(), String> {
+        sqlx::query!(
+            r#"
+            DELETE FROM features WHERE id = $1
+            "#,
+            id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to delete feature: {:?}", e);
+            "Failed to delete feature".to_string()
+        })?;
+
+        Ok(())
+    }
+
+    async fn set_plan_features(
+        &self,
+        plan_id: uuid::Uuid,
+        features: SetPlanFeaturesRequest,
+    ) -> Result<Vec<String>, String> {
+        let mut tx = self.pool.begin().await.map_err(|e| {
+            tracing::error!("Failed to begin transaction: {:?}", e);
+            "Failed to set plan features".to_string()
+        })?;
+
+        sqlx::query!(
+            r#"
+            DELETE FROM plan_features WHERE plan_id = $1
+            "#,
+            plan_id
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to clear plan features: {:?}", e);
+            "Failed to set plan features".to_string()
+        })?;
+
+        let inserted = sqlx::query!(
+            r#"
+            INSERT INTO plan_features (plan_id, feature_id)
+            SELECT $1, f.id
+            FROM features AS f
+            WHERE f.code = ANY($2)
+            RETURNING feature_id
+            "#,
+            plan_id,
+            features.features.as_slice()
+        )
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to set plan features: {:?}", e);
+            "Failed to set plan features".to_string()
+        })?;
+
+        if inserted.len() != features.features.len() {
+            return Err("Unknown or duplicated feature".to_string());
+        }
+
+        tx.commit().await.map_err(|e| {
+            tracing::error!("Failed to commit plan features: {:?}", e);
+            "Failed to set plan features".to_string()
+        })?;
+
+        Ok(features.features)
+    }
+
+    async fn has_feature(&self, user_id: uuid::Uuid, code: &str) -> Result<bool, String> {
+        let user_id = SubscriptionService::subscriber_id(&self.pool, user_id).await?;
+
+        let feature = sqlx::query!(
+            r#"
+            SELECT EXISTS (
+                SELECT 1
+                FROM subscriptions AS s
+                INNER JOIN plan_features AS pf ON pf.plan_id = s.plan_id
+                INNER JOIN features AS f ON f.id = pf.feature_id
+                WHERE s.user_id = $1
+                  AND s.is_active = TRUE
+                  AND (s.end_date IS NULL OR s.end_date > CURRENT_TIMESTAMP)
+                  AND f.code = $2
+            ) AS "has_feature!"
+            "#,
+            user_id,
+            code
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to check feature: {:?}", e);
+            "Failed to check feature".to_string()
+        })?;
+
+        Ok(feature.has_feature)
+    }
+}
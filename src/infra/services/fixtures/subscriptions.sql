@@ -0,0 +1,19 @@
+-- parent: gói Basic đang hoạt động, kỳ hiện tại bắt đầu từ hôm qua
+-- child: chưa có Subscription
+-- other: Subscription gói Premium chưa thanh toán
+INSERT INTO users (id, name, email, username, password) VALUES
+('a0000000-0000-0000-0000-000000000001', 'Parent', 'parent@example.com', 'parent', ''),
+('a0000000-0000-0000-0000-000000000002', 'Child', 'child@example.com', 'child', ''),
+('a0000000-0000-0000-0000-000000000003', 'Other', 'other@example.com', 'other', '');
+
+INSERT INTO subscriptions (id, user_id, plan_id, plan_version_id, status, start_date, end_date)
+SELECT s.id, s.user_id, s.plan_id, v.id, s.status, s.start_date, s.end_date
+FROM (VALUES
+    ('b0000000-0000-0000-0000-000000000001'::UUID, 'a0000000-0000-0000-0000-000000000001'::UUID,
+     '13cafdb3-a88d-4987-8119-0470caebd56c'::UUID, 'active',
+     CURRENT_TIMESTAMP - INTERVAL '1 day', CURRENT_TIMESTAMP + INTERVAL '29 days'),
+    ('b0000000-0000-0000-0000-000000000003'::UUID, 'a0000000-0000-0000-0000-000000000003'::UUID,
+     '0137ac8e-ca1e-4446-a017-40b4cdbbe92f'::UUID, 'incomplete',
+     CURRENT_TIMESTAMP, CURRENT_TIMESTAMP + INTERVAL '30 days')
+) AS s (id, user_id, plan_id, status, start_date, end_date)
+INNER JOIN plan_versions AS v ON v.plan_id = s.plan_id AND v.version = 1;
@@ -0,0 +1,1102 @@
+use axum::http::StatusCode;
+use chrono::NaiveDateTime;
+
+use crate::{
+    domain::{
+        dtos::{
+            invoice_dtos::{
+                EInvoiceExportResponse, InvoiceLineResponse, InvoiceResponse, InvoiceSearchQuery,
+                VoidInvoiceRequest,
+            },
+            pagination_dtos::{PageResponse, PaginationQuery},
+        },
+        models::{
+            invoice_model::{self, InvoiceKind, InvoiceLine, InvoiceLineKind, InvoiceStatus},
+            money_model::{Currency, Money},
+            subscription_model::{Actor, SubscriptionStatus},
+            tax_model::{TaxBreakdown, TaxRate},
+        },
+    },
+    infra::documents::{render_einvoice, Buyer, DocumentPayment, InvoiceDocument, Seller},
+};
+
+use super::{
+    addon_service::AddonService,
+    coupon_service::CouponService,
+    subscription_service::{BillingTerms, SubscriptionService},
+    tax_service::{AppliedTax, TaxService, TaxedAmount},
+};
+
+pub struct InvoiceService {
+    pub pool: sqlx::PgPool,
+}
+
+pub trait InvoiceServiceImpl {
+    fn new(pool: sqlx::PgPool) -> Self;
+
+    async fn get_invoices(&self, user_id: uuid::Uuid) -> Result<Vec<InvoiceResponse>, String>;
+
+    async fn get_invoice(
+        &self,
+        id: uuid::Uuid,
+        user_id: uuid::Uuid,
+    ) -> Result<InvoiceResponse, (StatusCode, String)>;
+
+    async fn get_document(
+        &self,
+        id: uuid::Uuid,
+        user_id: uuid::Uuid,
+        seller: Seller,
+    ) -> Result<InvoiceDocument, (StatusCode, String)>;
+
+    async fn export_einvoice(
+        &self,
+        id: uuid::Uuid,
+        seller: Seller,
+    ) -> Result<EInvoiceExportResponse, (StatusCode, String)>;
+
+    async fn export_einvoices(
+        &self,
+        query: InvoiceSearchQuery,
+        pagination: PaginationQuery,
+        seller: Seller,
+    ) -> Result<PageResponse<EInvoiceExportResponse>, String>;
+
+    async fn search_invoices(
+        &self,
+        query: InvoiceSearchQuery,
+        pagination: PaginationQuery,
+    ) -> Result<PageResponse<InvoiceResponse>, String>;
+
+    async fn void_invoice(
+        &self,
+        id: uuid::Uuid,
+        sys_id: uuid::Uuid,
+        request: VoidInvoiceRequest,
+    ) -> Result<InvoiceResponse, (StatusCode, String)>;
+}
+
+/// An invoice to issue for a subscription.
+pub struct NewInvoice {
+    pub subscription_id: uuid::Uuid,
+    pub user_id: uuid::Uuid,
+    pub kind: InvoiceKind,
+    pub period: Option<(NaiveDateTime, NaiveDateTime)>,
+    pub currency: Currency,
+    pub due_at: NaiveDateTime,
+    pub lines: Vec<InvoiceLine>,
+    pub coupon_redemption_id: Option<uuid::Uuid>,
+}
+
+/// An issued invoice, with the tax a payment of it records.
+pub struct IssuedInvoice {
+    pub id: uuid::Uuid,
+    pub kind: InvoiceKind,
+    pub period_start: Option<NaiveDateTime>,
+    pub period_end: Option<NaiveDateTime>,
+    pub amount: TaxedAmount,
+}
+
+#[derive(Default)]
+struct InvoiceFilter {
+    id: Option<uuid::Uuid>,
+    user_id: Option<uuid::Uuid>,
+    subscription_id: Option<uuid::Uuid>,
+    status: Option<InvoiceStatus>,
+    kind: Option<InvoiceKind>,
+    number: Option<String>,
+    from: Option<NaiveDateTime>,
+    to: Option<NaiveDateTime>,
+}
+
+fn einvoice_export(document: &InvoiceDocument) -> EInvoiceExportResponse {
+    let (xml, errors) = match render_einvoice(document) {
+        Ok(xml) => (Some(xml), vec![]),
+        Err(errors) => (None, errors),
+    };
+
+    EInvoiceExportResponse {
+        invoice_id: document.invoice.id,
+        number: document.invoice.number.clone(),
+        xml,
+        errors,
+    }
+}
+
+fn internal_error(e: sqlx::Error) -> (StatusCode, String) {
+    tracing::error!("Failed to save invoice: {:?}", e);
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "Failed to save invoice".to_string(),
+    )
+}
+
+impl InvoiceService {
+    /// Saves `invoice` as a draft, with the tax `user_id` pays at this time
+    /// added to the sum of its lines.
+    pub async fn create_draft(
+        conn: &mut sqlx::PgConnection,
+        invoice: NewInvoice,
+    ) -> Result<uuid::Uuid, (StatusCode, String)> {
+        let subtotal = invoice_model::lines_total(&invoice.lines, invoice.currency)
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+        let now = chrono::Utc::now().naive_utc();
+        let tax = TaxService::applicable(&mut *conn, invoice.user_id, now)
+            .await
+            .map_err(internal_error)?;
+        let breakdown = tax.rate.charge(subtotal);
+
+        let id = sqlx::query_scalar!(
+            r#"
+            INSERT INTO invoices (subscription_id, user_id, kind, period_start, period_end, amount, subtotal,
+                tax_amount, currency, coupon_redemption_id, tax_rule_id, tax_name, tax_region, tax_rate_bps,
+                tax_inclusive, due_at, status)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, 'draft')
+            RETURNING id
+            "#,
+            invoice.subscription_id,
+            invoice.user_id,
+            invoice.kind.as_str(),
+            invoice.period.map(|(start, _)| start),
+            invoice.period.map(|(_, end)| end),
+            breakdown.total.amount_minor,
+            breakdown.subtotal.amount_minor,
+            breakdown.tax.amount_minor,
+            invoice.currency.as_str(),
+            invoice.coupon_redemption_id,
+            tax.rule_id,
+            tax.name,
+            tax.region,
+            tax.rate.rate_bps,
+            tax.rate.is_inclusive,
+            invoice.due_at
+        )
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(internal_error)?;
+
+        for (position, line) in invoice.lines.iter().enumerate() {
+            sqlx::query!(
+                r#"
+                INSERT INTO invoice_lines (invoice_id, kind, description, quantity, unit_amount, amount, period_start,
+                    period_end, position)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+                "#,
+                id,
+                line.kind.as_str(),
+                line.description,
+                line.quantity,
+                line.unit_amount.amount_minor,
+                line.amount.amount_minor,
+                line.period_start,
+                line.period_end,
+                position as i32 + 1
+            )
+            .execute(&mut *conn)
+            .await
+            .map_err(internal_error)?;
+        }
+
+        Ok(id)
+    }
+
+    /// Issues a draft: gives it the next number of the year, keeps the
+    /// billing details the buyer has at this time and opens it. The year's
+    /// counter stays locked until the transaction ends, so a rolled back
+    /// invoice leaves no gap.
+    pub async fn finalize(
+        conn: &mut sqlx::PgConnection,
+        id: uuid::Uuid,
+    ) -> Result<String, (StatusCode, String)> {
+        let sequence = sqlx::query!(
+            r#"
+            INSERT INTO invoice_number_sequences (year, last_number)
+            VALUES (EXTRACT(YEAR FROM CURRENT_TIMESTAMP)::INT, 1)
+            ON CONFLICT (year) DO UPDATE SET last_number = invoice_number_sequences.last_number + 1
+            RETURNING year, last_number
+            "#
+        )
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(internal_error)?;
+
+        let number = invoice_model::format_number(sequence.year, sequence.last_number);
+
+        sqlx::query!(
+            r#"
+            UPDATE invoices AS i
+            SET status = 'open', number = $1, issued_at = CURRENT_TIMESTAMP, updated_at = CURRENT_TIMESTAMP,
+                buyer_name = COALESCE(u.billing_name, u.name), buyer_email = u.email,
+                buyer_address = u.billing_address, buyer_region = u.billing_region, buyer_tax_id = u.tax_id
+            FROM users AS u
+            WHERE i.id = $2 AND i.status = 'draft' AND u.id = i.user_id
+            RETURNING i.id
+            "#,
+            number,
+            id
+        )
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(internal_error)?
+        .ok_or((
+            StatusCode::CONFLICT,
+            "Only draft invoices can be issued".to_string(),
+        ))?;
+
+        Ok(number)
+    }
+
+    /// Creates and issues `invoice` at once.
+    pub async fn issue(
+        conn: &mut sqlx::PgConnection,
+        invoice: NewInvoice,
+    ) -> Result<uuid::Uuid, (StatusCode, String)> {
+        let id = Self::create_draft(conn, invoice).await?;
+
+        Self::finalize(conn, id).await?;
+
+        Ok(id)
+    }
+
+    /// Moves an invoice to `status`, validating the transition. `reason` is
+    /// kept when voiding.
+    pub async fn transition(
+        conn: &mut sqlx::PgConnection,
+        id: uuid::Uuid,
+        status: InvoiceStatus,
+        reason: Option<String>,
+    ) -> Result<(), (StatusCode, String)> {
+        let current = sqlx::query_scalar!(
+            r#"
+            SELECT status FROM invoices WHERE id = $1 FOR UPDATE
+            "#,
+            id
+        )
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Invoice not found".to_string()))?;
+
+        let current: InvoiceStatus = current
+            .parse()
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+        if !current.can_transition_to(status) {
+            return Err((
+                StatusCode::CONFLICT,
+                format!("Cannot change an invoice that is {} to {}", current, status),
+            ));
+        }
+
+        sqlx::query!(
+            r#"
+            UPDATE invoices
+            SET status = $1::VARCHAR,
+                paid_at = CASE WHEN $1::VARCHAR = 'paid' THEN CURRENT_TIMESTAMP ELSE paid_at END,
+                voided_at = CASE WHEN $1::VARCHAR = 'void' THEN CURRENT_TIMESTAMP ELSE voided_at END,
+                void_reason = CASE WHEN $1::VARCHAR = 'void' THEN $2 ELSE void_reason END,
+                updated_at = CURRENT_TIMESTAMP
+            WHERE id = $3
+            "#,
+            status.as_str(),
+            reason,
+            id
+        )
+        .execute(&mut *conn)
+        .await
+        .map_err(internal_error)?;
+
+        Ok(())
+    }
+
+    /// Voids the open invoices of a subscription that ended, so they can't be
+    /// paid anymore.
+    pub async fn void_open(
+        conn: &mut sqlx::PgConnection,
+        subscription_id: uuid::Uuid,
+        reason: String,
+    ) -> Result<(), (StatusCode, String)> {
+        let open = sqlx::query_scalar!(
+            r#"
+            SELECT id FROM invoices WHERE subscription_id = $1 AND status = 'open'
+            "#,
+            subscription_id
+        )
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(internal_error)?;
+
+        for id in open {
+            Self::transition(conn, id, InvoiceStatus::Void, Some(reason.clone())).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Locks an invoice of `subscription_id` for a payment.
+    pub async fn lock_payable(
+        conn: &mut sqlx::PgConnection,
+        id: uuid::Uuid,
+        subscription_id: uuid::Uuid,
+    ) -> Result<IssuedInvoice, (StatusCode, String)> {
+        let invoice = sqlx::query!(
+            r#"
+            SELECT id, kind, status, period_start, period_end, amount, subtotal, tax_amount, currency, tax_rule_id,
+                tax_name, tax_region, tax_rate_bps, tax_inclusive
+            FROM invoices
+            WHERE id = $1 AND subscription_id = $2
+            FOR UPDATE
+            "#,
+            id,
+            subscription_id
+        )
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Invoice not found".to_string()))?;
+
+        let status: InvoiceStatus = invoice
+            .status
+            .parse()
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+        if !status.can_transition_to(InvoiceStatus::Paid) {
+            return Err((
+                StatusCode::CONFLICT,
+                format!("Cannot pay an invoice that is {}", status),
+            ));
+        }
+
+        let money = |amount| {
+            Money::parse(amount, &invoice.currency)
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))
+        };
+
+        Ok(IssuedInvoice {
+            id: invoice.id,
+            kind: invoice
+                .kind
+                .parse()
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?,
+            period_start: invoice.period_start,
+            period_end: invoice.period_end,
+            amount: TaxedAmount {
+                breakdown: TaxBreakdown {
+                    subtotal: money(invoice.subtotal)?,
+                    tax: money(invoice.tax_amount)?,
+                    total: money(invoice.amount)?,
+                },
+                tax: AppliedTax {
+                    rule_id: invoice.tax_rule_id,
+                    name: invoice.tax_name,
+                    region: invoice.tax_region,
+                    rate: TaxRate {
+                        rate_bps: invoice.tax_rate_bps,
+                        is_inclusive: invoice.tax_inclusive,
+                    },
+                },
+            },
+        })
+    }
+
+    /// The invoice of a billing period: the plan less its coupon, which is
+    /// counted as applied, the add-on packs at full price and the credit of
+    /// plan changes to a cheaper plan.
+    pub async fn period_invoice(
+        conn: &mut sqlx::PgConnection,
+        subscription_id: uuid::Uuid,
+        user_id: uuid::Uuid,
+        plan_name: &str,
+        terms: &BillingTerms,
+        (period_start, period_end): (NaiveDateTime, NaiveDateTime),
+    ) -> Result<NewInvoice, (StatusCode, String)> {
+        let mut lines =
+            vec![
+                InvoiceLine::single(InvoiceLineKind::Plan, plan_name.to_string(), terms.price)
+                    .for_period(period_start, period_end),
+            ];
+
+        let discount =
+            CouponService::apply_discount(&mut *conn, subscription_id, period_start, terms.price)
+                .await
+                .map_err(internal_error)?;
+
+        if let Some(discount) = &discount {
+            lines.push(InvoiceLine::single(
+                InvoiceLineKind::Discount,
+                format!("Coupon {}", discount.code),
+                -discount.amount,
+            ));
+        }
+
+        for line in AddonService::period_lines(
+            &mut *conn,
+            subscription_id,
+            terms.period,
+            terms.price.currency,
+        )
+        .await?
+        {
+            lines.push(line.for_period(period_start, period_end));
+        }
+
+        let subtotal = invoice_model::lines_total(&lines, terms.price.currency)
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+        lines.extend(Self::apply_credits(&mut *conn, subscription_id, subtotal).await?);
+
+        Ok(NewInvoice {
+            subscription_id,
+            user_id,
+            kind: InvoiceKind::Period,
+            period: Some((period_start, period_end)),
+            currency: terms.price.currency,
+            due_at: period_start,
+            lines,
+            coupon_redemption_id: discount.map(|d| d.redemption_id),
+        })
+    }
+
+    /// Counts the credit left of plan changes to a cheaper plan as applied,
+    /// up to `available`, and returns its line. What doesn't fit is credited
+    /// on the next invoices.
+    async fn apply_credits(
+        conn: &mut sqlx::PgConnection,
+        subscription_id: uuid::Uuid,
+        available: Money,
+    ) -> Result<Option<InvoiceLine>, (StatusCode, String)> {
+        let credits = sqlx::query!(
+            r#"
+            SELECT id, -proration_amount - credited_amount AS "left!"
+            FROM subscription_plan_changes
+            WHERE subscription_id = $1 AND status = 'applied' AND currency = $2
+              AND -proration_amount > credited_amount
+            ORDER BY created_at, id
+            FOR UPDATE
+            "#,
+            subscription_id,
+            available.currency.as_str()
+        )
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(internal_error)?;
+
+        let mut credited = 0;
+
+        for credit in credits {
+            let amount = credit.left.min(available.amount_minor - credited);
+
+            if amount <= 0 {
+                break;
+            }
+
+            sqlx::query!(
+                r#"
+                UPDATE subscription_plan_changes SET credited_amount = credited_amount + $2 WHERE id = $1
+                "#,
+                credit.id,
+                amount
+            )
+            .execute(&mut *conn)
+            .await
+            .map_err(internal_error)?;
+
+            credited += amount;
+        }
+
+        Ok((credited > 0).then(|| {
+            InvoiceLine::single(
+                InvoiceLineKind::Proration,
+                "Credit of plan changes".to_string(),
+                Money::new(-credited, available.currency),
+            )
+        }))
+    }
+
+    /// Marks the overage of the periods before `period_start` as billed and
+    /// returns its lines. Overage is priced in the currency of the plan, so
+    /// overage of a subscription billed in another currency stays unbilled.
+    pub async fn bill_overages(
+        conn: &mut sqlx::PgConnection,
+        subscription_id: uuid::Uuid,
+        period_start: NaiveDateTime,
+        currency: Currency,
+    ) -> Result<Vec<InvoiceLine>, (StatusCode, String)> {
+        let overages = sqlx::query!(
+            r#"
+            UPDATE usage_overages AS o
+            SET billed_at = CURRENT_TIMESTAMP, updated_at = CURRENT_TIMESTAMP
+            FROM subscriptions AS s, plans AS p, resource_types AS rt
+            WHERE o.subscription_id = $1 AND o.billed_at IS NULL AND o.period_start < $2
+              AND s.id = o.subscription_id AND p.id = s.plan_id AND p.currency = $3
+              AND rt.id = o.resource_type_id AND o.quantity > 0
+            RETURNING rt.code, o.quantity, o.unit_price, o.period_start, o.period_end
+            "#,
+            subscription_id,
+            period_start,
+            currency.as_str()
+        )
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(internal_error)?;
+
+        Ok(overages
+            .into_iter()
+            .map(|overage| {
+                InvoiceLine::new(
+                    InvoiceLineKind::Overage,
+                    format!("{} over the limit", overage.code),
+                    overage.quantity,
+                    Money::new(overage.unit_price, currency),
+                )
+                .for_period(
+                    overage.period_start,
+                    overage.period_end.unwrap_or(period_start),
+                )
+            })
+            .collect())
+    }
+
+    /// What is printed on the documents of `invoices`. Buyers are shown with
+    /// the billing details kept when the invoice was issued, drafts with the
+    /// ones customers have now.
+    async fn documents(
+        &self,
+        invoices: Vec<InvoiceResponse>,
+        seller: &Seller,
+    ) -> Result<Vec<InvoiceDocument>, String> {
+        let internal_error = |e: sqlx::Error| {
+            tracing::error!("Failed to get invoice documents: {:?}", e);
+            "Failed to get invoice documents".to_string()
+        };
+
+        let invoice_ids: Vec<uuid::Uuid> = invoices.iter().map(|invoice| invoice.id).collect();
+
+        let buyers = sqlx::query!(
+            r#"
+            SELECT i.id, COALESCE(i.buyer_name, u.billing_name, u.name) AS "name!",
+                COALESCE(i.buyer_email, u.email) AS "email!", COALESCE(i.buyer_address, u.billing_address) AS address,
+                COALESCE(i.buyer_region, u.billing_region) AS region, COALESCE(i.buyer_tax_id, u.tax_id) AS tax_id
+            FROM invoices AS i
+            LEFT JOIN users AS u ON u.id = i.user_id AND i.issued_at IS NULL
+            WHERE i.id = ANY($1)
+            "#,
+            &invoice_ids
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(internal_error)?;
+
+        let payments = sqlx::query!(
+            r#"
+            SELECT invoice_id AS "invoice_id!", payment_date, payment_method, amount, currency
+            FROM payments
+            WHERE invoice_id = ANY($1) AND status IN ('succeeded', 'refunded')
+            ORDER BY payment_date, id
+            "#,
+            &invoice_ids
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(internal_error)?;
+
+        invoices
+            .into_iter()
+            .map(|invoice| {
+                let buyer = buyers
+                    .iter()
+                    .find(|buyer| buyer.id == invoice.id)
+                    .ok_or_else(|| format!("Buyer of invoice {} not found", invoice.id))?;
+
+                Ok(InvoiceDocument {
+                    seller: seller.clone(),
+                    buyer: Buyer {
+                        name: buyer.name.clone(),
+                        email: buyer.email.clone(),
+                        address: buyer.address.clone(),
+                        region: buyer.region.clone(),
+                        tax_id: buyer.tax_id.clone(),
+                    },
+                    payments: payments
+                        .iter()
+                        .filter(|payment| payment.invoice_id == invoice.id)
+                        .map(|payment| {
+                            Ok(DocumentPayment {
+                                paid_at: payment.payment_date,
+                                method: payment.payment_method.clone(),
+                                amount: Money::parse(payment.amount, &payment.currency)?,
+                            })
+                        })
+                        .collect::<Result<_, String>>()?,
+                    invoice,
+                })
+            })
+            .collect()
+    }
+
+    async fn find(
+        &self,
+        filter: &InvoiceFilter,
+        limit: Option<i64>,
+        offset: i64,
+    ) -> Result<Vec<InvoiceResponse>, String> {
+        let invoices = sqlx::query!(
+            r#"
+            SELECT id, number, kind, status, subscription_id, user_id, period_start, period_end, subtotal, tax_amount,
+                tax_rate_bps, tax_name, tax_inclusive, amount, currency, issued_at, due_at, paid_at, voided_at, void_reason
+            FROM invoices
+            WHERE ($1::UUID IS NULL OR id = $1)
+              AND ($2::UUID IS NULL OR user_id = $2)
+              AND ($3::UUID IS NULL OR subscription_id = $3)
+              AND ($4::VARCHAR IS NULL OR status = $4)
+              AND ($5::VARCHAR IS NULL OR kind = $5)
+              AND ($6::VARCHAR IS NULL OR number LIKE $6 || '%')
+              AND ($7::TIMESTAMP IS NULL OR issued_at >= $7)
+              AND ($8::TIMESTAMP IS NULL OR issued_at < $8)
+            ORDER BY COALESCE(issued_at, created_at) DESC, id
+            LIMIT $9 OFFSET $10
+            "#,
+            filter.id,
+            filter.user_id,
+            filter.subscription_id,
+            filter.status.map(|s| s.as_str()),
+            filter.kind.map(|k| k.as_str()),
+            filter.number,
+            filter.from,
+            filter.to,
+            limit,
+            offset
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get invoices: {:?}", e);
+            "Failed to get invoices".to_string()
+        })?;
+
+        let ids: Vec<uuid::Uuid> = invoices.iter().map(|invoice| invoice.id).collect();
+
+        let lines = sqlx::query!(
+            r#"
+            SELECT l.invoice_id, l.kind, l.description, l.quantity, l.unit_amount, l.amount, l.period_start,
+                l.period_end, i.currency
+            FROM invoice_lines AS l
+            INNER JOIN invoices AS i ON i.id = l.invoice_id
+            WHERE l.invoice_id = ANY($1)
+            ORDER BY l.invoice_id, l.position
+            "#,
+            &ids
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get invoice lines: {:?}", e);
+            "Failed to get invoice lines".to_string()
+        })?;
+
+        invoices
+            .into_iter()
+            .map(|invoice| {
+                let money = |amount| Money::parse(amount, &invoice.currency);
+
+                Ok(InvoiceResponse {
+                    id: invoice.id,
+                    number: invoice.number,
+                    kind: invoice.kind.parse()?,
+                    status: invoice.status.parse()?,
+                    subscription_id: invoice.subscription_id,
+                    user_id: invoice.user_id,
+                    period_start: invoice.period_start,
+                    period_end: invoice.period_end,
+                    subtotal: money(invoice.subtotal)?,
+                    tax_amount: money(invoice.tax_amount)?,
+                    tax_rate_bps: invoice.tax_rate_bps,
+                    tax_name: invoice.tax_name,
+                    tax_inclusive: invoice.tax_inclusive,
+                    total: money(invoice.amount)?,
+                    issued_at: invoice.issued_at,
+                    due_at: invoice.due_at,
+                    paid_at: invoice.paid_at,
+                    voided_at: invoice.voided_at,
+                    void_reason: invoice.void_reason,
+                    lines: lines
+                        .iter()
+                        .filter(|line| line.invoice_id == invoice.id)
+                        .map(|line| {
+                            Ok(InvoiceLineResponse {
+                                kind: line.kind.parse()?,
+                                description: line.description.clone(),
+                                quantity: line.quantity,
+                                unit_amount: Money::parse(line.unit_amount, &line.currency)?,
+                                amount: Money::parse(line.amount, &line.currency)?,
+                                period_start: line.period_start,
+                                period_end: line.period_end,
+                            })
+                        })
+                        .collect::<Result<_, String>>()?,
+                })
+            })
+            .collect()
+    }
+}
+
+impl InvoiceServiceImpl for InvoiceService {
+    fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// The issued invoices of `user_id`, newest first. Drafts are not shown.
+    async fn get_invoices(&self, user_id: uuid::Uuid) -> Result<Vec<InvoiceResponse>, String> {
+        let invoices = self
+            .find(
+                &InvoiceFilter {
+                    user_id: Some(user_id),
+                    ..Default::default()
+                },
+                None,
+                0,
+            )
+            .await?;
+
+        Ok(invoices
+            .into_iter()
+            .filter(|invoice| invoice.status != InvoiceStatus::Draft)
+            .collect())
+    }
+
+    async fn get_invoice(
+        &self,
+        id: uuid::Uuid,
+        user_id: uuid::Uuid,
+    ) -> Result<InvoiceResponse, (StatusCode, String)> {
+        self.find(
+            &InvoiceFilter {
+                id: Some(id),
+                user_id: Some(user_id),
+                ..Default::default()
+            },
+            Some(1),
+            0,
+        )
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?
+        .into_iter()
+        .find(|invoice| invoice.status != InvoiceStatus::Draft)
+        .ok_or((StatusCode::NOT_FOUND, "Invoice not found".to_string()))
+    }
+
+    async fn get_document(
+        &self,
+        id: uuid::Uuid,
+        user_id: uuid::Uuid,
+        seller: Seller,
+    ) -> Result<InvoiceDocument, (StatusCode, String)> {
+        let invoice = self.get_invoice(id, user_id).await?;
+
+        self.documents(vec![invoice], &seller)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?
+            .pop()
+            .ok_or((StatusCode::NOT_FOUND, "Invoice not found".to_string()))
+    }
+
+    /// The e-invoice XML of `id`, or why it can't be exported.
+    async fn export_einvoice(
+        &self,
+        id: uuid::Uuid,
+        seller: Seller,
+    ) -> Result<EInvoiceExportResponse, (StatusCode, String)> {
+        let invoices = self
+            .find(
+                &InvoiceFilter {
+                    id: Some(id),
+                    ..Default::default()
+                },
+                Some(1),
+                0,
+            )
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+        let document = self
+            .documents(invoices, &seller)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?
+            .pop()
+            .ok_or((StatusCode::NOT_FOUND, "Invoice not found".to_string()))?;
+
+        let export = einvoice_export(&document);
+
+        match export.errors.is_empty() {
+            true => Ok(export),
+            false => Err((StatusCode::UNPROCESSABLE_ENTITY, export.errors.join("; "))),
+        }
+    }
+
+    /// Exports a page of the invoices matching `query`, for accounting to
+    /// submit through their e-invoice provider. Invoices that can't be
+    /// exported are listed with the reasons.
+    async fn export_einvoices(
+        &self,
+        query: InvoiceSearchQuery,
+        pagination: PaginationQuery,
+        seller: Seller,
+    ) -> Result<PageResponse<EInvoiceExportResponse>, String> {
+        let page = self.search_invoices(query, pagination).await?;
+        let documents = self.documents(page.items, &seller).await?;
+
+        Ok(PageResponse {
+            items: documents.iter().map(einvoice_export).collect(),
+            page: page.page,
+            per_page: page.per_page,
+            total: page.total,
+        })
+    }
+
+    async fn search_invoices(
+        &self,
+        query: InvoiceSearchQuery,
+        pagination: PaginationQuery,
+    ) -> Result<PageResponse<InvoiceResponse>, String> {
+        let filter = InvoiceFilter {
+            user_id: query.user_id,
+            subscription_id: query.subscription_id,
+            status: query.status,
+            kind: query.kind,
+            number: query.number.map(|number| number.trim().to_uppercase()),
+            from: query.from,
+            to: query.to,
+            ..Default::default()
+        };
+
+        let total = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) AS "count!" FROM invoices
+            WHERE ($1::UUID IS NULL OR user_id = $1)
+              AND ($2::UUID IS NULL OR subscription_id = $2)
+              AND ($3::VARCHAR IS NULL OR status = $3)
+              AND ($4::VARCHAR IS NULL OR kind = $4)
+              AND ($5::VARCHAR IS NULL OR number LIKE $5 || '%')
+              AND ($6::TIMESTAMP IS NULL OR issued_at >= $6)
+              AND ($7::TIMESTAMP IS NULL OR issued_at < $7)
+            "#,
+            filter.user_id,
+            filter.subscription_id,
+            filter.status.map(|s| s.as_str()),
+            filter.kind.map(|k| k.as_str()),
+            filter.number,
+            filter.from,
+            filter.to
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get invoices: {:?}", e);
+            "Failed to get invoices".to_string()
+        })?;
+
+        let items = self
+            .find(&filter, Some(pagination.per_page()), pagination.offset())
+            .await?;
+
+        Ok(PageResponse {
+            items,
+            page: pagination.page(),
+            per_page: pagination.per_page(),
+            total,
+        })
+    }
+
+    /// Cancels an invoice that won't be paid. Voiding the renewal invoice of
+    /// a past due subscription forgives it and makes the subscription active
+    /// again.
+    async fn void_invoice(
+        &self,
+        id: uuid::Uuid,
+        sys_id: uuid::Uuid,
+        request: VoidInvoiceRequest,
+    ) -> Result<InvoiceResponse, (StatusCode, String)> {
+        if request.reason.trim().is_empty() {
+            return Err((StatusCode::BAD_REQUEST, "reason is required".to_string()));
+        }
+
+        let mut tx = self.pool.begin().await.map_err(internal_error)?;
+
+        // The subscription is locked first, as payments do.
+        let invoice = sqlx::query!(
+            r#"
+            SELECT i.kind, i.status, i.number, i.subscription_id, i.period_start, s.status AS subscription_status,
+                s.start_date
+            FROM invoices AS i
+            INNER JOIN subscriptions AS s ON s.id = i.subscription_id
+            WHERE i.id = $1
+            FOR UPDATE OF s
+            "#,
+            id
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Invoice not found".to_string()))?;
+
+        Self::transition(
+            &mut tx,
+            id,
+            InvoiceStatus::Void,
+            Some(request.reason.clone()),
+        )
+        .await?;
+
+        if invoice.kind == InvoiceKind::Period.as_str()
+            && invoice.status == InvoiceStatus::Open.as_str()
+            && invoice.subscription_status == SubscriptionStatus::PastDue.as_str()
+            && invoice.period_start.is_some()
+            && invoice.period_start == invoice.start_date
+        {
+            SubscriptionService::transition(
+                &mut tx,
+                invoice.subscription_id,
+                SubscriptionStatus::Active,
+                Actor::Sys(sys_id),
+                Some(format!(
+                    "Invoice {} voided: {}",
+                    invoice.number.unwrap_or_default(),
+                    request.reason
+                )),
+            )
+            .await?;
+        }
+
+        tx.commit().await.map_err(internal_error)?;
+
+        self.find(
+            &InvoiceFilter {
+                id: Some(id),
+                ..Default::default()
+            },
+            Some(1),
+            0,
+        )
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?
+        .pop()
+        .ok_or((StatusCode::NOT_FOUND, "Invoice not found".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::models::plan_model::BillingPeriod;
+
+    const PARENT_ID: uuid::Uuid = uuid::uuid!("a0000000-0000-0000-0000-000000000001");
+    const SUBSCRIPTION_ID: uuid::Uuid = uuid::uuid!("b0000000-0000-0000-0000-000000000001");
+
+    #[sqlx::test(fixtures("subscriptions"))]
+    async fn test_issued_invoices_keep_the_buyer_details(pool: sqlx::PgPool) {
+        let invoices = InvoiceService::new(pool.clone());
+        let now = chrono::Utc::now().naive_utc();
+
+        let mut tx = pool.begin().await.unwrap();
+        let id = InvoiceService::issue(
+            &mut tx,
+            NewInvoice {
+                subscription_id: SUBSCRIPTION_ID,
+                user_id: PARENT_ID,
+                kind: InvoiceKind::Period,
+                period: None,
+                currency: Currency::Vnd,
+                due_at: now,
+                lines: vec![InvoiceLine::single(
+                    InvoiceLineKind::Plan,
+                    "Basic".to_string(),
+                    Money::new(19000, Currency::Vnd),
+                )],
+                coupon_redemption_id: None,
+            },
+        )
+        .await
+        .unwrap();
+        tx.commit().await.unwrap();
+
+        sqlx::query!(
+            r#"
+            UPDATE users SET billing_name = 'Renamed', billing_address = 'Somewhere else', tax_id = '0312345678'
+            WHERE id = $1
+            "#,
+            PARENT_ID
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let document = invoices
+            .get_document(id, PARENT_ID, Seller::default())
+            .await
+            .unwrap();
+
+        assert_eq!(document.buyer.name, "Parent");
+        assert_eq!(document.buyer.email, "parent@example.com");
+        assert_eq!(document.buyer.address, None);
+        assert_eq!(document.buyer.tax_id, None);
+    }
+
+    #[sqlx::test(fixtures("subscriptions"))]
+    async fn test_plan_change_credit_is_taken_off_the_next_invoices(pool: sqlx::PgPool) {
+        let basic = uuid::uuid!("13cafdb3-a88d-4987-8119-0470caebd56c");
+        let premium = uuid::uuid!("0137ac8e-ca1e-4446-a017-40b4cdbbe92f");
+
+        sqlx::query!(
+            r#"
+            INSERT INTO subscription_plan_changes (subscription_id, from_plan_id, to_plan_id, mode, status,
+                proration_amount, currency, effective_at)
+            VALUES ($1, $2, $3, 'immediate', 'applied', -15000, 'VND', CURRENT_TIMESTAMP)
+            "#,
+            SUBSCRIPTION_ID,
+            premium,
+            basic
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let terms = BillingTerms {
+            price: Money::new(10000, Currency::Vnd),
+            period: BillingPeriod::new("month", 1).unwrap(),
+        };
+        let now = chrono::Utc::now().naive_utc();
+        let mut conn = pool.acquire().await.unwrap();
+
+        // The credit is taken off up to the amount of each invoice.
+        for credited in [-10000, -5000] {
+            let invoice = InvoiceService::period_invoice(
+                &mut conn,
+                SUBSCRIPTION_ID,
+                PARENT_ID,
+                "Basic",
+                &terms,
+                (now, terms.period.end_of(now)),
+            )
+            .await
+            .unwrap();
+
+            assert_eq!(
+                invoice_model::lines_total(&invoice.lines, Currency::Vnd),
+                Ok(Money::new(10000 + credited, Currency::Vnd))
+            );
+        }
+
+        let invoice = InvoiceService::period_invoice(
+            &mut conn,
+            SUBSCRIPTION_ID,
+            PARENT_ID,
+            "Basic",
+            &terms,
+            (now, terms.period.end_of(now)),
+        )
+        .await
+        .unwrap();
+        assert_eq!(invoice.lines.len(), 1);
+    }
+}
@@ -0,0 +1,119 @@
+use crate::domain::dtos::job_dtos::JobRunResponse;
+
+pub struct JobService {
+    pub pool: sqlx::PgPool,
+}
+
+pub trait JobServiceImpl {
+    fn new(pool: sqlx::PgPool) -> Self;
+
+    async fn record_run(
+        &self,
+        job: &str,
+        started_at: chrono::NaiveDateTime,
+        result: &Result<u64, String>,
+    ) -> Result<(), String>;
+
+    async fn get_latest_runs(&self) -> Result<Vec<JobRunResponse>, String>;
+
+    async fn get_runs(&self, job: &str) -> Result<Vec<JobRunResponse>, String>;
+}
+
+impl JobServiceImpl for JobService {
+    fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+
+    async fn record_run(
+        &self,
+        job: &str,
+        started_at: chrono::NaiveDateTime,
+        result: &Result<u64, String>,
+    ) -> Result<(), String> {
+        let (status, processed, error) = match result {
+            Ok(processed) => ("succeeded", *processed as i64, None),
+            Err(e) => ("failed", 0, Some(e.as_str())),
+        };
+
+        sqlx::query!(
+            r#"
+            INSERT INTO job_runs (job, status, processed, error, started_at)
+            VALUES ($1, $2, $3, $4, $5)
+            "#,
+            job,
+            status,
+            processed,
+            error,
+            started_at
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to record job run: {:?}", e);
+            "Failed to record job run".to_string()
+        })?;
+
+        Ok(())
+    }
+
+    async fn get_latest_runs(&self) -> Result<Vec<JobRunResponse>, String> {
+        let runs = sqlx::query!(
+            r#"
+            SELECT DISTINCT ON (job) id, job, status, processed, error, started_at, finished_at
+            FROM job_runs
+            ORDER BY job, started_at DESC
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get job runs: {:?}", e);
+            "Failed to get job runs".to_string()
+        })?;
+
+        Ok(runs
+            .into_iter()
+            .map(|run| JobRunResponse {
+                id: run.id,
+                job: run.job,
+                status: run.status,
+                processed: run.processed,
+                error: run.error,
+                started_at: run.started_at,
+                finished_at: run.finished_at,
+            })
+            .collect())
+    }
+
+    async fn get_runs(&self, job: &str) -> Result<Vec<JobRunResponse>, String> {
+        let runs = sqlx::query!(
+            r#"
+            SELECT id, job, status, processed, error, started_at, finished_at
+            FROM job_runs
+            WHERE job = $1
+            ORDER BY started_at DESC
+            LIMIT 100
+            "#,
+            job
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get job runs: {:?}", e);
+            "Failed to get job runs".to_string()
+        })?;
+
+        Ok(runs
+            .into_iter()
+            .map(|run| JobRunResponse {
+                id: run.id,
+                job: run.job,
+                status: run.status,
+                processed: run.processed,
+                error: run.error,
+                started_at: run.started_at,
+                finished_at: run.finished_at,
+            })
+            .collect())
+    }
+}
@@ -1,11 +1,22 @@
+pub mod addon_service;
 pub mod auth_service;
 pub mod claim_service;
+pub mod coupon_service;
+pub mod dunning_service;
+pub mod feature_service;
+pub mod invoice_service;
+pub mod job_service;
 pub mod payment_service;
 pub mod permission_service;
+pub mod plan_change_service;
 pub mod plan_service;
+pub mod plan_version_service;
+pub mod quota_service;
 pub mod resource_service;
 pub mod role_service;
 pub mod subscription_service;
 pub mod sys_service;
+pub mod tax_service;
+pub mod usage_service;
 pub mod user_group_service;
 pub mod user_service;
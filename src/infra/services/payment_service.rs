@@ -1,53 +1,1035 @@
-use crate::domain::dtos::{
-    payment_dtos::{CreatePaymentRequest, PaymentForSysResponse, PaymentResponse},
-    plan_dtos::PlanResponse,
-    subscription_dtos::SubscriptionResponse,
+use axum::http::StatusCode;
+
+use crate::domain::{
+    dtos::{
+        payment_dtos::{
+            CreatePaymentRequest, PaymentForSysResponse, PaymentResponse, RefundPaymentRequest,
+        },
+        plan_dtos::PlanResponse,
+        subscription_dtos::SubscriptionResponse,
+    },
+    models::{
+        invoice_model::{InvoiceKind, InvoiceStatus},
+        money_model::Money,
+        payment_model::PaymentStatus,
+        subscription_model::{Actor, SubscriptionStatus},
+    },
+};
+
+use crate::infra::payments::{PaymentProvider, PaymentProviders, WebhookError, WebhookEventKind};
+
+use super::{
+    invoice_service::{InvoiceService, IssuedInvoice},
+    subscription_service::SubscriptionService,
 };
 
 pub struct PaymentService {
     pub pool: sqlx::PgPool,
 }
 
+struct LockedPayment {
+    id: uuid::Uuid,
+    subscription_id: uuid::Uuid,
+    invoice_id: Option<uuid::Uuid>,
+    status: PaymentStatus,
+    amount: Money,
+    refunded: Money,
+    provider: String,
+    provider_payment_id: Option<String>,
+}
+
+impl LockedPayment {
+    fn provider_payment_id(&self) -> Result<String, (StatusCode, String)> {
+        self.provider_payment_id.clone().ok_or((
+            StatusCode::CONFLICT,
+            "The payment was not made with a provider".to_string(),
+        ))
+    }
+}
+
+fn internal_error(e: sqlx::Error) -> (StatusCode, String) {
+    tracing::error!("Failed to make payment: {:?}", e);
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "Failed to make payment".to_string(),
+    )
+}
+
+fn provider_disabled(provider: &str) -> (StatusCode, String) {
+    (
+        StatusCode::CONFLICT,
+        format!("The payment provider {} is not enabled", provider),
+    )
+}
+
 impl PaymentService {
     pub fn new(pool: sqlx::PgPool) -> Self {
         Self { pool }
     }
 
+    /// Opens a payment with its provider of an invoice of the subscription,
+    /// by default the one it is waiting for: the invoice of the first paid
+    /// period of an incomplete or trialing subscription, issued with the
+    /// first payment, the renewal invoice of the current period, otherwise
+    /// the oldest open invoice. Customers only pay their own subscriptions, the
+    /// amount due is the invoice total, after discounts and tax. The payment
+    /// is pending until the provider says it succeeded, a payment opened
+    /// before for the invoice is replaced. Nothing is collected for invoices
+    /// with nothing due, which are settled right away.
     pub async fn make_payment(
         &self,
         payment: CreatePaymentRequest,
-    ) -> Result<PaymentResponse, String> {
-        let payment = sqlx::query!(
+        user_id: uuid::Uuid,
+        providers: &PaymentProviders,
+    ) -> Result<PaymentResponse, (StatusCode, String)> {
+        let provider_name = payment.provider.as_deref().unwrap_or("manual");
+        let provider = providers.get(provider_name).ok_or((
+            StatusCode::BAD_REQUEST,
+            format!("Unknown payment provider: {}", provider_name),
+        ))?;
+
+        let mut tx = self.pool.begin().await.map_err(internal_error)?;
+
+        let subscription_id = match (payment.subscription_id, payment.invoice_id) {
+            (Some(subscription_id), _) => subscription_id,
+            (None, Some(invoice_id)) => sqlx::query_scalar!(
+                r#"
+                SELECT subscription_id FROM invoices WHERE id = $1 AND user_id = $2
+                "#,
+                invoice_id,
+                user_id
+            )
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(internal_error)?
+            .ok_or((StatusCode::NOT_FOUND, "Invoice not found".to_string()))?,
+            (None, None) => {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    "subscription_id or invoice_id is required".to_string(),
+                ))
+            }
+        };
+
+        // Subscriptions of other users are not found, rather than forbidden,
+        // so their ids can't be probed.
+        let subscription = sqlx::query!(
+            r#"
+            SELECT s.user_id AS "user_id!", s.plan_id AS "plan_id!", s.plan_version_id, s.plan_price_id, s.status,
+                s.start_date, s.trial_end_date, p.name AS plan_name
+            FROM subscriptions AS s
+            INNER JOIN plans AS p ON p.id = s.plan_id
+            WHERE s.id = $1 AND s.user_id = $2
+            FOR UPDATE OF s
+            "#,
+            subscription_id,
+            user_id
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Subscription not found".to_string()))?;
+
+        let status: SubscriptionStatus = subscription
+            .status
+            .parse()
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+        if !matches!(
+            status,
+            SubscriptionStatus::Incomplete
+                | SubscriptionStatus::Trialing
+                | SubscriptionStatus::PastDue
+                | SubscriptionStatus::Active
+        ) {
+            return Err((
+                StatusCode::CONFLICT,
+                format!("Cannot pay for a subscription that is {}", status),
+            ));
+        }
+
+        let invoice_id = match (payment.invoice_id, status) {
+            (Some(invoice_id), _) => invoice_id,
+            (None, SubscriptionStatus::Incomplete | SubscriptionStatus::Trialing) => {
+                // Paying again reuses the invoice issued with the first payment.
+                let issued = sqlx::query_scalar!(
+                    r#"
+                    SELECT id FROM invoices
+                    WHERE subscription_id = $1 AND status = 'open' AND kind = 'period'
+                    ORDER BY issued_at DESC, id
+                    LIMIT 1
+                    "#,
+                    subscription_id
+                )
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(internal_error)?;
+
+                match issued {
+                    Some(invoice_id) => invoice_id,
+                    None => {
+                        let terms = SubscriptionService::billing_terms(
+                            &mut tx,
+                            subscription.plan_id,
+                            subscription.plan_version_id,
+                            subscription.plan_price_id,
+                            false,
+                        )
+                        .await?;
+
+                        // A trial that is still running is paid from its end,
+                        // otherwise from now.
+                        let now = chrono::Utc::now().naive_utc();
+                        let start_date = subscription
+                            .trial_end_date
+                            .filter(|trial_end_date| *trial_end_date > now)
+                            .unwrap_or(now);
+                        let period = (start_date, terms.period.end_of(start_date));
+
+                        // Rolled back with the payment when the amount is wrong.
+                        let invoice = InvoiceService::period_invoice(
+                            &mut tx,
+                            subscription_id,
+                            subscription.user_id,
+                            &subscription.plan_name,
+                            &terms,
+                            period,
+                        )
+                        .await?;
+
+                        InvoiceService::issue(&mut tx, invoice).await?
+                    }
+                }
+            }
+            (None, _) => sqlx::query_scalar!(
+                r#"
+                SELECT id FROM invoices
+                WHERE subscription_id = $1 AND status = 'open'
+                ORDER BY (kind = 'period' AND period_start = $2) DESC, issued_at, id
+                LIMIT 1
+                "#,
+                subscription_id,
+                subscription.start_date
+            )
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(internal_error)?
+            .ok_or((
+                StatusCode::CONFLICT,
+                "The subscription has no open invoice".to_string(),
+            ))?,
+        };
+
+        let IssuedInvoice {
+            id: invoice_id,
+            amount,
+            ..
+        } = InvoiceService::lock_payable(&mut tx, invoice_id, subscription_id).await?;
+        let breakdown = amount.breakdown;
+        let tax = amount.tax;
+
+        if let Some(amount) = payment.amount {
+            if amount.currency != breakdown.total.currency {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    format!("The invoice is billed in {}", breakdown.total.currency),
+                ));
+            }
+
+            if amount != breakdown.total {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    format!("The amount due is {}", breakdown.total),
+                ));
+            }
+        }
+
+        sqlx::query!(
+            r#"
+            UPDATE payments
+            SET status = 'failed', failure_reason = 'Replaced by a new payment', updated_at = CURRENT_TIMESTAMP
+            WHERE invoice_id = $1 AND status = 'pending'
+            "#,
+            invoice_id
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(internal_error)?;
+
+        let id = uuid::Uuid::new_v4();
+
+        sqlx::query!(
+            r#"
+            INSERT INTO payments (id, subscription_id, invoice_id, amount, subtotal, tax_amount, currency, payment_method,
+                tax_rule_id, tax_name, tax_region, tax_rate_bps, tax_inclusive, provider)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+            "#,
+            id,
+            subscription_id,
+            invoice_id,
+            breakdown.total.amount_minor,
+            breakdown.subtotal.amount_minor,
+            breakdown.tax.amount_minor,
+            breakdown.total.currency.as_str(),
+            payment.payment_method,
+            tax.rule_id,
+            tax.name,
+            tax.region,
+            tax.rate.rate_bps,
+            tax.rate.is_inclusive,
+            provider.name()
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(internal_error)?;
+
+        if breakdown.total.amount_minor == 0 {
+            let payment = Self::lock(&mut tx, id).await?;
+
+            Self::succeed(&mut tx, &payment, Actor::User(user_id)).await?;
+
+            let created = Self::find(&mut tx, id).await?;
+
+            tx.commit().await.map_err(internal_error)?;
+
+            return Ok(created);
+        }
+
+        // The provider is called once the payment is recorded, without
+        // holding the locks of the subscription and the invoice.
+        tx.commit().await.map_err(internal_error)?;
+
+        let intent = provider.create_intent(id, breakdown.total).await;
+
+        let mut tx = self.pool.begin().await.map_err(internal_error)?;
+
+        match &intent {
+            Ok(intent) => {
+                sqlx::query!(
+                    r#"
+                    UPDATE payments SET provider_payment_id = $2, instructions = $3, updated_at = CURRENT_TIMESTAMP
+                    WHERE id = $1
+                    "#,
+                    id,
+                    intent.provider_payment_id,
+                    intent.instructions
+                )
+                .execute(&mut *tx)
+                .await
+                .map_err(internal_error)?;
+            }
+            Err(e) => {
+                sqlx::query!(
+                    r#"
+                    UPDATE payments
+                    SET status = 'failed', failure_reason = $2, updated_at = CURRENT_TIMESTAMP
+                    WHERE id = $1 AND status = 'pending'
+                    "#,
+                    id,
+                    format!("The provider could not open the payment: {}", e)
+                )
+                .execute(&mut *tx)
+                .await
+                .map_err(internal_error)?;
+            }
+        }
+
+        tx.commit().await.map_err(internal_error)?;
+
+        if let Err(e) = intent {
+            return Err((StatusCode::BAD_GATEWAY, e));
+        }
+
+        let mut conn = self.pool.acquire().await.map_err(internal_error)?;
+
+        Self::find(&mut conn, id).await
+    }
+
+    /// Takes the money of a pending payment with its provider, e.g. once a
+    /// bank transfer shows on the account, and settles its invoice.
+    pub async fn capture_payment(
+        &self,
+        id: uuid::Uuid,
+        providers: &PaymentProviders,
+        sys_id: uuid::Uuid,
+    ) -> Result<PaymentResponse, (StatusCode, String)> {
+        let mut tx = self.pool.begin().await.map_err(internal_error)?;
+
+        let payment = Self::lock(&mut tx, id).await?;
+
+        if !payment.status.can_transition_to(PaymentStatus::Succeeded) {
+            return Err((
+                StatusCode::CONFLICT,
+                format!("Cannot capture a payment that is {}", payment.status),
+            ));
+        }
+
+        // Released while the provider is called.
+        tx.commit().await.map_err(internal_error)?;
+
+        providers
+            .get(&payment.provider)
+            .ok_or_else(|| provider_disabled(&payment.provider))?
+            .capture(&payment.provider_payment_id()?, payment.amount)
+            .await
+            .map_err(|e| (StatusCode::BAD_GATEWAY, e))?;
+
+        let mut tx = self.pool.begin().await.map_err(internal_error)?;
+
+        let payment = Self::lock(&mut tx, id).await?;
+
+        match payment.status {
+            PaymentStatus::Pending => Self::succeed(&mut tx, &payment, Actor::Sys(sys_id)).await?,
+            // Settled by a webhook of the provider in the meantime.
+            PaymentStatus::Succeeded | PaymentStatus::Refunded => {}
+            status => {
+                Self::mark_for_refund(
+                    &mut tx,
+                    id,
+                    format!("Captured after the payment {}", status),
+                )
+                .await?;
+                tx.commit().await.map_err(internal_error)?;
+
+                return Err((
+                    StatusCode::CONFLICT,
+                    format!("The payment {} while it was captured", status),
+                ));
+            }
+        }
+
+        let captured = Self::find(&mut tx, id).await?;
+
+        tx.commit().await.map_err(internal_error)?;
+
+        Ok(captured)
+    }
+
+    /// Pays back a succeeded payment with its provider, by default in full.
+    /// The invoice stays paid. The refund is confirmed, not counted again,
+    /// by the refund webhook of the provider.
+    pub async fn refund_payment(
+        &self,
+        id: uuid::Uuid,
+        request: RefundPaymentRequest,
+        providers: &PaymentProviders,
+    ) -> Result<PaymentResponse, (StatusCode, String)> {
+        let mut tx = self.pool.begin().await.map_err(internal_error)?;
+
+        let payment = Self::lock(&mut tx, id).await?;
+
+        if !payment.status.can_transition_to(PaymentStatus::Refunded) {
+            return Err((
+                StatusCode::CONFLICT,
+                format!("Cannot refund a payment that is {}", payment.status),
+            ));
+        }
+
+        let amount = match request.amount {
+            Some(amount) => amount,
+            None => payment
+                .amount
+                .checked_sub(payment.refunded)
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?,
+        };
+
+        Self::check_refund(&payment, amount)?;
+
+        let provider = providers
+            .get(&payment.provider)
+            .ok_or_else(|| provider_disabled(&payment.provider))?;
+        let provider_payment_id = payment.provider_payment_id()?;
+
+        // The refund is recorded before the provider is called, so concurrent
+        // refunds can't pay back more than the payment.
+        let refund_id = sqlx::query_scalar!(
             r#"
-            INSERT INTO payments (subscription_id, amount, payment_method)
+            INSERT INTO payment_refunds (payment_id, amount, currency)
             VALUES ($1, $2, $3)
-            RETURNING id, subscription_id, amount, payment_method, payment_date
+            RETURNING id
             "#,
-            payment.subscription_id,
-            payment.amount,
-            payment.payment_method
+            id,
+            amount.amount_minor,
+            amount.currency.as_str()
         )
-        .fetch_one(&self.pool)
+        .fetch_one(&mut *tx)
         .await
-        .map_err(|e| {
-            tracing::error!("Failed to make payment: {:?}", e);
-            "Failed to make payment".to_string()
+        .map_err(internal_error)?;
+
+        Self::record_refund(&mut tx, &payment, amount).await?;
+
+        tx.commit().await.map_err(internal_error)?;
+
+        match provider
+            .refund(&provider_payment_id, refund_id, amount)
+            .await
+        {
+            Ok(provider_refund_id) => {
+                sqlx::query!(
+                    r#"
+                    UPDATE payment_refunds SET provider_refund_id = $2 WHERE id = $1
+                    "#,
+                    refund_id,
+                    provider_refund_id
+                )
+                .execute(&self.pool)
+                .await
+                .map_err(internal_error)?;
+            }
+            Err(e) => {
+                let mut tx = self.pool.begin().await.map_err(internal_error)?;
+
+                let payment = Self::lock(&mut tx, id).await?;
+
+                // Unless the provider confirmed it in the meantime.
+                let failed = sqlx::query_scalar!(
+                    r#"
+                    UPDATE payment_refunds SET status = 'failed' WHERE id = $1 AND status = 'pending'
+                    RETURNING id
+                    "#,
+                    refund_id
+                )
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(internal_error)?;
+
+                if failed.is_some() {
+                    Self::record_refund(&mut tx, &payment, -amount).await?;
+                }
+
+                tx.commit().await.map_err(internal_error)?;
+
+                return Err((StatusCode::BAD_GATEWAY, e));
+            }
+        }
+
+        let mut conn = self.pool.acquire().await.map_err(internal_error)?;
+
+        Self::find(&mut conn, id).await
+    }
+
+    /// Applies a signed webhook event of `provider_name`. Every event is
+    /// applied once, `false` is returned for events received before. Events
+    /// that don't apply to the payment anymore, e.g. a failure after it
+    /// succeeded, are recorded and skipped.
+    pub async fn handle_webhook(
+        &self,
+        provider_name: &str,
+        signature: Option<&str>,
+        body: &[u8],
+        providers: &PaymentProviders,
+    ) -> Result<bool, (StatusCode, String)> {
+        let provider = providers.get(provider_name).ok_or((
+            StatusCode::NOT_FOUND,
+            format!("Unknown payment provider: {}", provider_name),
+        ))?;
+
+        let event = provider
+            .parse_webhook(signature, body, chrono::Utc::now().timestamp())
+            .map_err(|e| match e {
+                WebhookError::InvalidSignature => {
+                    (StatusCode::UNAUTHORIZED, "Invalid signature".to_string())
+                }
+                WebhookError::InvalidPayload(e) => (StatusCode::BAD_REQUEST, e),
+            })?;
+
+        let mut tx = self.pool.begin().await.map_err(internal_error)?;
+
+        let payment_id = sqlx::query_scalar!(
+            r#"
+            SELECT id FROM payments WHERE provider = $1 AND provider_payment_id = $2
+            "#,
+            provider.name(),
+            event.provider_payment_id
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Payment not found".to_string()))?;
+
+        let payload = String::from_utf8_lossy(body);
+
+        // Deliveries of the same event wait for each other on the unique key.
+        let recorded = sqlx::query_scalar!(
+            r#"
+            INSERT INTO webhook_events (provider, event_id, event_type, payment_id, payload)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (provider, event_id) DO NOTHING
+            RETURNING id
+            "#,
+            provider.name(),
+            event.id,
+            event.event_type,
+            payment_id,
+            payload.as_ref()
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(internal_error)?;
+
+        if recorded.is_none() {
+            return Ok(false);
+        }
+
+        let payment = Self::lock(&mut tx, payment_id).await?;
+
+        match (event.kind, payment.status) {
+            (WebhookEventKind::Succeeded { amount }, PaymentStatus::Pending) => {
+                if amount == payment.amount {
+                    Self::succeed(&mut tx, &payment, Actor::System).await?;
+                } else {
+                    Self::fail(
+                        &mut tx,
+                        &payment,
+                        format!("Received {} instead of {}", amount, payment.amount),
+                    )
+                    .await?;
+                }
+            }
+            // The money came in for a payment that failed or was replaced
+            // before, e.g. a transfer with the reference of the first payment.
+            // It pays the invoice while that is payable, otherwise it is
+            // marked to be refunded.
+            (WebhookEventKind::Succeeded { amount }, PaymentStatus::Failed)
+                if amount == payment.amount =>
+            {
+                Self::succeed(&mut tx, &payment, Actor::System).await?;
+            }
+            (WebhookEventKind::Failed { reason }, PaymentStatus::Pending) => {
+                Self::fail(&mut tx, &payment, reason).await?;
+            }
+            (
+                WebhookEventKind::Refunded { amount, refund_id },
+                PaymentStatus::Succeeded | PaymentStatus::Refunded,
+            ) => {
+                Self::confirm_refund(&mut tx, &payment, amount, refund_id).await?;
+            }
+            (_, status) => {
+                tracing::warn!(
+                    "Skipped {} event {} of payment {} that is {}",
+                    event.event_type,
+                    event.id,
+                    payment_id,
+                    status
+                );
+            }
+        }
+
+        tx.commit().await.map_err(internal_error)?;
+
+        Ok(true)
+    }
+
+    /// Locks a payment on `conn`, after its subscription as payments are
+    /// opened with the subscription locked.
+    async fn lock(
+        conn: &mut sqlx::PgConnection,
+        id: uuid::Uuid,
+    ) -> Result<LockedPayment, (StatusCode, String)> {
+        let not_found = || (StatusCode::NOT_FOUND, "Payment not found".to_string());
+
+        let subscription_id = sqlx::query_scalar!(
+            r#"
+            SELECT subscription_id FROM payments WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(internal_error)?
+        .ok_or_else(not_found)?;
+
+        sqlx::query!(
+            r#"
+            SELECT id FROM subscriptions WHERE id = $1 FOR UPDATE
+            "#,
+            subscription_id
+        )
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(internal_error)?;
+
+        let payment = sqlx::query!(
+            r#"
+            SELECT id, subscription_id, invoice_id, status, amount, refunded_amount, currency, provider,
+                provider_payment_id
+            FROM payments
+            WHERE id = $1
+            FOR UPDATE
+            "#,
+            id
+        )
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(internal_error)?
+        .ok_or_else(not_found)?;
+
+        let money = |amount| {
+            Money::parse(amount, &payment.currency)
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))
+        };
+
+        Ok(LockedPayment {
+            id: payment.id,
+            subscription_id: payment.subscription_id.ok_or_else(not_found)?,
+            invoice_id: payment.invoice_id,
+            status: payment
+                .status
+                .parse()
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?,
+            amount: money(payment.amount)?,
+            refunded: money(payment.refunded_amount)?,
+            provider: payment.provider,
+            provider_payment_id: payment.provider_payment_id,
         })
-        .unwrap();
+    }
+
+    /// Marks a pending payment as succeeded and its invoice as paid. Paying
+    /// the first invoice or the renewal invoice makes the subscription
+    /// active. The money of an invoice that can't be paid anymore, e.g. of a
+    /// subscription that ended, is marked to be refunded.
+    async fn succeed(
+        conn: &mut sqlx::PgConnection,
+        payment: &LockedPayment,
+        actor: Actor,
+    ) -> Result<(), (StatusCode, String)> {
+        let subscription = sqlx::query!(
+            r#"
+            SELECT status, start_date FROM subscriptions WHERE id = $1
+            "#,
+            payment.subscription_id
+        )
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(internal_error)?;
+
+        let status: SubscriptionStatus = subscription
+            .status
+            .parse()
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+        sqlx::query!(
+            r#"
+            UPDATE payments
+            SET status = 'succeeded', payment_date = CURRENT_TIMESTAMP, updated_at = CURRENT_TIMESTAMP
+            WHERE id = $1
+            "#,
+            payment.id
+        )
+        .execute(&mut *conn)
+        .await
+        .map_err(internal_error)?;
+
+        let Some(invoice_id) = payment.invoice_id else {
+            return Ok(());
+        };
+
+        if matches!(
+            status,
+            SubscriptionStatus::Canceled | SubscriptionStatus::Expired
+        ) {
+            return Self::mark_for_refund(
+                conn,
+                payment.id,
+                format!("The subscription is {}", status),
+            )
+            .await;
+        }
+
+        let invoice =
+            match InvoiceService::lock_payable(conn, invoice_id, payment.subscription_id).await {
+                Ok(invoice) => invoice,
+                // The money came in anyway, e.g. for an invoice voided since.
+                Err((StatusCode::CONFLICT, e)) => {
+                    return Self::mark_for_refund(conn, payment.id, e).await
+                }
+                Err(e) => return Err(e),
+            };
+
+        InvoiceService::transition(conn, invoice_id, InvoiceStatus::Paid, None).await?;
+
+        let first_period = match (
+            status,
+            invoice.kind,
+            invoice.period_start,
+            invoice.period_end,
+        ) {
+            (
+                SubscriptionStatus::Incomplete | SubscriptionStatus::Trialing,
+                InvoiceKind::Period,
+                Some(start),
+                Some(end),
+            ) => Some((start, end)),
+            _ => None,
+        };
+
+        // The renewal invoice of the current period settles a past due subscription.
+        let settles_renewal = status == SubscriptionStatus::PastDue
+            && invoice.kind == InvoiceKind::Period
+            && invoice.period_start.is_some()
+            && invoice.period_start == subscription.start_date;
+
+        if first_period.is_some() || settles_renewal {
+            SubscriptionService::convert_to_paid(
+                conn,
+                payment.subscription_id,
+                actor,
+                first_period,
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Records that the money of a succeeded payment has to be paid back,
+    /// which sys does with a refund.
+    async fn mark_for_refund(
+        conn: &mut sqlx::PgConnection,
+        id: uuid::Uuid,
+        reason: String,
+    ) -> Result<(), (StatusCode, String)> {
+        tracing::warn!("Payment {} has to be refunded: {}", id, reason);
+
+        sqlx::query!(
+            r#"
+            UPDATE payments SET refund_reason = $2, updated_at = CURRENT_TIMESTAMP WHERE id = $1
+            "#,
+            id,
+            reason
+        )
+        .execute(&mut *conn)
+        .await
+        .map_err(internal_error)?;
+
+        Ok(())
+    }
+
+    /// Marks a pending payment as failed. A failed payment of the renewal
+    /// invoice moves the subscription to past due.
+    async fn fail(
+        conn: &mut sqlx::PgConnection,
+        payment: &LockedPayment,
+        reason: String,
+    ) -> Result<(), (StatusCode, String)> {
+        sqlx::query!(
+            r#"
+            UPDATE payments
+            SET status = 'failed', failure_reason = $2, updated_at = CURRENT_TIMESTAMP
+            WHERE id = $1
+            "#,
+            payment.id,
+            reason
+        )
+        .execute(&mut *conn)
+        .await
+        .map_err(internal_error)?;
+
+        if let Some(invoice_id) = payment.invoice_id {
+            SubscriptionService::mark_past_due(
+                conn,
+                payment.subscription_id,
+                invoice_id,
+                format!("Payment of the renewal invoice failed: {}", reason),
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Applies a refund reported by the provider. A refund made here is
+    /// confirmed, found by the provider's id of the refund or, while its
+    /// id is not stored yet, by its amount. Other refunds, e.g. made on the
+    /// provider's dashboard, are added.
+    async fn confirm_refund(
+        conn: &mut sqlx::PgConnection,
+        payment: &LockedPayment,
+        amount: Money,
+        provider_refund_id: Option<String>,
+    ) -> Result<(), (StatusCode, String)> {
+        let refund = sqlx::query!(
+            r#"
+            SELECT id, status FROM payment_refunds
+            WHERE payment_id = $1
+              AND (provider_refund_id = $2
+                OR (status = 'pending' AND (provider_refund_id IS NULL OR $2::TEXT IS NULL) AND amount = $3))
+            ORDER BY provider_refund_id IS NOT DISTINCT FROM $2 DESC, created_at
+            LIMIT 1
+            "#,
+            payment.id,
+            provider_refund_id,
+            amount.amount_minor
+        )
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(internal_error)?;
+
+        let Some(refund) = refund else {
+            if payment.status != PaymentStatus::Succeeded {
+                tracing::warn!(
+                    "Skipped a refund of payment {} that is {}",
+                    payment.id,
+                    payment.status
+                );
+                return Ok(());
+            }
+
+            Self::check_refund(payment, amount)?;
+
+            sqlx::query!(
+                r#"
+                INSERT INTO payment_refunds (payment_id, amount, currency, provider_refund_id, status, confirmed_at)
+                VALUES ($1, $2, $3, $4, 'succeeded', CURRENT_TIMESTAMP)
+                "#,
+                payment.id,
+                amount.amount_minor,
+                amount.currency.as_str(),
+                provider_refund_id
+            )
+            .execute(&mut *conn)
+            .await
+            .map_err(internal_error)?;
+
+            return Self::record_refund(conn, payment, amount).await;
+        };
+
+        match refund.status.as_str() {
+            "succeeded" => {
+                tracing::warn!(
+                    "Refund {} of payment {} was confirmed before",
+                    refund.id,
+                    payment.id
+                );
+                return Ok(());
+            }
+            // Turned down by the provider at first, paid back anyway.
+            "failed" => {
+                Self::check_refund(payment, amount)?;
+                Self::record_refund(conn, payment, amount).await?;
+            }
+            _ => {}
+        }
+
+        sqlx::query!(
+            r#"
+            UPDATE payment_refunds
+            SET status = 'succeeded', provider_refund_id = COALESCE($2, provider_refund_id),
+                confirmed_at = CURRENT_TIMESTAMP
+            WHERE id = $1
+            "#,
+            refund.id,
+            provider_refund_id
+        )
+        .execute(&mut *conn)
+        .await
+        .map_err(internal_error)?;
+
+        Ok(())
+    }
+
+    fn check_refund(payment: &LockedPayment, amount: Money) -> Result<(), (StatusCode, String)> {
+        let left = payment
+            .amount
+            .checked_sub(payment.refunded)
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+        if amount.currency != left.currency {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("The payment was made in {}", left.currency),
+            ));
+        }
+
+        if amount.amount_minor <= 0 || amount.amount_minor > left.amount_minor {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("Up to {} can be refunded", left),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Adds a refund checked by `check_refund`, the payment is refunded once
+    /// paid back in full. A negative `amount` takes back a refund the
+    /// provider turned down.
+    async fn record_refund(
+        conn: &mut sqlx::PgConnection,
+        payment: &LockedPayment,
+        amount: Money,
+    ) -> Result<(), (StatusCode, String)> {
+        let refunded = payment
+            .refunded
+            .checked_add(amount)
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+        let status = match refunded == payment.amount {
+            true => PaymentStatus::Refunded,
+            false => PaymentStatus::Succeeded,
+        };
+
+        sqlx::query!(
+            r#"
+            UPDATE payments
+            SET refunded_amount = $2, status = $3, updated_at = CURRENT_TIMESTAMP
+            WHERE id = $1
+            "#,
+            payment.id,
+            refunded.amount_minor,
+            status.as_str()
+        )
+        .execute(&mut *conn)
+        .await
+        .map_err(internal_error)?;
+
+        Ok(())
+    }
+
+    async fn find(
+        conn: &mut sqlx::PgConnection,
+        id: uuid::Uuid,
+    ) -> Result<PaymentResponse, (StatusCode, String)> {
+        let payment = sqlx::query!(
+            r#"
+            SELECT id, subscription_id, invoice_id, amount, subtotal, tax_amount, tax_rate_bps, currency, status,
+                payment_date, payment_method, provider, provider_payment_id, instructions, failure_reason,
+                refunded_amount
+            FROM payments
+            WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Payment not found".to_string()))?;
+
+        let parse_error = |e| (StatusCode::INTERNAL_SERVER_ERROR, e);
+        let money = |amount| Money::parse(amount, &payment.currency).map_err(parse_error);
 
         Ok(PaymentResponse {
             id: payment.id,
             subscription_id: payment.subscription_id,
-            amount: payment.amount,
+            invoice_id: payment.invoice_id,
+            amount: money(payment.amount)?,
+            subtotal: money(payment.subtotal)?,
+            tax_amount: money(payment.tax_amount)?,
+            tax_rate_bps: payment.tax_rate_bps,
+            status: payment.status.parse().map_err(parse_error)?,
             payment_date: payment.payment_date,
             payment_method: payment.payment_method.unwrap_or_default(),
+            provider: payment.provider,
+            provider_payment_id: payment.provider_payment_id,
+            instructions: payment.instructions,
+            failure_reason: payment.failure_reason,
+            refunded_amount: money(payment.refunded_amount)?,
         })
     }
 
     pub async fn get_payments(&self) -> Result<Vec<PaymentForSysResponse>, String> {
         let payments = sqlx::query!(
             r#"
-            SELECT p.id, p.subscription_id, p.amount, p.payment_date, p.payment_method, s.user_id, s.plan_id, s.start_date, s.end_date, s.is_active, pl.name, pl.price, pl.description, pl.trial_days, u.username, u.name as user_name, u.email
+            SELECT p.id, p.subscription_id, p.invoice_id, p.amount, p.subtotal, p.tax_amount, p.tax_rate_bps, p.currency, p.status AS payment_status, p.payment_date, p.payment_method, p.provider, p.provider_payment_id, p.failure_reason, p.refunded_amount, p.refund_reason, s.user_id, s.plan_id, s.plan_price_id, s.plan_version_id, s.start_date, s.end_date, s.trial_start_date, s.trial_end_date, s.status, s.cancel_at_period_end, pl.name, pl.price, pl.currency AS plan_currency, pl.description, pl.trial_days, u.username, u.name as user_name, u.email
             FROM payments as p
             INNER JOIN subscriptions as s ON p.subscription_id = s.id
             INNER JOIN plans as pl ON s.plan_id = pl.id
@@ -61,35 +1043,418 @@ impl PaymentService {
             "Failed to get payments".to_string()
         })?;
 
-        Ok(payments
+        payments
             .into_iter()
-            .map(|payment| PaymentForSysResponse {
-                id: payment.id,
-                amount: payment.amount,
-                payment_date: payment.payment_date,
-                payment_method: payment.payment_method.unwrap_or_default(),
-                user_id: payment.user_id.unwrap_or_default(),
-                username: payment.username,
-                email: payment.email,
-                subscription: SubscriptionResponse {
-                    id: payment.subscription_id.unwrap(),
-                    user_id: payment.user_id,
-                    plan_id: payment.plan_id,
-                    start_date: payment.start_date,
-                    end_date: payment.end_date,
-                    is_active: payment.is_active.unwrap_or_default(),
-                },
-                plan: PlanResponse {
-                    id: payment.plan_id.unwrap(),
-                    name: payment.name,
-                    price: payment.price,
-                    trial_days: None,
-                    description: payment.description,
-                    created_at: None,
-                    tags: None,
-                    is_active: None,
+            .map(|payment| {
+                let status: SubscriptionStatus = payment.status.parse()?;
+
+                Ok(PaymentForSysResponse {
+                    id: payment.id,
+                    invoice_id: payment.invoice_id,
+                    amount: Money::parse(payment.amount, &payment.currency)?,
+                    subtotal: Money::parse(payment.subtotal, &payment.currency)?,
+                    tax_amount: Money::parse(payment.tax_amount, &payment.currency)?,
+                    tax_rate_bps: payment.tax_rate_bps,
+                    status: payment.payment_status.parse()?,
+                    payment_date: payment.payment_date,
+                    payment_method: payment.payment_method.unwrap_or_default(),
+                    provider: payment.provider,
+                    provider_payment_id: payment.provider_payment_id,
+                    failure_reason: payment.failure_reason,
+                    refunded_amount: Money::parse(payment.refunded_amount, &payment.currency)?,
+                    refund_reason: payment.refund_reason,
+                    user_id: payment.user_id.unwrap_or_default(),
+                    username: payment.username,
+                    email: payment.email,
+                    subscription: SubscriptionResponse {
+                        id: payment
+                            .subscription_id
+                            .ok_or_else(|| format!("Payment {} has no subscription", payment.id))?,
+                        user_id: payment.user_id,
+                        plan_id: payment.plan_id,
+                        plan_price_id: payment.plan_price_id,
+                        plan_version_id: payment.plan_version_id,
+                        start_date: payment.start_date,
+                        end_date: payment.end_date,
+                        trial_start_date: payment.trial_start_date,
+                        trial_end_date: payment.trial_end_date,
+                        status,
+                        is_active: status.is_active(),
+                        cancel_at_period_end: payment.cancel_at_period_end,
+                    },
+                    plan: PlanResponse {
+                        id: payment
+                            .plan_id
+                            .ok_or_else(|| format!("Payment {} has no plan", payment.id))?,
+                        name: payment.name,
+                        price: Money::parse(payment.price, &payment.plan_currency)?,
+                        trial_days: None,
+                        is_free_forever: None,
+                        max_pauses_per_year: None,
+                        billing_interval: None,
+                        billing_interval_count: None,
+                        features: None,
+                        description: payment.description,
+                        created_at: None,
+                        tags: None,
+                        is_active: None,
+                    },
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infra::{
+        payments::{sign, MockProvider, Provider},
+        services::subscription_service::SubscriptionServiceImpl,
+    };
+
+    const PARENT_ID: uuid::Uuid = uuid::uuid!("a0000000-0000-0000-0000-000000000001");
+    const SUBSCRIPTION_ID: uuid::Uuid = uuid::uuid!("b0000000-0000-0000-0000-000000000001");
+    const OTHER_ID: uuid::Uuid = uuid::uuid!("a0000000-0000-0000-0000-000000000003");
+    const OTHER_SUBSCRIPTION_ID: uuid::Uuid = uuid::uuid!("b0000000-0000-0000-0000-000000000003");
+
+    fn providers() -> PaymentProviders {
+        PaymentProviders {
+            providers: vec![Provider::Mock(MockProvider::new("secret".to_string()))],
+        }
+    }
+
+    async fn pay(
+        payments: &PaymentService,
+        subscription_id: uuid::Uuid,
+        user_id: uuid::Uuid,
+    ) -> PaymentResponse {
+        payments
+            .make_payment(
+                CreatePaymentRequest {
+                    subscription_id: Some(subscription_id),
+                    invoice_id: None,
+                    amount: None,
+                    payment_method: "card".to_string(),
+                    provider: Some("mock".to_string()),
                 },
+                user_id,
+                &providers(),
+            )
+            .await
+            .unwrap()
+    }
+
+    /// Delivers a signed webhook `event` of the mock provider.
+    async fn send(
+        payments: &PaymentService,
+        event: serde_json::Value,
+    ) -> Result<bool, (StatusCode, String)> {
+        let body = event.to_string();
+        let signature = sign("secret", chrono::Utc::now().timestamp(), body.as_bytes());
+
+        payments
+            .handle_webhook("mock", Some(&signature), body.as_bytes(), &providers())
+            .await
+    }
+
+    /// Delivers a new webhook event of the mock provider about `payment`.
+    async fn deliver(payments: &PaymentService, payment: &PaymentResponse, event_type: &str) {
+        let event = serde_json::json!({
+            "id": format!("evt_{}", uuid::Uuid::new_v4()),
+            "type": event_type,
+            "payment_id": payment.provider_payment_id,
+            "amount": payment.amount,
+        });
+
+        assert_eq!(send(payments, event).await, Ok(true));
+    }
+
+    async fn statuses(pool: &sqlx::PgPool, payment: &PaymentResponse) -> (String, String, String) {
+        let row = sqlx::query!(
+            r#"
+            SELECT p.status AS payment_status, i.status AS invoice_status, s.status AS subscription_status
+            FROM payments AS p
+            INNER JOIN invoices AS i ON i.id = p.invoice_id
+            INNER JOIN subscriptions AS s ON s.id = p.subscription_id
+            WHERE p.id = $1
+            "#,
+            payment.id
+        )
+        .fetch_one(pool)
+        .await
+        .unwrap();
+
+        (
+            row.payment_status,
+            row.invoice_status,
+            row.subscription_status,
+        )
+    }
+
+    async fn refund_reason(pool: &sqlx::PgPool, payment: &PaymentResponse) -> Option<String> {
+        sqlx::query_scalar!(
+            r#"
+            SELECT refund_reason FROM payments WHERE id = $1
+            "#,
+            payment.id
+        )
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    #[sqlx::test(fixtures("subscriptions"))]
+    async fn test_payment_activates_the_subscription(pool: sqlx::PgPool) {
+        let payments = PaymentService::new(pool.clone());
+
+        let payment = pay(&payments, OTHER_SUBSCRIPTION_ID, OTHER_ID).await;
+        assert_eq!(payment.status, PaymentStatus::Pending);
+        assert!(payment.provider_payment_id.is_some());
+
+        deliver(&payments, &payment, "payment.succeeded").await;
+
+        assert_eq!(
+            statuses(&pool, &payment).await,
+            (
+                "succeeded".to_string(),
+                "paid".to_string(),
+                "active".to_string()
+            )
+        );
+    }
+
+    #[sqlx::test(fixtures("subscriptions"))]
+    async fn test_payment_of_an_ended_subscription_is_marked_for_refund(pool: sqlx::PgPool) {
+        let payments = PaymentService::new(pool.clone());
+
+        let payment = pay(&payments, OTHER_SUBSCRIPTION_ID, OTHER_ID).await;
+
+        SubscriptionService::new(pool.clone())
+            .update_status(
+                OTHER_SUBSCRIPTION_ID,
+                SubscriptionStatus::Expired,
+                Actor::System,
+                None,
+            )
+            .await
+            .unwrap();
+
+        deliver(&payments, &payment, "payment.succeeded").await;
+
+        assert_eq!(
+            statuses(&pool, &payment).await,
+            (
+                "succeeded".to_string(),
+                "void".to_string(),
+                "expired".to_string()
+            )
+        );
+
+        assert!(refund_reason(&pool, &payment).await.is_some());
+    }
+
+    #[sqlx::test(fixtures("subscriptions"))]
+    async fn test_failed_renewal_payment_moves_to_past_due(pool: sqlx::PgPool) {
+        let payments = PaymentService::new(pool.clone());
+        let subscriptions = SubscriptionService::new(pool.clone());
+
+        sqlx::query!(
+            r#"
+            UPDATE subscriptions
+            SET start_date = CURRENT_TIMESTAMP - INTERVAL '31 days', end_date = CURRENT_TIMESTAMP - INTERVAL '1 day'
+            WHERE id = $1
+            "#,
+            SUBSCRIPTION_ID
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        assert_eq!(subscriptions.renew_due().await, Ok(1));
+
+        let payment = pay(&payments, SUBSCRIPTION_ID, PARENT_ID).await;
+        deliver(&payments, &payment, "payment.failed").await;
+
+        assert_eq!(
+            statuses(&pool, &payment).await,
+            (
+                "failed".to_string(),
+                "open".to_string(),
+                "past_due".to_string()
+            )
+        );
+
+        // Paying again settles the renewal.
+        let payment = pay(&payments, SUBSCRIPTION_ID, PARENT_ID).await;
+        deliver(&payments, &payment, "payment.succeeded").await;
+
+        assert_eq!(
+            statuses(&pool, &payment).await,
+            (
+                "succeeded".to_string(),
+                "paid".to_string(),
+                "active".to_string()
+            )
+        );
+    }
+
+    #[sqlx::test(fixtures("subscriptions"))]
+    async fn test_money_of_a_replaced_payment_is_applied_or_marked_for_refund(pool: sqlx::PgPool) {
+        let payments = PaymentService::new(pool.clone());
+
+        let first = pay(&payments, OTHER_SUBSCRIPTION_ID, OTHER_ID).await;
+        let second = pay(&payments, OTHER_SUBSCRIPTION_ID, OTHER_ID).await;
+        assert_eq!(statuses(&pool, &first).await.0, "failed".to_string());
+
+        // Paid with the first payment anyway.
+        deliver(&payments, &first, "payment.succeeded").await;
+
+        assert_eq!(
+            statuses(&pool, &first).await,
+            (
+                "succeeded".to_string(),
+                "paid".to_string(),
+                "active".to_string()
+            )
+        );
+
+        // And with the second one too.
+        deliver(&payments, &second, "payment.succeeded").await;
+
+        assert_eq!(refund_reason(&pool, &first).await, None);
+        assert!(refund_reason(&pool, &second).await.is_some());
+        assert_eq!(statuses(&pool, &second).await.0, "succeeded".to_string());
+    }
+
+    #[sqlx::test(fixtures("subscriptions"))]
+    async fn test_refund_webhook_confirms_the_refund(pool: sqlx::PgPool) {
+        let payments = PaymentService::new(pool.clone());
+
+        let payment = pay(&payments, OTHER_SUBSCRIPTION_ID, OTHER_ID).await;
+        deliver(&payments, &payment, "payment.succeeded").await;
+
+        let half = Money::new(payment.amount.amount_minor / 2, payment.amount.currency);
+        let refunded = payments
+            .refund_payment(
+                payment.id,
+                RefundPaymentRequest { amount: Some(half) },
+                &providers(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(refunded.refunded_amount, half);
+
+        let refund_id = sqlx::query_scalar!(
+            r#"
+            SELECT provider_refund_id AS "provider_refund_id!" FROM payment_refunds WHERE payment_id = $1
+            "#,
+            payment.id
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+        // Confirmations of the refund, however many, don't pay back more.
+        for _ in 0..2 {
+            let event = serde_json::json!({
+                "id": format!("evt_{}", uuid::Uuid::new_v4()),
+                "type": "payment.refunded",
+                "payment_id": payment.provider_payment_id,
+                "amount": half,
+                "refund_id": refund_id,
+            });
+            assert_eq!(send(&payments, event).await, Ok(true));
+        }
+
+        let mut conn = pool.acquire().await.unwrap();
+        let confirmed = PaymentService::find(&mut conn, payment.id).await.unwrap();
+        assert_eq!(confirmed.refunded_amount, half);
+        assert_eq!(confirmed.status, PaymentStatus::Succeeded);
+
+        // A refund made on the provider's side is added.
+        let event = serde_json::json!({
+            "id": format!("evt_{}", uuid::Uuid::new_v4()),
+            "type": "payment.refunded",
+            "payment_id": payment.provider_payment_id,
+            "amount": payment.amount.checked_sub(half).unwrap(),
+            "refund_id": "mock_re_dashboard",
+        });
+        assert_eq!(send(&payments, event).await, Ok(true));
+
+        let refunded = PaymentService::find(&mut conn, payment.id).await.unwrap();
+        assert_eq!(refunded.refunded_amount, payment.amount);
+        assert_eq!(refunded.status, PaymentStatus::Refunded);
+    }
+
+    async fn webhook_events(pool: &sqlx::PgPool) -> i64 {
+        sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) AS "count!" FROM webhook_events
+            "#
+        )
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    #[sqlx::test(fixtures("subscriptions"))]
+    async fn test_webhook_events_are_applied_once(pool: sqlx::PgPool) {
+        let payments = PaymentService::new(pool.clone());
+
+        let payment = pay(&payments, OTHER_SUBSCRIPTION_ID, OTHER_ID).await;
+        let event = |event_type: &str| {
+            serde_json::json!({
+                "id": "evt_1",
+                "type": event_type,
+                "payment_id": payment.provider_payment_id,
+                "amount": payment.amount,
             })
-            .collect())
+        };
+
+        assert_eq!(send(&payments, event("payment.succeeded")).await, Ok(true));
+        let applied = statuses(&pool, &payment).await;
+
+        // Delivered again, even with other content, the event is skipped.
+        assert_eq!(send(&payments, event("payment.succeeded")).await, Ok(false));
+        assert_eq!(send(&payments, event("payment.refunded")).await, Ok(false));
+
+        assert_eq!(statuses(&pool, &payment).await, applied);
+        assert_eq!(webhook_events(&pool).await, 1);
+
+        let mut conn = pool.acquire().await.unwrap();
+        let payment = PaymentService::find(&mut conn, payment.id).await.unwrap();
+        assert!(payment.refunded_amount.is_zero());
+    }
+
+    #[sqlx::test(fixtures("subscriptions"))]
+    async fn test_webhook_with_a_bad_signature_is_rejected(pool: sqlx::PgPool) {
+        let payments = PaymentService::new(pool.clone());
+
+        let payment = pay(&payments, OTHER_SUBSCRIPTION_ID, OTHER_ID).await;
+        let body = serde_json::json!({
+            "id": "evt_1",
+            "type": "payment.succeeded",
+            "payment_id": payment.provider_payment_id,
+            "amount": payment.amount,
+        })
+        .to_string();
+        let now = chrono::Utc::now().timestamp();
+
+        for signature in [
+            Some(sign("other", now, body.as_bytes())),
+            Some(sign("secret", now, b"{}")),
+            None,
+        ] {
+            assert_eq!(
+                payments
+                    .handle_webhook("mock", signature.as_deref(), body.as_bytes(), &providers())
+                    .await
+                    .map_err(|(status, _)| status),
+                Err(StatusCode::UNAUTHORIZED)
+            );
+        }
+
+        assert_eq!(webhook_events(&pool).await, 0);
+        assert_eq!(statuses(&pool, &payment).await.0, "pending".to_string());
     }
 }
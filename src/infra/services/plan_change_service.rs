@@ -0,0 +1,507 @@
+use axum::http::StatusCode;
+
+use crate::domain::{
+    dtos::subscription_dtos::{ChangePlanMode, ChangePlanRequest, PlanChangeResponse},
+    models::{
+        invoice_model::{self, InvoiceKind, InvoiceLine, InvoiceLineKind},
+        money_model::Money,
+        subscription_model::{Actor, SubscriptionStatus},
+    },
+};
+
+use super::{
+    coupon_service::CouponService,
+    invoice_service::{InvoiceService, NewInvoice},
+    quota_service::{QuotaService, QuotaServiceImpl},
+    resource_service::{ResourceService, ResourceServiceImpl},
+    subscription_service::SubscriptionService,
+};
+
+pub struct PlanChangeService {
+    pub pool: sqlx::PgPool,
+}
+
+pub trait PlanChangeServiceImpl {
+    fn new(pool: sqlx::PgPool) -> Self;
+
+    async fn change_plan(
+        &self,
+        subscription_id: uuid::Uuid,
+        user_id: uuid::Uuid,
+        change: ChangePlanRequest,
+    ) -> Result<PlanChangeResponse, (StatusCode, String)>;
+
+    async fn apply_scheduled(&self) -> Result<u64, String>;
+}
+
+fn internal_error(e: sqlx::Error) -> (StatusCode, String) {
+    tracing::error!("Failed to change plan: {:?}", e);
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "Failed to change plan".to_string(),
+    )
+}
+
+impl PlanChangeService {
+    /// Resources whose current usage is above the hard limit `user_id` would
+    /// have on `plan_id`, formatted as `<type> <usage>/<limit>`.
+    async fn usage_violations(
+        &self,
+        user_id: uuid::Uuid,
+        plan_id: uuid::Uuid,
+    ) -> Result<Vec<String>, String> {
+        let limits = ResourceService::new(self.pool.clone())
+            .get_effective_limits(user_id, Some(plan_id))
+            .await?;
+        let quota_service = QuotaService::new(self.pool.clone());
+
+        let mut violations = vec![];
+
+        for limit in limits {
+            let Some(hard_limit) = limit.hard_limit else {
+                continue;
+            };
+
+            let usage = quota_service
+                .get_usage(user_id, &limit.resource_type)
+                .await?;
+
+            if usage > hard_limit {
+                violations.push(format!("{} {}/{}", limit.resource_type, usage, hard_limit));
+            }
+        }
+
+        Ok(violations)
+    }
+}
+
+impl PlanChangeServiceImpl for PlanChangeService {
+    fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+
+    async fn change_plan(
+        &self,
+        subscription_id: uuid::Uuid,
+        user_id: uuid::Uuid,
+        change: ChangePlanRequest,
+    ) -> Result<PlanChangeResponse, (StatusCode, String)> {
+        let mut tx = self.pool.begin().await.map_err(internal_error)?;
+
+        // Renewals, cancels, pauses and other plan changes wait for this one.
+        let subscription = SubscriptionService::lock_own(&mut tx, subscription_id, user_id).await?;
+        let status = subscription.status;
+
+        if !matches!(
+            status,
+            SubscriptionStatus::Active | SubscriptionStatus::Trialing
+        ) {
+            return Err((
+                StatusCode::CONFLICT,
+                format!(
+                    "Cannot change the plan of a subscription that is {}",
+                    status
+                ),
+            ));
+        }
+
+        if subscription.cancel_at_period_end {
+            return Err((
+                StatusCode::CONFLICT,
+                "Reactivate the subscription before changing its plan".to_string(),
+            ));
+        }
+
+        if subscription.plan_id == change.plan_id
+            && subscription.plan_price_id == change.plan_price_id
+        {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "The subscription is already on this plan and price".to_string(),
+            ));
+        }
+
+        let plan = sqlx::query!(
+            r#"
+            SELECT id, name, is_active, is_free_forever FROM plans WHERE id = $1
+            "#,
+            change.plan_id
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(internal_error)?
+        .filter(|plan| plan.is_active.unwrap_or(true))
+        .ok_or((StatusCode::NOT_FOUND, "Plan not found".to_string()))?;
+
+        let current = SubscriptionService::billing_terms(
+            &mut tx,
+            subscription.plan_id,
+            subscription.plan_version_id,
+            subscription.plan_price_id,
+            false,
+        )
+        .await?;
+        let target =
+            SubscriptionService::billing_terms(&mut tx, plan.id, None, change.plan_price_id, true)
+                .await?;
+
+        if target.price.currency != current.price.currency {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!(
+                    "The subscription is billed in {}, choose a price in this currency",
+                    current.price.currency
+                ),
+            ));
+        }
+
+        let now = chrono::Utc::now().naive_utc();
+
+        // A paid period restarts when there is none yet or when the billing
+        // period changes, e.g. from monthly to yearly.
+        let restarts_period = !plan.is_free_forever
+            && (subscription.end_date.is_none()
+                || (status != SubscriptionStatus::Trialing && current.period != target.period));
+
+        let (effective_at, change_status, proration_amount) = match change.mode {
+            ChangePlanMode::Immediate => {
+                let violations = self
+                    .usage_violations(user_id, plan.id)
+                    .await
+                    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+                if !violations.is_empty() {
+                    return Err((
+                        StatusCode::CONFLICT,
+                        format!(
+                            "Current usage exceeds the limits of the target plan ({}), reduce it or change at the end of the period",
+                            violations.join(", ")
+                        ),
+                    ));
+                }
+
+                // Nothing was paid during a trial. A restarted period is
+                // charged in full, less the unused part of the current one.
+                let proration_amount =
+                    match (status, subscription.start_date, subscription.end_date) {
+                        (SubscriptionStatus::Trialing, _, _) => {
+                            Ok(Money::zero(target.price.currency))
+                        }
+                        (_, Some(start_date), Some(end_date)) if restarts_period => {
+                            target.price.checked_add(invoice_model::prorate(
+                                -current.price,
+                                start_date,
+                                end_date,
+                                now,
+                            ))
+                        }
+                        (_, Some(start_date), Some(end_date)) => {
+                            target.price.checked_sub(current.price).map(|difference| {
+                                invoice_model::prorate(difference, start_date, end_date, now)
+                            })
+                        }
+                        _ => Ok(target.price),
+                    }
+                    .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+                (now, "applied", proration_amount)
+            }
+            ChangePlanMode::EndOfPeriod => {
+                let end_date = subscription.end_date.ok_or((
+                    StatusCode::BAD_REQUEST,
+                    "The subscription has no period end, change the plan immediately".to_string(),
+                ))?;
+
+                (end_date, "scheduled", Money::zero(target.price.currency))
+            }
+        };
+
+        sqlx::query!(
+            r#"
+            UPDATE subscription_plan_changes SET status = 'canceled', reason = 'Replaced by a new plan change'
+            WHERE subscription_id = $1 AND status = 'scheduled'
+            "#,
+            subscription_id
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(internal_error)?;
+
+        if let Some(code) = &change.promo_code {
+            CouponService::redeem(
+                &mut tx,
+                code,
+                user_id,
+                subscription_id,
+                plan.id,
+                target.price.currency,
+            )
+            .await?;
+        }
+
+        // A charge is invoiced, a credit is taken off the next period invoices.
+        let mut invoice = None;
+        let proration_amount = if proration_amount.amount_minor > 0 {
+            let period_end = match subscription.end_date {
+                Some(end_date) if !restarts_period => end_date,
+                _ => target.period.end_of(now),
+            };
+            let mut lines = vec![InvoiceLine::single(
+                InvoiceLineKind::Proration,
+                format!("Change to {}", plan.name),
+                proration_amount,
+            )
+            .for_period(now, period_end)];
+
+            let discount =
+                CouponService::apply_discount(&mut tx, subscription_id, now, proration_amount)
+                    .await
+                    .map_err(internal_error)?;
+
+            let proration_amount = match &discount {
+                Some(discount) => {
+                    lines.push(InvoiceLine::single(
+                        InvoiceLineKind::Discount,
+                        format!("Coupon {}", discount.code),
+                        -discount.amount,
+                    ));
+
+                    proration_amount
+                        .checked_sub(discount.amount)
+                        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?
+                }
+                None => proration_amount,
+            };
+
+            if proration_amount.amount_minor > 0 {
+                invoice = Some(NewInvoice {
+                    subscription_id,
+                    user_id,
+                    kind: InvoiceKind::PlanChange,
+                    period: Some((now, period_end)),
+                    currency: proration_amount.currency,
+                    due_at: now + chrono::Duration::days(invoice_model::PAYMENT_TERMS_DAYS),
+                    lines,
+                    coupon_redemption_id: discount.map(|d| d.redemption_id),
+                });
+            }
+
+            proration_amount
+        } else {
+            proration_amount
+        };
+
+        if change.mode == ChangePlanMode::Immediate {
+            if plan.is_free_forever {
+                if status == SubscriptionStatus::Trialing {
+                    SubscriptionService::transition(
+                        &mut tx,
+                        subscription_id,
+                        SubscriptionStatus::Active,
+                        Actor::User(user_id),
+                        Some("Changed to a free forever plan".to_string()),
+                    )
+                    .await?;
+                }
+
+                sqlx::query!(
+                    r#"
+                    UPDATE subscriptions
+                    SET plan_id = $1, plan_price_id = $2, end_date = NULL,
+                        plan_version_id = (SELECT current_version_id FROM plans WHERE id = $1)
+                    WHERE id = $3
+                    "#,
+                    plan.id,
+                    change.plan_price_id,
+                    subscription_id
+                )
+                .execute(&mut *tx)
+                .await
+                .map_err(internal_error)?;
+            } else if restarts_period {
+                sqlx::query!(
+                    r#"
+                    UPDATE subscriptions
+                    SET plan_id = $1, plan_price_id = $2, start_date = $3, end_date = $4,
+                        plan_version_id = (SELECT current_version_id FROM plans WHERE id = $1)
+                    WHERE id = $5
+                    "#,
+                    plan.id,
+                    change.plan_price_id,
+                    now,
+                    target.period.end_of(now),
+                    subscription_id
+                )
+                .execute(&mut *tx)
+                .await
+                .map_err(internal_error)?;
+            } else {
+                sqlx::query!(
+                    r#"
+                    UPDATE subscriptions
+                    SET plan_id = $1, plan_price_id = $2,
+                        plan_version_id = (SELECT current_version_id FROM plans WHERE id = $1)
+                    WHERE id = $3
+                    "#,
+                    plan.id,
+                    change.plan_price_id,
+                    subscription_id
+                )
+                .execute(&mut *tx)
+                .await
+                .map_err(internal_error)?;
+            }
+        }
+
+        let invoice_id = match invoice {
+            Some(invoice) => Some(InvoiceService::issue(&mut tx, invoice).await?),
+            None => None,
+        };
+
+        let plan_change = sqlx::query!(
+            r#"
+            INSERT INTO subscription_plan_changes
+                (subscription_id, from_plan_id, to_plan_id, to_plan_price_id, mode, status, proration_amount, currency,
+                effective_at, requested_by, applied_at, invoice_id)
+            VALUES ($1, $2, $3, $4, $5, $6::VARCHAR, $7, $10, $8, $9,
+                CASE WHEN $6::VARCHAR = 'applied' THEN CURRENT_TIMESTAMP END, $11)
+            RETURNING id, subscription_id, from_plan_id, to_plan_id, to_plan_price_id, mode, status, proration_amount,
+                currency, effective_at, created_at, invoice_id
+            "#,
+            subscription_id,
+            subscription.plan_id,
+            plan.id,
+            change.plan_price_id,
+            change.mode.as_str(),
+            change_status,
+            proration_amount.amount_minor,
+            effective_at,
+            user_id,
+            proration_amount.currency.as_str(),
+            invoice_id
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(internal_error)?;
+
+        tx.commit().await.map_err(internal_error)?;
+
+        Ok(PlanChangeResponse {
+            id: plan_change.id,
+            subscription_id: plan_change.subscription_id,
+            from_plan_id: plan_change.from_plan_id,
+            to_plan_id: plan_change.to_plan_id,
+            to_plan_price_id: plan_change.to_plan_price_id,
+            mode: plan_change.mode,
+            status: plan_change.status,
+            proration_amount: Money::parse(plan_change.proration_amount, &plan_change.currency)
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?,
+            invoice_id: plan_change.invoice_id,
+            effective_at: plan_change.effective_at,
+            created_at: plan_change.created_at,
+        })
+    }
+
+    async fn apply_scheduled(&self) -> Result<u64, String> {
+        let due = sqlx::query_scalar!(
+            r#"
+            SELECT id FROM subscription_plan_changes
+            WHERE status = 'scheduled' AND effective_at <= CURRENT_TIMESTAMP
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get scheduled plan changes: {:?}", e);
+            "Failed to get scheduled plan changes".to_string()
+        })?;
+
+        let mut count = 0;
+
+        for id in due {
+            let mut tx = self.pool.begin().await.map_err(|e| internal_error(e).1)?;
+
+            let plan_change = sqlx::query!(
+                r#"
+                SELECT c.subscription_id, c.to_plan_id, c.to_plan_price_id, s.user_id AS "user_id!", s.status, p.is_free_forever
+                FROM subscription_plan_changes AS c
+                INNER JOIN subscriptions AS s ON s.id = c.subscription_id
+                INNER JOIN plans AS p ON p.id = c.to_plan_id
+                WHERE c.id = $1 AND c.status = 'scheduled'
+                FOR UPDATE OF c SKIP LOCKED
+                "#,
+                id
+            )
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|e| internal_error(e).1)?;
+
+            let Some(plan_change) = plan_change else {
+                continue;
+            };
+
+            let status: SubscriptionStatus = plan_change.status.parse()?;
+
+            let cancel_reason = if !status.is_active() {
+                Some(format!("Subscription is {}", status))
+            } else {
+                let violations = self
+                    .usage_violations(plan_change.user_id, plan_change.to_plan_id)
+                    .await?;
+
+                (!violations.is_empty()).then(|| {
+                    format!(
+                        "Current usage exceeds the limits of the target plan ({})",
+                        violations.join(", ")
+                    )
+                })
+            };
+
+            if let Some(reason) = cancel_reason {
+                sqlx::query!(
+                    r#"
+                    UPDATE subscription_plan_changes SET status = 'canceled', reason = $1 WHERE id = $2
+                    "#,
+                    reason,
+                    id
+                )
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| internal_error(e).1)?;
+            } else {
+                sqlx::query!(
+                    r#"
+                    UPDATE subscriptions
+                    SET plan_id = $1, plan_price_id = $2, end_date = CASE WHEN $3 THEN NULL ELSE end_date END,
+                        plan_version_id = (SELECT current_version_id FROM plans WHERE id = $1)
+                    WHERE id = $4
+                    "#,
+                    plan_change.to_plan_id,
+                    plan_change.to_plan_price_id,
+                    plan_change.is_free_forever,
+                    plan_change.subscription_id
+                )
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| internal_error(e).1)?;
+
+                sqlx::query!(
+                    r#"
+                    UPDATE subscription_plan_changes SET status = 'applied', applied_at = CURRENT_TIMESTAMP
+                    WHERE id = $1
+                    "#,
+                    id
+                )
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| internal_error(e).1)?;
+
+                count += 1;
+            }
+
+            tx.commit().await.map_err(|e| internal_error(e).1)?;
+        }
+
+        Ok(count)
+    }
+}
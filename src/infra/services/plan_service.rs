@@ -1,4 +1,7 @@
-use crate::domain::dtos::plan_dtos::{CreatePlanRequest, PlanResponse};
+use crate::domain::{
+    dtos::plan_dtos::{CreatePlanPriceRequest, CreatePlanRequest, PlanPriceResponse, PlanResponse},
+    models::{money_model::Money, plan_model::BillingInterval},
+};
 
 pub struct PlanService {
     pub pool: sqlx::PgPool,
@@ -18,6 +21,81 @@ pub trait PlanServiceImpl {
         id: uuid::Uuid,
         plan: CreatePlanRequest,
     ) -> Result<PlanResponse, String>;
+
+    async fn create_plan_price(
+        &self,
+        plan_id: uuid::Uuid,
+        price: CreatePlanPriceRequest,
+    ) -> Result<PlanPriceResponse, String>;
+
+    async fn get_plan_prices(&self, plan_id: uuid::Uuid) -> Result<Vec<PlanPriceResponse>, String>;
+
+    async fn deactivate_plan_price(&self, id: uuid::Uuid) -> Result<(), String>;
+}
+
+fn validate_plan(plan: &CreatePlanRequest) -> Result<(), String> {
+    if plan.price.amount_minor < 0 {
+        return Err("price must be 0 or more".to_string());
+    }
+
+    if plan.trial_days.unwrap_or_default() < 0 {
+        return Err("trial_days must be 0 (no trial) or more".to_string());
+    }
+
+    if plan.max_pauses_per_year.unwrap_or_default() < 0 {
+        return Err("max_pauses_per_year must be 0 (no pauses) or more".to_string());
+    }
+
+    if plan.billing_interval_count.is_some_and(|count| count <= 0) {
+        return Err("billing_interval_count must be 1 or more".to_string());
+    }
+
+    if plan.is_free_forever.unwrap_or_default() && !plan.price.is_zero() {
+        return Err("A free forever plan must have a price of 0".to_string());
+    }
+
+    Ok(())
+}
+
+impl PlanService {
+    /// Records the plan's current terms as a new version when they differ
+    /// from its current version. Subscriptions keep the version they were
+    /// created on, so edits never reprice existing subscribers.
+    async fn snapshot_version(
+        conn: &mut sqlx::PgConnection,
+        plan_id: uuid::Uuid,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            WITH changed AS (
+                SELECT p.id, p.price, COALESCE(p.trial_days, 0) AS trial_days, p.is_free_forever,
+                    p.max_pauses_per_year, p.billing_interval, p.billing_interval_count
+                FROM plans AS p
+                LEFT JOIN plan_versions AS v ON v.id = p.current_version_id
+                WHERE p.id = $1
+                  AND (v.price, v.trial_days, v.is_free_forever, v.max_pauses_per_year, v.billing_interval,
+                      v.billing_interval_count)
+                      IS DISTINCT FROM
+                      (p.price, COALESCE(p.trial_days, 0), p.is_free_forever, p.max_pauses_per_year,
+                      p.billing_interval, p.billing_interval_count)
+            ),
+            created AS (
+                INSERT INTO plan_versions (plan_id, version, price, trial_days, is_free_forever, max_pauses_per_year,
+                    billing_interval, billing_interval_count)
+                SELECT id, COALESCE((SELECT MAX(version) FROM plan_versions WHERE plan_id = $1), 0) + 1, price,
+                    trial_days, is_free_forever, max_pauses_per_year, billing_interval, billing_interval_count
+                FROM changed
+                RETURNING id, plan_id
+            )
+            UPDATE plans SET current_version_id = created.id FROM created WHERE plans.id = created.plan_id
+            "#,
+            plan_id
+        )
+        .execute(&mut *conn)
+        .await?;
+
+        Ok(())
+    }
 }
 
 impl PlanServiceImpl for PlanService {
@@ -28,7 +106,14 @@ impl PlanServiceImpl for PlanService {
     async fn get_plan(&self, id: uuid::Uuid) -> Result<PlanResponse, String> {
         let plan = sqlx::query!(
             r#"
-            SELECT id, name, description, price, is_active, tags, trial_days, created_at
+            SELECT id, name, description, price, currency, is_active, tags, trial_days, is_free_forever, max_pauses_per_year,
+            billing_interval, billing_interval_count, created_at,
+            ARRAY(
+                SELECT f.code FROM plan_features AS pf
+                INNER JOIN features AS f ON f.id = pf.feature_id
+                WHERE pf.plan_id = plans.id
+                ORDER BY f.code
+            ) AS "features!"
             FROM plans
             WHERE id = $1
             "#,
@@ -45,43 +130,79 @@ impl PlanServiceImpl for PlanService {
             id: plan.id,
             name: plan.name,
             description: plan.description,
-            price: plan.price,
+            price: Money::parse(plan.price, &plan.currency)?,
             is_active: plan.is_active,
             tags: plan.tags,
             trial_days: plan.trial_days,
+            is_free_forever: Some(plan.is_free_forever),
+            max_pauses_per_year: Some(plan.max_pauses_per_year),
+            billing_interval: Some(plan.billing_interval),
+            billing_interval_count: Some(plan.billing_interval_count),
+            features: Some(plan.features),
             created_at: plan.created_at,
         })
     }
 
     async fn create_plan(&self, plan: CreatePlanRequest) -> Result<PlanResponse, String> {
+        validate_plan(&plan)?;
+
+        let mut tx = self.pool.begin().await.map_err(|e| {
+            tracing::error!("Failed to create plan: {:?}", e);
+            "Failed to create plan".to_string()
+        })?;
+
         let plan = sqlx::query!(
             r#"
-            INSERT INTO plans (name, description, price, is_active, tags, trial_days)
-            VALUES ($1, $2, $3, $4, $5, $6)
-            RETURNING id, name, description, price, is_active, tags, trial_days, created_at
+            INSERT INTO plans (name, description, price, currency, is_active, tags, trial_days, is_free_forever,
+                max_pauses_per_year, billing_interval, billing_interval_count)
+            VALUES ($1, $2, $3, $11, $4, $5, $6, $7, $8, $9, $10)
+            RETURNING id, name, description, price, currency, is_active, tags, trial_days, is_free_forever, max_pauses_per_year,
+            billing_interval, billing_interval_count, created_at
             "#,
             plan.name,
             plan.description,
-            plan.price,
+            plan.price.amount_minor,
             plan.is_active,
             plan.tags.as_slice(),
-            plan.trial_days
+            plan.trial_days.unwrap_or_default(),
+            plan.is_free_forever.unwrap_or_default(),
+            plan.max_pauses_per_year.unwrap_or_default(),
+            plan.billing_interval.unwrap_or(BillingInterval::Month).as_str(),
+            plan.billing_interval_count.unwrap_or(1),
+            plan.price.currency.as_str()
         )
-        .fetch_one(&self.pool)
+        .fetch_one(&mut *tx)
         .await
         .map_err(|e| {
             tracing::error!("Failed to create plan: {:?}", e);
             "Failed to create plan".to_string()
         })?;
 
+        Self::snapshot_version(&mut tx, plan.id)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to create plan version: {:?}", e);
+                "Failed to create plan".to_string()
+            })?;
+
+        tx.commit().await.map_err(|e| {
+            tracing::error!("Failed to create plan: {:?}", e);
+            "Failed to create plan".to_string()
+        })?;
+
         Ok(PlanResponse {
             id: plan.id,
             name: plan.name,
             description: plan.description,
-            price: plan.price,
+            price: Money::parse(plan.price, &plan.currency)?,
             is_active: plan.is_active,
             tags: plan.tags,
             trial_days: plan.trial_days,
+            is_free_forever: Some(plan.is_free_forever),
+            max_pauses_per_year: Some(plan.max_pauses_per_year),
+            billing_interval: Some(plan.billing_interval),
+            billing_interval_count: Some(plan.billing_interval_count),
+            features: None,
             created_at: plan.created_at,
         })
     }
@@ -89,7 +210,14 @@ impl PlanServiceImpl for PlanService {
     async fn get_plans(&self) -> Result<Vec<PlanResponse>, String> {
         let plans = sqlx::query!(
             r#"
-            SELECT id, name, description, price, is_active, tags, trial_days, created_at
+            SELECT id, name, description, price, currency, is_active, tags, trial_days, is_free_forever, max_pauses_per_year,
+            billing_interval, billing_interval_count, created_at,
+            ARRAY(
+                SELECT f.code FROM plan_features AS pf
+                INNER JOIN features AS f ON f.id = pf.feature_id
+                WHERE pf.plan_id = plans.id
+                ORDER BY f.code
+            ) AS "features!"
             FROM plans
             "#,
         )
@@ -100,19 +228,26 @@ impl PlanServiceImpl for PlanService {
             "Failed to get plans".to_string()
         })?;
 
-        Ok(plans
+        plans
             .into_iter()
-            .map(|plan| PlanResponse {
-                id: plan.id,
-                name: plan.name,
-                description: plan.description,
-                price: plan.price,
-                is_active: plan.is_active,
-                tags: plan.tags,
-                trial_days: plan.trial_days,
-                created_at: plan.created_at,
+            .map(|plan| {
+                Ok(PlanResponse {
+                    id: plan.id,
+                    name: plan.name,
+                    description: plan.description,
+                    price: Money::parse(plan.price, &plan.currency)?,
+                    is_active: plan.is_active,
+                    tags: plan.tags,
+                    trial_days: plan.trial_days,
+                    is_free_forever: Some(plan.is_free_forever),
+                    max_pauses_per_year: Some(plan.max_pauses_per_year),
+                    billing_interval: Some(plan.billing_interval),
+                    billing_interval_count: Some(plan.billing_interval_count),
+                    features: Some(plan.features),
+                    created_at: plan.created_at,
+                })
             })
-            .collect())
+            .collect()
     }
 
     async fn update_plan(
@@ -120,37 +255,220 @@ impl PlanServiceImpl for PlanService {
         id: uuid::Uuid,
         plan: CreatePlanRequest,
     ) -> Result<PlanResponse, String> {
+        validate_plan(&plan)?;
+
+        let mut tx = self.pool.begin().await.map_err(|e| {
+            tracing::error!("Failed to update plan: {:?}", e);
+            "Failed to update plan".to_string()
+        })?;
+
+        let currency = sqlx::query_scalar!(
+            r#"
+            SELECT currency FROM plans WHERE id = $1 FOR UPDATE
+            "#,
+            id
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to update plan: {:?}", e);
+            "Failed to update plan".to_string()
+        })?
+        .ok_or("Plan not found".to_string())?;
+
+        if currency != plan.price.currency.as_str() {
+            return Err(format!(
+                "The plan is priced in {}, add a plan price for other currencies",
+                currency
+            ));
+        }
+
         let plan = sqlx::query!(
             r#"
             UPDATE plans
-            SET name = $1, description = $2, price = $3, is_active = $4, tags = $5, trial_days = $6
-            WHERE id = $7
-            RETURNING id, name, description, price, is_active, tags, trial_days, created_at
+            SET name = $1, description = $2, price = $3, is_active = $4, tags = $5, trial_days = $6,
+                is_free_forever = $7, max_pauses_per_year = $8, billing_interval = $9,
+                billing_interval_count = $10, updated_at = CURRENT_TIMESTAMP
+            WHERE id = $11
+            RETURNING id, name, description, price, currency, is_active, tags, trial_days, is_free_forever, max_pauses_per_year,
+            billing_interval, billing_interval_count, created_at
             "#,
             plan.name,
             plan.description,
-            plan.price,
+            plan.price.amount_minor,
             plan.is_active,
             plan.tags.as_slice(),
-            plan.trial_days,
+            plan.trial_days.unwrap_or_default(),
+            plan.is_free_forever.unwrap_or_default(),
+            plan.max_pauses_per_year.unwrap_or_default(),
+            plan.billing_interval.unwrap_or(BillingInterval::Month).as_str(),
+            plan.billing_interval_count.unwrap_or(1),
             id
         )
-        .fetch_one(&self.pool)
+        .fetch_one(&mut *tx)
         .await
         .map_err(|e| {
             tracing::error!("Failed to update plan: {:?}", e);
             "Failed to update plan".to_string()
         })?;
 
+        Self::snapshot_version(&mut tx, plan.id)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to create plan version: {:?}", e);
+                "Failed to update plan".to_string()
+            })?;
+
+        tx.commit().await.map_err(|e| {
+            tracing::error!("Failed to update plan: {:?}", e);
+            "Failed to update plan".to_string()
+        })?;
+
         Ok(PlanResponse {
             id: plan.id,
             name: plan.name,
             description: plan.description,
-            price: plan.price,
+            price: Money::parse(plan.price, &plan.currency)?,
             is_active: plan.is_active,
             tags: plan.tags,
             trial_days: plan.trial_days,
+            is_free_forever: Some(plan.is_free_forever),
+            max_pauses_per_year: Some(plan.max_pauses_per_year),
+            billing_interval: Some(plan.billing_interval),
+            billing_interval_count: Some(plan.billing_interval_count),
+            features: None,
             created_at: plan.created_at,
         })
     }
+
+    async fn create_plan_price(
+        &self,
+        plan_id: uuid::Uuid,
+        price: CreatePlanPriceRequest,
+    ) -> Result<PlanPriceResponse, String> {
+        if price.billing_interval_count.is_some_and(|count| count <= 0) {
+            return Err("billing_interval_count must be 1 or more".to_string());
+        }
+
+        if price.price.amount_minor < 0 {
+            return Err("price must be 0 or more".to_string());
+        }
+
+        let internal_error = |e: sqlx::Error| {
+            tracing::error!("Failed to create plan price: {:?}", e);
+            "Failed to create plan price".to_string()
+        };
+
+        let mut tx = self.pool.begin().await.map_err(internal_error)?;
+
+        // Serializes price changes of the plan.
+        sqlx::query!(
+            r#"
+            SELECT id FROM plans WHERE id = $1 FOR UPDATE
+            "#,
+            plan_id
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(internal_error)?
+        .ok_or("Plan not found".to_string())?;
+
+        let billing_interval_count = price.billing_interval_count.unwrap_or(1);
+
+        // Prices are never changed, subscriptions keep the row they bought.
+        // A new price replaces the one sold for the same period and currency.
+        sqlx::query!(
+            r#"
+            UPDATE plan_prices SET is_active = FALSE
+            WHERE plan_id = $1 AND billing_interval = $2 AND billing_interval_count = $3 AND currency = $4
+              AND is_active
+            "#,
+            plan_id,
+            price.billing_interval.as_str(),
+            billing_interval_count,
+            price.price.currency.as_str()
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(internal_error)?;
+
+        let price = sqlx::query!(
+            r#"
+            INSERT INTO plan_prices (plan_id, billing_interval, billing_interval_count, price, currency)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id, plan_id, billing_interval, billing_interval_count, price, currency, is_active, created_at
+            "#,
+            plan_id,
+            price.billing_interval.as_str(),
+            billing_interval_count,
+            price.price.amount_minor,
+            price.price.currency.as_str()
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(internal_error)?;
+
+        tx.commit().await.map_err(internal_error)?;
+
+        Ok(PlanPriceResponse {
+            id: price.id,
+            plan_id: price.plan_id,
+            billing_interval: price.billing_interval,
+            billing_interval_count: price.billing_interval_count,
+            price: Money::parse(price.price, &price.currency)?,
+            is_active: price.is_active,
+            created_at: price.created_at,
+        })
+    }
+
+    async fn get_plan_prices(&self, plan_id: uuid::Uuid) -> Result<Vec<PlanPriceResponse>, String> {
+        let prices = sqlx::query!(
+            r#"
+            SELECT id, plan_id, billing_interval, billing_interval_count, price, currency, is_active, created_at
+            FROM plan_prices
+            WHERE plan_id = $1 AND is_active
+            ORDER BY currency, price
+            "#,
+            plan_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get plan prices: {:?}", e);
+            "Failed to get plan prices".to_string()
+        })?;
+
+        prices
+            .into_iter()
+            .map(|price| {
+                Ok(PlanPriceResponse {
+                    id: price.id,
+                    plan_id: price.plan_id,
+                    billing_interval: price.billing_interval,
+                    billing_interval_count: price.billing_interval_count,
+                    price: Money::parse(price.price, &price.currency)?,
+                    is_active: price.is_active,
+                    created_at: price.created_at,
+                })
+            })
+            .collect()
+    }
+
+    /// Existing subscriptions keep the price, it is only hidden from new ones.
+    async fn deactivate_plan_price(&self, id: uuid::Uuid) -> Result<(), String> {
+        sqlx::query!(
+            r#"
+            UPDATE plan_prices SET is_active = FALSE WHERE id = $1
+            "#,
+            id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to deactivate plan price: {:?}", e);
+            "Failed to deactivate plan price".to_string()
+        })?;
+
+        Ok(())
+    }
 }
@@ -0,0 +1,252 @@
+use axum::http::StatusCode;
+
+use crate::{
+    domain::{
+        dtos::plan_dtos::{
+            MigratePlanVersionRequest, PlanVersionMigrationResponse, PlanVersionResponse,
+        },
+        models::money_model::Money,
+    },
+    infra::events::{self, DomainEvent},
+};
+
+pub struct PlanVersionService {
+    pub pool: sqlx::PgPool,
+}
+
+pub trait PlanVersionServiceImpl {
+    fn new(pool: sqlx::PgPool) -> Self;
+
+    async fn get_plan_versions(
+        &self,
+        plan_id: uuid::Uuid,
+    ) -> Result<Vec<PlanVersionResponse>, String>;
+
+    async fn migrate_subscribers(
+        &self,
+        plan_id: uuid::Uuid,
+        version_id: uuid::Uuid,
+        migration: MigratePlanVersionRequest,
+        requested_by: uuid::Uuid,
+    ) -> Result<PlanVersionMigrationResponse, (StatusCode, String)>;
+
+    async fn apply_scheduled(&self) -> Result<u64, String>;
+}
+
+fn internal_error(e: sqlx::Error) -> (StatusCode, String) {
+    tracing::error!("Failed to migrate plan version: {:?}", e);
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "Failed to migrate plan version".to_string(),
+    )
+}
+
+impl PlanVersionServiceImpl for PlanVersionService {
+    fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+
+    async fn get_plan_versions(
+        &self,
+        plan_id: uuid::Uuid,
+    ) -> Result<Vec<PlanVersionResponse>, String> {
+        let versions = sqlx::query!(
+            r#"
+            SELECT v.id, v.plan_id, v.version, v.price, p.currency, v.trial_days, v.is_free_forever, v.max_pauses_per_year,
+                v.billing_interval, v.billing_interval_count, v.created_at,
+                p.current_version_id IS NOT DISTINCT FROM v.id AS "is_current!",
+                (
+                    SELECT COUNT(*) FROM subscriptions
+                    WHERE plan_version_id = v.id AND status NOT IN ('canceled', 'expired')
+                ) AS "subscribers!"
+            FROM plan_versions AS v
+            INNER JOIN plans AS p ON p.id = v.plan_id
+            WHERE v.plan_id = $1
+            ORDER BY v.version DESC
+            "#,
+            plan_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get plan versions: {:?}", e);
+            "Failed to get plan versions".to_string()
+        })?;
+
+        versions
+            .into_iter()
+            .map(|version| {
+                Ok(PlanVersionResponse {
+                    id: version.id,
+                    plan_id: version.plan_id,
+                    version: version.version,
+                    price: Money::parse(version.price, &version.currency)?,
+                    trial_days: version.trial_days,
+                    is_free_forever: version.is_free_forever,
+                    max_pauses_per_year: version.max_pauses_per_year,
+                    billing_interval: version.billing_interval,
+                    billing_interval_count: version.billing_interval_count,
+                    is_current: version.is_current,
+                    subscribers: version.subscribers,
+                    created_at: version.created_at,
+                })
+            })
+            .collect()
+    }
+
+    async fn migrate_subscribers(
+        &self,
+        plan_id: uuid::Uuid,
+        version_id: uuid::Uuid,
+        migration: MigratePlanVersionRequest,
+        requested_by: uuid::Uuid,
+    ) -> Result<PlanVersionMigrationResponse, (StatusCode, String)> {
+        let notice_days = migration.notice_days.unwrap_or_default();
+
+        if notice_days < 0 {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "notice_days must be 0 (immediately) or more".to_string(),
+            ));
+        }
+
+        let mut tx = self.pool.begin().await.map_err(internal_error)?;
+
+        sqlx::query!(
+            r#"
+            SELECT id FROM plan_versions WHERE id = $1 AND plan_id = $2
+            "#,
+            version_id,
+            plan_id
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Plan version not found".to_string()))?;
+
+        let now = chrono::Utc::now().naive_utc();
+        let effective_at = now + chrono::Duration::days(notice_days as i64);
+        let status = if notice_days > 0 {
+            "scheduled"
+        } else {
+            "applied"
+        };
+
+        // A newer request replaces the moves still waiting for their notice.
+        sqlx::query!(
+            r#"
+            UPDATE plan_version_migrations AS m SET status = 'canceled'
+            FROM subscriptions AS s
+            WHERE s.id = m.subscription_id AND m.status = 'scheduled'
+              AND s.plan_id = $1 AND ($2::UUID IS NULL OR s.plan_version_id = $2)
+              AND s.plan_version_id IS DISTINCT FROM $3
+            "#,
+            plan_id,
+            migration.from_version_id,
+            version_id
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(internal_error)?;
+
+        let migrated = sqlx::query!(
+            r#"
+            INSERT INTO plan_version_migrations
+                (subscription_id, from_version_id, to_version_id, status, effective_at, requested_by, applied_at)
+            SELECT id, plan_version_id, $3, $4::VARCHAR, $5::TIMESTAMP, $6,
+                CASE WHEN $4::VARCHAR = 'applied' THEN $5::TIMESTAMP END
+            FROM subscriptions
+            WHERE plan_id = $1 AND ($2::UUID IS NULL OR plan_version_id = $2)
+              AND plan_version_id IS DISTINCT FROM $3
+              AND status NOT IN ('canceled', 'expired')
+            RETURNING subscription_id, from_version_id,
+                (SELECT user_id FROM subscriptions WHERE id = subscription_id) AS "user_id!"
+            "#,
+            plan_id,
+            migration.from_version_id,
+            version_id,
+            status,
+            effective_at,
+            requested_by
+        )
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(internal_error)?;
+
+        if notice_days == 0 {
+            sqlx::query!(
+                r#"
+                UPDATE subscriptions SET plan_version_id = $1 WHERE id = ANY($2)
+                "#,
+                version_id,
+                &migrated
+                    .iter()
+                    .map(|m| m.subscription_id)
+                    .collect::<Vec<_>>()
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(internal_error)?;
+        }
+
+        tx.commit().await.map_err(internal_error)?;
+
+        if notice_days > 0 {
+            for m in &migrated {
+                events::publish(DomainEvent::PlanVersionMigrationScheduled {
+                    subscription_id: m.subscription_id,
+                    user_id: m.user_id,
+                    from_version_id: m.from_version_id,
+                    to_version_id: version_id,
+                    effective_at,
+                });
+            }
+        }
+
+        Ok(PlanVersionMigrationResponse {
+            to_version_id: version_id,
+            subscriptions: migrated.len() as u64,
+            status: status.to_string(),
+            effective_at,
+        })
+    }
+
+    async fn apply_scheduled(&self) -> Result<u64, String> {
+        let mut tx = self.pool.begin().await.map_err(|e| internal_error(e).1)?;
+
+        // Subscriptions that changed plan in the meantime already use a
+        // current version of their new plan.
+        sqlx::query!(
+            r#"
+            UPDATE plan_version_migrations AS m SET status = 'canceled'
+            FROM subscriptions AS s, plan_versions AS v
+            WHERE s.id = m.subscription_id AND v.id = m.to_version_id
+              AND m.status = 'scheduled' AND (s.plan_id <> v.plan_id OR s.status IN ('canceled', 'expired'))
+            "#,
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| internal_error(e).1)?;
+
+        let applied = sqlx::query!(
+            r#"
+            WITH due AS (
+                UPDATE plan_version_migrations
+                SET status = 'applied', applied_at = CURRENT_TIMESTAMP
+                WHERE status = 'scheduled' AND effective_at <= CURRENT_TIMESTAMP
+                RETURNING subscription_id, to_version_id
+            )
+            UPDATE subscriptions AS s SET plan_version_id = due.to_version_id
+            FROM due
+            WHERE s.id = due.subscription_id
+            "#,
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| internal_error(e).1)?;
+
+        tx.commit().await.map_err(|e| internal_error(e).1)?;
+
+        Ok(applied.rows_affected())
+    }
+}
@@ -0,0 +1,609 @@
+use axum::http::StatusCode;
+
+use crate::{
+    domain::{
+        dtos::quota_dtos::{CreateQuotaOverrideRequest, QuotaOverrideResponse, QuotaResponse},
+        models::{resource_type_model, subscription_model::SubscriptionStatus},
+    },
+    infra::events::DomainEvent,
+};
+
+use super::{
+    resource_service::{ResourceService, ResourceServiceImpl},
+    subscription_service::SubscriptionService,
+};
+
+/// Percentages of the soft limit that trigger a usage notification.
+const THRESHOLDS: [i32; 2] = [80, 100];
+
+pub struct QuotaService {
+    pub pool: sqlx::PgPool,
+}
+
+pub trait QuotaServiceImpl {
+    fn new(pool: sqlx::PgPool) -> Self;
+
+    async fn get_usage(&self, user_id: uuid::Uuid, resource_type: &str) -> Result<i64, String>;
+
+    async fn get_quotas(&self, user_id: uuid::Uuid) -> Result<Vec<QuotaResponse>, String>;
+
+    /// Checks that `quantity` more of `resource_type` fits the quotas of
+    /// `user_id` and records the thresholds and overage it crosses. The
+    /// subscription stays locked until the transaction of `conn` ends, so
+    /// what is consumed has to be created in that transaction. Returns the
+    /// events to publish once it commits.
+    async fn consume(
+        &self,
+        conn: &mut sqlx::PgConnection,
+        user_id: uuid::Uuid,
+        resource_type: &str,
+        quantity: i64,
+    ) -> Result<Vec<DomainEvent>, (StatusCode, String)>;
+
+    async fn create_override(
+        &self,
+        created_by: uuid::Uuid,
+        quota_override: CreateQuotaOverrideRequest,
+    ) -> Result<QuotaOverrideResponse, String>;
+
+    async fn get_overrides(
+        &self,
+        user_id: Option<uuid::Uuid>,
+    ) -> Result<Vec<QuotaOverrideResponse>, String>;
+
+    async fn revoke_override(&self, id: uuid::Uuid) -> Result<(), String>;
+}
+
+struct ActiveSubscription {
+    id: uuid::Uuid,
+    status: SubscriptionStatus,
+    plan_id: Option<uuid::Uuid>,
+    start_date: Option<chrono::NaiveDateTime>,
+    end_date: Option<chrono::NaiveDateTime>,
+    /// Past due for longer than the dunning policy allows before quotas
+    /// become read-only.
+    is_read_only: bool,
+}
+
+/// Thresholds (in percent of `limit`) passed when usage grows from `before` to `after`.
+fn crossed_thresholds(before: i64, after: i64, limit: i64) -> Vec<i32> {
+    if limit <= 0 {
+        return vec![];
+    }
+
+    THRESHOLDS
+        .into_iter()
+        .filter(|threshold| {
+            let mark = limit * *threshold as i64;
+            before * 100 < mark && mark <= after * 100
+        })
+        .collect()
+}
+
+impl QuotaService {
+    /// The active subscription of `user_id`, or the paused one whose quotas
+    /// can still be read.
+    async fn get_active_subscription(
+        executor: impl sqlx::PgExecutor<'_>,
+        user_id: uuid::Uuid,
+    ) -> Result<Option<ActiveSubscription>, String> {
+        let subscription = sqlx::query!(
+            r#"
+            SELECT id, status, plan_id, start_date, end_date, EXISTS (
+                SELECT 1 FROM invoices AS i
+                WHERE i.subscription_id = subscriptions.id AND i.status = 'open' AND i.read_only_at IS NOT NULL
+            ) AS "is_read_only!"
+            FROM subscriptions
+            WHERE user_id = $1 AND (is_active = TRUE OR status = 'paused')
+            ORDER BY start_date DESC
+            LIMIT 1
+            "#,
+            user_id
+        )
+        .fetch_optional(executor)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get active subscription: {:?}", e);
+            "Failed to get active subscription".to_string()
+        })?;
+
+        subscription
+            .map(|s| {
+                Ok(ActiveSubscription {
+                    id: s.id,
+                    status: s.status.parse()?,
+                    plan_id: s.plan_id,
+                    start_date: s.start_date,
+                    end_date: s.end_date,
+                    is_read_only: s.is_read_only,
+                })
+            })
+            .transpose()
+    }
+
+    /// Usage of `resource_type` by the subscription of `subscriber_id`.
+    async fn usage(
+        executor: impl sqlx::PgExecutor<'_>,
+        subscriber_id: uuid::Uuid,
+        resource_type: &str,
+    ) -> Result<i64, String> {
+        let usage = match resource_type {
+            resource_type_model::USERS => {
+                sqlx::query_scalar!(
+                    r#"
+                    SELECT COUNT(*) AS "count!"
+                    FROM subscription_seats AS ss
+                    INNER JOIN subscriptions AS s ON s.id = ss.subscription_id
+                    WHERE s.user_id = $1 AND ss.released_at IS NULL
+                      AND s.status NOT IN ('canceled', 'expired')
+                    "#,
+                    subscriber_id
+                )
+                .fetch_one(executor)
+                .await
+            }
+            resource_type_model::ROLES => {
+                sqlx::query_scalar!(
+                    r#"
+                    SELECT COUNT(*) AS "count!" FROM roles
+                    WHERE deleted_at IS NULL
+                      AND (created_by = $1 OR created_by IN (
+                          SELECT ss.user_id
+                          FROM subscription_seats AS ss
+                          INNER JOIN subscriptions AS s ON s.id = ss.subscription_id
+                          WHERE s.user_id = $1 AND ss.released_at IS NULL
+                      ))
+                    "#,
+                    subscriber_id
+                )
+                .fetch_one(executor)
+                .await
+            }
+            // Metered types are counted from the usage events of the current period.
+            _ => {
+                sqlx::query_scalar!(
+                    r#"
+                    SELECT COALESCE(SUM(r.quantity), 0)::BIGINT AS "count!"
+                    FROM usage_period_rollups AS r
+                    INNER JOIN subscriptions AS s ON s.id = r.subscription_id
+                    INNER JOIN resource_types AS rt ON rt.id = r.resource_type_id
+                    WHERE s.user_id = $1
+                      AND (s.is_active = TRUE OR s.status = 'paused')
+                      AND rt.code = $2
+                      AND r.period_start = COALESCE(s.start_date, r.period_start)
+                    "#,
+                    subscriber_id,
+                    resource_type
+                )
+                .fetch_one(executor)
+                .await
+            }
+        };
+
+        usage.map_err(|e| {
+            tracing::error!("Failed to get usage: {:?}", e);
+            "Failed to get usage".to_string()
+        })
+    }
+}
+
+impl QuotaServiceImpl for QuotaService {
+    fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+
+    async fn get_usage(&self, user_id: uuid::Uuid, resource_type: &str) -> Result<i64, String> {
+        // Usage is shared by everyone on a group subscription.
+        let user_id = SubscriptionService::subscriber_id(&self.pool, user_id).await?;
+
+        Self::usage(&self.pool, user_id, resource_type).await
+    }
+
+    async fn get_quotas(&self, user_id: uuid::Uuid) -> Result<Vec<QuotaResponse>, String> {
+        let user_id = SubscriptionService::subscriber_id(&self.pool, user_id).await?;
+
+        let Some(subscription) = Self::get_active_subscription(&self.pool, user_id).await? else {
+            return Ok(vec![]);
+        };
+
+        let limits = ResourceService::new(self.pool.clone())
+            .get_effective_limits(user_id, subscription.plan_id)
+            .await?;
+
+        let overages = sqlx::query!(
+            r#"
+            SELECT resource_type_id, quantity
+            FROM usage_overages
+            WHERE subscription_id = $1 AND period_start = COALESCE($2, period_start)
+            "#,
+            subscription.id,
+            subscription.start_date
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get quotas: {:?}", e);
+            "Failed to get quotas".to_string()
+        })?;
+
+        let mut quotas = Vec::with_capacity(limits.len());
+
+        for limit in limits {
+            quotas.push(QuotaResponse {
+                usage: Self::usage(&self.pool, user_id, &limit.resource_type).await?,
+                overage_quantity: overages
+                    .iter()
+                    .find(|o| o.resource_type_id == limit.resource_type_id)
+                    .map(|o| o.quantity)
+                    .unwrap_or_default(),
+                resource_type: limit.resource_type,
+                max: limit.max,
+                hard_limit: limit.hard_limit,
+                source: limit.source,
+                addon_quantity: limit.addon_quantity,
+                overage_unit_price: limit.overage_unit_price,
+            });
+        }
+
+        Ok(quotas)
+    }
+
+    async fn consume(
+        &self,
+        conn: &mut sqlx::PgConnection,
+        user_id: uuid::Uuid,
+        resource_type: &str,
+        quantity: i64,
+    ) -> Result<Vec<DomainEvent>, (StatusCode, String)> {
+        let internal_error = |e: sqlx::Error| {
+            tracing::error!("Failed to check quota: {:?}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to check quota".to_string(),
+            )
+        };
+
+        // Seat holders consume the quotas of the group subscription.
+        let user_id = SubscriptionService::subscriber_id(&mut *conn, user_id)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+        let no_subscription = || (StatusCode::FORBIDDEN, "No active subscription".to_string());
+
+        let found = Self::get_active_subscription(&mut *conn, user_id)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?
+            .ok_or_else(no_subscription)?;
+
+        // Consumers of the same subscription wait for each other here, so
+        // the usage read below counts what the others created.
+        sqlx::query!(
+            r#"
+            SELECT id FROM subscriptions WHERE id = $1 FOR UPDATE
+            "#,
+            found.id
+        )
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(internal_error)?;
+
+        // Read again now that it is locked, it may have changed meanwhile.
+        let subscription = Self::get_active_subscription(&mut *conn, user_id)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?
+            .filter(|subscription| subscription.id == found.id)
+            .ok_or_else(no_subscription)?;
+
+        if subscription.status == SubscriptionStatus::Paused {
+            return Err((
+                StatusCode::FORBIDDEN,
+                "Your subscription is paused, quotas are read-only".to_string(),
+            ));
+        }
+
+        if subscription.is_read_only {
+            return Err((
+                StatusCode::FORBIDDEN,
+                "Your renewal invoice is overdue, quotas are read-only until it is paid"
+                    .to_string(),
+            ));
+        }
+
+        let limit = ResourceService::new(self.pool.clone())
+            .get_effective_limits(user_id, subscription.plan_id)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?
+            .into_iter()
+            .find(|limit| limit.resource_type == resource_type);
+
+        let Some(limit) = limit else {
+            // Neither the plan nor an override limits this resource type.
+            return Ok(vec![]);
+        };
+        let (resource_type_id, max) = (limit.resource_type_id, limit.max);
+
+        let usage = Self::usage(&mut *conn, user_id, resource_type)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+        let new_usage = usage + quantity;
+
+        if let Some(hard_limit) = limit.hard_limit {
+            if new_usage > hard_limit {
+                return Err((
+                    StatusCode::FORBIDDEN,
+                    format!(
+                        "The {} limit of your plan has been reached ({}/{})",
+                        resource_type, usage, hard_limit
+                    ),
+                ));
+            }
+        }
+
+        let period_start = subscription
+            .start_date
+            .unwrap_or_else(|| chrono::Utc::now().naive_utc());
+
+        let mut crossed = vec![];
+
+        for threshold in crossed_thresholds(usage, new_usage, max) {
+            let notification = sqlx::query!(
+                r#"
+                INSERT INTO usage_notifications (subscription_id, resource_type_id, threshold, period_start, usage, limit_value)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                ON CONFLICT (subscription_id, resource_type_id, threshold, period_start) DO NOTHING
+                RETURNING id
+                "#,
+                subscription.id,
+                resource_type_id,
+                threshold,
+                period_start,
+                new_usage,
+                max
+            )
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(internal_error)?;
+
+            if notification.is_some() {
+                crossed.push(DomainEvent::UsageThresholdCrossed {
+                    subscription_id: subscription.id,
+                    user_id,
+                    resource_type: resource_type.to_string(),
+                    threshold,
+                    usage: new_usage,
+                    limit: max,
+                });
+            }
+        }
+
+        if new_usage > max {
+            sqlx::query!(
+                r#"
+                INSERT INTO usage_overages (subscription_id, resource_type_id, period_start, period_end, quantity, unit_price)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                ON CONFLICT (subscription_id, resource_type_id, period_start)
+                DO UPDATE SET quantity = GREATEST(usage_overages.quantity, EXCLUDED.quantity), updated_at = CURRENT_TIMESTAMP
+                "#,
+                subscription.id,
+                resource_type_id,
+                period_start,
+                subscription.end_date,
+                new_usage - max,
+                limit.overage_unit_price
+            )
+            .execute(&mut *conn)
+            .await
+            .map_err(internal_error)?;
+        }
+
+        Ok(crossed)
+    }
+
+    async fn create_override(
+        &self,
+        created_by: uuid::Uuid,
+        quota_override: CreateQuotaOverrideRequest,
+    ) -> Result<QuotaOverrideResponse, String> {
+        let created = sqlx::query!(
+            r#"
+            INSERT INTO quota_overrides (user_id, resource_type_id, max, hard_limit, reason, expires_at, created_by)
+            SELECT $1, rt.id, $3, $4, $5, $6, $7
+            FROM resource_types AS rt
+            WHERE rt.code = $2
+            RETURNING id, user_id, max, hard_limit, reason, expires_at, created_by, created_at, revoked_at
+            "#,
+            quota_override.user_id,
+            quota_override.resource_type,
+            quota_override.max,
+            quota_override.hard_limit.unwrap_or(quota_override.max),
+            quota_override.reason,
+            quota_override.expires_at,
+            created_by
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to create quota override: {:?}", e);
+            "Failed to create quota override".to_string()
+        })?
+        .ok_or_else(|| format!("Unknown resource type: {}", quota_override.resource_type))?;
+
+        Ok(QuotaOverrideResponse {
+            id: created.id,
+            user_id: created.user_id,
+            resource_type: quota_override.resource_type,
+            max: created.max,
+            hard_limit: created.hard_limit,
+            reason: created.reason,
+            expires_at: created.expires_at,
+            created_by: created.created_by,
+            created_at: created.created_at,
+            revoked_at: created.revoked_at,
+        })
+    }
+
+    async fn get_overrides(
+        &self,
+        user_id: Option<uuid::Uuid>,
+    ) -> Result<Vec<QuotaOverrideResponse>, String> {
+        let overrides = sqlx::query!(
+            r#"
+            SELECT o.id, o.user_id, rt.code AS resource_type, o.max, o.hard_limit, o.reason,
+                o.expires_at, o.created_by, o.created_at, o.revoked_at
+            FROM quota_overrides AS o
+            INNER JOIN resource_types AS rt ON rt.id = o.resource_type_id
+            WHERE $1::UUID IS NULL OR o.user_id = $1
+            ORDER BY o.created_at DESC
+            "#,
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get quota overrides: {:?}", e);
+            "Failed to get quota overrides".to_string()
+        })?;
+
+        Ok(overrides
+            .into_iter()
+            .map(|o| QuotaOverrideResponse {
+                id: o.id,
+                user_id: o.user_id,
+                resource_type: o.resource_type,
+                max: o.max,
+                hard_limit: o.hard_limit,
+                reason: o.reason,
+                expires_at: o.expires_at,
+                created_by: o.created_by,
+                created_at: o.created_at,
+                revoked_at: o.revoked_at,
+            })
+            .collect())
+    }
+
+    async fn revoke_override(&self, id: uuid::Uuid) -> Result<(), String> {
+        sqlx::query!(
+            r#"
+            UPDATE quota_overrides SET revoked_at = CURRENT_TIMESTAMP
+            WHERE id = $1 AND revoked_at IS NULL
+            "#,
+            id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to revoke quota override: {:?}", e);
+            "Failed to revoke quota override".to_string()
+        })?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        domain::dtos::role_dtos::CreateRoleRequest,
+        infra::services::role_service::{RoleService, RoleServiceImpl},
+    };
+
+    const PARENT_ID: uuid::Uuid = uuid::uuid!("a0000000-0000-0000-0000-000000000001");
+    const SUBSCRIPTION_ID: uuid::Uuid = uuid::uuid!("b0000000-0000-0000-0000-000000000001");
+
+    async fn set_roles_limit(pool: &sqlx::PgPool, max: i64, hard_limit: i64) {
+        sqlx::query!(
+            r#"
+            UPDATE plan_limits SET max = $1, hard_limit = $2, overage_unit_price = 500
+            WHERE plan_id = (SELECT plan_id FROM subscriptions WHERE id = $3)
+              AND resource_type_id = (SELECT id FROM resource_types WHERE code = 'roles')
+            "#,
+            max,
+            hard_limit,
+            SUBSCRIPTION_ID
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    fn role(name: &str) -> CreateRoleRequest {
+        CreateRoleRequest {
+            name: name.to_string(),
+            description: String::new(),
+            created_by: PARENT_ID,
+        }
+    }
+
+    #[test]
+    fn test_crossed_thresholds() {
+        assert_eq!(crossed_thresholds(0, 7, 10), Vec::<i32>::new());
+        assert_eq!(crossed_thresholds(7, 8, 10), vec![80]);
+        assert_eq!(crossed_thresholds(8, 9, 10), Vec::<i32>::new());
+        assert_eq!(crossed_thresholds(9, 10, 10), vec![100]);
+        assert_eq!(crossed_thresholds(3, 12, 10), vec![80, 100]);
+        assert_eq!(crossed_thresholds(0, 1, 0), Vec::<i32>::new());
+    }
+
+    #[sqlx::test(fixtures("subscriptions"))]
+    async fn test_consume_keeps_concurrent_requests_within_the_hard_limit(pool: sqlx::PgPool) {
+        set_roles_limit(&pool, 1, 1).await;
+        let roles = RoleService::new(pool.clone());
+
+        let (first, second) = tokio::join!(
+            roles.create_role(role("first")),
+            roles.create_role(role("second"))
+        );
+
+        assert!(first.is_ok() != second.is_ok());
+        assert_eq!(
+            [first, second]
+                .into_iter()
+                .find_map(Result::err)
+                .map(|(status, _)| status),
+            Some(StatusCode::FORBIDDEN)
+        );
+        assert_eq!(
+            QuotaService::new(pool)
+                .get_usage(PARENT_ID, resource_type_model::ROLES)
+                .await,
+            Ok(1)
+        );
+    }
+
+    #[sqlx::test(fixtures("subscriptions"))]
+    async fn test_consume_records_overage_and_thresholds(pool: sqlx::PgPool) {
+        set_roles_limit(&pool, 2, 3).await;
+        let roles = RoleService::new(pool.clone());
+
+        for name in ["first", "second", "third"] {
+            roles.create_role(role(name)).await.unwrap();
+        }
+        assert!(matches!(
+            roles.create_role(role("fourth")).await,
+            Err((StatusCode::FORBIDDEN, _))
+        ));
+
+        let thresholds = sqlx::query_scalar!(
+            r#"
+            SELECT threshold FROM usage_notifications WHERE subscription_id = $1 ORDER BY threshold
+            "#,
+            SUBSCRIPTION_ID
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(thresholds, vec![80, 100]);
+
+        let overage = sqlx::query!(
+            r#"
+            SELECT quantity, unit_price FROM usage_overages WHERE subscription_id = $1
+            "#,
+            SUBSCRIPTION_ID
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!((overage.quantity, overage.unit_price), (1, 500));
+    }
+}
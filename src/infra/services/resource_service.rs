@@ -1,4 +1,10 @@
-use crate::domain::dtos::resource_dtos::{CreateResourceRequest, ResourceResponse};
+use crate::domain::{
+    dtos::resource_dtos::{
+        CreateResourceRequest, CreateResourceTypeRequest, EffectiveLimitResponse,
+        PlanLimitResponse, ResourceResponse, ResourceTypeResponse, SetPlanLimitRequest,
+    },
+    models::quota_override_model::LimitSource,
+};
 
 pub struct ResourceService {
     pub pool: sqlx::PgPool,
@@ -23,6 +29,40 @@ pub trait ResourceServiceImpl {
     ) -> Result<(), String>;
 
     async fn get_resources(&self) -> Result<Vec<ResourceResponse>, String>;
+
+    async fn create_resource_type(
+        &self,
+        resource_type: CreateResourceTypeRequest,
+    ) -> Result<ResourceTypeResponse, String>;
+
+    async fn get_resource_types(&self) -> Result<Vec<ResourceTypeResponse>, String>;
+
+    async fn get_plan_limits(&self, plan_id: uuid::Uuid) -> Result<Vec<PlanLimitResponse>, String>;
+
+    async fn get_plan_limit(
+        &self,
+        plan_id: uuid::Uuid,
+        resource_type: &str,
+    ) -> Result<Option<PlanLimitResponse>, String>;
+
+    async fn set_plan_limit(
+        &self,
+        plan_id: uuid::Uuid,
+        resource_type: &str,
+        limit: SetPlanLimitRequest,
+    ) -> Result<PlanLimitResponse, String>;
+
+    async fn get_effective_limits(
+        &self,
+        user_id: uuid::Uuid,
+        plan_id: Option<uuid::Uuid>,
+    ) -> Result<Vec<EffectiveLimitResponse>, String>;
+}
+
+/// Resources created before the `resource_types` catalog were named
+/// `<type>_<plan>_resource`, e.g. `user_free_resource` maps to `users`.
+fn legacy_resource_type(name: &str) -> String {
+    format!("{}s", name.split('_').next().unwrap_or_default())
 }
 
 impl ResourceServiceImpl for ResourceService {
@@ -31,12 +71,19 @@ impl ResourceServiceImpl for ResourceService {
     }
 
     async fn create_resource(&self, resource: CreateResourceRequest) -> Result<(), String> {
-        sqlx::query!(
+        let resource_type = resource
+            .resource_type
+            .unwrap_or_else(|| legacy_resource_type(&resource.name));
+
+        let created = sqlx::query!(
             r#"
-            INSERT INTO resources (plan_id, max, name, description)
-            VALUES ($1, $2, $3, $4)
+            INSERT INTO plan_limits (plan_id, resource_type_id, max, hard_limit, name, description)
+            SELECT $1, rt.id, $3, $3, $4, $5
+            FROM resource_types AS rt
+            WHERE rt.code = $2
             "#,
             resource.plan_id,
+            resource_type,
             resource.max,
             resource.name,
             resource.description
@@ -48,15 +95,20 @@ impl ResourceServiceImpl for ResourceService {
             "Failed to create resource".to_string()
         })?;
 
+        if created.rows_affected() == 0 {
+            return Err(format!("Unknown resource type: {}", resource_type));
+        }
+
         Ok(())
     }
 
     async fn get_resource(&self, id: uuid::Uuid) -> Result<ResourceResponse, String> {
         let resource = sqlx::query!(
             r#"
-            SELECT id, plan_id, max, name, description, created_at
-            FROM resources
-            WHERE id = $1
+            SELECT pl.id, pl.plan_id, rt.code AS resource_type, pl.max, pl.name, pl.description, pl.created_at
+            FROM plan_limits AS pl
+            INNER JOIN resource_types AS rt ON pl.resource_type_id = rt.id
+            WHERE pl.id = $1
             "#,
             id
         )
@@ -70,6 +122,7 @@ impl ResourceServiceImpl for ResourceService {
         Ok(ResourceResponse {
             id: resource.id,
             plan_id: resource.plan_id,
+            resource_type: resource.resource_type,
             max: resource.max,
             name: resource.name,
             description: resource.description.unwrap_or_default(),
@@ -83,9 +136,10 @@ impl ResourceServiceImpl for ResourceService {
     ) -> Result<Vec<ResourceResponse>, String> {
         let resources = sqlx::query!(
             r#"
-            SELECT id, plan_id, max, name, description, created_at
-            FROM resources
-            WHERE plan_id = $1
+            SELECT pl.id, pl.plan_id, rt.code AS resource_type, pl.max, pl.name, pl.description, pl.created_at
+            FROM plan_limits AS pl
+            INNER JOIN resource_types AS rt ON pl.resource_type_id = rt.id
+            WHERE pl.plan_id = $1
             "#,
             plan_id
         )
@@ -101,6 +155,7 @@ impl ResourceServiceImpl for ResourceService {
             .map(|resource| ResourceResponse {
                 id: resource.id,
                 plan_id: resource.plan_id,
+                resource_type: resource.resource_type,
                 max: resource.max,
                 name: resource.name,
                 description: resource.description.unwrap_or_default(),
@@ -114,13 +169,19 @@ impl ResourceServiceImpl for ResourceService {
         id: uuid::Uuid,
         resource: CreateResourceRequest,
     ) -> Result<(), String> {
-        sqlx::query!(
+        let resource_type = resource
+            .resource_type
+            .unwrap_or_else(|| legacy_resource_type(&resource.name));
+
+        let updated = sqlx::query!(
             r#"
-            UPDATE resources
-            SET plan_id = $1, max = $2, name = $3, description = $4
-            WHERE id = $5
+            UPDATE plan_limits
+            SET plan_id = $1, resource_type_id = rt.id, max = $3, hard_limit = GREATEST(hard_limit, $3), name = $4, description = $5, updated_at = CURRENT_TIMESTAMP
+            FROM resource_types AS rt
+            WHERE rt.code = $2 AND plan_limits.id = $6
             "#,
             resource.plan_id,
+            resource_type,
             resource.max,
             resource.name,
             resource.description,
@@ -133,14 +194,19 @@ impl ResourceServiceImpl for ResourceService {
             "Failed to update resource".to_string()
         })?;
 
+        if updated.rows_affected() == 0 {
+            return Err("Failed to update resource".to_string());
+        }
+
         Ok(())
     }
 
     async fn get_resources(&self) -> Result<Vec<ResourceResponse>, String> {
         let resources = sqlx::query!(
             r#"
-            SELECT id, plan_id, max, name, description, created_at
-            FROM resources
+            SELECT pl.id, pl.plan_id, rt.code AS resource_type, pl.max, pl.name, pl.description, pl.created_at
+            FROM plan_limits AS pl
+            INNER JOIN resource_types AS rt ON pl.resource_type_id = rt.id
             "#,
         )
         .fetch_all(&self.pool)
@@ -155,6 +221,7 @@ impl ResourceServiceImpl for ResourceService {
             .map(|resource| ResourceResponse {
                 id: resource.id,
                 plan_id: resource.plan_id,
+                resource_type: resource.resource_type,
                 max: resource.max,
                 name: resource.name,
                 description: resource.description.unwrap_or_default(),
@@ -162,4 +229,252 @@ impl ResourceServiceImpl for ResourceService {
             })
             .collect())
     }
+
+    async fn create_resource_type(
+        &self,
+        resource_type: CreateResourceTypeRequest,
+    ) -> Result<ResourceTypeResponse, String> {
+        let resource_type = sqlx::query!(
+            r#"
+            INSERT INTO resource_types (code, name, description, is_metered)
+            VALUES ($1, $2, $3, $4)
+            RETURNING id, code, name, description, is_metered, created_at
+            "#,
+            resource_type.code,
+            resource_type.name,
+            resource_type.description,
+            resource_type.is_metered.unwrap_or_default()
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to create resource type: {:?}", e);
+            "Failed to create resource type".to_string()
+        })?;
+
+        Ok(ResourceTypeResponse {
+            id: resource_type.id,
+            code: resource_type.code,
+            name: resource_type.name,
+            description: resource_type.description.unwrap_or_default(),
+            is_metered: resource_type.is_metered,
+            created_at: resource_type.created_at,
+        })
+    }
+
+    async fn get_resource_types(&self) -> Result<Vec<ResourceTypeResponse>, String> {
+        let resource_types = sqlx::query!(
+            r#"
+            SELECT id, code, name, description, is_metered, created_at
+            FROM resource_types
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get resource types: {:?}", e);
+            "Failed to get resource types".to_string()
+        })?;
+
+        Ok(resource_types
+            .into_iter()
+            .map(|resource_type| ResourceTypeResponse {
+                id: resource_type.id,
+                code: resource_type.code,
+                name: resource_type.name,
+                description: resource_type.description.unwrap_or_default(),
+                is_metered: resource_type.is_metered,
+                created_at: resource_type.created_at,
+            })
+            .collect())
+    }
+
+    async fn get_plan_limits(&self, plan_id: uuid::Uuid) -> Result<Vec<PlanLimitResponse>, String> {
+        let limits = sqlx::query!(
+            r#"
+            SELECT pl.plan_id, rt.code AS resource_type, pl.max, pl.hard_limit, pl.overage_unit_price
+            FROM plan_limits AS pl
+            INNER JOIN resource_types AS rt ON pl.resource_type_id = rt.id
+            WHERE pl.plan_id = $1
+            "#,
+            plan_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get plan limits: {:?}", e);
+            "Failed to get plan limits".to_string()
+        })?;
+
+        Ok(limits
+            .into_iter()
+            .map(|limit| PlanLimitResponse {
+                plan_id: limit.plan_id,
+                resource_type: limit.resource_type,
+                max: limit.max,
+                hard_limit: limit.hard_limit,
+                overage_unit_price: limit.overage_unit_price,
+            })
+            .collect())
+    }
+
+    async fn get_plan_limit(
+        &self,
+        plan_id: uuid::Uuid,
+        resource_type: &str,
+    ) -> Result<Option<PlanLimitResponse>, String> {
+        let limit = sqlx::query!(
+            r#"
+            SELECT pl.plan_id, rt.code AS resource_type, pl.max, pl.hard_limit, pl.overage_unit_price
+            FROM plan_limits AS pl
+            INNER JOIN resource_types AS rt ON pl.resource_type_id = rt.id
+            WHERE pl.plan_id = $1 AND rt.code = $2
+            "#,
+            plan_id,
+            resource_type
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get plan limit: {:?}", e);
+            "Failed to get plan limit".to_string()
+        })?;
+
+        Ok(limit.map(|limit| PlanLimitResponse {
+            plan_id: limit.plan_id,
+            resource_type: limit.resource_type,
+            max: limit.max,
+            hard_limit: limit.hard_limit,
+            overage_unit_price: limit.overage_unit_price,
+        }))
+    }
+
+    async fn set_plan_limit(
+        &self,
+        plan_id: uuid::Uuid,
+        resource_type: &str,
+        limit: SetPlanLimitRequest,
+    ) -> Result<PlanLimitResponse, String> {
+        let limit = sqlx::query!(
+            r#"
+            INSERT INTO plan_limits (plan_id, resource_type_id, max, hard_limit, overage_unit_price, name, description)
+            SELECT p.id, rt.id, $3, $4, $5, regexp_replace(rt.code, 's$', '') || '_' || lower(p.name) || '_resource', $6
+            FROM plans AS p, resource_types AS rt
+            WHERE p.id = $1 AND rt.code = $2
+            ON CONFLICT (plan_id, resource_type_id)
+            DO UPDATE SET max = EXCLUDED.max, hard_limit = EXCLUDED.hard_limit, overage_unit_price = EXCLUDED.overage_unit_price,
+                description = COALESCE(EXCLUDED.description, plan_limits.description), updated_at = CURRENT_TIMESTAMP
+            RETURNING plan_id, max, hard_limit, overage_unit_price
+            "#,
+            plan_id,
+            resource_type,
+            limit.max,
+            limit.hard_limit,
+            limit.overage_unit_price.unwrap_or_default(),
+            limit.description
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to set plan limit: {:?}", e);
+            "Failed to set plan limit".to_string()
+        })?
+        .ok_or_else(|| format!("Unknown plan or resource type: {}", resource_type))?;
+
+        Ok(PlanLimitResponse {
+            plan_id: limit.plan_id,
+            resource_type: resource_type.to_string(),
+            max: limit.max,
+            hard_limit: limit.hard_limit,
+            overage_unit_price: limit.overage_unit_price,
+        })
+    }
+
+    async fn get_effective_limits(
+        &self,
+        user_id: uuid::Uuid,
+        plan_id: Option<uuid::Uuid>,
+    ) -> Result<Vec<EffectiveLimitResponse>, String> {
+        let limits = sqlx::query!(
+            r#"
+            SELECT rt.id AS resource_type_id, rt.code AS resource_type,
+                pl.id AS "plan_limit_id?", pl.name AS "plan_limit_name?", pl.max AS "plan_max?",
+                pl.hard_limit AS plan_hard_limit, pl.overage_unit_price AS "overage_unit_price?",
+                o.id AS "override_id?", o.max AS "override_max?", o.hard_limit AS "override_hard_limit?",
+                ad.quantity AS "addon_quantity!"
+            FROM resource_types AS rt
+            LEFT JOIN plan_limits AS pl ON pl.resource_type_id = rt.id AND pl.plan_id = $2
+            LEFT JOIN LATERAL (
+                SELECT id, max, hard_limit
+                FROM quota_overrides
+                WHERE user_id = $1
+                  AND resource_type_id = rt.id
+                  AND revoked_at IS NULL
+                  AND (expires_at IS NULL OR expires_at > CURRENT_TIMESTAMP)
+                ORDER BY created_at DESC
+                LIMIT 1
+            ) AS o ON TRUE
+            LEFT JOIN LATERAL (
+                SELECT COALESCE(SUM(a.quantity * sa.quantity), 0)::BIGINT AS quantity
+                FROM subscription_addons AS sa
+                INNER JOIN addons AS a ON a.id = sa.addon_id
+                INNER JOIN subscriptions AS s ON s.id = sa.subscription_id
+                WHERE s.user_id = $1
+                  AND s.status NOT IN ('canceled', 'expired')
+                  AND a.resource_type_id = rt.id
+            ) AS ad ON TRUE
+            WHERE pl.id IS NOT NULL OR o.id IS NOT NULL
+            ORDER BY rt.code
+            "#,
+            user_id,
+            plan_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get effective limits: {:?}", e);
+            "Failed to get effective limits".to_string()
+        })?;
+
+        Ok(limits
+            .into_iter()
+            .map(|limit| {
+                let name = limit
+                    .plan_limit_name
+                    .unwrap_or_else(|| limit.resource_type.clone());
+                let overage_unit_price = limit.overage_unit_price.unwrap_or_default();
+                // Add-on packs raise the limit on top of the plan or override.
+                let addon_quantity = limit.addon_quantity;
+
+                match (limit.override_id, limit.override_max) {
+                    (Some(override_id), Some(max)) => EffectiveLimitResponse {
+                        id: limit.plan_limit_id.unwrap_or(override_id),
+                        name,
+                        resource_type_id: limit.resource_type_id,
+                        resource_type: limit.resource_type,
+                        max: max + addon_quantity,
+                        hard_limit: limit
+                            .override_hard_limit
+                            .map(|hard_limit| hard_limit + addon_quantity),
+                        overage_unit_price,
+                        source: LimitSource::Override,
+                        addon_quantity,
+                    },
+                    _ => EffectiveLimitResponse {
+                        id: limit.plan_limit_id.unwrap_or_default(),
+                        name,
+                        resource_type_id: limit.resource_type_id,
+                        resource_type: limit.resource_type,
+                        max: limit.plan_max.unwrap_or_default() + addon_quantity,
+                        hard_limit: limit
+                            .plan_hard_limit
+                            .map(|hard_limit| hard_limit + addon_quantity),
+                        overage_unit_price,
+                        source: LimitSource::Plan,
+                        addon_quantity,
+                    },
+                }
+            })
+            .collect())
+    }
 }
@@ -1,4 +1,14 @@
-use crate::domain::dtos::role_dtos::{CreateRoleRequest, RoleResponse};
+use axum::http::StatusCode;
+
+use crate::{
+    domain::{
+        dtos::role_dtos::{CreateRoleRequest, RoleResponse},
+        models::resource_type_model,
+    },
+    infra::events,
+};
+
+use super::quota_service::{QuotaService, QuotaServiceImpl};
 
 pub struct RoleService {
     pub pool: sqlx::PgPool,
@@ -7,7 +17,8 @@ pub struct RoleService {
 pub trait RoleServiceImpl {
     fn new(pool: sqlx::PgPool) -> Self;
 
-    async fn create_role(&self, role: CreateRoleRequest) -> Result<(), String>;
+    /// Creates a role counted against the `ROLES` quota of its creator.
+    async fn create_role(&self, role: CreateRoleRequest) -> Result<(), (StatusCode, String)>;
 
     async fn update_role(&self, role: CreateRoleRequest) -> Result<(), String>;
 
@@ -22,7 +33,21 @@ impl RoleServiceImpl for RoleService {
         Self { pool }
     }
 
-    async fn create_role(&self, role: CreateRoleRequest) -> Result<(), String> {
+    async fn create_role(&self, role: CreateRoleRequest) -> Result<(), (StatusCode, String)> {
+        let internal_error = |e: sqlx::Error| {
+            tracing::error!("Failed to create role: {:?}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to create role".to_string(),
+            )
+        };
+
+        let mut tx = self.pool.begin().await.map_err(internal_error)?;
+
+        let crossed = QuotaService::new(self.pool.clone())
+            .consume(&mut tx, role.created_by, resource_type_model::ROLES, 1)
+            .await?;
+
         sqlx::query!(
             r#"
             INSERT INTO roles (name, description, created_by)
@@ -33,12 +58,13 @@ impl RoleServiceImpl for RoleService {
             role.description,
             role.created_by
         )
-        .fetch_one(&self.pool)
+        .fetch_one(&mut *tx)
         .await
-        .map_err(|e| {
-            tracing::error!("Failed to create role: {:?}", e);
-            "Failed to create role".to_string()
-        })?;
+        .map_err(internal_error)?;
+
+        tx.commit().await.map_err(internal_error)?;
+
+        crossed.into_iter().for_each(events::publish);
 
         Ok(())
     }
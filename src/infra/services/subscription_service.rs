@@ -1,12 +1,27 @@
-use crate::domain::dtos::{
-    plan_dtos::PlanResponse,
-    subscription_dtos::{
-        CreateSubscriptionRequest, SubscriptionForSysResponse, SubscriptionResponse,
+use axum::http::StatusCode;
+
+use crate::domain::{
+    dtos::{
+        pagination_dtos::{PageResponse, PaginationQuery},
+        plan_dtos::PlanResponse,
+        subscription_dtos::{
+            CancelSubscriptionRequest, CreateSubscriptionRequest, PauseSubscriptionRequest,
+            PendingCancellationResponse, SeatResponse, SubscriptionEventResponse,
+            SubscriptionForSysResponse, SubscriptionResponse,
+        },
+        user_dtos::UserResponse,
+    },
+    models::{
+        invoice_model,
+        money_model::Money,
+        plan_model::BillingPeriod,
+        subscription_model::{Actor, SubscriptionStatus},
     },
-    user_dtos::UserResponse,
 };
 
 use super::{
+    coupon_service::CouponService,
+    invoice_service::InvoiceService,
     plan_service::{PlanService, PlanServiceImpl},
     user_service::{UserService, UserServiceImpl},
 };
@@ -15,26 +30,523 @@ pub struct SubscriptionService {
     pub pool: sqlx::PgPool,
 }
 
+/// What a subscription pays per billing period.
+pub struct BillingTerms {
+    pub price: Money,
+    pub period: BillingPeriod,
+}
+
+/// A subscription locked by `SubscriptionService::lock_own`.
+pub struct OwnSubscription {
+    pub plan_id: uuid::Uuid,
+    pub plan_version_id: Option<uuid::Uuid>,
+    pub plan_price_id: Option<uuid::Uuid>,
+    pub status: SubscriptionStatus,
+    pub start_date: Option<chrono::NaiveDateTime>,
+    pub end_date: Option<chrono::NaiveDateTime>,
+    pub cancel_at_period_end: bool,
+}
+
 pub trait SubscriptionServiceImpl {
     fn new(pool: sqlx::PgPool) -> Self;
 
-    async fn activate_subscription(&self, subscription_id: uuid::Uuid) -> Result<(), String>;
+    async fn activate_subscription(
+        &self,
+        subscription_id: uuid::Uuid,
+        actor: Actor,
+        reason: Option<String>,
+    ) -> Result<SubscriptionResponse, (StatusCode, String)>;
+
+    async fn deactivate_subscription(
+        &self,
+        subscription_id: uuid::Uuid,
+        actor: Actor,
+        reason: Option<String>,
+    ) -> Result<SubscriptionResponse, (StatusCode, String)>;
+
+    async fn update_status(
+        &self,
+        subscription_id: uuid::Uuid,
+        status: SubscriptionStatus,
+        actor: Actor,
+        reason: Option<String>,
+    ) -> Result<SubscriptionResponse, (StatusCode, String)>;
+
+    async fn expire_trials(&self) -> Result<u64, String>;
+
+    async fn expire_lapsed(&self) -> Result<u64, String>;
+
+    async fn renew_due(&self) -> Result<u64, String>;
+
+    async fn past_due(&self) -> Result<u64, String>;
 
-    async fn deactivate_subscription(&self, subscription_id: uuid::Uuid) -> Result<(), String>;
+    async fn cancel_subscription(
+        &self,
+        subscription_id: uuid::Uuid,
+        user_id: uuid::Uuid,
+        cancellation: CancelSubscriptionRequest,
+    ) -> Result<SubscriptionResponse, (StatusCode, String)>;
+
+    async fn reactivate_subscription(
+        &self,
+        subscription_id: uuid::Uuid,
+        user_id: uuid::Uuid,
+    ) -> Result<SubscriptionResponse, (StatusCode, String)>;
+
+    async fn cancel_due(&self) -> Result<u64, String>;
+
+    async fn pause_subscription(
+        &self,
+        subscription_id: uuid::Uuid,
+        user_id: uuid::Uuid,
+        pause: PauseSubscriptionRequest,
+    ) -> Result<SubscriptionResponse, (StatusCode, String)>;
+
+    async fn resume_subscription(
+        &self,
+        subscription_id: uuid::Uuid,
+        user_id: uuid::Uuid,
+    ) -> Result<SubscriptionResponse, (StatusCode, String)>;
+
+    async fn resume_due(&self) -> Result<u64, String>;
+
+    async fn get_pending_cancellations(&self) -> Result<Vec<PendingCancellationResponse>, String>;
+
+    async fn get_subscription_events(
+        &self,
+        subscription_id: uuid::Uuid,
+    ) -> Result<Vec<SubscriptionEventResponse>, String>;
+
+    async fn get_seats(
+        &self,
+        subscription_id: uuid::Uuid,
+        user_id: uuid::Uuid,
+    ) -> Result<Vec<SeatResponse>, (StatusCode, String)>;
+
+    async fn release_seat(
+        &self,
+        subscription_id: uuid::Uuid,
+        seat_user_id: uuid::Uuid,
+        user_id: uuid::Uuid,
+    ) -> Result<(), (StatusCode, String)>;
 
     async fn create_subscription(
         &self,
         subscription: CreateSubscriptionRequest,
-    ) -> Result<SubscriptionResponse, String>;
+        actor: Actor,
+    ) -> Result<SubscriptionResponse, (StatusCode, String)>;
 
     async fn get_subscriptions(&self) -> Result<Vec<SubscriptionForSysResponse>, String>;
 
     async fn get_subscription(&self, id: uuid::Uuid) -> Result<SubscriptionResponse, String>;
 
+    async fn get_current_subscription(
+        &self,
+        user_id: uuid::Uuid,
+    ) -> Result<Option<SubscriptionResponse>, String>;
+
     async fn get_subscriptions_for_by_username(
         &self,
         username: String,
-    ) -> Result<Vec<SubscriptionResponse>, String>;
+        pagination: PaginationQuery,
+    ) -> Result<PageResponse<SubscriptionResponse>, String>;
+}
+
+/// The billing period following one that ended at `ended_at`, skipping
+/// periods that already passed by `now`.
+fn next_period(
+    ended_at: chrono::NaiveDateTime,
+    now: chrono::NaiveDateTime,
+    period: BillingPeriod,
+) -> (chrono::NaiveDateTime, chrono::NaiveDateTime) {
+    // Periods are counted from `ended_at` so month ends don't drift.
+    let mut n = 0;
+
+    while period.nth(ended_at, n + 1) <= now {
+        n += 1;
+    }
+
+    (period.nth(ended_at, n), period.nth(ended_at, n + 1))
+}
+
+fn internal_error(e: sqlx::Error) -> (StatusCode, String) {
+    tracing::error!("Failed to update subscription status: {:?}", e);
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "Failed to update subscription status".to_string(),
+    )
+}
+
+impl SubscriptionService {
+    /// The user whose subscription `user_id` is covered by: the owner of the
+    /// group subscription they hold a seat on, or `user_id` itself.
+    pub async fn subscriber_id(
+        executor: impl sqlx::PgExecutor<'_>,
+        user_id: uuid::Uuid,
+    ) -> Result<uuid::Uuid, String> {
+        let owner_id = sqlx::query_scalar!(
+            r#"
+            SELECT s.user_id AS "user_id!"
+            FROM subscription_seats AS ss
+            INNER JOIN subscriptions AS s ON s.id = ss.subscription_id
+            WHERE ss.user_id = $1 AND ss.released_at IS NULL
+            "#,
+            user_id
+        )
+        .fetch_optional(executor)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get seat: {:?}", e);
+            "Failed to get seat".to_string()
+        })?;
+
+        Ok(owner_id.unwrap_or(user_id))
+    }
+
+    /// Makes a subscription active on `conn` once its invoice is paid. The
+    /// first payment starts the paid `period`, a renewal already rolled it.
+    pub async fn convert_to_paid(
+        conn: &mut sqlx::PgConnection,
+        subscription_id: uuid::Uuid,
+        actor: Actor,
+        period: Option<(chrono::NaiveDateTime, chrono::NaiveDateTime)>,
+    ) -> Result<SubscriptionResponse, (StatusCode, String)> {
+        let subscription = Self::transition(
+            conn,
+            subscription_id,
+            SubscriptionStatus::Active,
+            actor,
+            Some("Payment succeeded".to_string()),
+        )
+        .await?;
+
+        let Some((start_date, end_date)) = period else {
+            return Ok(subscription);
+        };
+
+        sqlx::query!(
+            r#"
+            UPDATE subscriptions SET start_date = $1, end_date = $2 WHERE id = $3
+            "#,
+            start_date,
+            end_date,
+            subscription_id
+        )
+        .execute(&mut *conn)
+        .await
+        .map_err(internal_error)?;
+
+        Ok(SubscriptionResponse {
+            start_date: Some(start_date),
+            end_date: Some(end_date),
+            ..subscription
+        })
+    }
+
+    /// Moves an active subscription to past due on `conn` when `invoice_id`
+    /// is the open renewal invoice of its current period, which starts the
+    /// dunning of the invoice. Returns whether it moved.
+    pub async fn mark_past_due(
+        conn: &mut sqlx::PgConnection,
+        subscription_id: uuid::Uuid,
+        invoice_id: uuid::Uuid,
+        reason: String,
+    ) -> Result<bool, (StatusCode, String)> {
+        let renewal = sqlx::query_scalar!(
+            r#"
+            SELECT i.id
+            FROM invoices AS i
+            INNER JOIN subscriptions AS s ON s.id = i.subscription_id
+            WHERE i.id = $1 AND i.subscription_id = $2 AND i.status = 'open' AND i.kind = 'period'
+              AND i.period_start = s.start_date AND s.status = $3
+            FOR UPDATE OF i, s
+            "#,
+            invoice_id,
+            subscription_id,
+            SubscriptionStatus::Active.as_str()
+        )
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(internal_error)?;
+
+        if renewal.is_none() {
+            return Ok(false);
+        }
+
+        Self::transition(
+            conn,
+            subscription_id,
+            SubscriptionStatus::PastDue,
+            Actor::System,
+            Some(reason),
+        )
+        .await?;
+
+        sqlx::query!(
+            r#"
+            UPDATE invoices SET past_due_at = CURRENT_TIMESTAMP WHERE id = $1
+            "#,
+            invoice_id
+        )
+        .execute(&mut *conn)
+        .await
+        .map_err(internal_error)?;
+
+        Ok(true)
+    }
+
+    /// Moves a subscription to `status` on `conn`, validating the transition
+    /// and recording it in `subscription_events`. The open invoices of a
+    /// subscription that is canceled or expires are voided.
+    pub async fn transition(
+        conn: &mut sqlx::PgConnection,
+        subscription_id: uuid::Uuid,
+        status: SubscriptionStatus,
+        actor: Actor,
+        reason: Option<String>,
+    ) -> Result<SubscriptionResponse, (StatusCode, String)> {
+        let current = sqlx::query!(
+            r#"
+            SELECT status FROM subscriptions WHERE id = $1 FOR UPDATE
+            "#,
+            subscription_id
+        )
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Subscription not found".to_string()))?;
+
+        let current: SubscriptionStatus = current
+            .status
+            .parse()
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+        if !current.can_transition_to(status) {
+            return Err((
+                StatusCode::CONFLICT,
+                format!("Cannot change subscription from {} to {}", current, status),
+            ));
+        }
+
+        let subscription = sqlx::query!(
+            r#"
+            UPDATE subscriptions
+            SET status = $1
+            WHERE id = $2
+            RETURNING id, user_id, plan_id, plan_price_id, plan_version_id, start_date, end_date, trial_start_date, trial_end_date, cancel_at_period_end
+            "#,
+            status.as_str(),
+            subscription_id,
+        )
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(internal_error)?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO subscription_events (subscription_id, from_status, to_status, actor_type, actor_id, reason)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+            subscription_id,
+            current.as_str(),
+            status.as_str(),
+            actor.actor_type(),
+            actor.actor_id(),
+            reason
+        )
+        .execute(&mut *conn)
+        .await
+        .map_err(internal_error)?;
+
+        if matches!(
+            status,
+            SubscriptionStatus::Canceled | SubscriptionStatus::Expired
+        ) {
+            InvoiceService::void_open(conn, subscription_id, format!("Subscription {}", status))
+                .await?;
+        }
+
+        Ok(SubscriptionResponse {
+            id: subscription.id,
+            user_id: subscription.user_id,
+            plan_id: subscription.plan_id,
+            plan_price_id: subscription.plan_price_id,
+            plan_version_id: subscription.plan_version_id,
+            start_date: subscription.start_date,
+            end_date: subscription.end_date,
+            trial_start_date: subscription.trial_start_date,
+            trial_end_date: subscription.trial_end_date,
+            status,
+            is_active: status.is_active(),
+            cancel_at_period_end: subscription.cancel_at_period_end,
+        })
+    }
+
+    /// Records a change that keeps the status, such as scheduling a cancellation.
+    async fn record_event(
+        conn: &mut sqlx::PgConnection,
+        subscription_id: uuid::Uuid,
+        status: SubscriptionStatus,
+        actor: Actor,
+        reason: &str,
+    ) -> Result<(), (StatusCode, String)> {
+        sqlx::query!(
+            r#"
+            INSERT INTO subscription_events (subscription_id, from_status, to_status, actor_type, actor_id, reason)
+            VALUES ($1, $2, $2, $3, $4, $5)
+            "#,
+            subscription_id,
+            status.as_str(),
+            actor.actor_type(),
+            actor.actor_id(),
+            reason
+        )
+        .execute(&mut *conn)
+        .await
+        .map_err(internal_error)?;
+
+        Ok(())
+    }
+
+    /// Price and billing period of `plan_id` at `plan_version_id` (the current
+    /// version when `None`), or of its price `plan_price_id` when set. Inactive
+    /// prices are refused when `selecting` a new price but still apply to
+    /// subscriptions that already use them.
+    pub async fn billing_terms(
+        conn: &mut sqlx::PgConnection,
+        plan_id: uuid::Uuid,
+        plan_version_id: Option<uuid::Uuid>,
+        plan_price_id: Option<uuid::Uuid>,
+        selecting: bool,
+    ) -> Result<BillingTerms, (StatusCode, String)> {
+        let terms = sqlx::query!(
+            r#"
+            SELECT pp.id AS "plan_price_id?",
+                COALESCE(pp.price, v.price) AS "price!", COALESCE(pp.currency, p.currency) AS "currency!",
+                COALESCE(pp.billing_interval, v.billing_interval) AS "billing_interval!",
+                COALESCE(pp.billing_interval_count, v.billing_interval_count) AS "billing_interval_count!"
+            FROM plans AS p
+            INNER JOIN plan_versions AS v ON v.id = COALESCE($4, p.current_version_id) AND v.plan_id = p.id
+            LEFT JOIN plan_prices AS pp ON pp.id = $2 AND pp.plan_id = p.id AND (pp.is_active OR NOT $3)
+            WHERE p.id = $1
+            "#,
+            plan_id,
+            plan_price_id,
+            selecting,
+            plan_version_id
+        )
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Plan not found".to_string()))?;
+
+        if plan_price_id.is_some() && terms.plan_price_id.is_none() {
+            return Err((
+                StatusCode::NOT_FOUND,
+                "Price not found for this plan".to_string(),
+            ));
+        }
+
+        Ok(BillingTerms {
+            price: Money::parse(terms.price, &terms.currency)
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?,
+            period: BillingPeriod::new(&terms.billing_interval, terms.billing_interval_count)
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?,
+        })
+    }
+
+    /// Ends the open pause of a paused subscription and pushes its end date
+    /// back by the time it was paused.
+    async fn resume(
+        conn: &mut sqlx::PgConnection,
+        subscription_id: uuid::Uuid,
+        actor: Actor,
+        reason: &str,
+    ) -> Result<SubscriptionResponse, (StatusCode, String)> {
+        let subscription = Self::transition(
+            &mut *conn,
+            subscription_id,
+            SubscriptionStatus::Active,
+            actor,
+            Some(reason.to_string()),
+        )
+        .await?;
+
+        let now = chrono::Utc::now().naive_utc();
+
+        let paused_at = sqlx::query_scalar!(
+            r#"
+            UPDATE subscription_pauses SET resumed_at = $1
+            WHERE subscription_id = $2 AND resumed_at IS NULL
+            RETURNING paused_at
+            "#,
+            now,
+            subscription_id
+        )
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(internal_error)?;
+
+        let Some(paused_at) = paused_at else {
+            return Ok(subscription);
+        };
+
+        let end_date = subscription
+            .end_date
+            .map(|end_date| end_date + (now - paused_at));
+
+        sqlx::query!(
+            r#"
+            UPDATE subscriptions SET end_date = $1 WHERE id = $2
+            "#,
+            end_date,
+            subscription_id
+        )
+        .execute(&mut *conn)
+        .await
+        .map_err(internal_error)?;
+
+        Ok(SubscriptionResponse {
+            end_date,
+            ..subscription
+        })
+    }
+
+    /// Locks a subscription owned by `user_id`, others are reported as not found.
+    pub async fn lock_own(
+        conn: &mut sqlx::PgConnection,
+        subscription_id: uuid::Uuid,
+        user_id: uuid::Uuid,
+    ) -> Result<OwnSubscription, (StatusCode, String)> {
+        let subscription = sqlx::query!(
+            r#"
+            SELECT plan_id AS "plan_id!", plan_version_id, plan_price_id, status, start_date, end_date,
+                cancel_at_period_end
+            FROM subscriptions
+            WHERE id = $1 AND user_id = $2
+            FOR UPDATE
+            "#,
+            subscription_id,
+            user_id
+        )
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Subscription not found".to_string()))?;
+
+        Ok(OwnSubscription {
+            plan_id: subscription.plan_id,
+            plan_version_id: subscription.plan_version_id,
+            plan_price_id: subscription.plan_price_id,
+            status: subscription
+                .status
+                .parse()
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?,
+            start_date: subscription.start_date,
+            end_date: subscription.end_date,
+            cancel_at_period_end: subscription.cancel_at_period_end,
+        })
+    }
 }
 
 impl SubscriptionServiceImpl for SubscriptionService {
@@ -42,91 +554,946 @@ impl SubscriptionServiceImpl for SubscriptionService {
         Self { pool }
     }
 
-    async fn activate_subscription(&self, subscription_id: uuid::Uuid) -> Result<(), String> {
+    async fn activate_subscription(
+        &self,
+        subscription_id: uuid::Uuid,
+        actor: Actor,
+        reason: Option<String>,
+    ) -> Result<SubscriptionResponse, (StatusCode, String)> {
+        self.update_status(subscription_id, SubscriptionStatus::Active, actor, reason)
+            .await
+    }
+
+    async fn deactivate_subscription(
+        &self,
+        subscription_id: uuid::Uuid,
+        actor: Actor,
+        reason: Option<String>,
+    ) -> Result<SubscriptionResponse, (StatusCode, String)> {
+        self.update_status(subscription_id, SubscriptionStatus::Canceled, actor, reason)
+            .await
+    }
+
+    async fn update_status(
+        &self,
+        subscription_id: uuid::Uuid,
+        status: SubscriptionStatus,
+        actor: Actor,
+        reason: Option<String>,
+    ) -> Result<SubscriptionResponse, (StatusCode, String)> {
+        let mut tx = self.pool.begin().await.map_err(internal_error)?;
+
+        let subscription =
+            Self::transition(&mut tx, subscription_id, status, actor, reason).await?;
+
+        tx.commit().await.map_err(internal_error)?;
+
+        Ok(subscription)
+    }
+
+    async fn expire_trials(&self) -> Result<u64, String> {
+        let expired = sqlx::query_scalar!(
+            r#"
+            SELECT id FROM subscriptions
+            WHERE status = $1 AND trial_end_date <= CURRENT_TIMESTAMP AND NOT cancel_at_period_end
+            "#,
+            SubscriptionStatus::Trialing.as_str()
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get ended trials: {:?}", e);
+            "Failed to get ended trials".to_string()
+        })?;
+
+        let mut count = 0;
+
+        for id in expired {
+            match self
+                .update_status(
+                    id,
+                    SubscriptionStatus::Expired,
+                    Actor::System,
+                    Some("Trial ended without payment".to_string()),
+                )
+                .await
+            {
+                Ok(_) => count += 1,
+                // Converted or canceled in the meantime.
+                Err((StatusCode::CONFLICT, _)) => {}
+                Err((_, e)) => return Err(e),
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// Past due subscriptions are left to dunning, which cancels them at the
+    /// end of the grace period.
+    async fn expire_lapsed(&self) -> Result<u64, String> {
+        let lapsed = sqlx::query_scalar!(
+            r#"
+            SELECT id FROM subscriptions
+            WHERE status = $1 AND end_date <= CURRENT_TIMESTAMP
+            "#,
+            SubscriptionStatus::Incomplete.as_str()
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get lapsed subscriptions: {:?}", e);
+            "Failed to get lapsed subscriptions".to_string()
+        })?;
+
+        let mut count = 0;
+
+        for id in lapsed {
+            match self
+                .update_status(
+                    id,
+                    SubscriptionStatus::Expired,
+                    Actor::System,
+                    Some("Period ended without payment".to_string()),
+                )
+                .await
+            {
+                Ok(_) => count += 1,
+                Err((StatusCode::CONFLICT, _)) => {}
+                Err((_, e)) => return Err(e),
+            }
+        }
+
+        Ok(count)
+    }
+
+    async fn renew_due(&self) -> Result<u64, String> {
+        let due = sqlx::query_scalar!(
+            r#"
+            SELECT s.id
+            FROM subscriptions AS s
+            INNER JOIN plans AS p ON p.id = s.plan_id
+            INNER JOIN plan_versions AS v ON v.id = COALESCE(s.plan_version_id, p.current_version_id)
+            WHERE s.status = $1 AND s.end_date <= CURRENT_TIMESTAMP AND NOT v.is_free_forever
+              AND NOT s.cancel_at_period_end
+            "#,
+            SubscriptionStatus::Active.as_str()
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get due subscriptions: {:?}", e);
+            "Failed to get due subscriptions".to_string()
+        })?;
+
+        let mut count = 0;
+
+        for id in due {
+            let mut tx = self.pool.begin().await.map_err(|e| internal_error(e).1)?;
+
+            // Rows locked by another replica are left for its run.
+            let subscription = sqlx::query!(
+                r#"
+                SELECT s.user_id AS "user_id!", s.end_date AS "end_date!", s.plan_id AS "plan_id!", s.plan_version_id,
+                    s.plan_price_id, p.name AS plan_name
+                FROM subscriptions AS s
+                INNER JOIN plans AS p ON p.id = s.plan_id
+                WHERE s.id = $1 AND s.status = $2 AND s.end_date <= CURRENT_TIMESTAMP
+                  AND NOT s.cancel_at_period_end
+                FOR UPDATE OF s SKIP LOCKED
+                "#,
+                id,
+                SubscriptionStatus::Active.as_str()
+            )
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|e| internal_error(e).1)?;
+
+            let Some(subscription) = subscription else {
+                continue;
+            };
+
+            let terms = Self::billing_terms(
+                &mut tx,
+                subscription.plan_id,
+                subscription.plan_version_id,
+                subscription.plan_price_id,
+                false,
+            )
+            .await
+            .map_err(|(_, e)| e)?;
+
+            let now = chrono::Utc::now().naive_utc();
+            let (start_date, end_date) = next_period(subscription.end_date, now, terms.period);
+
+            sqlx::query!(
+                r#"
+                UPDATE subscriptions SET start_date = $1, end_date = $2 WHERE id = $3
+                "#,
+                start_date,
+                end_date,
+                id
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| internal_error(e).1)?;
+
+            // Overage of the period that ended is billed with the next one.
+            let mut invoice = InvoiceService::period_invoice(
+                &mut tx,
+                id,
+                subscription.user_id,
+                &subscription.plan_name,
+                &terms,
+                (start_date, end_date),
+            )
+            .await
+            .map_err(|(_, e)| e)?;
+            invoice.lines.extend(
+                InvoiceService::bill_overages(&mut tx, id, start_date, terms.price.currency)
+                    .await
+                    .map_err(|(_, e)| e)?,
+            );
+
+            // The subscription stays active until the invoice is due.
+            invoice.due_at = now + chrono::Duration::days(invoice_model::PAYMENT_TERMS_DAYS);
+
+            InvoiceService::issue(&mut tx, invoice)
+                .await
+                .map_err(|(_, e)| e)?;
+
+            tx.commit().await.map_err(|e| internal_error(e).1)?;
+
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
+    /// Moves active subscriptions whose renewal invoice is unpaid past its
+    /// due date to past due.
+    async fn past_due(&self) -> Result<u64, String> {
+        let overdue = sqlx::query!(
+            r#"
+            SELECT i.id, i.subscription_id, i.number AS "number!"
+            FROM invoices AS i
+            INNER JOIN subscriptions AS s ON s.id = i.subscription_id
+            WHERE i.status = 'open' AND i.kind = 'period' AND i.past_due_at IS NULL
+              AND i.due_at <= CURRENT_TIMESTAMP AND i.period_start = s.start_date AND s.status = $1
+            "#,
+            SubscriptionStatus::Active.as_str()
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get overdue invoices: {:?}", e);
+            "Failed to get overdue invoices".to_string()
+        })?;
+
+        let mut count = 0;
+
+        for invoice in overdue {
+            let mut tx = self.pool.begin().await.map_err(|e| internal_error(e).1)?;
+
+            let moved = Self::mark_past_due(
+                &mut tx,
+                invoice.subscription_id,
+                invoice.id,
+                format!("Renewal invoice {} is overdue", invoice.number),
+            )
+            .await;
+
+            match moved {
+                Ok(moved) => {
+                    tx.commit().await.map_err(|e| internal_error(e).1)?;
+                    count += moved as u64;
+                }
+                Err((_, e)) => {
+                    tracing::error!(
+                        "Failed to move subscription {} to past due: {}",
+                        invoice.subscription_id,
+                        e
+                    );
+                }
+            }
+        }
+
+        Ok(count)
+    }
+
+    async fn cancel_subscription(
+        &self,
+        subscription_id: uuid::Uuid,
+        user_id: uuid::Uuid,
+        cancellation: CancelSubscriptionRequest,
+    ) -> Result<SubscriptionResponse, (StatusCode, String)> {
+        let mut tx = self.pool.begin().await.map_err(internal_error)?;
+
+        let subscription = Self::lock_own(&mut tx, subscription_id, user_id).await?;
+
+        if cancellation.immediately {
+            Self::transition(
+                &mut tx,
+                subscription_id,
+                SubscriptionStatus::Canceled,
+                Actor::User(user_id),
+                cancellation.reason.clone(),
+            )
+            .await?;
+        } else {
+            if !matches!(
+                subscription.status,
+                SubscriptionStatus::Active | SubscriptionStatus::Trialing
+            ) {
+                return Err((
+                    StatusCode::CONFLICT,
+                    format!(
+                        "Cannot cancel a subscription that is {} at the period end",
+                        subscription.status
+                    ),
+                ));
+            }
+
+            if subscription.end_date.is_none() {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    "The subscription has no period end, cancel it immediately".to_string(),
+                ));
+            }
+
+            Self::record_event(
+                &mut tx,
+                subscription_id,
+                subscription.status,
+                Actor::User(user_id),
+                "Scheduled to cancel at period end",
+            )
+            .await?;
+        }
+
         sqlx::query!(
             r#"
             UPDATE subscriptions
-            SET is_active = true
+            SET cancel_at_period_end = $1, canceled_at = CURRENT_TIMESTAMP, cancellation_reason = $2,
+                cancellation_feedback = $3
+            WHERE id = $4
+            "#,
+            !cancellation.immediately,
+            cancellation.reason,
+            cancellation.feedback,
+            subscription_id
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(internal_error)?;
+
+        sqlx::query!(
+            r#"
+            UPDATE subscription_plan_changes SET status = 'canceled', reason = 'Subscription canceled'
+            WHERE subscription_id = $1 AND status = 'scheduled'
+            "#,
+            subscription_id
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(internal_error)?;
+
+        tx.commit().await.map_err(internal_error)?;
+
+        self.get_subscription(subscription_id)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))
+    }
+
+    async fn reactivate_subscription(
+        &self,
+        subscription_id: uuid::Uuid,
+        user_id: uuid::Uuid,
+    ) -> Result<SubscriptionResponse, (StatusCode, String)> {
+        let mut tx = self.pool.begin().await.map_err(internal_error)?;
+
+        let subscription = Self::lock_own(&mut tx, subscription_id, user_id).await?;
+
+        if !subscription.cancel_at_period_end {
+            return Err((
+                StatusCode::CONFLICT,
+                "The subscription is not set to cancel".to_string(),
+            ));
+        }
+
+        let now = chrono::Utc::now().naive_utc();
+
+        if subscription
+            .end_date
+            .is_some_and(|end_date| end_date <= now)
+        {
+            return Err((
+                StatusCode::CONFLICT,
+                "The period has already ended".to_string(),
+            ));
+        }
+
+        sqlx::query!(
+            r#"
+            UPDATE subscriptions
+            SET cancel_at_period_end = FALSE, canceled_at = NULL, cancellation_reason = NULL,
+                cancellation_feedback = NULL
             WHERE id = $1
             "#,
+            subscription_id
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(internal_error)?;
+
+        Self::record_event(
+            &mut tx,
             subscription_id,
+            subscription.status,
+            Actor::User(user_id),
+            "Reactivated",
         )
-        .execute(&self.pool)
+        .await?;
+
+        tx.commit().await.map_err(internal_error)?;
+
+        self.get_subscription(subscription_id)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))
+    }
+
+    async fn cancel_due(&self) -> Result<u64, String> {
+        let due = sqlx::query_scalar!(
+            r#"
+            SELECT id FROM subscriptions
+            WHERE cancel_at_period_end AND status = ANY($1) AND end_date <= CURRENT_TIMESTAMP
+            "#,
+            &[
+                SubscriptionStatus::Trialing.as_str().to_string(),
+                SubscriptionStatus::Active.as_str().to_string(),
+            ]
+        )
+        .fetch_all(&self.pool)
         .await
         .map_err(|e| {
-            tracing::error!("Failed to set active subscription: {:?}", e);
-            "Failed to set active subscription".to_string()
+            tracing::error!("Failed to get due cancellations: {:?}", e);
+            "Failed to get due cancellations".to_string()
         })?;
 
-        Ok(())
+        let mut count = 0;
+
+        for id in due {
+            match self
+                .update_status(
+                    id,
+                    SubscriptionStatus::Canceled,
+                    Actor::System,
+                    Some("Canceled at period end".to_string()),
+                )
+                .await
+            {
+                Ok(_) => count += 1,
+                Err((StatusCode::CONFLICT, _)) => {}
+                Err((_, e)) => return Err(e),
+            }
+        }
+
+        Ok(count)
     }
 
-    async fn deactivate_subscription(&self, subscription_id: uuid::Uuid) -> Result<(), String> {
+    async fn pause_subscription(
+        &self,
+        subscription_id: uuid::Uuid,
+        user_id: uuid::Uuid,
+        pause: PauseSubscriptionRequest,
+    ) -> Result<SubscriptionResponse, (StatusCode, String)> {
+        if pause
+            .resume_at
+            .is_some_and(|resume_at| resume_at <= chrono::Utc::now().naive_utc())
+        {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "resume_at must be in the future".to_string(),
+            ));
+        }
+
+        let mut tx = self.pool.begin().await.map_err(internal_error)?;
+
+        let subscription = Self::lock_own(&mut tx, subscription_id, user_id).await?;
+
+        if subscription.cancel_at_period_end {
+            return Err((
+                StatusCode::CONFLICT,
+                "Reactivate the subscription before pausing it".to_string(),
+            ));
+        }
+
+        let allowance = sqlx::query!(
+            r#"
+            SELECT v.max_pauses_per_year,
+                (
+                    SELECT COUNT(*) FROM subscription_pauses
+                    WHERE subscription_id = s.id AND paused_at > CURRENT_TIMESTAMP - INTERVAL '1 year'
+                ) AS "pauses!"
+            FROM subscriptions AS s
+            INNER JOIN plans AS p ON p.id = s.plan_id
+            INNER JOIN plan_versions AS v ON v.id = COALESCE(s.plan_version_id, p.current_version_id)
+            WHERE s.id = $1
+            "#,
+            subscription_id
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(internal_error)?;
+
+        if allowance.pauses >= allowance.max_pauses_per_year as i64 {
+            return Err((
+                StatusCode::CONFLICT,
+                match allowance.max_pauses_per_year {
+                    0 => "The plan does not allow pausing".to_string(),
+                    max => format!("The plan allows {} pauses per year", max),
+                },
+            ));
+        }
+
+        Self::transition(
+            &mut tx,
+            subscription_id,
+            SubscriptionStatus::Paused,
+            Actor::User(user_id),
+            pause.reason.clone(),
+        )
+        .await?;
+
         sqlx::query!(
             r#"
-            UPDATE subscriptions
-            SET is_active = false
-            WHERE id = $1
+            INSERT INTO subscription_pauses (subscription_id, resume_at, paused_by, reason)
+            VALUES ($1, $2, $3, $4)
             "#,
             subscription_id,
+            pause.resume_at,
+            user_id,
+            pause.reason
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(internal_error)?;
+
+        tx.commit().await.map_err(internal_error)?;
+
+        self.get_subscription(subscription_id)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))
+    }
+
+    async fn resume_subscription(
+        &self,
+        subscription_id: uuid::Uuid,
+        user_id: uuid::Uuid,
+    ) -> Result<SubscriptionResponse, (StatusCode, String)> {
+        let mut tx = self.pool.begin().await.map_err(internal_error)?;
+
+        Self::lock_own(&mut tx, subscription_id, user_id).await?;
+
+        let subscription =
+            Self::resume(&mut tx, subscription_id, Actor::User(user_id), "Resumed").await?;
+
+        tx.commit().await.map_err(internal_error)?;
+
+        Ok(subscription)
+    }
+
+    async fn resume_due(&self) -> Result<u64, String> {
+        let due = sqlx::query_scalar!(
+            r#"
+            SELECT sp.subscription_id
+            FROM subscription_pauses AS sp
+            INNER JOIN subscriptions AS s ON s.id = sp.subscription_id
+            WHERE sp.resumed_at IS NULL AND sp.resume_at <= CURRENT_TIMESTAMP AND s.status = $1
+            "#,
+            SubscriptionStatus::Paused.as_str()
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get due pauses: {:?}", e);
+            "Failed to get due pauses".to_string()
+        })?;
+
+        let mut count = 0;
+
+        for id in due {
+            let mut tx = self.pool.begin().await.map_err(|e| internal_error(e).1)?;
+
+            match Self::resume(&mut tx, id, Actor::System, "Pause ended").await {
+                Ok(_) => {
+                    tx.commit().await.map_err(|e| internal_error(e).1)?;
+                    count += 1;
+                }
+                // Resumed or canceled in the meantime.
+                Err((StatusCode::CONFLICT, _)) => {}
+                Err((_, e)) => return Err(e),
+            }
+        }
+
+        Ok(count)
+    }
+
+    async fn get_pending_cancellations(&self) -> Result<Vec<PendingCancellationResponse>, String> {
+        let subscriptions = sqlx::query!(
+            r#"
+            SELECT s.id, u.id AS user_id, u.username, u.email, p.id AS plan_id, p.name AS plan_name, s.status,
+                s.end_date AS "end_date!", s.canceled_at, s.cancellation_reason, s.cancellation_feedback
+            FROM subscriptions AS s
+            INNER JOIN users AS u ON u.id = s.user_id
+            INNER JOIN plans AS p ON p.id = s.plan_id
+            WHERE s.cancel_at_period_end AND s.is_active AND s.end_date IS NOT NULL
+            ORDER BY s.end_date
+            "#,
         )
-        .execute(&self.pool)
+        .fetch_all(&self.pool)
         .await
         .map_err(|e| {
-            tracing::error!("Failed to set active subscription: {:?}", e);
-            "Failed to set active subscription".to_string()
+            tracing::error!("Failed to get pending cancellations: {:?}", e);
+            "Failed to get pending cancellations".to_string()
         })?;
 
+        subscriptions
+            .into_iter()
+            .map(|subscription| {
+                Ok(PendingCancellationResponse {
+                    subscription_id: subscription.id,
+                    user_id: subscription.user_id,
+                    username: subscription.username,
+                    email: subscription.email,
+                    plan_id: subscription.plan_id,
+                    plan_name: subscription.plan_name,
+                    status: subscription.status.parse()?,
+                    end_date: subscription.end_date,
+                    canceled_at: subscription.canceled_at,
+                    cancellation_reason: subscription.cancellation_reason,
+                    cancellation_feedback: subscription.cancellation_feedback,
+                })
+            })
+            .collect()
+    }
+
+    async fn get_subscription_events(
+        &self,
+        subscription_id: uuid::Uuid,
+    ) -> Result<Vec<SubscriptionEventResponse>, String> {
+        let events = sqlx::query!(
+            r#"
+            SELECT id, subscription_id, from_status, to_status, actor_type, actor_id, reason, created_at
+            FROM subscription_events
+            WHERE subscription_id = $1
+            ORDER BY created_at
+            "#,
+            subscription_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get subscription events: {:?}", e);
+            "Failed to get subscription events".to_string()
+        })?;
+
+        Ok(events
+            .into_iter()
+            .map(|event| SubscriptionEventResponse {
+                id: event.id,
+                subscription_id: event.subscription_id,
+                from_status: event.from_status,
+                to_status: event.to_status,
+                actor_type: event.actor_type,
+                actor_id: event.actor_id,
+                reason: event.reason,
+                created_at: event.created_at,
+            })
+            .collect())
+    }
+
+    async fn get_seats(
+        &self,
+        subscription_id: uuid::Uuid,
+        user_id: uuid::Uuid,
+    ) -> Result<Vec<SeatResponse>, (StatusCode, String)> {
+        let mut tx = self.pool.begin().await.map_err(internal_error)?;
+
+        Self::lock_own(&mut tx, subscription_id, user_id).await?;
+
+        let seats = sqlx::query!(
+            r#"
+            SELECT ss.id, ss.subscription_id, ss.user_id, u.username, u.email, ss.assigned_by, ss.assigned_at
+            FROM subscription_seats AS ss
+            INNER JOIN users AS u ON u.id = ss.user_id
+            WHERE ss.subscription_id = $1 AND ss.released_at IS NULL
+            ORDER BY ss.assigned_at
+            "#,
+            subscription_id
+        )
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(internal_error)?;
+
+        tx.commit().await.map_err(internal_error)?;
+
+        Ok(seats
+            .into_iter()
+            .map(|seat| SeatResponse {
+                id: seat.id,
+                subscription_id: seat.subscription_id,
+                user_id: seat.user_id,
+                username: seat.username,
+                email: seat.email,
+                assigned_by: seat.assigned_by,
+                assigned_at: seat.assigned_at,
+            })
+            .collect())
+    }
+
+    async fn release_seat(
+        &self,
+        subscription_id: uuid::Uuid,
+        seat_user_id: uuid::Uuid,
+        user_id: uuid::Uuid,
+    ) -> Result<(), (StatusCode, String)> {
+        let mut tx = self.pool.begin().await.map_err(internal_error)?;
+
+        Self::lock_own(&mut tx, subscription_id, user_id).await?;
+
+        let released = sqlx::query!(
+            r#"
+            UPDATE subscription_seats SET released_at = CURRENT_TIMESTAMP
+            WHERE subscription_id = $1 AND user_id = $2 AND released_at IS NULL
+            "#,
+            subscription_id,
+            seat_user_id
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(internal_error)?;
+
+        if released.rows_affected() == 0 {
+            return Err((StatusCode::NOT_FOUND, "Seat not found".to_string()));
+        }
+
+        // The child leaves the group along with the seat.
+        sqlx::query!(
+            r#"
+            DELETE FROM user_groups WHERE parent_id = $1 AND user_id = $2
+            "#,
+            user_id,
+            seat_user_id
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(internal_error)?;
+
+        tx.commit().await.map_err(internal_error)?;
+
         Ok(())
     }
 
     async fn create_subscription(
         &self,
         subscription: CreateSubscriptionRequest,
-    ) -> Result<SubscriptionResponse, String> {
+        actor: Actor,
+    ) -> Result<SubscriptionResponse, (StatusCode, String)> {
+        let internal_error = |e: sqlx::Error| {
+            tracing::error!("Failed to create subscription: {:?}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to create subscription".to_string(),
+            )
+        };
+        let already_subscribed = || {
+            (
+                StatusCode::CONFLICT,
+                "The user already has a current subscription".to_string(),
+            )
+        };
+
+        // Users subscribe themselves, sys names the user.
+        let user_id = match (actor, subscription.user_id) {
+            (Actor::User(id), None) => id,
+            (Actor::User(id), Some(user_id)) if user_id == id => id,
+            (Actor::User(_), Some(_)) => {
+                return Err((
+                    StatusCode::FORBIDDEN,
+                    "Cannot subscribe another user".to_string(),
+                ))
+            }
+            (_, Some(user_id)) => user_id,
+            (_, None) => {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    "The user to subscribe is required".to_string(),
+                ))
+            }
+        };
+
         let plan_service = PlanService::new(self.pool.clone());
 
-        let plan = plan_service.get_plan(subscription.plan_id).await?;
+        let plan = plan_service
+            .get_plan(subscription.plan_id)
+            .await
+            .map_err(|e| (StatusCode::NOT_FOUND, e))?;
+
+        let mut tx = self.pool.begin().await.map_err(internal_error)?;
+
+        let terms = Self::billing_terms(
+            &mut tx,
+            subscription.plan_id,
+            None,
+            subscription.plan_price_id,
+            true,
+        )
+        .await?;
+
+        let subscribed = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM subscriptions WHERE user_id = $1 AND status NOT IN ('canceled', 'expired')
+            ) AS "exists!"
+            "#,
+            user_id
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(internal_error)?;
+
+        if subscribed {
+            return Err(already_subscribed());
+        }
+
+        let seated = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM subscription_seats WHERE user_id = $1 AND released_at IS NULL
+            ) AS "exists!"
+            "#,
+            user_id
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(internal_error)?;
+
+        if seated {
+            return Err((
+                StatusCode::CONFLICT,
+                "The user holds a seat on a group subscription".to_string(),
+            ));
+        }
 
         let start_date = chrono::Utc::now().naive_utc();
-        let end_date = match plan.trial_days {
-            Some(trial_days) => start_date + chrono::Duration::days(trial_days as i64),
-            None => start_date + chrono::Duration::days(30),
+        let trial_days = plan.trial_days.unwrap_or_default();
+
+        // Free forever plans start active and never end, plans with a trial
+        // start trialing until payment, others wait for the first payment.
+        let (status, end_date, trial_end_date) = if plan.is_free_forever.unwrap_or_default() {
+            (SubscriptionStatus::Active, None, None)
+        } else if trial_days > 0 {
+            let trial_end_date = start_date + chrono::Duration::days(trial_days as i64);
+            (
+                SubscriptionStatus::Trialing,
+                Some(trial_end_date),
+                Some(trial_end_date),
+            )
+        } else {
+            (
+                SubscriptionStatus::Incomplete,
+                Some(terms.period.end_of(start_date)),
+                None,
+            )
         };
+        let trial_start_date = trial_end_date.map(|_| start_date);
 
         let sub = sqlx::query!(
             r#"
-            INSERT INTO subscriptions (user_id, plan_id, is_active, start_date, end_date)
-            VALUES ($1, $2, $3, $4, $5)
-            RETURNING id, user_id, plan_id, start_date, end_date
+            INSERT INTO subscriptions (user_id, plan_id, plan_price_id, plan_version_id, status, start_date, end_date,
+                trial_start_date, trial_end_date)
+            VALUES ($1, $2, $3, (SELECT current_version_id FROM plans WHERE id = $2), $4, $5, $6, $7, $8)
+            RETURNING id, user_id, plan_id, plan_price_id, plan_version_id, start_date, end_date, trial_start_date, trial_end_date
             "#,
-            subscription.user_id,
+            user_id,
             subscription.plan_id,
-            false,
+            subscription.plan_price_id,
+            status.as_str(),
             start_date,
-            end_date
+            end_date,
+            trial_start_date,
+            trial_end_date
         )
-        .fetch_one(&self.pool)
+        .fetch_one(&mut *tx)
         .await
-        .map_err(|e| {
-            tracing::error!("Failed to create subscription: {:?}", e);
-            "Failed to create subscription".to_string()
+        .map_err(|e| match e {
+            // Created by a concurrent request in the meantime.
+            sqlx::Error::Database(e) if e.is_unique_violation() => already_subscribed(),
+            e => internal_error(e),
         })?;
 
+        if let Some(code) = &subscription.promo_code {
+            CouponService::redeem(
+                &mut tx,
+                code,
+                user_id,
+                sub.id,
+                subscription.plan_id,
+                terms.price.currency,
+            )
+            .await?;
+        }
+
+        sqlx::query!(
+            r#"
+            INSERT INTO subscription_events (subscription_id, to_status, actor_type, actor_id)
+            VALUES ($1, $2, $3, $4)
+            "#,
+            sub.id,
+            status.as_str(),
+            actor.actor_type(),
+            actor.actor_id()
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(internal_error)?;
+
+        // Seats of the previous subscription carry over to the new one.
+        sqlx::query!(
+            r#"
+            UPDATE subscription_seats SET subscription_id = $1
+            WHERE released_at IS NULL
+              AND subscription_id IN (SELECT id FROM subscriptions WHERE user_id = $2 AND id <> $1)
+            "#,
+            sub.id,
+            user_id
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(internal_error)?;
+
+        tx.commit().await.map_err(internal_error)?;
+
         Ok(SubscriptionResponse {
             id: sub.id,
             user_id: sub.user_id,
             plan_id: sub.plan_id,
+            plan_price_id: sub.plan_price_id,
+            plan_version_id: sub.plan_version_id,
             start_date: sub.start_date,
             end_date: sub.end_date,
-            is_active: false,
+            trial_start_date: sub.trial_start_date,
+            trial_end_date: sub.trial_end_date,
+            status,
+            is_active: status.is_active(),
+            cancel_at_period_end: false,
         })
     }
 
     async fn get_subscriptions(&self) -> Result<Vec<SubscriptionForSysResponse>, String> {
         let subscriptions = sqlx::query!(
             r#"
-            SELECT s.id, user_id, plan_id, start_date, end_date, u.username, u.name as user_name, u.email, p.name as plan_name, p.price, p.trial_days, p.description
+            SELECT s.id, user_id, plan_id, start_date, end_date, s.status, u.username, u.name as user_name, u.email, p.name as plan_name, p.price, p.currency, p.trial_days, p.description
             FROM subscriptions as s
             INNER JOIN users as u ON s.user_id = u.id
             INNER JOIN plans as p ON s.plan_id = p.id
@@ -139,38 +1506,43 @@ impl SubscriptionServiceImpl for SubscriptionService {
             "Failed to get subscriptions".to_string()
         })?;
 
-        Ok(subscriptions
+        subscriptions
             .into_iter()
-            .map(|subscription| SubscriptionForSysResponse {
-                id: subscription.id,
-                user_id: subscription.user_id,
-                plan_id: subscription.plan_id,
-                start_date: subscription.start_date,
-                end_date: subscription.end_date,
-                user: UserResponse::new(
-                    subscription.user_id.unwrap_or_default(),
-                    subscription.username,
-                    subscription.user_name,
-                    subscription.email,
-                ),
-                plan: PlanResponse::new(
-                    subscription.plan_id.unwrap_or_default(),
-                    subscription.plan_name,
-                    subscription.description,
-                    subscription.price,
-                    None,
-                    None,
-                    None,
-                    None,
-                ),
+            .map(|subscription| {
+                Ok(SubscriptionForSysResponse {
+                    id: subscription.id,
+                    user_id: subscription.user_id,
+                    plan_id: subscription.plan_id,
+                    start_date: subscription.start_date,
+                    end_date: subscription.end_date,
+                    status: subscription.status.parse()?,
+                    user: UserResponse::new(
+                        subscription.user_id.unwrap_or_default(),
+                        subscription.username,
+                        subscription.user_name,
+                        subscription.email,
+                    ),
+                    plan: PlanResponse::new(
+                        subscription.plan_id.unwrap_or_default(),
+                        subscription.plan_name,
+                        subscription.description,
+                        Money::parse(subscription.price, &subscription.currency)?,
+                        None,
+                        None,
+                        None,
+                        None,
+                    ),
+                })
             })
-            .collect())
+            .collect()
     }
 
     async fn get_subscription(&self, id: uuid::Uuid) -> Result<SubscriptionResponse, String> {
         let subscription = sqlx::query!(
             r#"
-            SELECT id, user_id, plan_id, start_date, end_date, is_active
+            SELECT id, user_id, plan_id, plan_price_id, plan_version_id, start_date, end_date, trial_start_date, trial_end_date,
+                status,
+                cancel_at_period_end
             FROM subscriptions
             WHERE id = $1
             "#,
@@ -183,31 +1555,104 @@ impl SubscriptionServiceImpl for SubscriptionService {
             "Failed to get subscription".to_string()
         })?;
 
+        let status: SubscriptionStatus = subscription.status.parse()?;
+
         Ok(SubscriptionResponse {
             id: subscription.id,
             user_id: subscription.user_id,
             plan_id: subscription.plan_id,
+            plan_price_id: subscription.plan_price_id,
+            plan_version_id: subscription.plan_version_id,
             start_date: subscription.start_date,
             end_date: subscription.end_date,
-            is_active: subscription.is_active.unwrap_or_default(),
+            trial_start_date: subscription.trial_start_date,
+            trial_end_date: subscription.trial_end_date,
+            status,
+            is_active: status.is_active(),
+            cancel_at_period_end: subscription.cancel_at_period_end,
         })
     }
 
+    async fn get_current_subscription(
+        &self,
+        user_id: uuid::Uuid,
+    ) -> Result<Option<SubscriptionResponse>, String> {
+        let user_id = Self::subscriber_id(&self.pool, user_id).await?;
+
+        let subscription = sqlx::query!(
+            r#"
+            SELECT id, user_id, plan_id, plan_price_id, plan_version_id, start_date, end_date, trial_start_date, trial_end_date,
+                status, cancel_at_period_end
+            FROM subscriptions
+            WHERE user_id = $1 AND status NOT IN ('canceled', 'expired')
+            "#,
+            user_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get current subscription: {:?}", e);
+            "Failed to get current subscription".to_string()
+        })?;
+
+        subscription
+            .map(|subscription| {
+                let status: SubscriptionStatus = subscription.status.parse()?;
+
+                Ok(SubscriptionResponse {
+                    id: subscription.id,
+                    user_id: subscription.user_id,
+                    plan_id: subscription.plan_id,
+                    plan_price_id: subscription.plan_price_id,
+                    plan_version_id: subscription.plan_version_id,
+                    start_date: subscription.start_date,
+                    end_date: subscription.end_date,
+                    trial_start_date: subscription.trial_start_date,
+                    trial_end_date: subscription.trial_end_date,
+                    status,
+                    is_active: status.is_active(),
+                    cancel_at_period_end: subscription.cancel_at_period_end,
+                })
+            })
+            .transpose()
+    }
+
     async fn get_subscriptions_for_by_username(
         &self,
         username: String,
-    ) -> Result<Vec<SubscriptionResponse>, String> {
+        pagination: PaginationQuery,
+    ) -> Result<PageResponse<SubscriptionResponse>, String> {
         let user_service = UserService::new(self.pool.clone());
 
         let user: UserResponse = user_service.get_user(username).await?;
 
+        let total = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) AS "count!" FROM subscriptions
+            WHERE user_id = $1 OR id IN (SELECT subscription_id FROM subscription_seats WHERE user_id = $1)
+            "#,
+            user.id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get subscriptions: {:?}", e);
+            "Failed to get subscriptions".to_string()
+        })?;
+
         let subscriptions = sqlx::query!(
             r#"
-            SELECT id, user_id, plan_id, start_date, end_date, is_active
+            SELECT id, user_id, plan_id, plan_price_id, plan_version_id, start_date, end_date, trial_start_date, trial_end_date,
+                status,
+                cancel_at_period_end
             FROM subscriptions
-            WHERE user_id = $1
+            WHERE user_id = $1 OR id IN (SELECT subscription_id FROM subscription_seats WHERE user_id = $1)
+            ORDER BY start_date DESC NULLS LAST, id
+            LIMIT $2 OFFSET $3
             "#,
-            user.id
+            user.id,
+            pagination.per_page(),
+            pagination.offset()
         )
         .fetch_all(&self.pool)
         .await
@@ -216,16 +1661,120 @@ impl SubscriptionServiceImpl for SubscriptionService {
             "Failed to get subscriptions".to_string()
         })?;
 
-        Ok(subscriptions
+        subscriptions
             .into_iter()
-            .map(|subscription| SubscriptionResponse {
-                id: subscription.id,
-                user_id: subscription.user_id,
-                plan_id: subscription.plan_id,
-                start_date: subscription.start_date,
-                end_date: subscription.end_date,
-                is_active: subscription.is_active.unwrap_or_default(),
+            .map(|subscription| {
+                let status: SubscriptionStatus = subscription.status.parse()?;
+
+                Ok(SubscriptionResponse {
+                    id: subscription.id,
+                    user_id: subscription.user_id,
+                    plan_id: subscription.plan_id,
+                    plan_price_id: subscription.plan_price_id,
+                    plan_version_id: subscription.plan_version_id,
+                    start_date: subscription.start_date,
+                    end_date: subscription.end_date,
+                    trial_start_date: subscription.trial_start_date,
+                    trial_end_date: subscription.trial_end_date,
+                    status,
+                    is_active: status.is_active(),
+                    cancel_at_period_end: subscription.cancel_at_period_end,
+                })
             })
-            .collect())
+            .collect::<Result<_, String>>()
+            .map(|items| PageResponse {
+                items,
+                page: pagination.page(),
+                per_page: pagination.per_page(),
+                total,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_period() {
+        let day = |d: u32| {
+            chrono::NaiveDate::from_ymd_opt(2024, 1, d)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap()
+        };
+        let length = BillingPeriod::new("day", 10).unwrap();
+
+        assert_eq!(next_period(day(1), day(5), length), (day(1), day(11)));
+        assert_eq!(next_period(day(1), day(11), length), (day(11), day(21)));
+        assert_eq!(next_period(day(1), day(25), length), (day(21), day(31)));
+
+        // Catching up on missed months keeps the end of month anchor.
+        let monthly = BillingPeriod::new("month", 1).unwrap();
+        let date = |m: u32, d: u32| {
+            chrono::NaiveDate::from_ymd_opt(2024, m, d)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap()
+        };
+
+        assert_eq!(
+            next_period(date(1, 31), date(2, 10), monthly),
+            (date(1, 31), date(2, 29))
+        );
+        assert_eq!(
+            next_period(date(1, 31), date(3, 10), monthly),
+            (date(2, 29), date(3, 31))
+        );
+    }
+
+    const CHILD_ID: uuid::Uuid = uuid::uuid!("a0000000-0000-0000-0000-000000000002");
+    const OTHER_ID: uuid::Uuid = uuid::uuid!("a0000000-0000-0000-0000-000000000003");
+    const BASIC_PLAN_ID: uuid::Uuid = uuid::uuid!("13cafdb3-a88d-4987-8119-0470caebd56c");
+
+    fn subscribe(user_id: Option<uuid::Uuid>) -> CreateSubscriptionRequest {
+        CreateSubscriptionRequest {
+            user_id,
+            plan_id: BASIC_PLAN_ID,
+            plan_price_id: None,
+            promo_code: None,
+        }
+    }
+
+    #[sqlx::test(fixtures("subscriptions"))]
+    async fn test_create_subscription_is_for_the_caller(pool: sqlx::PgPool) {
+        let subscriptions = SubscriptionService::new(pool.clone());
+
+        assert_eq!(
+            subscriptions
+                .create_subscription(subscribe(Some(CHILD_ID)), Actor::User(OTHER_ID))
+                .await
+                .map(|sub| sub.id)
+                .map_err(|(status, _)| status),
+            Err(StatusCode::FORBIDDEN)
+        );
+        assert_eq!(
+            subscriptions
+                .get_current_subscription(CHILD_ID)
+                .await
+                .map(|sub| sub.is_none()),
+            Ok(true)
+        );
+
+        let sub = subscriptions
+            .create_subscription(subscribe(None), Actor::User(CHILD_ID))
+            .await
+            .unwrap();
+        assert_eq!(sub.user_id, Some(CHILD_ID));
+
+        // Sys names the user to subscribe.
+        assert_eq!(
+            subscriptions
+                .create_subscription(subscribe(None), Actor::Sys(OTHER_ID))
+                .await
+                .map(|sub| sub.id)
+                .map_err(|(status, _)| status),
+            Err(StatusCode::BAD_REQUEST)
+        );
     }
 }
@@ -0,0 +1,259 @@
+use axum::http::StatusCode;
+use chrono::NaiveDateTime;
+
+use crate::domain::{
+    dtos::tax_dtos::{CreateTaxRuleRequest, TaxRuleResponse},
+    models::tax_model::{self, TaxBreakdown, TaxRate},
+};
+
+pub struct TaxService {
+    pub pool: sqlx::PgPool,
+}
+
+pub trait TaxServiceImpl {
+    fn new(pool: sqlx::PgPool) -> Self;
+
+    async fn create_tax_rule(
+        &self,
+        rule: CreateTaxRuleRequest,
+    ) -> Result<TaxRuleResponse, (StatusCode, String)>;
+
+    async fn get_tax_rules(&self) -> Result<Vec<TaxRuleResponse>, String>;
+
+    async fn update_tax_rule(
+        &self,
+        id: uuid::Uuid,
+        rule: CreateTaxRuleRequest,
+    ) -> Result<TaxRuleResponse, (StatusCode, String)>;
+
+    async fn delete_tax_rule(&self, id: uuid::Uuid) -> Result<(), (StatusCode, String)>;
+}
+
+/// The tax charged to a customer, stored next to the amounts it applies to
+/// so later rate changes don't alter them.
+pub struct AppliedTax {
+    pub rule_id: Option<uuid::Uuid>,
+    pub name: Option<String>,
+    /// The customer's billing region, `None` when not set.
+    pub region: Option<String>,
+    pub rate: TaxRate,
+}
+
+/// An amount due with the tax charged on it.
+pub struct TaxedAmount {
+    pub tax: AppliedTax,
+    pub breakdown: TaxBreakdown,
+}
+
+fn internal_error(e: sqlx::Error) -> (StatusCode, String) {
+    tracing::error!("Failed to save tax rule: {:?}", e);
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "Failed to save tax rule".to_string(),
+    )
+}
+
+fn save_error(e: sqlx::Error) -> (StatusCode, String) {
+    match e {
+        sqlx::Error::Database(e) if e.is_check_violation() => (
+            StatusCode::BAD_REQUEST,
+            "effective_until must be after effective_from".to_string(),
+        ),
+        e => internal_error(e),
+    }
+}
+
+fn validate_rule(rule: &CreateTaxRuleRequest) -> Result<(String, TaxRate), (StatusCode, String)> {
+    let region = tax_model::parse_region(&rule.region).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let rate = TaxRate::new(rule.rate_bps, rule.is_inclusive.unwrap_or_default())
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    Ok((region, rate))
+}
+
+impl TaxService {
+    /// The tax rule in effect at `at`, when the amount is calculated, for
+    /// the billing region of `user_id`.
+    /// A rule for the whole country applies to subdivisions without their
+    /// own rule. Customers without a billing region pay no tax.
+    pub async fn applicable(
+        conn: &mut sqlx::PgConnection,
+        user_id: uuid::Uuid,
+        at: NaiveDateTime,
+    ) -> Result<AppliedTax, sqlx::Error> {
+        let tax = sqlx::query!(
+            r#"
+            SELECT u.billing_region, t.id AS "rule_id?", t.name AS "name?", t.rate_bps AS "rate_bps?",
+                t.is_inclusive AS "is_inclusive?"
+            FROM users AS u
+            LEFT JOIN LATERAL (
+                SELECT id, name, rate_bps, is_inclusive
+                FROM tax_rules
+                WHERE region IN (u.billing_region, split_part(u.billing_region, '-', 1))
+                  AND effective_from <= $2 AND (effective_until IS NULL OR $2 < effective_until)
+                ORDER BY length(region) DESC, effective_from DESC
+                LIMIT 1
+            ) AS t ON TRUE
+            WHERE u.id = $1
+            "#,
+            user_id,
+            at
+        )
+        .fetch_optional(&mut *conn)
+        .await?;
+
+        let Some(tax) = tax else {
+            return Ok(AppliedTax {
+                rule_id: None,
+                name: None,
+                region: None,
+                rate: TaxRate::none(),
+            });
+        };
+
+        Ok(AppliedTax {
+            rule_id: tax.rule_id,
+            name: tax.name,
+            region: tax.billing_region,
+            rate: match (tax.rate_bps, tax.is_inclusive) {
+                (Some(rate_bps), Some(is_inclusive)) => TaxRate {
+                    rate_bps,
+                    is_inclusive,
+                },
+                _ => TaxRate::none(),
+            },
+        })
+    }
+}
+
+impl TaxServiceImpl for TaxService {
+    fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+
+    async fn create_tax_rule(
+        &self,
+        rule: CreateTaxRuleRequest,
+    ) -> Result<TaxRuleResponse, (StatusCode, String)> {
+        let (region, rate) = validate_rule(&rule)?;
+        let effective_from = rule
+            .effective_from
+            .unwrap_or_else(|| chrono::Utc::now().naive_utc());
+
+        let rule = sqlx::query!(
+            r#"
+            INSERT INTO tax_rules (region, name, rate_bps, is_inclusive, effective_from, effective_until)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING id, region, name, rate_bps, is_inclusive, effective_from, effective_until, created_at
+            "#,
+            region,
+            rule.name,
+            rate.rate_bps,
+            rate.is_inclusive,
+            effective_from,
+            rule.effective_until
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(save_error)?;
+
+        Ok(TaxRuleResponse {
+            id: rule.id,
+            region: rule.region,
+            name: rule.name,
+            rate_bps: rule.rate_bps,
+            is_inclusive: rule.is_inclusive,
+            effective_from: rule.effective_from,
+            effective_until: rule.effective_until,
+            created_at: rule.created_at,
+        })
+    }
+
+    async fn get_tax_rules(&self) -> Result<Vec<TaxRuleResponse>, String> {
+        let rules = sqlx::query!(
+            r#"
+            SELECT id, region, name, rate_bps, is_inclusive, effective_from, effective_until, created_at
+            FROM tax_rules
+            ORDER BY region, effective_from DESC
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get tax rules: {:?}", e);
+            "Failed to get tax rules".to_string()
+        })?;
+
+        Ok(rules
+            .into_iter()
+            .map(|rule| TaxRuleResponse {
+                id: rule.id,
+                region: rule.region,
+                name: rule.name,
+                rate_bps: rule.rate_bps,
+                is_inclusive: rule.is_inclusive,
+                effective_from: rule.effective_from,
+                effective_until: rule.effective_until,
+                created_at: rule.created_at,
+            })
+            .collect())
+    }
+
+    /// Only changes the tax of amounts calculated from now on. The rule
+    /// keeps its `effective_from` when none is given.
+    async fn update_tax_rule(
+        &self,
+        id: uuid::Uuid,
+        rule: CreateTaxRuleRequest,
+    ) -> Result<TaxRuleResponse, (StatusCode, String)> {
+        let (region, rate) = validate_rule(&rule)?;
+
+        let rule = sqlx::query!(
+            r#"
+            UPDATE tax_rules
+            SET region = $1, name = $2, rate_bps = $3, is_inclusive = $4, effective_from = COALESCE($5, effective_from),
+                effective_until = $6, updated_at = CURRENT_TIMESTAMP
+            WHERE id = $7
+            RETURNING id, region, name, rate_bps, is_inclusive, effective_from, effective_until, created_at
+            "#,
+            region,
+            rule.name,
+            rate.rate_bps,
+            rate.is_inclusive,
+            rule.effective_from,
+            rule.effective_until,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(save_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Tax rule not found".to_string()))?;
+
+        Ok(TaxRuleResponse {
+            id: rule.id,
+            region: rule.region,
+            name: rule.name,
+            rate_bps: rule.rate_bps,
+            is_inclusive: rule.is_inclusive,
+            effective_from: rule.effective_from,
+            effective_until: rule.effective_until,
+            created_at: rule.created_at,
+        })
+    }
+
+    async fn delete_tax_rule(&self, id: uuid::Uuid) -> Result<(), (StatusCode, String)> {
+        sqlx::query!(
+            r#"
+            DELETE FROM tax_rules WHERE id = $1
+            RETURNING id
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Tax rule not found".to_string()))?;
+
+        Ok(())
+    }
+}
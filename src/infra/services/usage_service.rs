@@ -0,0 +1,396 @@
+use axum::http::StatusCode;
+
+use crate::{
+    domain::{
+        dtos::usage_dtos::{
+            DailyUsageResponse, PeriodUsageResponse, RejectedUsageEvent, UsageEventRequest,
+            UsageIngestionResponse, UsageRollupQuery,
+        },
+        models::subscription_model::SubscriptionStatus,
+    },
+    infra::events,
+};
+
+use super::{
+    quota_service::{QuotaService, QuotaServiceImpl},
+    subscription_service::SubscriptionService,
+};
+
+/// Maximum number of events accepted by a single bulk request.
+pub const MAX_BULK_EVENTS: usize = 1000;
+
+pub struct UsageService {
+    pub pool: sqlx::PgPool,
+}
+
+pub trait UsageServiceImpl {
+    fn new(pool: sqlx::PgPool) -> Self;
+
+    async fn record_events(
+        &self,
+        user_id: uuid::Uuid,
+        events: Vec<UsageEventRequest>,
+    ) -> Result<UsageIngestionResponse, (StatusCode, String)>;
+
+    async fn get_daily_usage(
+        &self,
+        user_id: uuid::Uuid,
+        query: UsageRollupQuery,
+    ) -> Result<Vec<DailyUsageResponse>, String>;
+
+    async fn get_period_usage(
+        &self,
+        user_id: uuid::Uuid,
+        query: UsageRollupQuery,
+    ) -> Result<Vec<PeriodUsageResponse>, String>;
+}
+
+impl UsageServiceImpl for UsageService {
+    fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+
+    async fn record_events(
+        &self,
+        user_id: uuid::Uuid,
+        events: Vec<UsageEventRequest>,
+    ) -> Result<UsageIngestionResponse, (StatusCode, String)> {
+        if events.len() > MAX_BULK_EVENTS {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("At most {} events can be sent at once", MAX_BULK_EVENTS),
+            ));
+        }
+
+        let internal_error = |e: sqlx::Error| {
+            tracing::error!("Failed to record usage event: {:?}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to record usage event".to_string(),
+            )
+        };
+
+        let subscriber_id = SubscriptionService::subscriber_id(&self.pool, user_id)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+        let subscription = sqlx::query!(
+            r#"
+            SELECT id, status, start_date, end_date, EXISTS (
+                SELECT 1 FROM invoices AS i
+                WHERE i.subscription_id = subscriptions.id AND i.status = 'open' AND i.read_only_at IS NOT NULL
+            ) AS "is_read_only!"
+            FROM subscriptions
+            WHERE user_id = $1 AND (is_active = TRUE OR status = 'paused')
+            ORDER BY start_date DESC
+            LIMIT 1
+            "#,
+            subscriber_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(internal_error)?
+        .ok_or((StatusCode::FORBIDDEN, "No active subscription".to_string()))?;
+
+        if subscription.status == SubscriptionStatus::Paused.as_str() {
+            return Err((
+                StatusCode::FORBIDDEN,
+                "Your subscription is paused, quotas are read-only".to_string(),
+            ));
+        }
+
+        if subscription.is_read_only {
+            return Err((
+                StatusCode::FORBIDDEN,
+                "Your renewal invoice is overdue, quotas are read-only until it is paid"
+                    .to_string(),
+            ));
+        }
+
+        let period_start = subscription
+            .start_date
+            .unwrap_or_else(|| chrono::Utc::now().naive_utc());
+
+        let quota_service = QuotaService::new(self.pool.clone());
+        let mut response = UsageIngestionResponse::default();
+
+        for event in events {
+            if event.quantity <= 0 {
+                response.rejected.push(RejectedUsageEvent {
+                    event_id: event.event_id,
+                    error: "Quantity must be positive".to_string(),
+                });
+                continue;
+            }
+
+            let resource_type_id = sqlx::query_scalar!(
+                r#"
+                SELECT id FROM resource_types WHERE code = $1 AND is_metered = TRUE
+                "#,
+                event.resource_type
+            )
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(internal_error)?;
+
+            let Some(resource_type_id) = resource_type_id else {
+                response.rejected.push(RejectedUsageEvent {
+                    event_id: event.event_id,
+                    error: format!("Unknown metered resource type: {}", event.resource_type),
+                });
+                continue;
+            };
+
+            let occurred_at = event
+                .occurred_at
+                .unwrap_or_else(|| chrono::Utc::now().naive_utc());
+
+            let mut tx = self.pool.begin().await.map_err(internal_error)?;
+
+            // Recording the event first dedupes it, a concurrent request with
+            // the same event waits here until this transaction ends.
+            let inserted = sqlx::query!(
+                r#"
+                INSERT INTO usage_events (event_id, user_id, subscription_id, resource_type_id, quantity, occurred_at)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                ON CONFLICT (user_id, event_id) DO NOTHING
+                RETURNING id
+                "#,
+                event.event_id,
+                user_id,
+                subscription.id,
+                resource_type_id,
+                event.quantity,
+                occurred_at
+            )
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(internal_error)?;
+
+            if inserted.is_none() {
+                response.duplicates += 1;
+                continue;
+            }
+
+            let crossed = match quota_service
+                .consume(&mut tx, user_id, &event.resource_type, event.quantity)
+                .await
+            {
+                Ok(crossed) => crossed,
+                Err((StatusCode::FORBIDDEN, e)) => {
+                    // Rolls the event back, so it can be sent again later.
+                    response.rejected.push(RejectedUsageEvent {
+                        event_id: event.event_id,
+                        error: e,
+                    });
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+
+            sqlx::query!(
+                r#"
+                INSERT INTO usage_daily_rollups (subscription_id, resource_type_id, day, quantity)
+                VALUES ($1, $2, $3, $4)
+                ON CONFLICT (subscription_id, resource_type_id, day)
+                DO UPDATE SET quantity = usage_daily_rollups.quantity + EXCLUDED.quantity, updated_at = CURRENT_TIMESTAMP
+                "#,
+                subscription.id,
+                resource_type_id,
+                occurred_at.date(),
+                event.quantity
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(internal_error)?;
+
+            sqlx::query!(
+                r#"
+                INSERT INTO usage_period_rollups (subscription_id, resource_type_id, period_start, period_end, quantity)
+                VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT (subscription_id, resource_type_id, period_start)
+                DO UPDATE SET quantity = usage_period_rollups.quantity + EXCLUDED.quantity, updated_at = CURRENT_TIMESTAMP
+                "#,
+                subscription.id,
+                resource_type_id,
+                period_start,
+                subscription.end_date,
+                event.quantity
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(internal_error)?;
+
+            tx.commit().await.map_err(internal_error)?;
+
+            crossed.into_iter().for_each(events::publish);
+            response.accepted += 1;
+        }
+
+        Ok(response)
+    }
+
+    async fn get_daily_usage(
+        &self,
+        user_id: uuid::Uuid,
+        query: UsageRollupQuery,
+    ) -> Result<Vec<DailyUsageResponse>, String> {
+        let user_id = SubscriptionService::subscriber_id(&self.pool, user_id).await?;
+
+        let rollups = sqlx::query!(
+            r#"
+            SELECT r.subscription_id, rt.code AS resource_type, r.day, r.quantity
+            FROM usage_daily_rollups AS r
+            INNER JOIN subscriptions AS s ON s.id = r.subscription_id
+            INNER JOIN resource_types AS rt ON rt.id = r.resource_type_id
+            WHERE s.user_id = $1
+              AND ($2::VARCHAR IS NULL OR rt.code = $2)
+              AND ($3::DATE IS NULL OR r.day >= $3)
+              AND ($4::DATE IS NULL OR r.day <= $4)
+            ORDER BY r.day, rt.code
+            "#,
+            user_id,
+            query.resource_type,
+            query.from,
+            query.to
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get daily usage: {:?}", e);
+            "Failed to get daily usage".to_string()
+        })?;
+
+        Ok(rollups
+            .into_iter()
+            .map(|r| DailyUsageResponse {
+                subscription_id: r.subscription_id,
+                resource_type: r.resource_type,
+                day: r.day,
+                quantity: r.quantity,
+            })
+            .collect())
+    }
+
+    async fn get_period_usage(
+        &self,
+        user_id: uuid::Uuid,
+        query: UsageRollupQuery,
+    ) -> Result<Vec<PeriodUsageResponse>, String> {
+        let user_id = SubscriptionService::subscriber_id(&self.pool, user_id).await?;
+
+        let rollups = sqlx::query!(
+            r#"
+            SELECT r.subscription_id, rt.code AS resource_type, r.period_start, r.period_end, r.quantity
+            FROM usage_period_rollups AS r
+            INNER JOIN subscriptions AS s ON s.id = r.subscription_id
+            INNER JOIN resource_types AS rt ON rt.id = r.resource_type_id
+            WHERE s.user_id = $1
+              AND ($2::VARCHAR IS NULL OR rt.code = $2)
+              AND ($3::DATE IS NULL OR r.period_start >= $3)
+              AND ($4::DATE IS NULL OR r.period_start < $4 + 1)
+            ORDER BY r.period_start DESC, rt.code
+            "#,
+            user_id,
+            query.resource_type,
+            query.from,
+            query.to
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get period usage: {:?}", e);
+            "Failed to get period usage".to_string()
+        })?;
+
+        Ok(rollups
+            .into_iter()
+            .map(|r| PeriodUsageResponse {
+                subscription_id: r.subscription_id,
+                resource_type: r.resource_type,
+                period_start: r.period_start,
+                period_end: r.period_end,
+                quantity: r.quantity,
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PARENT_ID: uuid::Uuid = uuid::uuid!("a0000000-0000-0000-0000-000000000001");
+    const SUBSCRIPTION_ID: uuid::Uuid = uuid::uuid!("b0000000-0000-0000-0000-000000000001");
+
+    fn event(event_id: &str, quantity: i64) -> UsageEventRequest {
+        UsageEventRequest {
+            event_id: event_id.to_string(),
+            resource_type: "api_calls".to_string(),
+            quantity,
+            occurred_at: None,
+        }
+    }
+
+    async fn set_api_calls_limit(pool: &sqlx::PgPool, hard_limit: i64) {
+        sqlx::query!(
+            r#"
+            INSERT INTO plan_limits (plan_id, resource_type_id, max, hard_limit, name)
+            SELECT s.plan_id, rt.id, $1, $1, 'api_calls'
+            FROM subscriptions AS s, resource_types AS rt
+            WHERE s.id = $2 AND rt.code = 'api_calls'
+            ON CONFLICT (plan_id, resource_type_id) DO UPDATE SET max = $1, hard_limit = $1
+            "#,
+            hard_limit,
+            SUBSCRIPTION_ID
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    #[sqlx::test(fixtures("subscriptions"))]
+    async fn test_record_events_dedupes_and_rolls_back_rejected_events(pool: sqlx::PgPool) {
+        set_api_calls_limit(&pool, 10).await;
+        let usage = UsageService::new(pool.clone());
+
+        let response = usage
+            .record_events(
+                PARENT_ID,
+                vec![event("e1", 4), event("e1", 4), event("e2", 7)],
+            )
+            .await
+            .unwrap();
+
+        assert_eq!((response.accepted, response.duplicates), (1, 1));
+        assert_eq!(
+            response
+                .rejected
+                .iter()
+                .map(|rejected| rejected.event_id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["e2"]
+        );
+
+        // The rejected event was not kept, so it is accepted once it fits.
+        set_api_calls_limit(&pool, 20).await;
+        let response = usage
+            .record_events(PARENT_ID, vec![event("e1", 4), event("e2", 7)])
+            .await
+            .unwrap();
+
+        assert_eq!((response.accepted, response.duplicates), (1, 1));
+        assert!(response.rejected.is_empty());
+
+        let recorded = sqlx::query_scalar!(
+            r#"
+            SELECT quantity FROM usage_period_rollups WHERE subscription_id = $1
+            "#,
+            SUBSCRIPTION_ID
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(recorded, 11);
+    }
+}
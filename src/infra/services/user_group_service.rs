@@ -46,7 +46,10 @@ impl UserGroupServiceImpl for UserGroupService {
                 id: user_group.id,
                 user_id: user_group.user_id,
                 parent_id: user_group.parent_id,
-                created_at: chrono::DateTime::from_utc(user_group.created_at.unwrap(), chrono::Utc),
+                created_at: chrono::DateTime::from_naive_utc_and_offset(
+                    user_group.created_at.unwrap(),
+                    chrono::Utc,
+                ),
             })
             .collect())
     }
@@ -76,7 +79,10 @@ impl UserGroupServiceImpl for UserGroupService {
                 id: user_group.id,
                 user_id: user_group.user_id,
                 parent_id: user_group.parent_id,
-                created_at: chrono::DateTime::from_utc(user_group.created_at.unwrap(), chrono::Utc),
+                created_at: chrono::DateTime::from_naive_utc_and_offset(
+                    user_group.created_at.unwrap(),
+                    chrono::Utc,
+                ),
             })
             .collect())
     }
@@ -1,10 +1,26 @@
+use axum::http::StatusCode;
 use serde::{Deserialize, Serialize};
 
-use crate::domain::dtos::user_dtos::{CreateUserRequest, UpdateUserRequest, UserResponse};
+use crate::{
+    domain::{
+        dtos::user_dtos::{
+            BillingDetailsResponse, CreateUserRequest, UpdateBillingDetailsRequest,
+            UpdateUserRequest, UserResponse,
+        },
+        models::{
+            quota_override_model::LimitSource, resource_type_model,
+            subscription_model::SubscriptionStatus, tax_model,
+        },
+    },
+    infra::events,
+};
 
 use super::{
     auth_service::{AuthService, AuthServiceImpl},
     claim_service,
+    quota_service::{QuotaService, QuotaServiceImpl},
+    resource_service::{ResourceService, ResourceServiceImpl},
+    subscription_service::SubscriptionService,
 };
 
 #[derive(Serialize)]
@@ -22,18 +38,22 @@ pub struct UserWithSubscriptionResponse {
 pub struct SubscriptionForUser {
     pub id: uuid::Uuid,
     pub plan_id: uuid::Uuid,
+    pub status: SubscriptionStatus,
     pub is_active: bool,
     pub start_date: Option<chrono::NaiveDateTime>,
     pub end_date: Option<chrono::NaiveDateTime>,
     pub trial_start_date: Option<chrono::NaiveDateTime>,
     pub trial_end_date: Option<chrono::NaiveDateTime>,
+    pub cancel_at_period_end: bool,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct ResourceForUser {
     pub id: uuid::Uuid,
     pub name: String,
+    pub resource_type: String,
     pub max: i64,
+    pub source: LimitSource,
 }
 
 pub struct UserService {
@@ -62,11 +82,31 @@ pub trait UserServiceImpl {
         new_password: String,
     ) -> Result<(), String>;
 
+    /// Seats `child_username` on the subscription of `username`, counted
+    /// against its `USERS` quota.
     async fn create_child_user(
         &self,
         username: String,
         child_username: String,
-    ) -> Result<(), String>;
+    ) -> Result<(), (StatusCode, String)>;
+
+    async fn get_billing_details(
+        &self,
+        user_id: uuid::Uuid,
+    ) -> Result<BillingDetailsResponse, String>;
+
+    async fn update_billing_details(
+        &self,
+        user_id: uuid::Uuid,
+        details: UpdateBillingDetailsRequest,
+    ) -> Result<BillingDetailsResponse, (StatusCode, String)>;
+}
+
+/// Blank fields are cleared.
+fn non_blank(value: Option<String>) -> Option<String> {
+    value
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
 }
 
 impl UserServiceImpl for UserService {
@@ -206,11 +246,14 @@ impl UserServiceImpl for UserService {
             return Err("Invalid password".to_string());
         }
 
+        // Seat holders are covered by the group subscription of its owner.
+        let subscriber_id = SubscriptionService::subscriber_id(&self.pool, user.id).await?;
+
         let user_subscription = sqlx::query!(
             r#"
-            SELECT * FROM subscriptions WHERE user_id = $1
+            SELECT * FROM subscriptions WHERE user_id = $1 AND status NOT IN ('canceled', 'expired')
             "#,
-            user.id
+            subscriber_id
         )
         .fetch_optional(&self.pool)
         .await
@@ -219,21 +262,14 @@ impl UserServiceImpl for UserService {
             "Failed to get user subscription".to_string()
         })?;
 
-        let resources = sqlx::query!(
-            r#"
-            SELECT * FROM resources WHERE plan_id = $1
-            "#,
-            user_subscription
-                .as_ref()
-                .map(|s| s.plan_id)
-                .unwrap_or_default()
-        )
-        .fetch_all(&self.pool)
-        .await
-        .map_err(|e| {
-            tracing::error!("Failed to get resources: {:?}", e);
-            "Failed to get resources".to_string()
-        })?;
+        let resources = ResourceService::new(self.pool.clone())
+            .get_effective_limits(
+                subscriber_id,
+                user_subscription.as_ref().and_then(|s| s.plan_id),
+            )
+            .await?;
+
+        let now = chrono::Utc::now().naive_utc();
 
         let jwt = claim_service::Claims::encode_jwt(UserWithSubscriptionResponse {
             id: user.id,
@@ -241,21 +277,32 @@ impl UserServiceImpl for UserService {
             name: user.name,
             email: user.email,
             is_sys: None,
-            subscription: user_subscription.map(|s| SubscriptionForUser {
-                id: s.id,
-                plan_id: s.plan_id.unwrap_or_default(),
-                is_active: s.is_active.unwrap_or_default(),
-                start_date: s.start_date,
-                end_date: s.end_date,
-                trial_start_date: s.trial_start_date,
-                trial_end_date: s.trial_end_date,
-            }),
+            subscription: user_subscription
+                .map(|s| -> Result<SubscriptionForUser, String> {
+                    Ok(SubscriptionForUser {
+                        id: s.id,
+                        plan_id: s.plan_id.unwrap_or_default(),
+                        status: s.status.parse()?,
+                        // Access ends with the period once the subscription is set to cancel.
+                        is_active: s.is_active.unwrap_or_default()
+                            && !(s.cancel_at_period_end
+                                && s.end_date.is_some_and(|end_date| end_date <= now)),
+                        start_date: s.start_date,
+                        end_date: s.end_date,
+                        trial_start_date: s.trial_start_date,
+                        trial_end_date: s.trial_end_date,
+                        cancel_at_period_end: s.cancel_at_period_end,
+                    })
+                })
+                .transpose()?,
             resources: resources
-                .iter()
+                .into_iter()
                 .map(|r| ResourceForUser {
                     id: r.id,
-                    name: r.name.clone(),
+                    name: r.name,
+                    resource_type: r.resource_type,
                     max: r.max,
+                    source: r.source,
                 })
                 .collect(),
         })
@@ -315,11 +362,14 @@ impl UserServiceImpl for UserService {
             "Failed to get user".to_string()
         })?;
 
+        // Seat holders are covered by the group subscription of its owner.
+        let subscriber_id = SubscriptionService::subscriber_id(&self.pool, user.id).await?;
+
         let user_subscription = sqlx::query!(
             r#"
-            SELECT * FROM subscriptions WHERE user_id = $1
+            SELECT * FROM subscriptions WHERE user_id = $1 AND status NOT IN ('canceled', 'expired')
             "#,
-            user.id
+            subscriber_id
         )
         .fetch_optional(&self.pool)
         .await
@@ -328,21 +378,14 @@ impl UserServiceImpl for UserService {
             "Failed to get user subscription".to_string()
         })?;
 
-        let resources = sqlx::query!(
-            r#"
-            SELECT * FROM resources WHERE plan_id = $1
-            "#,
-            user_subscription
-                .as_ref()
-                .map(|s| s.plan_id)
-                .unwrap_or_default()
-        )
-        .fetch_all(&self.pool)
-        .await
-        .map_err(|e| {
-            tracing::error!("Failed to get resources: {:?}", e);
-            "Failed to get resources".to_string()
-        })?;
+        let resources = ResourceService::new(self.pool.clone())
+            .get_effective_limits(
+                subscriber_id,
+                user_subscription.as_ref().and_then(|s| s.plan_id),
+            )
+            .await?;
+
+        let now = chrono::Utc::now().naive_utc();
 
         let jwt = claim_service::Claims::encode_jwt(UserWithSubscriptionResponse {
             id: user.id,
@@ -350,21 +393,32 @@ impl UserServiceImpl for UserService {
             name: user.name,
             email: user.email,
             is_sys: None,
-            subscription: user_subscription.map(|s| SubscriptionForUser {
-                id: s.id,
-                plan_id: s.plan_id.unwrap_or_default(),
-                is_active: s.is_active.unwrap_or_default(),
-                start_date: s.start_date,
-                end_date: s.end_date,
-                trial_start_date: s.trial_start_date,
-                trial_end_date: s.trial_end_date,
-            }),
+            subscription: user_subscription
+                .map(|s| -> Result<SubscriptionForUser, String> {
+                    Ok(SubscriptionForUser {
+                        id: s.id,
+                        plan_id: s.plan_id.unwrap_or_default(),
+                        status: s.status.parse()?,
+                        // Access ends with the period once the subscription is set to cancel.
+                        is_active: s.is_active.unwrap_or_default()
+                            && !(s.cancel_at_period_end
+                                && s.end_date.is_some_and(|end_date| end_date <= now)),
+                        start_date: s.start_date,
+                        end_date: s.end_date,
+                        trial_start_date: s.trial_start_date,
+                        trial_end_date: s.trial_end_date,
+                        cancel_at_period_end: s.cancel_at_period_end,
+                    })
+                })
+                .transpose()?,
             resources: resources
-                .iter()
+                .into_iter()
                 .map(|r| ResourceForUser {
                     id: r.id,
-                    name: r.name.clone(),
+                    name: r.name,
+                    resource_type: r.resource_type,
                     max: r.max,
+                    source: r.source,
                 })
                 .collect(),
         })
@@ -485,94 +539,93 @@ impl UserServiceImpl for UserService {
         &self,
         username: String,
         child_username: String,
-    ) -> Result<(), String> {
+    ) -> Result<(), (StatusCode, String)> {
+        let internal_error = |e: sqlx::Error| {
+            tracing::error!("Failed to create child user: {:?}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to create child user".to_string(),
+            )
+        };
+
         let user = sqlx::query!(
             r#"
-            SELECT * FROM users WHERE username = $1
+            SELECT id FROM users WHERE username = $1
             "#,
             username
         )
-        .fetch_one(&self.pool)
+        .fetch_optional(&self.pool)
         .await
-        .map_err(|e| {
-            tracing::error!("Failed to get user: {:?}", e);
-            "Failed to get user".to_string()
-        })?;
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "User not found".to_string()))?;
 
         let child_user = sqlx::query!(
             r#"
-            SELECT * FROM users WHERE username = $1
+            SELECT id FROM users WHERE username = $1
             "#,
             child_username
         )
-        .fetch_one(&self.pool)
+        .fetch_optional(&self.pool)
         .await
-        .map_err(|e| {
-            tracing::error!("Failed to get child user: {:?}", e);
-            "Failed to get child user".to_string()
-        })?;
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Child user not found".to_string()))?;
 
         let user_subscription = sqlx::query!(
             r#"
-            SELECT * FROM subscriptions WHERE user_id = $1
+            SELECT id FROM subscriptions WHERE user_id = $1 AND is_active = TRUE
             "#,
             user.id
         )
-        .fetch_one(&self.pool)
+        .fetch_optional(&self.pool)
         .await
-        .map_err(|e| {
-            tracing::error!("Failed to get user subscription: {:?}", e);
-            "Failed to get user subscription".to_string()
-        })?;
+        .map_err(internal_error)?
+        .ok_or((
+            StatusCode::CONFLICT,
+            "User has no active subscription".to_string(),
+        ))?;
+
+        let mut tx = self.pool.begin().await.map_err(internal_error)?;
 
         let child_user_subscription = sqlx::query!(
             r#"
-            SELECT * FROM subscriptions WHERE user_id = $1
+            SELECT id FROM subscriptions
+            WHERE user_id = $1 AND status NOT IN ('canceled', 'expired')
             "#,
             child_user.id
         )
-        .fetch_optional(&self.pool)
+        .fetch_optional(&mut *tx)
         .await
-        .map_err(|e| {
-            tracing::error!("Failed to get child user subscription: {:?}", e);
-            "Failed to get child user subscription".to_string()
-        })?;
+        .map_err(internal_error)?;
 
+        // Only the child can cancel its own subscription.
         if child_user_subscription.is_some() {
-            if child_user_subscription.unwrap().is_active.unwrap() {
-                return Err("Child user already has an active subscription".to_string());
-            }
-
-            sqlx::query!(
-                r#"
-                DELETE FROM subscriptions WHERE user_id = $1
-                "#,
-                child_user.id
-            )
-            .execute(&self.pool)
-            .await
-            .map_err(|e| {
-                tracing::error!("Failed to delete child user subscription: {:?}", e);
-                "Failed to delete child user subscription".to_string()
-            })?;
+            return Err((
+                StatusCode::CONFLICT,
+                "Child user already has a current subscription".to_string(),
+            ));
         }
 
+        let crossed = QuotaService::new(self.pool.clone())
+            .consume(&mut tx, user.id, resource_type_model::USERS, 1)
+            .await?;
+
         sqlx::query!(
             r#"
-            INSERT INTO subscriptions (user_id, plan_id, is_active, start_date, end_date)
-            VALUES ($1, $2, $3, $4, $5)
+            INSERT INTO subscription_seats (subscription_id, user_id, assigned_by)
+            VALUES ($1, $2, $3)
             "#,
+            user_subscription.id,
             child_user.id,
-            user_subscription.plan_id,
-            user_subscription.is_active,
-            user_subscription.start_date,
-            user_subscription.end_date
+            user.id
         )
-        .execute(&self.pool)
+        .execute(&mut *tx)
         .await
-        .map_err(|e| {
-            tracing::error!("Failed to create child user subscription: {:?}", e);
-            "Failed to create child user subscription".to_string()
+        .map_err(|e| match e {
+            sqlx::Error::Database(e) if e.is_unique_violation() => (
+                StatusCode::CONFLICT,
+                "Child user already holds a seat".to_string(),
+            ),
+            e => internal_error(e),
         })?;
 
         sqlx::query!(
@@ -583,13 +636,153 @@ impl UserServiceImpl for UserService {
             user.id,
             child_user.id
         )
-        .execute(&self.pool)
+        .execute(&mut *tx)
+        .await
+        .map_err(internal_error)?;
+
+        tx.commit().await.map_err(internal_error)?;
+
+        crossed.into_iter().for_each(events::publish);
+
+        Ok(())
+    }
+
+    async fn get_billing_details(
+        &self,
+        user_id: uuid::Uuid,
+    ) -> Result<BillingDetailsResponse, String> {
+        let details = sqlx::query!(
+            r#"
+            SELECT billing_name, billing_address, billing_region, tax_id FROM users WHERE id = $1
+            "#,
+            user_id
+        )
+        .fetch_one(&self.pool)
         .await
         .map_err(|e| {
-            tracing::error!("Failed to create child user group: {:?}", e);
-            "Failed to create child user group".to_string()
+            tracing::error!("Failed to get billing details: {:?}", e);
+            "Failed to get billing details".to_string()
         })?;
 
-        Ok(())
+        Ok(BillingDetailsResponse {
+            billing_name: details.billing_name,
+            billing_address: details.billing_address,
+            billing_region: details.billing_region,
+            tax_id: details.tax_id,
+        })
+    }
+
+    /// Only amounts calculated afterwards are taxed for the new region.
+    async fn update_billing_details(
+        &self,
+        user_id: uuid::Uuid,
+        details: UpdateBillingDetailsRequest,
+    ) -> Result<BillingDetailsResponse, (StatusCode, String)> {
+        let billing_region = non_blank(details.billing_region)
+            .map(|region| tax_model::parse_region(&region))
+            .transpose()
+            .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+        let details = sqlx::query!(
+            r#"
+            UPDATE users
+            SET billing_name = $1, billing_address = $2, billing_region = $3, tax_id = $4,
+                updated_at = CURRENT_TIMESTAMP
+            WHERE id = $5
+            RETURNING billing_name, billing_address, billing_region, tax_id
+            "#,
+            non_blank(details.billing_name),
+            non_blank(details.billing_address),
+            billing_region,
+            non_blank(details.tax_id),
+            user_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to update billing details: {:?}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to update billing details".to_string(),
+            )
+        })?
+        .ok_or((StatusCode::NOT_FOUND, "User not found".to_string()))?;
+
+        Ok(BillingDetailsResponse {
+            billing_name: details.billing_name,
+            billing_address: details.billing_address,
+            billing_region: details.billing_region,
+            tax_id: details.tax_id,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PARENT_ID: uuid::Uuid = uuid::uuid!("a0000000-0000-0000-0000-000000000001");
+    const CHILD_ID: uuid::Uuid = uuid::uuid!("a0000000-0000-0000-0000-000000000002");
+    const OTHER_SUBSCRIPTION_ID: uuid::Uuid = uuid::uuid!("b0000000-0000-0000-0000-000000000003");
+
+    #[sqlx::test(fixtures("subscriptions"))]
+    async fn test_create_child_user_seats_the_child(pool: sqlx::PgPool) {
+        let users = UserService::new(pool.clone());
+
+        users
+            .create_child_user("parent".to_string(), "child".to_string())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            SubscriptionService::subscriber_id(&pool, CHILD_ID).await,
+            Ok(PARENT_ID)
+        );
+        assert_eq!(
+            QuotaService::new(pool.clone())
+                .get_usage(CHILD_ID, resource_type_model::USERS)
+                .await,
+            Ok(1)
+        );
+
+        // A seat is held once.
+        assert!(matches!(
+            users
+                .create_child_user("parent".to_string(), "child".to_string())
+                .await,
+            Err((StatusCode::CONFLICT, _))
+        ));
+    }
+
+    #[sqlx::test(fixtures("subscriptions"))]
+    async fn test_create_child_user_keeps_current_subscriptions(pool: sqlx::PgPool) {
+        let users = UserService::new(pool.clone());
+
+        // The child's own subscription is left to the child.
+        assert!(matches!(
+            users
+                .create_child_user("parent".to_string(), "other".to_string())
+                .await,
+            Err((StatusCode::CONFLICT, _))
+        ));
+
+        let status = sqlx::query_scalar!(
+            r#"
+            SELECT status FROM subscriptions WHERE id = $1
+            "#,
+            OTHER_SUBSCRIPTION_ID
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(status, SubscriptionStatus::Incomplete.as_str());
+
+        // Children are only seated on an active subscription.
+        assert!(matches!(
+            users
+                .create_child_user("other".to_string(), "child".to_string())
+                .await,
+            Err((StatusCode::CONFLICT, _))
+        ));
     }
 }
@@ -2,7 +2,7 @@ use std::sync::Arc;
 
 use apps::app::{self, AppState};
 use dotenv::dotenv;
-use infra::{db::postgres, tracing::init_tracing};
+use infra::{configs::Config, db::postgres, events, scheduler, tracing::init_tracing};
 
 mod apps;
 mod domain;
@@ -12,8 +12,19 @@ mod infra;
 async fn main() {
     dotenv().ok();
     init_tracing();
+    events::spawn_listeners();
 
     let pool: sqlx::Pool<sqlx::Postgres> = postgres::connect().await;
 
-    app::run_app(Arc::new(AppState { pool })).await;
+    let config = Config::init();
+
+    scheduler::spawn(pool.clone(), config.clone());
+
+    app::run_app(Arc::new(AppState {
+        pool,
+        dunning: config.dunning,
+        seller: config.seller,
+        payment_providers: config.payment_providers,
+    }))
+    .await;
 }